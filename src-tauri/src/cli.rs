@@ -0,0 +1,489 @@
+//! Headless command-line mode. `office-tools <command> ...` runs one of the
+//! native document operations and exits without opening a window; any other
+//! arguments start the app as usual.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::commands::annotations;
+use crate::commands::documents::write_atomic;
+use crate::commands::error::CommandError;
+use crate::commands::forms::{self, FieldValue, FillOptions};
+use crate::commands::optimize::{self, OptimizeOptions, OptimizePreset};
+use crate::commands::pages::{self, PageRange, SplitMode};
+use crate::commands::pdf::{self, Annotation, SaveMode, SignatureStyle};
+use crate::commands::security::{self, EncryptionMethod, PdfPermissions, ProtectOptions};
+use crate::commands::text;
+
+/// Exit code for a command that ran and failed.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code for arguments we couldn't make sense of.
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage: office-tools <command> [options]
+       office-tools [file]              Open a file in the app
+
+Commands:
+  flatten <in.pdf> --annotations <annotations.json> [--signatures <signatures.json>]
+          [--incremental] -o <out.pdf>
+      Draw text and signature annotations into the page content.
+  annotate <in.pdf> --annotations <annotations.json> [--signatures <signatures.json>]
+          [--incremental] -o <out.pdf>
+      Save text and signature annotations as PDF annotations that stay
+      editable in other viewers.
+  fill-form <in.pdf> --values <values.json> [--flatten] [--no-appearances]
+          [--incremental] -o <out.pdf>
+      Fill AcroForm fields; values are keyed by fully qualified field name.
+  merge <a.pdf> <b.pdf>... -o <out.pdf>
+      Combine documents in the order given.
+  split <in.pdf> (--every <n> | --ranges <1-3,4-6,...>) -o <dir>
+      Write one file per range (or every n pages) into <dir>.
+  reorder <in.pdf> --pages <3,1-2,...> -o <out.pdf>
+  rotate <in.pdf> --pages <1,3-5> [--degrees <90|180|270>] -o <out.pdf>
+  delete <in.pdf> --pages <2,7-9> -o <out.pdf>
+  protect <in.pdf> --user-password <pw> [--owner-password <pw>] [--password <pw>]
+          [--method <aes256|aes128|rc4>] [--no-print] [--no-copy] [--no-modify]
+          -o <out.pdf>
+      Encrypt with a password needed to open the file (may be empty) and
+      one needed to change it (defaults to the first), restricting printing,
+      copying or editing. --password is the owner password of an input that
+      is already protected.
+  optimize <in.pdf> [--preset <email|print|archive>] [--dpi <n>] -o <out.pdf>
+      Make the file smaller: downsample images (150 dpi for email, the
+      default, 300 for print, none for archive; --dpi overrides it, 0 keeps
+      them), recompress streams and store repeated objects once.
+  convert <in.pdf> [-o <out.txt|out.json>]
+      Extract the text of every page: plain text (pages separated by form
+      feeds), or JSON with glyph positions when the output ends in .json.
+      Writes plain text to stdout without -o.
+
+Options:
+  -o, --output <path>   Where to write the result
+  -h, --help            Show this help
+  -V, --version         Show the version
+
+Exit status is 0 on success, 1 when the operation fails and 2 for invalid
+arguments.";
+
+const COMMANDS: [&str; 11] = [
+    "flatten",
+    "annotate",
+    "fill-form",
+    "merge",
+    "split",
+    "reorder",
+    "rotate",
+    "delete",
+    "protect",
+    "optimize",
+    "convert",
+];
+
+/// Run the command in `args` (without the program name). Returns the exit
+/// code, or `None` when the arguments aren't a CLI command and the app
+/// should start normally.
+pub fn run(args: &[String]) -> Option<i32> {
+    let first = args.first()?.as_str();
+    if !COMMANDS.contains(&first) && !matches!(first, "-h" | "--help" | "help" | "-V" | "--version")
+    {
+        return None;
+    }
+    attach_console();
+
+    let result = match first {
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            return Some(0);
+        }
+        "-V" | "--version" => {
+            println!("office-tools {}", env!("CARGO_PKG_VERSION"));
+            return Some(0);
+        }
+        command => run_command(command, &args[1..]),
+    };
+    Some(match result {
+        Ok(()) => 0,
+        Err(CliError::Usage(message)) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            EXIT_USAGE
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("error: {}", message);
+            EXIT_FAILURE
+        }
+    })
+}
+
+/// The files the app was asked to open ("Open With", or `office-tools
+/// a.pdf b.pdf`): every argument that isn't an option. Relative paths are
+/// resolved against `cwd`, the working directory of the launch that passed
+/// them.
+pub fn files_to_open(args: &[String], cwd: &Path) -> Vec<String> {
+    args.iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .map(|arg| cwd.join(arg).to_string_lossy().into_owned())
+        .collect()
+}
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Failed(message)
+    }
+}
+
+impl From<CommandError> for CliError {
+    fn from(e: CommandError) -> Self {
+        CliError::Failed(e.message)
+    }
+}
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+fn run_command(command: &str, args: &[String]) -> Result<(), CliError> {
+    match command {
+        "flatten" | "annotate" => {
+            let args = Args::parse(
+                args,
+                &["annotations", "signatures", "output"],
+                &["incremental"],
+            )?;
+            let input = args.single_input()?;
+            let annotations: Vec<Annotation> = read_json(args.required("annotations")?)?;
+            let signatures: Vec<SignatureStyle> = match args.option("signatures") {
+                Some(path) => read_json(path)?,
+                None => Vec::new(),
+            };
+            let output = args.required("output")?;
+            let save = match command {
+                "annotate" => annotations::annotate,
+                _ => pdf::flatten,
+            };
+            save(
+                input,
+                &annotations,
+                &signatures,
+                &[],
+                output,
+                args.save_mode(),
+            )?;
+            println!("{}", output);
+        }
+        "fill-form" => {
+            let args = Args::parse(
+                args,
+                &["values", "output"],
+                &["flatten", "no-appearances", "incremental"],
+            )?;
+            let input = args.single_input()?;
+            let values: BTreeMap<String, FieldValue> = read_json(args.required("values")?)?;
+            let output = args.required("output")?;
+            let options = FillOptions {
+                regenerate_appearances: !args.flag("no-appearances"),
+                flatten: args.flag("flatten"),
+                mode: args.save_mode(),
+            };
+            forms::fill(input, output, &values, &options)?;
+            println!("{}", output);
+        }
+        "merge" => {
+            let args = Args::parse(args, &["output"], &[])?;
+            if args.inputs.len() < 2 {
+                return Err(usage("merge needs at least two input files"));
+            }
+            let output = args.required("output")?;
+            pages::merge(&args.inputs, output)?;
+            println!("{}", output);
+        }
+        "split" => {
+            let args = Args::parse(args, &["every", "ranges", "output"], &[])?;
+            let input = args.single_input()?;
+            let mode = match (args.option("every"), args.option("ranges")) {
+                (Some(every), None) => SplitMode::Every {
+                    pages: every
+                        .parse()
+                        .map_err(|_| usage(format!("Invalid page count: {}", every)))?,
+                },
+                (None, Some(ranges)) => SplitMode::Ranges {
+                    ranges: parse_ranges(ranges)?,
+                },
+                _ => return Err(usage("split needs either --every or --ranges")),
+            };
+            let output_dir = args.required("output")?;
+            fs::create_dir_all(output_dir)
+                .map_err(|e| format!("Failed to create output folder: {}", e))?;
+            let written = pages::split(input, output_dir, &mode, |path, bytes| {
+                write_atomic(path, bytes)
+            })?;
+            for path in written {
+                println!("{}", path);
+            }
+        }
+        "reorder" | "delete" => {
+            let args = Args::parse(args, &["pages", "output"], &[])?;
+            let input = args.single_input()?;
+            let page_list = parse_page_list(args.required("pages")?)?;
+            let output = args.required("output")?;
+            if command == "reorder" {
+                pages::reorder(input, output, &page_list)?;
+            } else {
+                pages::delete(input, output, &page_list)?;
+            }
+            println!("{}", output);
+        }
+        "rotate" => {
+            let args = Args::parse(args, &["pages", "degrees", "output"], &[])?;
+            let input = args.single_input()?;
+            let page_list = parse_page_list(args.required("pages")?)?;
+            let degrees = match args.option("degrees") {
+                Some(degrees) => degrees
+                    .parse()
+                    .map_err(|_| usage(format!("Invalid rotation: {}", degrees)))?,
+                None => 90,
+            };
+            let output = args.required("output")?;
+            pages::rotate(input, output, &page_list, degrees)?;
+            println!("{}", output);
+        }
+        "protect" => {
+            let args = Args::parse(
+                args,
+                &[
+                    "user-password",
+                    "owner-password",
+                    "password",
+                    "method",
+                    "output",
+                ],
+                &["no-print", "no-copy", "no-modify"],
+            )?;
+            let input = args.single_input()?;
+            let method = match args.option("method") {
+                None | Some("aes256") => EncryptionMethod::Aes256,
+                Some("aes128") => EncryptionMethod::Aes128,
+                Some("rc4") => EncryptionMethod::Rc4,
+                Some(method) => return Err(usage(format!("Unknown method: {}", method))),
+            };
+            let options = ProtectOptions {
+                user_password: args.required("user-password")?.to_string(),
+                owner_password: args.option("owner-password").map(str::to_string),
+                permissions: PdfPermissions {
+                    print: !args.flag("no-print"),
+                    copy: !args.flag("no-copy"),
+                    modify: !args.flag("no-modify"),
+                },
+                method,
+            };
+            let output = args.required("output")?;
+            security::protect(input, output, &options, args.option("password"))?;
+            println!("{}", output);
+        }
+        "optimize" => {
+            let args = Args::parse(args, &["preset", "dpi", "output"], &[])?;
+            let input = args.single_input()?;
+            let preset = match args.option("preset") {
+                None | Some("email") => OptimizePreset::Email,
+                Some("print") => OptimizePreset::Print,
+                Some("archive") => OptimizePreset::Archive,
+                Some(preset) => return Err(usage(format!("Unknown preset: {}", preset))),
+            };
+            let image_dpi = match args.option("dpi") {
+                Some(dpi) => Some(
+                    dpi.parse()
+                        .map_err(|_| usage(format!("Invalid resolution: {}", dpi)))?,
+                ),
+                None => None,
+            };
+            let output = args.required("output")?;
+            optimize::optimize(input, output, &OptimizeOptions { preset, image_dpi })?;
+            println!("{}", output);
+        }
+        "convert" => {
+            let args = Args::parse(args, &["output"], &[])?;
+            let input = args.single_input()?;
+            let page_texts = text::extract_text(input, None)?;
+            let output = args.option("output");
+            let contents = if output.is_some_and(|o| o.to_ascii_lowercase().ends_with(".json")) {
+                serde_json::to_string_pretty(&page_texts)
+                    .map_err(|e| format!("Failed to encode text: {}", e))?
+            } else {
+                let pages: Vec<&str> = page_texts.iter().map(|p| p.text.as_str()).collect();
+                pages.join("\n\u{c}")
+            };
+            match output {
+                Some(output) => {
+                    fs::write(output, contents)
+                        .map_err(|e| format!("Failed to write file: {}", e))?;
+                    println!("{}", output);
+                }
+                None => {
+                    let mut stdout = std::io::stdout().lock();
+                    writeln!(stdout, "{}", contents)
+                        .map_err(|e| format!("Failed to write output: {}", e))?;
+                }
+            }
+        }
+        _ => unreachable!("command is one of COMMANDS"),
+    }
+    Ok(())
+}
+
+/// Parsed arguments: positional inputs, `--name value` options and
+/// `--name` flags. `-o` is short for `--output`.
+struct Args {
+    inputs: Vec<String>,
+    options: BTreeMap<String, String>,
+    flags: BTreeSet<String>,
+}
+
+impl Args {
+    fn parse(args: &[String], options: &[&str], flags: &[&str]) -> Result<Self, CliError> {
+        let mut parsed = Args {
+            inputs: Vec::new(),
+            options: BTreeMap::new(),
+            flags: BTreeSet::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let name = match arg.as_str() {
+                "-o" => "output",
+                "--" => {
+                    parsed.inputs.extend(iter.by_ref().cloned());
+                    break;
+                }
+                other => match other.strip_prefix("--") {
+                    Some(name) => name,
+                    None if other.starts_with('-') && other.len() > 1 => {
+                        return Err(usage(format!("Unknown option: {}", other)))
+                    }
+                    None => {
+                        parsed.inputs.push(other.to_string());
+                        continue;
+                    }
+                },
+            };
+            let (name, inline_value) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            if options.contains(&name) {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| usage(format!("--{} needs a value", name)))?,
+                };
+                parsed.options.insert(name.to_string(), value);
+            } else if flags.contains(&name) && inline_value.is_none() {
+                parsed.flags.insert(name.to_string());
+            } else {
+                return Err(usage(format!("Unknown option: {}", arg)));
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn single_input(&self) -> Result<&str, CliError> {
+        match self.inputs.as_slice() {
+            [input] => Ok(input),
+            [] => Err(usage("Missing input file")),
+            _ => Err(usage("Expected a single input file")),
+        }
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, CliError> {
+        self.option(name).ok_or_else(|| {
+            usage(if name == "output" {
+                "Missing output path (-o)".to_string()
+            } else {
+                format!("Missing --{}", name)
+            })
+        })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    fn save_mode(&self) -> SaveMode {
+        if self.flag("incremental") {
+            SaveMode::Incremental
+        } else {
+            SaveMode::Rewrite
+        }
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, CliError> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    serde_json::from_slice(&data)
+        .map_err(|e| CliError::Failed(format!("Failed to parse {}: {}", path, e)))
+}
+
+/// Parse `1-3,5,7-9` into inclusive ranges.
+fn parse_ranges(list: &str) -> Result<Vec<PageRange>, CliError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            match (start.trim().parse(), end.trim().parse()) {
+                (Ok(start), Ok(end)) => Ok(PageRange { start, end }),
+                _ => Err(usage(format!("Invalid page range: {}", part))),
+            }
+        })
+        .collect()
+}
+
+/// Parse `3,1-2,4` into the page numbers it lists, in order.
+fn parse_page_list(list: &str) -> Result<Vec<u32>, CliError> {
+    let mut pages = Vec::new();
+    for range in parse_ranges(list)? {
+        if range.start > range.end {
+            return Err(usage(format!(
+                "Invalid page range: {}-{}",
+                range.start, range.end
+            )));
+        }
+        pages.extend(range.start..=range.end);
+    }
+    if pages.is_empty() {
+        return Err(usage("No pages given"));
+    }
+    Ok(pages)
+}
+
+/// Release builds are GUI-subsystem executables on Windows, which start
+/// without a console. Attach to the console of the shell that ran us so
+/// output and errors are visible there.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Fails harmlessly when there's no parent console (e.g. run from
+    // Explorer) or one is already attached.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
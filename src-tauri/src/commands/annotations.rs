@@ -0,0 +1,631 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::SystemTime;
+
+use lopdf::content::Content;
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+use serde::Serialize;
+
+use super::documents::overwrite;
+use super::error::CommandError;
+use super::forms::{display_rect, parse_default_appearance, remove_page_annotations};
+use super::pdf::{
+    decode_text_string, encode_text_string, hex_to_rgb, signature_font_bytes, Annotation,
+    AnnotationType, PageGeometry, PdfEditor, PdfFont, SaveMode, SignatureStyle,
+    SIGNATURE_FONT_SIZE, TEXT_DEFAULT_FONT_SIZE,
+};
+use super::signing::{add_page_annotation, pdf_date};
+
+/// Private entry on the Stamp annotations signatures are saved as. Holds
+/// the signature's name, font and color so it can be matched with a saved
+/// signature when the PDF is opened again.
+const SIGNATURE_STYLE_KEY: &[u8] = b"OfficeToolsSignature";
+
+/// Annotation flag Print (ISO 32000-1, 12.5.3).
+const PRINT_FLAG: i64 = 4;
+
+/// An overlay read from one of a PDF's annotations.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeAnnotation {
+    /// The overlay. Its id is the annotation's /NM, which is the overlay id
+    /// for annotations this app wrote. `signatureId` is never set; the
+    /// frontend finds or creates the signature described by `signature`.
+    #[serde(flatten)]
+    pub annotation: Annotation,
+    /// The annotation object in pdf.js' notation (`12R`, or `12R1` for
+    /// generation 1), so the viewer can hide it while the overlay shows.
+    pub object_ref: String,
+    pub signature: Option<ImportedSignature>,
+}
+
+/// How an imported signature was drawn.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedSignature {
+    pub name: String,
+    pub font_family: String,
+    pub color: String,
+}
+
+/// Save text and signature overlays into the PDF at `source_path` as
+/// annotations that other viewers can still move, edit and delete, and
+/// write the result to `output_path`.
+#[tauri::command]
+pub async fn annotate_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    annotations: Vec<Annotation>,
+    signatures: Vec<SignatureStyle>,
+    removed: Option<Vec<String>>,
+    output_path: String,
+    mode: Option<SaveMode>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        annotate(
+            &source_path,
+            &annotations,
+            &signatures,
+            &removed.unwrap_or_default(),
+            &output_path,
+            mode.unwrap_or_default(),
+        )
+    })
+}
+
+/// Read the FreeText annotations and saved signatures of the PDF at `path`
+/// as overlays.
+#[tauri::command]
+pub async fn read_pdf_annotations(path: String) -> Result<Vec<NativeAnnotation>, String> {
+    read_annotations(&path)
+}
+
+/// Write each overlay as an annotation: text as a FreeText annotation in
+/// Helvetica, signatures as a Stamp drawn in their script font. Both carry
+/// an appearance stream, so they look the same in every viewer, and the
+/// overlay id as their /NM.
+///
+/// Annotations this app wrote before, or read as overlays, are replaced
+/// rather than duplicated: those whose overlay is among `annotations` or
+/// listed in `removed` are removed first. Other annotations are untouched.
+///
+/// As with flattening, text is positioned like the overlay but neither
+/// clipped nor wrapped; the annotation's box grows to fit it.
+pub fn annotate(
+    source_path: &str,
+    annotations: &[Annotation],
+    signatures: &[SignatureStyle],
+    removed: &[String],
+    output_path: &str,
+    mode: SaveMode,
+) -> Result<(), CommandError> {
+    let mut editor = PdfEditor::open(source_path, mode)?;
+    remove_replaced(&mut editor.doc, annotations, removed)?;
+    annotate_into(&mut editor.doc, annotations, signatures)?;
+    editor.save(output_path)
+}
+
+/// Add an annotation to `doc` for each overlay (see `annotate`).
+///
+/// Only FreeText and Stamp are written: the overlay model has no ink or
+/// highlight overlays to save. Ink and Highlight annotations already in the
+/// PDF are kept as they are.
+pub(crate) fn annotate_into(
+    doc: &mut Document,
+    annotations: &[Annotation],
+    signatures: &[SignatureStyle],
+) -> Result<(), String> {
+    let pages = doc.get_pages();
+    let modified = Object::string_literal(pdf_date(SystemTime::now())?);
+
+    // Embed each signature font once, covering every name drawn with it.
+    let mut signature_text: BTreeMap<&str, String> = BTreeMap::new();
+    for annotation in annotations {
+        if let Some(sig) = signature_of(annotation, signatures) {
+            signature_text
+                .entry(&sig.font_family)
+                .or_default()
+                .push_str(&sig.name);
+        }
+    }
+    let mut fonts: BTreeMap<&str, PdfFont> = BTreeMap::new();
+    for (family, text) in signature_text {
+        let bytes = signature_font_bytes(family);
+        if !bytes.is_empty() {
+            fonts.insert(family, PdfFont::embed(doc, family, bytes, &text)?);
+        }
+    }
+    let mut helvetica: Option<PdfFont> = None;
+
+    for annotation in annotations {
+        let Some(&page_id) = pages.get(&annotation.page_number) else {
+            continue;
+        };
+        let geometry = PageGeometry::of(doc, page_id);
+
+        let mut dict = match annotation.annotation_type {
+            AnnotationType::Text => {
+                let text = annotation.text_content.as_deref().unwrap_or("");
+                if text.is_empty() {
+                    continue;
+                }
+                let size = annotation.font_size.unwrap_or(TEXT_DEFAULT_FONT_SIZE);
+                let color = hex_to_rgb(annotation.color.as_deref().unwrap_or("#000000"));
+                let font = helvetica.get_or_insert_with(|| PdfFont::helvetica(doc));
+                let (rect, appearance) =
+                    appearance(doc, geometry, annotation, font, b"Helv", text, size, color)?;
+                dictionary! {
+                    "Subtype" => "FreeText",
+                    "Rect" => rect,
+                    "Contents" => encode_text_string(text),
+                    "DA" => Object::string_literal(format!(
+                        "/Helv {} Tf {:.3} {:.3} {:.3} rg",
+                        size, color[0], color[1], color[2]
+                    )),
+                    "IT" => "FreeTextTypeWriter",
+                    "Border" => vec![0.into(), 0.into(), 0.into()],
+                    "AP" => dictionary! { "N" => appearance },
+                }
+            }
+            AnnotationType::Signature => {
+                let Some(sig) = signature_of(annotation, signatures) else {
+                    continue;
+                };
+                let font = match fonts.get(sig.font_family.as_str()) {
+                    Some(font) => font,
+                    None => helvetica.get_or_insert_with(|| PdfFont::helvetica(doc)),
+                };
+                let color = hex_to_rgb(&sig.color);
+                let (rect, appearance) = appearance(
+                    doc,
+                    geometry,
+                    annotation,
+                    font,
+                    b"F1",
+                    &sig.name,
+                    SIGNATURE_FONT_SIZE,
+                    color,
+                )?;
+                dictionary! {
+                    "Subtype" => "Stamp",
+                    "Rect" => rect,
+                    "Contents" => encode_text_string(&sig.name),
+                    "AP" => dictionary! { "N" => appearance },
+                    SIGNATURE_STYLE_KEY => dictionary! {
+                        "Name" => encode_text_string(&sig.name),
+                        "FontFamily" => encode_text_string(&sig.font_family),
+                        "Color" => Object::string_literal(sig.color.as_str()),
+                    },
+                }
+            }
+        };
+        dict.set("Type", "Annot");
+        dict.set("NM", encode_text_string(&annotation.id));
+        dict.set("M", modified.clone());
+        dict.set("F", PRINT_FLAG);
+        dict.set("P", page_id);
+
+        let annotation_id = doc.add_object(dict);
+        add_page_annotation(doc, page_id, annotation_id)?;
+    }
+
+    Ok(())
+}
+
+fn signature_of<'a>(
+    annotation: &Annotation,
+    signatures: &'a [SignatureStyle],
+) -> Option<&'a SignatureStyle> {
+    if annotation.annotation_type != AnnotationType::Signature {
+        return None;
+    }
+    signatures
+        .iter()
+        .find(|s| Some(s.id) == annotation.signature_id)
+}
+
+/// Build an annotation's normal appearance: `text` drawn from the top-left
+/// of a box the size of the overlay, grown to fit the text. Returns the
+/// annotation's /Rect, in MediaBox space, and the appearance stream, whose
+/// /Matrix turns it with the page so it shows upright.
+#[allow(clippy::too_many_arguments)]
+fn appearance(
+    doc: &mut Document,
+    geometry: PageGeometry,
+    annotation: &Annotation,
+    font: &PdfFont,
+    font_name: &[u8],
+    text: &str,
+    size: f32,
+    color: [f32; 3],
+) -> Result<(Vec<Object>, ObjectId), String> {
+    let line_height = (font.ascent - font.descent) * size / 1000.0;
+    let width = text
+        .lines()
+        .map(|line| font.width(line, size))
+        .fold(annotation.width, f32::max);
+    let height = (line_height * text.lines().count() as f32).max(annotation.height);
+
+    let (rect, rotation) = geometry.to_media_box_rect(annotation.x, annotation.y, width, height);
+
+    let operations = font.text_operations(
+        font_name,
+        text,
+        size,
+        color,
+        0.0,
+        height - font.ascent * size / 1000.0,
+        0.0,
+    );
+    let content = Content { operations }
+        .encode()
+        .map_err(|e| format!("Failed to encode annotation appearance: {}", e))?;
+
+    let (sin, cos) = match rotation as i64 {
+        90 => (1.0, 0.0),
+        180 => (0.0, -1.0),
+        270 => (-1.0, 0.0),
+        _ => (0.0, 1.0),
+    };
+    let mut stream = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Matrix" => vec![
+                Object::Real(cos),
+                Object::Real(sin),
+                Object::Real(-sin),
+                Object::Real(cos),
+                0.into(),
+                0.into(),
+            ],
+            "Resources" => dictionary! {
+                "Font" => dictionary! { font_name.to_vec() => font.id },
+            },
+        },
+        content,
+    );
+    let _ = stream.compress();
+    Ok((rect.map(Object::Real).to_vec(), doc.add_object(stream)))
+}
+
+/// Read the annotations of the PDF at `path` that can be shown as overlays.
+///
+/// FreeText annotations become text overlays, with the font size and color
+/// of their /DA; Stamps this app saved signatures as become signature
+/// overlays. Anything else (highlights, ink, notes, stamps from other
+/// programs, ...) stays part of the PDF and is only displayed.
+pub fn read_annotations(path: &str) -> Result<Vec<NativeAnnotation>, String> {
+    let editor = PdfEditor::open(path, SaveMode::Rewrite)?;
+    let doc = &editor.doc;
+
+    let mut imported = Vec::new();
+    for owned in owned_annotations(doc) {
+        let Ok(dict) = doc.get_dictionary(owned.id) else {
+            continue;
+        };
+        let (x, y, width, height) = display_rect(doc, owned.page_id, owned.id);
+        let mut annotation = Annotation {
+            id: owned.overlay_id,
+            page_number: owned.page_number,
+            annotation_type: AnnotationType::Text,
+            x,
+            y,
+            width,
+            height,
+            text_content: None,
+            font_size: None,
+            color: None,
+            signature_id: None,
+        };
+
+        let signature = match signature_style(doc, dict) {
+            Some(signature) => {
+                annotation.annotation_type = AnnotationType::Signature;
+                Some(signature)
+            }
+            None => {
+                let da = parse_default_appearance(text_entry(doc, dict, b"DA").as_bytes());
+                annotation.text_content = Some(text_entry(doc, dict, b"Contents"));
+                annotation.font_size = Some(if da.font_size > 0.0 {
+                    da.font_size
+                } else {
+                    TEXT_DEFAULT_FONT_SIZE
+                });
+                annotation.color = Some(color_to_hex(
+                    da.color.first().map_or(&[][..], |op| &op.operands),
+                ));
+                None
+            }
+        };
+
+        imported.push(NativeAnnotation {
+            annotation,
+            object_ref: object_ref(owned.id),
+            signature,
+        });
+    }
+    Ok(imported)
+}
+
+/// Remove the annotations standing behind overlays that are about to be
+/// written again: those whose overlay is among `annotations` or has been
+/// deleted (`removed`), along with their appearance streams and popups.
+pub(crate) fn remove_replaced(
+    doc: &mut Document,
+    annotations: &[Annotation],
+    removed: &[String],
+) -> Result<(), String> {
+    let ids: BTreeSet<&str> = annotations
+        .iter()
+        .map(|a| a.id.as_str())
+        .chain(removed.iter().map(String::as_str))
+        .collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut by_page: BTreeMap<ObjectId, BTreeSet<ObjectId>> = BTreeMap::new();
+    for owned in owned_annotations(doc) {
+        if !ids.contains(owned.overlay_id.as_str()) {
+            continue;
+        }
+        let replaced = by_page.entry(owned.page_id).or_default();
+        replaced.insert(owned.id);
+        if let Ok(popup) = doc
+            .get_dictionary(owned.id)
+            .and_then(|d| d.get(b"Popup"))
+            .and_then(Object::as_reference)
+        {
+            replaced.insert(popup);
+        }
+    }
+
+    for (page_id, replaced) in by_page {
+        remove_page_annotations(doc, page_id, &replaced)?;
+        for id in replaced {
+            if let Some(Object::Dictionary(dict)) = doc.objects.remove(&id) {
+                for stream in appearance_streams(&dict) {
+                    doc.objects.remove(&stream);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// An annotation that can be shown as an overlay.
+struct OwnedAnnotation {
+    page_number: u32,
+    page_id: ObjectId,
+    id: ObjectId,
+    overlay_id: String,
+}
+
+/// The FreeText annotations and signature Stamps of every page, with the
+/// overlay id each is shown under: its /NM, or one made from the object
+/// number for annotations without one.
+fn owned_annotations(doc: &Document) -> Vec<OwnedAnnotation> {
+    // Made-up ids start with part of the file's /ID so they don't collide
+    // with those of other documents in the `annotations` table.
+    let file_id: String = doc
+        .trailer
+        .get(b"ID")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|ids| ids.first())
+        .and_then(|id| id.as_str().ok())
+        .map(|bytes| bytes.iter().take(4).map(|b| format!("{:02x}", b)).collect())
+        .unwrap_or_default();
+
+    let mut owned = Vec::new();
+    for (page_number, page_id) in doc.get_pages() {
+        let Some(annots) = doc
+            .get_dictionary(page_id)
+            .and_then(|page| page.get_deref(b"Annots", doc))
+            .and_then(Object::as_array)
+            .ok()
+        else {
+            continue;
+        };
+        for id in annots.iter().filter_map(|a| a.as_reference().ok()) {
+            let Ok(dict) = doc.get_dictionary(id) else {
+                continue;
+            };
+            let shown = match dict.get(b"Subtype").and_then(Object::as_name) {
+                Ok(b"FreeText") => true,
+                Ok(b"Stamp") => dict.has(SIGNATURE_STYLE_KEY),
+                _ => false,
+            };
+            if !shown {
+                continue;
+            }
+            let overlay_id = dict
+                .get(b"NM")
+                .and_then(Object::as_str)
+                .map(decode_text_string)
+                .ok()
+                .filter(|nm| !nm.is_empty())
+                .unwrap_or_else(|| format!("annot_{}_{}_{}", file_id, id.0, id.1));
+            owned.push(OwnedAnnotation {
+                page_number,
+                page_id,
+                id,
+                overlay_id,
+            });
+        }
+    }
+    owned
+}
+
+/// The signature an annotation was saved from, if it's one of ours.
+fn signature_style(doc: &Document, dict: &Dictionary) -> Option<ImportedSignature> {
+    let style = dict
+        .get_deref(SIGNATURE_STYLE_KEY, doc)
+        .and_then(Object::as_dict)
+        .ok()?;
+    Some(ImportedSignature {
+        name: text_entry(doc, style, b"Name"),
+        font_family: text_entry(doc, style, b"FontFamily"),
+        color: text_entry(doc, style, b"Color"),
+    })
+}
+
+fn text_entry(doc: &Document, dict: &Dictionary, key: &[u8]) -> String {
+    dict.get_deref(key, doc)
+        .and_then(Object::as_str)
+        .map(decode_text_string)
+        .unwrap_or_default()
+}
+
+/// The streams of an annotation's /AP, whose entries are either a stream or
+/// a dictionary of streams keyed by state.
+fn appearance_streams(dict: &Dictionary) -> Vec<ObjectId> {
+    let Ok(ap) = dict.get(b"AP").and_then(Object::as_dict) else {
+        return Vec::new();
+    };
+    ap.iter()
+        .flat_map(|(_, entry)| match entry {
+            Object::Reference(id) => vec![*id],
+            Object::Dictionary(states) => states
+                .iter()
+                .filter_map(|(_, state)| state.as_reference().ok())
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Hex color for the operands of a /DA color operator (g, rg or k).
+pub(crate) fn color_to_hex(operands: &[Object]) -> String {
+    let c: Vec<f32> = operands.iter().filter_map(|o| o.as_float().ok()).collect();
+    let rgb = match c.as_slice() {
+        [gray] => [*gray; 3],
+        [r, g, b] => [*r, *g, *b],
+        [cyan, magenta, yellow, black] => [
+            (1.0 - cyan) * (1.0 - black),
+            (1.0 - magenta) * (1.0 - black),
+            (1.0 - yellow) * (1.0 - black),
+        ],
+        _ => [0.0; 3],
+    };
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(rgb[0]),
+        channel(rgb[1]),
+        channel(rgb[2])
+    )
+}
+
+/// pdf.js' id for an annotation object.
+fn object_ref((num, gen): ObjectId) -> String {
+    if gen == 0 {
+        format!("{}R", num)
+    } else {
+        format!("{}R{}", num, gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{path_in, save, temp_dir, text_pdf};
+
+    fn markup(doc: &mut Document, subtype: &str) -> ObjectId {
+        let page_id = doc.get_pages()[&1];
+        let id = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => subtype,
+            "Rect" => vec![72.into(), 700.into(), 200.into(), 720.into()],
+            "NM" => Object::string_literal(subtype),
+        });
+        add_page_annotation(doc, page_id, id).unwrap();
+        id
+    }
+
+    fn text_overlay(id: &str, text: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            page_number: 1,
+            annotation_type: AnnotationType::Text,
+            x: 100.0,
+            y: 100.0,
+            width: 120.0,
+            height: 20.0,
+            text_content: Some(text.to_string()),
+            font_size: Some(14.0),
+            color: Some("#ff0000".to_string()),
+            signature_id: None,
+        }
+    }
+
+    fn subtypes(path: &str) -> Vec<String> {
+        let doc = Document::load(path).unwrap();
+        let page_id = doc.get_pages()[&1];
+        doc.get_dictionary(page_id)
+            .and_then(|page| page.get_deref(b"Annots", &doc))
+            .and_then(Object::as_array)
+            .unwrap()
+            .iter()
+            .map(|a| {
+                let dict = doc.get_dictionary(a.as_reference().unwrap()).unwrap();
+                let subtype = dict.get(b"Subtype").and_then(Object::as_name).unwrap();
+                String::from_utf8_lossy(subtype).into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text_overlays_round_trip() {
+        let dir = temp_dir("annotations-text");
+        let source = save(&mut text_pdf(&[&["Hello"]]), &dir, "in.pdf");
+        let output = path_in(&dir, "out.pdf");
+        let overlay = text_overlay("overlay-1", "Note");
+        annotate(&source, &[overlay], &[], &[], &output, SaveMode::Rewrite).unwrap();
+
+        let read = read_annotations(&output).unwrap();
+        assert_eq!(read.len(), 1);
+        let annotation = &read[0].annotation;
+        assert_eq!(annotation.id, "overlay-1");
+        assert_eq!(annotation.annotation_type, AnnotationType::Text);
+        assert_eq!(annotation.text_content.as_deref(), Some("Note"));
+        assert_eq!(annotation.font_size, Some(14.0));
+        assert_eq!(annotation.color.as_deref(), Some("#ff0000"));
+
+        // Saving the overlay again replaces its annotation
+        let again = path_in(&dir, "again.pdf");
+        let edited = text_overlay("overlay-1", "Edited");
+        annotate(&output, &[edited], &[], &[], &again, SaveMode::Rewrite).unwrap();
+        let read = read_annotations(&again).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].annotation.text_content.as_deref(), Some("Edited"));
+    }
+
+    #[test]
+    fn ink_and_highlights_are_kept_but_not_imported() {
+        let dir = temp_dir("annotations-markup");
+        let mut doc = text_pdf(&[&["Hello"]]);
+        markup(&mut doc, "Ink");
+        markup(&mut doc, "Highlight");
+        let source = save(&mut doc, &dir, "in.pdf");
+        assert!(read_annotations(&source).unwrap().is_empty());
+
+        let output = path_in(&dir, "out.pdf");
+        let overlay = text_overlay("overlay-1", "Note");
+        let removed = ["Ink".to_string(), "Highlight".to_string()];
+        annotate(
+            &source,
+            &[overlay],
+            &[],
+            &removed,
+            &output,
+            SaveMode::Rewrite,
+        )
+        .unwrap();
+
+        assert_eq!(subtypes(&output), ["Ink", "Highlight", "FreeText"]);
+        assert_eq!(read_annotations(&output).unwrap().len(), 1);
+    }
+}
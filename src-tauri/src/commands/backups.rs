@@ -0,0 +1,150 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use super::documents::{force_overwrite, write_atomic};
+use super::error::{CommandError, ErrorCode};
+
+/// Backups of a file live in `{app_data}/backups/<key>/`, where the key is
+/// a hash of the file's path, as `<created_at>.bak` files.
+const BACKUP_DIR: &str = "backups";
+const BACKUP_EXTENSION: &str = "bak";
+/// The path the backups in a directory belong to, for reference.
+const SOURCE_FILE: &str = "source.txt";
+
+/// How many backups to keep per file. The oldest is removed when a save
+/// would make one more.
+const MAX_BACKUPS: usize = 5;
+
+/// A copy of a file as it was before it was overwritten.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    /// Pass to `restore_backup`.
+    pub id: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: u64,
+    pub size: u64,
+}
+
+/// Backups of the file at `path`, newest first.
+#[tauri::command]
+pub async fn list_backups(app: tauri::AppHandle, path: String) -> Result<Vec<BackupInfo>, String> {
+    list(&backup_dir(&app, Path::new(&path))?)
+}
+
+/// Put a backup of `path` back in place. The current contents are backed up
+/// (and kept as a version) first, so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_backup(
+    app: tauri::AppHandle,
+    path: String,
+    id: String,
+) -> Result<(), CommandError> {
+    let path = Path::new(&path);
+    let dir = backup_dir(&app, path)?;
+    // Ids are timestamps; anything else could point outside the directory
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(CommandError::new(
+            ErrorCode::InvalidPath,
+            format!("Invalid backup id: {}", id),
+        ));
+    }
+    let backup = dir.join(format!("{}.{}", id, BACKUP_EXTENSION));
+    let data = fs::read(&backup).map_err(|e| CommandError::io("read backup", &backup, e))?;
+    force_overwrite(&app, path, || write_atomic(path, &data))
+}
+
+/// Back up `path` into its backup directory, if it exists.
+pub fn back_up_file(app: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    back_up(&backup_dir(app, path)?, path)
+}
+
+/// Copy `path` into `dir` and drop the backups beyond `MAX_BACKUPS`.
+pub fn back_up(dir: &Path, path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create backup folder: {}", e))?;
+    fs::write(dir.join(SOURCE_FILE), path.to_string_lossy().as_bytes())
+        .map_err(|e| format!("Failed to back up file: {}", e))?;
+
+    // Two saves in the same millisecond get consecutive ids
+    let mut created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    while dir.join(backup_name(created_at)).exists() {
+        created_at += 1;
+    }
+    fs::copy(path, dir.join(backup_name(created_at)))
+        .map_err(|e| format!("Failed to back up file: {}", e))?;
+
+    for stale in list(dir)?.iter().skip(MAX_BACKUPS) {
+        fs::remove_file(dir.join(backup_name(stale.created_at)))
+            .map_err(|e| format!("Failed to remove old backup: {}", e))?;
+    }
+    Ok(())
+}
+
+/// The backups in `dir`, newest first. A missing directory has none.
+pub fn list(dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to list backups: {}", e)),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to list backups: {}", e))?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(BACKUP_EXTENSION) {
+            continue;
+        }
+        let Some(created_at) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        let size = entry
+            .metadata()
+            .map_err(|e| format!("Failed to list backups: {}", e))?
+            .len();
+        backups.push(BackupInfo {
+            id: created_at.to_string(),
+            created_at,
+            size,
+        });
+    }
+    backups.sort_by_key(|b| Reverse(b.created_at));
+    Ok(backups)
+}
+
+fn backup_name(created_at: u64) -> String {
+    format!("{}.{}", created_at, BACKUP_EXTENSION)
+}
+
+fn backup_dir(app: &tauri::AppHandle, path: &Path) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(BACKUP_DIR).join(path_key(path)))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+/// Directory name for the backups of `path`: the same file reached through
+/// a different spelling of its path gets the same key.
+fn path_key(path: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    Sha256::digest(path.to_string_lossy().as_bytes())
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
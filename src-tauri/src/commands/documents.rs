@@ -1,12 +1,23 @@
 use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use tauri_plugin_dialog::DialogExt;
 
+use super::error::{CommandError, ErrorCode};
+use super::{backups, locks, versions, watcher};
+
 #[tauri::command]
-pub async fn open_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+pub async fn open_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, CommandError> {
     let file = app
         .dialog()
         .file()
-        .add_filter("Office Documents", &["pdf", "docx", "doc", "xlsx", "xls", "csv", "pptx", "ppt"])
+        .add_filter(
+            "Office Documents",
+            &["pdf", "docx", "doc", "xlsx", "xls", "csv", "pptx", "ppt"],
+        )
         .add_filter("PDF Files", &["pdf"])
         .add_filter("Word Documents", &["docx", "doc"])
         .add_filter("Excel Spreadsheets", &["xlsx", "xls", "csv"])
@@ -24,7 +35,7 @@ pub async fn open_file_dialog(app: tauri::AppHandle) -> Result<Option<String>, S
 pub async fn save_file_dialog(
     app: tauri::AppHandle,
     default_name: Option<String>,
-) -> Result<Option<String>, String> {
+) -> Result<Option<String>, CommandError> {
     let mut builder = app
         .dialog()
         .file()
@@ -51,50 +62,238 @@ pub async fn save_file_dialog(
     }
 }
 
+/// Read a whole file. The file is watched from then on: the frontend hears
+/// when another program changes it, and writes refuse to replace changes
+/// they haven't seen.
+#[tauri::command]
+pub async fn read_file_bytes(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    path: String,
+) -> Result<Vec<u8>, CommandError> {
+    let data = fs::read(&path).map_err(|e| CommandError::io("read file", &path, e))?;
+    watcher::watch(&app, window.label(), &path, Some(&data));
+    Ok(data)
+}
+
+/// Read a file as a raw IPC response. The bytes reach JS as an ArrayBuffer
+/// rather than a JSON number array, so large files don't balloon in memory
+/// or stall the WebView while the array is parsed.
+#[tauri::command]
+pub async fn read_file_bytes_raw(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    path: String,
+) -> Result<tauri::ipc::Response, CommandError> {
+    let data = fs::read(&path).map_err(|e| CommandError::io("read file", &path, e))?;
+    watcher::watch(&app, window.label(), &path, Some(&data));
+    Ok(tauri::ipc::Response::new(data))
+}
+
+/// Read `length` bytes of a file starting at `offset`, as a raw IPC
+/// response. The result is shorter if the file ends first. pdf.js uses this
+/// to load large PDFs a range at a time.
 #[tauri::command]
-pub async fn read_file_bytes(path: String) -> Result<Vec<u8>, String> {
-    fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))
+pub async fn read_file_range(
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<tauri::ipc::Response, CommandError> {
+    let read_error = |e| CommandError::io("read file", &path, e);
+    let mut file = fs::File::open(&path).map_err(read_error)?;
+    file.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+    let mut data = Vec::new();
+    file.take(length)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    Ok(tauri::ipc::Response::new(data))
+}
+
+/// The size of a file about to be opened. Large PDFs are then only read a
+/// range at a time, so they are watched from here.
+#[tauri::command]
+pub async fn get_file_size(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    path: String,
+) -> Result<u64, CommandError> {
+    let size = fs::metadata(&path)
+        .map(|m| m.len())
+        .map_err(|e| CommandError::io("read file", &path, e))?;
+    watcher::watch(&app, window.label(), &path, None);
+    Ok(size)
 }
 
 /// Write file bytes via JSON-serialized data. Works for small files but
 /// incurs ~4x memory overhead due to JSON number-array encoding.
 /// For large files (>5 MB), prefer write_file_bytes_raw instead.
+///
+/// Fails if the file was changed by another program since it was read,
+/// unless `force` is set. The file is watched afterwards, as if read.
 #[tauri::command]
-pub async fn write_file_bytes(path: String, data: Vec<u8>) -> Result<(), String> {
-    fs::write(&path, data).map_err(|e| format!("Failed to write file: {}", e))
+pub async fn write_file_bytes(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    path: String,
+    data: Vec<u8>,
+    force: Option<bool>,
+) -> Result<(), CommandError> {
+    write_watching(&app, window.label(), &path, &data, force.unwrap_or(false))
 }
 
 /// Write file bytes using raw IPC body, bypassing JSON serialization.
 /// This avoids the ~4x memory overhead of encoding Uint8Array as a JSON
 /// number array, preventing OOM crashes on large PDFs (10+ MB).
-/// The file path is passed via the X-File-Path request header.
+/// The file path is passed via the X-File-Path request header, and
+/// `X-Force-Write: true` overwrites changes made by another program as
+/// write_file_bytes' `force` does.
 #[tauri::command]
-pub async fn write_file_bytes_raw(request: tauri::ipc::Request<'_>) -> Result<(), String> {
+pub async fn write_file_bytes_raw(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    request: tauri::ipc::Request<'_>,
+) -> Result<(), CommandError> {
     let path = request
         .headers()
         .get("X-File-Path")
         .and_then(|v: &tauri::http::HeaderValue| v.to_str().ok())
         .map(|s: &str| s.to_string())
-        .ok_or_else(|| "Missing X-File-Path header".to_string())?;
+        .ok_or_else(|| CommandError::new(ErrorCode::InvalidPath, "Missing X-File-Path header"))?;
 
     let data = match request.body() {
         tauri::ipc::InvokeBody::Raw(bytes) => bytes.clone(),
         tauri::ipc::InvokeBody::Json(value) => {
             // Fallback: handle JSON-encoded byte arrays for backward compatibility
-            serde_json::from_value::<Vec<u8>>(value.clone())
-                .map_err(|e| format!("Failed to deserialize data: {}", e))?
+            serde_json::from_value::<Vec<u8>>(value.clone()).map_err(|e| {
+                CommandError::new(
+                    ErrorCode::InvalidData,
+                    format!("Failed to deserialize data: {}", e),
+                )
+            })?
         }
     };
 
-    fs::write(&path, &data).map_err(|e| format!("Failed to write file: {}", e))
+    let force = request
+        .headers()
+        .get("X-Force-Write")
+        .is_some_and(|v: &tauri::http::HeaderValue| v.as_bytes() == b"true");
+
+    write_watching(&app, window.label(), &path, &data, force)
 }
 
-#[tauri::command]
-pub fn get_file_opened_with() -> Option<String> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 {
-        Some(args[1].clone())
+/// Write frontend-held bytes over `path`, which then counts as read.
+fn write_watching(
+    app: &tauri::AppHandle,
+    window: &str,
+    path: &str,
+    data: &[u8],
+    force: bool,
+) -> Result<(), CommandError> {
+    let file = Path::new(path);
+    if force {
+        force_overwrite(app, file, || write_atomic(file, data))?;
     } else {
-        None
+        overwrite(app, file, || write_atomic(file, data))?;
+    }
+    watcher::watch(app, window, path, Some(data));
+    Ok(())
+}
+
+/// Replace the file at `path` by running `write`. Every command that
+/// overwrites a file goes through here: a file someone else has open, or
+/// that another program changed since it was read, is refused; the old
+/// contents are kept first, and the file watcher doesn't report the new
+/// ones as someone else's.
+pub fn overwrite<T, E: Into<CommandError>>(
+    app: &tauri::AppHandle,
+    path: &Path,
+    write: impl FnOnce() -> Result<T, E>,
+) -> Result<T, CommandError> {
+    watcher::ensure_unchanged(app, path)?;
+    force_overwrite(app, path, write)
+}
+
+/// `overwrite`, replacing changes another program made to the file. For
+/// saves the user confirmed over those changes, and for restores, which
+/// keep the current contents as a backup and version anyway.
+pub fn force_overwrite<T, E: Into<CommandError>>(
+    app: &tauri::AppHandle,
+    path: &Path,
+    write: impl FnOnce() -> Result<T, E>,
+) -> Result<T, CommandError> {
+    locks::ensure_not_locked(path)?;
+    preserve_before_write(app, path);
+    watcher::write_watched(app, path, || write().map_err(Into::into))
+}
+
+/// Keep the current contents of `path` before a command overwrites it: as
+/// a rotating backup and as an entry in its version history. Neither stops
+/// the save if it fails; the error is only logged.
+fn preserve_before_write(app: &tauri::AppHandle, path: &Path) {
+    if let Err(e) = backups::back_up_file(app, path) {
+        log::warn!("{}", e);
+    }
+    if let Err(e) = versions::snapshot(app, path) {
+        log::warn!("{}", e);
     }
 }
+
+/// Replace the file at `path` with `data` without ever leaving it half
+/// written. The bytes go to a temporary file in the same directory, are
+/// flushed to disk, and the temporary file is then renamed over `path`; a
+/// crash or full disk before the rename leaves the original untouched.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), CommandError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().ok_or_else(|| {
+        CommandError::new(
+            ErrorCode::InvalidPath,
+            format!("Invalid file path: {}", path.display()),
+        )
+        .with_path(path)
+    })?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let temp_path = dir.join(format!(
+        ".{}.{}-{}.tmp",
+        file_name.to_string_lossy(),
+        process::id(),
+        nanos
+    ));
+
+    let result = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(CommandError::io("write file", path, e));
+    }
+
+    // Make the rename itself durable. Directories can't be opened this way
+    // on Windows, where the rename is already flushed.
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// The resolved form of `path`, which backups, version history and the
+/// library index key files by, so the same file reached through a different
+/// spelling is one file. A path that can't be resolved is kept as given.
+pub(crate) fn canonical_path(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
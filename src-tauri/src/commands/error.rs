@@ -0,0 +1,103 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+/// What went wrong, for the frontend to act on. Serialized in snake_case;
+/// mirrored by `ErrorCode` in src/types/document.ts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    DiskFull,
+    /// Another program has the file open and won't share it (Windows).
+    InUse,
+    InvalidPath,
+    InvalidData,
+    /// Changed by another program since it was read (see `watcher`).
+    FileChanged,
+    /// Someone else has the document open for editing (see `locks`).
+    FileLocked,
+    Other,
+}
+
+/// The error returned by commands: a code the frontend can switch on, a
+/// message for the log, and the file it concerns when there is one. What
+/// the user is shown is worded by the frontend, from the code.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub path: Option<String>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        CommandError {
+            code,
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().to_string());
+        self
+    }
+
+    /// An I/O error on `path`, coded by its kind. `action` completes
+    /// "Failed to ...".
+    pub fn io(action: &str, path: impl AsRef<Path>, e: io::Error) -> Self {
+        CommandError::new(io_code(&e), format!("Failed to {}: {}", action, e)).with_path(path)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Errors from helpers that still report a plain message.
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::new(ErrorCode::Other, message)
+    }
+}
+
+/// For commands that still return `Result<_, String>`.
+impl From<CommandError> for String {
+    fn from(e: CommandError) -> Self {
+        e.message
+    }
+}
+
+fn io_code(e: &io::Error) -> ErrorCode {
+    match e.kind() {
+        io::ErrorKind::NotFound => return ErrorCode::NotFound,
+        io::ErrorKind::PermissionDenied => return ErrorCode::PermissionDenied,
+        io::ErrorKind::InvalidInput => return ErrorCode::InvalidPath,
+        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => return ErrorCode::InvalidData,
+        _ => {}
+    }
+    // The kinds for these aren't stable on our minimum Rust version
+    match e.raw_os_error() {
+        // ENOSPC; EROFS
+        #[cfg(unix)]
+        Some(28) => ErrorCode::DiskFull,
+        #[cfg(unix)]
+        Some(30) => ErrorCode::PermissionDenied,
+        // ERROR_HANDLE_DISK_FULL, ERROR_DISK_FULL; ERROR_SHARING_VIOLATION,
+        // ERROR_LOCK_VIOLATION
+        #[cfg(windows)]
+        Some(39) | Some(112) => ErrorCode::DiskFull,
+        #[cfg(windows)]
+        Some(32) | Some(33) => ErrorCode::InUse,
+        _ => ErrorCode::Other,
+    }
+}
@@ -0,0 +1,945 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use lopdf::xref::XrefType;
+use lopdf::{dictionary, Document, Object};
+use quick_xml::escape::{escape, unescape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri_plugin_dialog::DialogExt;
+
+use super::annotations::{color_to_hex, ImportedSignature};
+use super::documents::{overwrite, write_atomic};
+use super::error::CommandError;
+use super::forms::{field_value, list_fields, parse_default_appearance, FieldType, FieldValue};
+use super::pdf::{
+    encode_text_string, hex_to_rgb, Annotation, AnnotationType, PageGeometry, PdfEditor, SaveMode,
+    SignatureStyle, TEXT_DEFAULT_FONT_SIZE,
+};
+use super::signing::pdf_date;
+
+const XFDF_NAMESPACE: &str = "http://ns.adobe.com/xfdf/";
+
+/// Namespace of the attributes that describe a signature's style on the
+/// <stamp> elements signatures are exported as.
+const STYLE_NAMESPACE: &str = "urn:office-tools:signature";
+const FONT_FAMILY_ATTRIBUTE: &str = "office-tools:font-family";
+const COLOR_ATTRIBUTE: &str = "office-tools:color";
+
+/// The files annotations and form data are exchanged in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeFormat {
+    /// XML Forms Data Format, for annotations.
+    Xfdf,
+    /// Forms Data Format, for form field values.
+    Fdf,
+}
+
+impl ExchangeFormat {
+    fn filter(self) -> (&'static str, &'static str) {
+        match self {
+            ExchangeFormat::Xfdf => ("XFDF Annotations", "xfdf"),
+            ExchangeFormat::Fdf => ("FDF Form Data", "fdf"),
+        }
+    }
+}
+
+/// An overlay read from an XFDF file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedAnnotation {
+    /// The overlay. As with annotations read from a PDF, `signatureId` is
+    /// never set; the frontend matches `signature` with a saved one.
+    #[serde(flatten)]
+    pub annotation: Annotation,
+    pub signature: Option<ImportedSignature>,
+}
+
+/// What `import_annotations_xfdf` read.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XfdfImport {
+    pub annotations: Vec<ImportedAnnotation>,
+    /// Markup that can't be shown as an overlay (highlights, ink, notes,
+    /// other programs' stamps, ...), which was left out.
+    pub skipped: usize,
+    /// The file was exported from a different PDF (its <ids> don't match).
+    pub other_document: bool,
+}
+
+/// Ask the user for an XFDF or FDF file to import.
+#[tauri::command]
+pub async fn open_exchange_dialog(
+    app: tauri::AppHandle,
+    format: ExchangeFormat,
+) -> Result<Option<String>, String> {
+    let (name, extension) = format.filter();
+    let file = app
+        .dialog()
+        .file()
+        .add_filter(name, &[extension])
+        .add_filter("All Files", &["*"])
+        .blocking_pick_file();
+
+    Ok(file.map(|path| path.to_string()))
+}
+
+/// Ask the user where to export an XFDF or FDF file.
+#[tauri::command]
+pub async fn save_exchange_dialog(
+    app: tauri::AppHandle,
+    format: ExchangeFormat,
+    default_name: Option<String>,
+) -> Result<Option<String>, String> {
+    let (name, extension) = format.filter();
+    let mut builder = app.dialog().file().add_filter(name, &[extension]);
+    if let Some(default_name) = default_name {
+        builder = builder.set_file_name(&default_name);
+    }
+
+    Ok(builder.blocking_save_file().map(|path| {
+        let path = path.to_string();
+        // The Windows save dialog may not append the filter's extension.
+        if path.to_lowercase().ends_with(&format!(".{}", extension)) {
+            path
+        } else {
+            format!("{}.{}", path, extension)
+        }
+    }))
+}
+
+/// Export the overlays of the PDF at `pdf_path` to an XFDF file at
+/// `output_path`. The PDF itself is only read.
+#[tauri::command]
+pub async fn export_annotations_xfdf(
+    app: tauri::AppHandle,
+    pdf_path: String,
+    annotations: Vec<Annotation>,
+    signatures: Vec<SignatureStyle>,
+    output_path: String,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        export_xfdf(&pdf_path, &annotations, &signatures, &output_path)
+    })
+}
+
+/// Read the annotations of the XFDF file at `path` as overlays for the PDF
+/// at `pdf_path`.
+#[tauri::command]
+pub async fn import_annotations_xfdf(path: String, pdf_path: String) -> Result<XfdfImport, String> {
+    import_xfdf(&path, &pdf_path)
+}
+
+/// Export form field values (keyed by fully qualified name) for the PDF at
+/// `pdf_path` to an FDF file at `output_path`.
+#[tauri::command]
+pub async fn export_form_fdf(
+    app: tauri::AppHandle,
+    pdf_path: String,
+    values: BTreeMap<String, FieldValue>,
+    output_path: String,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        export_fdf(&pdf_path, &values, &output_path)
+    })
+}
+
+/// Read the field values of the FDF file at `path` that apply to the form
+/// of the PDF at `pdf_path`, keyed by fully qualified name.
+#[tauri::command]
+pub async fn import_form_fdf(
+    path: String,
+    pdf_path: String,
+) -> Result<BTreeMap<String, FieldValue>, String> {
+    import_fdf(&path, &pdf_path)
+}
+
+/// Write overlays as XFDF (ISO 19444-1): text as <freetext> with the font
+/// size and color in its default appearance, signatures as <stamp> with
+/// the signature name as contents and its font and color in attributes of
+/// our own namespace. Positions are converted to PDF page space, so the
+/// PDF is read for its page geometry and /ID.
+///
+/// Stamps have no appearance in the file, so other programs show them with
+/// their default stamp icon.
+pub fn export_xfdf(
+    pdf_path: &str,
+    annotations: &[Annotation],
+    signatures: &[SignatureStyle],
+    output_path: &str,
+) -> Result<(), CommandError> {
+    let editor = PdfEditor::open(pdf_path, SaveMode::Rewrite)?;
+    let doc = &editor.doc;
+    let pages = doc.get_pages();
+    let date = pdf_date(SystemTime::now())?;
+
+    let mut annots = String::new();
+    for annotation in annotations {
+        let Some(&page_id) = pages.get(&annotation.page_number) else {
+            continue;
+        };
+        let (rect, rotation) = PageGeometry::of(doc, page_id).to_media_box_rect(
+            annotation.x,
+            annotation.y,
+            annotation.width,
+            annotation.height,
+        );
+        let mut attributes = vec![
+            ("page", (annotation.page_number - 1).to_string()),
+            ("rect", format_rect(rect)),
+            ("name", annotation.id.clone()),
+            ("date", date.clone()),
+            ("flags", "print".to_string()),
+        ];
+        if rotation != 0.0 {
+            attributes.push(("rotation", rotation.to_string()));
+        }
+
+        match annotation.annotation_type {
+            AnnotationType::Text => {
+                let text = annotation.text_content.as_deref().unwrap_or("");
+                if text.is_empty() {
+                    continue;
+                }
+                let size = annotation.font_size.unwrap_or(TEXT_DEFAULT_FONT_SIZE);
+                let color = annotation.color.as_deref().unwrap_or("#000000");
+                let [r, g, b] = hex_to_rgb(color);
+                attributes.push(("width", "0".to_string()));
+                annots.push_str(&element(
+                    "freetext",
+                    &attributes,
+                    &[
+                        ("contents", text.to_string()),
+                        (
+                            "defaultappearance",
+                            format!("/Helv {} Tf {:.3} {:.3} {:.3} rg", size, r, g, b),
+                        ),
+                        (
+                            "defaultstyle",
+                            format!("font: Helvetica {}pt; color: {}", size, color),
+                        ),
+                    ],
+                ));
+            }
+            AnnotationType::Signature => {
+                let Some(sig) = signatures
+                    .iter()
+                    .find(|s| Some(s.id) == annotation.signature_id)
+                else {
+                    continue;
+                };
+                attributes.push(("subject", "Signature".to_string()));
+                attributes.push((FONT_FAMILY_ATTRIBUTE, sig.font_family.clone()));
+                attributes.push((COLOR_ATTRIBUTE, sig.color.clone()));
+                annots.push_str(&element(
+                    "stamp",
+                    &attributes,
+                    &[("contents", sig.name.clone())],
+                ));
+            }
+        }
+    }
+
+    let file_name = Path::new(pdf_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ids = match file_ids(doc).as_slice() {
+        [original, modified, ..] => format!(
+            "  <ids original=\"{}\" modified=\"{}\"/>\n",
+            original, modified
+        ),
+        _ => String::new(),
+    };
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <xfdf xmlns=\"{}\" xmlns:office-tools=\"{}\" xml:space=\"preserve\">\n\
+         \x20 <annots>\n{}  </annots>\n\
+         \x20 <f href=\"{}\"/>\n{}</xfdf>\n",
+        XFDF_NAMESPACE,
+        STYLE_NAMESPACE,
+        annots,
+        escape(file_name.as_str()),
+        ids
+    );
+    write_atomic(Path::new(output_path), xml.as_bytes())
+}
+
+/// Read the <freetext> annotations of an XFDF file, and the <stamp>s
+/// `export_xfdf` wrote signatures as, as overlays on the PDF at `pdf_path`.
+/// Annotations without a name get one made from their contents, so
+/// importing the same file twice doesn't add them twice.
+pub fn import_xfdf(path: &str, pdf_path: &str) -> Result<XfdfImport, String> {
+    let xml = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let root = parse_xml(&xml)?;
+    if root.name != "xfdf" {
+        return Err("Not an XFDF file".to_string());
+    }
+
+    let editor = PdfEditor::open(pdf_path, SaveMode::Rewrite)?;
+    let doc = &editor.doc;
+    let pages = doc.get_pages();
+    let other_document = match (
+        root.child("ids")
+            .and_then(|ids| ids.attributes.get("original")),
+        file_ids(doc).first(),
+    ) {
+        (Some(theirs), Some(ours)) => !theirs.eq_ignore_ascii_case(ours),
+        _ => false,
+    };
+
+    let mut imported = Vec::new();
+    let mut skipped = 0;
+    for annot in root.child("annots").map_or(&[][..], |a| &a.children) {
+        let page_number = annot
+            .attributes
+            .get("page")
+            .and_then(|p| p.trim().parse::<u32>().ok())
+            .map(|p| p + 1);
+        let page_id = page_number.and_then(|n| pages.get(&n).copied());
+        let rect = annot.attributes.get("rect").and_then(|r| parse_rect(r));
+        let (Some(page_number), Some(page_id), Some(rect)) = (page_number, page_id, rect) else {
+            skipped += 1;
+            continue;
+        };
+        let (x, y, width, height) = PageGeometry::of(doc, page_id).to_display_rect(rect);
+        let contents = annot
+            .child("contents")
+            .or_else(|| annot.child("contents-richtext"))
+            .map(Element::all_text)
+            .unwrap_or_default();
+
+        let mut annotation = Annotation {
+            id: annot
+                .attributes
+                .get("name")
+                .filter(|name| !name.is_empty())
+                .cloned()
+                .unwrap_or_else(|| made_up_id(annot, &contents)),
+            page_number,
+            annotation_type: AnnotationType::Text,
+            x,
+            y,
+            width,
+            height,
+            text_content: None,
+            font_size: None,
+            color: None,
+            signature_id: None,
+        };
+
+        let signature = match annot.name.as_str() {
+            "freetext" if !contents.is_empty() => {
+                let (size, color) = text_style(annot);
+                annotation.text_content = Some(contents);
+                annotation.font_size = Some(size);
+                annotation.color = Some(color);
+                None
+            }
+            "stamp" if annot.attributes.contains_key(FONT_FAMILY_ATTRIBUTE) => {
+                annotation.annotation_type = AnnotationType::Signature;
+                Some(ImportedSignature {
+                    name: contents,
+                    font_family: annot.attributes[FONT_FAMILY_ATTRIBUTE].clone(),
+                    color: annot
+                        .attributes
+                        .get(COLOR_ATTRIBUTE)
+                        .cloned()
+                        .unwrap_or_else(|| "#000000".to_string()),
+                })
+            }
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        imported.push(ImportedAnnotation {
+            annotation,
+            signature,
+        });
+    }
+
+    Ok(XfdfImport {
+        annotations: imported,
+        skipped,
+        other_document,
+    })
+}
+
+/// Write form values as FDF (ISO 32000-1, 12.7.7), with fields nested by
+/// the parts of their names. Values for fields the form doesn't have, or
+/// that don't suit the field, are left out.
+pub fn export_fdf(
+    pdf_path: &str,
+    values: &BTreeMap<String, FieldValue>,
+    output_path: &str,
+) -> Result<(), CommandError> {
+    let mut root = FdfNode::default();
+    for field in list_fields(pdf_path)? {
+        let Some(value) = values.get(&field.name) else {
+            continue;
+        };
+        let object = match (field.field_type, value) {
+            (FieldType::Checkbox, FieldValue::Flag(checked)) => {
+                let on = field.options.first().map_or("Yes", |o| o.value.as_str());
+                Object::Name(if *checked { on } else { "Off" }.as_bytes().to_vec())
+            }
+            (FieldType::Checkbox | FieldType::Radio, FieldValue::Text(state)) => {
+                Object::Name(state.as_bytes().to_vec())
+            }
+            (
+                FieldType::Text | FieldType::ComboBox | FieldType::ListBox,
+                FieldValue::Text(text),
+            ) => encode_text_string(text),
+            (FieldType::ListBox | FieldType::ComboBox, FieldValue::Choices(choices)) => {
+                Object::Array(choices.iter().map(|c| encode_text_string(c)).collect())
+            }
+            _ => continue,
+        };
+        let mut node = &mut root;
+        for part in field.name.split('.') {
+            node = node.kids.entry(part.to_string()).or_default();
+        }
+        node.value = Some(object);
+    }
+
+    let file_name = Path::new(pdf_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut fdf = Document::with_version("1.2");
+    let catalog_id = fdf.add_object(dictionary! {
+        "FDF" => dictionary! {
+            "F" => encode_text_string(&file_name),
+            "Fields" => root.kids_array(),
+        },
+    });
+    fdf.trailer.set("Root", catalog_id);
+    // FDF readers expect a classic cross-reference table and trailer.
+    fdf.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+
+    let mut bytes = Vec::new();
+    fdf.save_to(&mut bytes)
+        .map_err(|e| format!("Failed to write FDF: {}", e))?;
+    // Same layout as a PDF apart from the header.
+    if bytes.starts_with(b"%PDF-") {
+        bytes[..5].copy_from_slice(b"%FDF-");
+    }
+    write_atomic(Path::new(output_path), &bytes)
+}
+
+/// Read the values of an FDF file for the fields the form of the PDF at
+/// `pdf_path` has, interpreted as `list_fields` reads them from the PDF.
+pub fn import_fdf(path: &str, pdf_path: &str) -> Result<BTreeMap<String, FieldValue>, String> {
+    let mut bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    if !bytes.starts_with(b"%FDF-") {
+        return Err("Not an FDF file".to_string());
+    }
+    bytes[..5].copy_from_slice(b"%PDF-");
+    let fdf = Document::load_mem(&bytes).map_err(|e| format!("Failed to load FDF: {}", e))?;
+    let fields = fdf
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"FDF", &fdf))
+        .and_then(Object::as_dict)
+        .and_then(|fdf_dict| fdf_dict.get_deref(b"Fields", &fdf))
+        .and_then(Object::as_array)
+        .map_err(|e| format!("Failed to read FDF fields: {}", e))?;
+
+    let mut fdf_values = Vec::new();
+    collect_fdf_values(&fdf, fields, "", &mut fdf_values);
+
+    let form: BTreeMap<String, FieldType> = list_fields(pdf_path)?
+        .into_iter()
+        .map(|field| (field.name, field.field_type))
+        .collect();
+    Ok(fdf_values
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = field_value(Some(value), *form.get(&name)?)?;
+            Some((name, value))
+        })
+        .collect())
+}
+
+/// A field in the /Fields tree of an FDF file being written.
+#[derive(Default)]
+struct FdfNode {
+    value: Option<Object>,
+    kids: BTreeMap<String, FdfNode>,
+}
+
+impl FdfNode {
+    fn kids_array(&self) -> Vec<Object> {
+        self.kids
+            .iter()
+            .map(|(name, kid)| {
+                let mut dict = dictionary! { "T" => encode_text_string(name) };
+                if let Some(value) = &kid.value {
+                    dict.set("V", value.clone());
+                }
+                if !kid.kids.is_empty() {
+                    dict.set("Kids", kid.kids_array());
+                }
+                Object::Dictionary(dict)
+            })
+            .collect()
+    }
+}
+
+/// Walk an FDF /Fields (or /Kids) array, collecting each field's fully
+/// qualified name and /V.
+fn collect_fdf_values<'a>(
+    fdf: &'a Document,
+    fields: &'a [Object],
+    prefix: &str,
+    out: &mut Vec<(String, &'a Object)>,
+) {
+    for field in fields {
+        let Ok(dict) = fdf.dereference(field).and_then(|(_, o)| o.as_dict()) else {
+            continue;
+        };
+        let partial = dict
+            .get_deref(b"T", fdf)
+            .and_then(Object::as_str)
+            .map(super::pdf::decode_text_string)
+            .unwrap_or_default();
+        let name = match (prefix.is_empty(), partial.is_empty()) {
+            (true, _) => partial,
+            (false, true) => prefix.to_string(),
+            (false, false) => format!("{}.{}", prefix, partial),
+        };
+        if let Ok(value) = dict.get_deref(b"V", fdf) {
+            out.push((name.clone(), value));
+        }
+        if let Ok(kids) = dict.get_deref(b"Kids", fdf).and_then(Object::as_array) {
+            collect_fdf_values(fdf, kids, &name, out);
+        }
+    }
+}
+
+/// The document's /ID strings as hex, as XFDF's <ids> holds them.
+fn file_ids(doc: &Document) -> Vec<String> {
+    doc.trailer
+        .get(b"ID")
+        .and_then(Object::as_array)
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| id.as_str().ok())
+                .map(|bytes| bytes.iter().map(|b| format!("{:02X}", b)).collect())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn format_rect(rect: [f32; 4]) -> String {
+    rect.iter()
+        .map(|v| format!("{:.2}", v))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_rect(rect: &str) -> Option<[f32; 4]> {
+    let values: Vec<f32> = rect
+        .split(',')
+        .map(|v| v.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match values.as_slice() {
+        [a, b, c, d] => Some([a.min(*c), b.min(*d), a.max(*c), b.max(*d)]),
+        _ => None,
+    }
+}
+
+/// An XFDF annotation element with its attributes and text children.
+fn element(name: &str, attributes: &[(&str, String)], children: &[(&str, String)]) -> String {
+    let mut xml = format!("    <{}", name);
+    for (key, value) in attributes {
+        xml.push_str(&format!(" {}=\"{}\"", key, escape(value.as_str())));
+    }
+    xml.push_str(">\n");
+    for (child, text) in children {
+        xml.push_str(&format!(
+            "      <{0}>{1}</{0}>\n",
+            child,
+            escape(text.as_str())
+        ));
+    }
+    xml.push_str(&format!("    </{}>\n", name));
+    xml
+}
+
+/// Font size and text color of a <freetext>, from its default appearance
+/// or else its default style (CSS such as `font: Helvetica 12pt; color:
+/// #FF0000`).
+fn text_style(annot: &Element) -> (f32, String) {
+    if let Some(da) = annot.child("defaultappearance") {
+        let da = parse_default_appearance(da.text.as_bytes());
+        let size = if da.font_size > 0.0 {
+            da.font_size
+        } else {
+            TEXT_DEFAULT_FONT_SIZE
+        };
+        let color = color_to_hex(da.color.first().map_or(&[][..], |op| &op.operands));
+        return (size, color);
+    }
+
+    let mut size = TEXT_DEFAULT_FONT_SIZE;
+    let mut color = "#000000".to_string();
+    let style = annot
+        .child("defaultstyle")
+        .map_or("", |style| style.text.as_str());
+    for declaration in style.split(';') {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        match property.trim() {
+            "color" if value.trim().starts_with('#') => color = value.trim().to_lowercase(),
+            "font" | "font-size" => {
+                if let Some(pt) = value
+                    .split_whitespace()
+                    .find_map(|token| token.strip_suffix("pt")?.parse().ok())
+                {
+                    size = pt;
+                }
+            }
+            _ => {}
+        }
+    }
+    (size, color)
+}
+
+/// An id for an annotation without a name, the same each time it's read.
+fn made_up_id(annot: &Element, contents: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        annot.name.as_str(),
+        annot.attributes.get("page").map_or("", String::as_str),
+        annot.attributes.get("rect").map_or("", String::as_str),
+        contents,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("xfdf_{}", hex)
+}
+
+/// An XML element, with its text content unescaped.
+#[derive(Debug, Default)]
+struct Element {
+    /// Local name, without any namespace prefix.
+    name: String,
+    /// Attributes keyed by their name as written (with prefix).
+    attributes: BTreeMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn from_start(start: &BytesStart) -> Result<Self, String> {
+        let mut attributes = BTreeMap::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(|e| format!("Invalid XFDF: {}", e))?;
+            let value = attribute
+                .unescape_value()
+                .map_err(|e| format!("Invalid XFDF: {}", e))?;
+            attributes.insert(
+                String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                value.into_owned(),
+            );
+        }
+        Ok(Element {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            children: Vec::new(),
+            text: String::new(),
+        })
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// The text of the element and its descendants, with a line break
+    /// between paragraphs (for <contents-richtext>).
+    fn all_text(&self) -> String {
+        let mut text = self.text.clone();
+        for child in &self.children {
+            if child.name == "p" && !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&child.all_text());
+        }
+        text
+    }
+}
+
+/// Parse an XML document into its root element.
+fn parse_xml(xml: &str) -> Result<Element, String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("Invalid XFDF: {}", e);
+    let mut reader = Reader::from_str(xml);
+    // Text is kept escaped until its element ends, then unescaped whole, so
+    // entity references (reported as separate events) land in place.
+    let mut stack = vec![Element::default()];
+    loop {
+        match reader.read_event().map_err(|e| invalid(&e))? {
+            Event::Start(start) => stack.push(Element::from_start(&start)?),
+            Event::Empty(start) => {
+                let element = Element::from_start(&start)?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(element);
+                }
+            }
+            Event::Text(text) => {
+                if let Some(current) = stack.last_mut() {
+                    current
+                        .text
+                        .push_str(&text.decode().map_err(|e| invalid(&e))?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(current) = stack.last_mut() {
+                    current
+                        .text
+                        .push_str(&escape(data.decode().map_err(|e| invalid(&e))?));
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let Some(current) = stack.last_mut() {
+                    let name = reference.decode().map_err(|e| invalid(&e))?;
+                    current.text.push_str(&format!("&{};", name));
+                }
+            }
+            Event::End(_) => {
+                let mut element = stack.pop().ok_or("Invalid XFDF: unbalanced tags")?;
+                element.text = unescape(&element.text)
+                    .map_err(|e| invalid(&e))?
+                    .into_owned();
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Err("Invalid XFDF: unbalanced tags".to_string()),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    stack
+        .pop()
+        .filter(|_| stack.is_empty())
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| "Invalid XFDF: no root element".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{ObjectId, Stream, StringFormat};
+
+    use super::*;
+    use crate::commands::test_support::{path_in, save, temp_dir, text_pdf};
+
+    fn with_id(mut doc: Document, id: &[u8]) -> Document {
+        let id = Object::String(id.to_vec(), StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+        doc
+    }
+
+    /// A page with a text field `person.name` and a checkbox `agree`.
+    fn form_pdf() -> Document {
+        let mut doc = text_pdf(&[&["Form"]]);
+        let page_id = doc.page_iter().next().unwrap();
+        let parent_id = doc.new_object_id();
+        let name_id = doc.add_object(dictionary! {
+            "FT" => "Tx",
+            "T" => Object::string_literal("name"),
+            "Parent" => parent_id,
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Rect" => vec![72.into(), 600.into(), 272.into(), 620.into()],
+            "P" => page_id,
+        });
+        doc.objects.insert(
+            parent_id,
+            Object::Dictionary(dictionary! {
+                "T" => Object::string_literal("person"),
+                "Kids" => vec![name_id.into()],
+            }),
+        );
+        let on: ObjectId = doc.add_object(Stream::new(dictionary! {}, b"0 0 m".to_vec()));
+        let off: ObjectId = doc.add_object(Stream::new(dictionary! {}, Vec::new()));
+        let agree_id = doc.add_object(dictionary! {
+            "FT" => "Btn",
+            "T" => Object::string_literal("agree"),
+            "V" => "Off",
+            "AS" => "Off",
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Rect" => vec![72.into(), 560.into(), 86.into(), 574.into()],
+            "P" => page_id,
+            "AP" => dictionary! { "N" => dictionary! { "Yes" => on, "Off" => off } },
+        });
+        doc.get_dictionary_mut(page_id)
+            .unwrap()
+            .set("Annots", vec![name_id.into(), agree_id.into()]);
+        let acro_form = doc.add_object(dictionary! {
+            "Fields" => vec![parent_id.into(), agree_id.into()],
+        });
+        doc.catalog_mut().unwrap().set("AcroForm", acro_form);
+        doc
+    }
+
+    fn text_annotation() -> Annotation {
+        Annotation {
+            id: "note-1".to_string(),
+            page_number: 1,
+            annotation_type: AnnotationType::Text,
+            x: 100.0,
+            y: 120.0,
+            width: 200.0,
+            height: 30.0,
+            text_content: Some("Fish & <chips>".to_string()),
+            font_size: Some(14.0),
+            color: Some("#ff0000".to_string()),
+            signature_id: None,
+        }
+    }
+
+    #[test]
+    fn xfdf_round_trip() {
+        let dir = temp_dir("xfdf");
+        let pdf = save(
+            &mut with_id(text_pdf(&[&["Page"]]), b"0123"),
+            &dir,
+            "doc.pdf",
+        );
+        let signature = Annotation {
+            id: "sig-1".to_string(),
+            annotation_type: AnnotationType::Signature,
+            text_content: None,
+            font_size: None,
+            color: None,
+            signature_id: Some(7),
+            ..text_annotation()
+        };
+        let style = SignatureStyle {
+            id: 7,
+            name: "Ada Lovelace".to_string(),
+            font_family: "Dancing Script".to_string(),
+            color: "#0000ff".to_string(),
+        };
+        let xfdf = path_in(&dir, "notes.xfdf");
+        export_xfdf(&pdf, &[text_annotation(), signature], &[style], &xfdf).unwrap();
+
+        let imported = import_xfdf(&xfdf, &pdf).unwrap();
+        assert_eq!(imported.skipped, 0);
+        assert!(!imported.other_document);
+        assert_eq!(imported.annotations.len(), 2);
+
+        let text = &imported.annotations[0];
+        assert_eq!(text.annotation.id, "note-1");
+        assert_eq!(text.annotation.annotation_type, AnnotationType::Text);
+        assert_eq!(
+            text.annotation.text_content.as_deref(),
+            Some("Fish & <chips>")
+        );
+        assert_eq!(text.annotation.font_size, Some(14.0));
+        assert_eq!(text.annotation.color.as_deref(), Some("#ff0000"));
+        let original = text_annotation();
+        for (a, b) in [
+            (text.annotation.x, original.x),
+            (text.annotation.y, original.y),
+            (text.annotation.width, original.width),
+            (text.annotation.height, original.height),
+        ] {
+            assert!((a - b).abs() < 0.01, "{} != {}", a, b);
+        }
+
+        let sig = &imported.annotations[1];
+        assert_eq!(sig.annotation.annotation_type, AnnotationType::Signature);
+        let imported_style = sig.signature.as_ref().unwrap();
+        assert_eq!(imported_style.name, "Ada Lovelace");
+        assert_eq!(imported_style.font_family, "Dancing Script");
+        assert_eq!(imported_style.color, "#0000ff");
+    }
+
+    #[test]
+    fn xfdf_from_another_document_is_flagged() {
+        let dir = temp_dir("xfdf-other");
+        let first = save(&mut with_id(text_pdf(&[&["A"]]), b"0123"), &dir, "a.pdf");
+        let second = save(&mut with_id(text_pdf(&[&["B"]]), b"4567"), &dir, "b.pdf");
+        let xfdf = path_in(&dir, "notes.xfdf");
+        export_xfdf(&first, &[text_annotation()], &[], &xfdf).unwrap();
+
+        let imported = import_xfdf(&xfdf, &second).unwrap();
+        assert!(imported.other_document);
+        assert_eq!(imported.annotations.len(), 1);
+    }
+
+    #[test]
+    fn xfdf_markup_without_an_overlay_is_skipped() {
+        let dir = temp_dir("xfdf-skip");
+        let pdf = save(&mut text_pdf(&[&["Page"]]), &dir, "doc.pdf");
+        let xfdf = path_in(&dir, "notes.xfdf");
+        fs::write(
+            &xfdf,
+            "<?xml version=\"1.0\"?>\n\
+             <xfdf xmlns=\"http://ns.adobe.com/xfdf/\"><annots>\
+             <ink page=\"0\" rect=\"10,10,50,50\"><inklist><gesture>10,10;50,50</gesture></inklist></ink>\
+             <freetext page=\"0\" rect=\"10,10,110,40\"><contents>Kept</contents></freetext>\
+             </annots></xfdf>",
+        )
+        .unwrap();
+
+        let imported = import_xfdf(&xfdf, &pdf).unwrap();
+        assert_eq!(imported.skipped, 1);
+        assert_eq!(imported.annotations.len(), 1);
+        assert_eq!(
+            imported.annotations[0].annotation.text_content.as_deref(),
+            Some("Kept")
+        );
+    }
+
+    #[test]
+    fn fdf_round_trip() {
+        let dir = temp_dir("fdf");
+        let pdf = save(&mut form_pdf(), &dir, "form.pdf");
+        let values = BTreeMap::from([
+            (
+                "person.name".to_string(),
+                FieldValue::Text("Ada".to_string()),
+            ),
+            ("agree".to_string(), FieldValue::Flag(true)),
+            (
+                "missing".to_string(),
+                FieldValue::Text("dropped".to_string()),
+            ),
+        ]);
+        let fdf = path_in(&dir, "data.fdf");
+        export_fdf(&pdf, &values, &fdf).unwrap();
+        assert!(fs::read(&fdf).unwrap().starts_with(b"%FDF-"));
+
+        let imported = import_fdf(&fdf, &pdf).unwrap();
+        assert_eq!(
+            imported,
+            BTreeMap::from([
+                (
+                    "person.name".to_string(),
+                    FieldValue::Text("Ada".to_string())
+                ),
+                ("agree".to_string(), FieldValue::Flag(true)),
+            ])
+        );
+    }
+
+    #[test]
+    fn non_fdf_files_are_refused() {
+        let dir = temp_dir("fdf-refused");
+        let pdf = save(&mut form_pdf(), &dir, "form.pdf");
+        assert_eq!(import_fdf(&pdf, &pdf).unwrap_err(), "Not an FDF file");
+    }
+}
@@ -0,0 +1,1475 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document, Encoding, Object, ObjectId, Stream, StringFormat};
+use serde::{Deserialize, Serialize};
+
+use super::documents::overwrite;
+use super::error::CommandError;
+use super::pdf::{
+    add_page_resources, append_page_content, decode_text_string, encode_text_string,
+    field_attribute, form_fields, widget_page, FieldNode, PageGeometry, PdfEditor, PdfFont,
+    SaveMode,
+};
+
+// Field flags (ISO 32000-1, tables 221, 226, 228 and 230).
+const FF_READ_ONLY: i64 = 1;
+const FF_REQUIRED: i64 = 1 << 1;
+const FF_MULTILINE: i64 = 1 << 12;
+const FF_PASSWORD: i64 = 1 << 13;
+const FF_RADIO: i64 = 1 << 15;
+const FF_PUSH_BUTTON: i64 = 1 << 16;
+const FF_COMBO: i64 = 1 << 17;
+const FF_MULTI_SELECT: i64 = 1 << 21;
+
+/// Annotation flag for widgets that must not be drawn.
+const ANNOT_HIDDEN: i64 = 1 << 1;
+
+/// Font size used for auto-sized (size 0) multiline fields and list boxes.
+const AUTO_FONT_SIZE: f32 = 12.0;
+
+/// Inset between a field's border and its text, in points.
+const FIELD_PADDING: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldType {
+    Text,
+    Checkbox,
+    Radio,
+    ComboBox,
+    ListBox,
+    PushButton,
+    Signature,
+}
+
+/// A field's value: a checkbox state, a text/choice/radio value, or the
+/// selection of a multi-select list box.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    Flag(bool),
+    Text(String),
+    Choices(Vec<String>),
+}
+
+/// An entry of a choice field, or an on-state of a checkbox/radio button.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldOption {
+    /// The value stored in the field when this option is chosen.
+    pub value: String,
+    /// The text shown to the user.
+    pub label: String,
+}
+
+/// A widget annotation showing a field on a page. The rectangle is in the
+/// same displayed-page coordinates as annotations (points from the top-left
+/// at pdf.js scale=1).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldWidget {
+    pub page_number: Option<u32>,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The on-state of a checkbox or radio button widget.
+    pub export_value: Option<String>,
+}
+
+/// An AcroForm field with its current value.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormField {
+    /// Fully qualified name, e.g. `applicant.address.city`.
+    pub name: String,
+    pub field_type: FieldType,
+    pub value: Option<FieldValue>,
+    pub options: Vec<FieldOption>,
+    pub widgets: Vec<FieldWidget>,
+    pub read_only: bool,
+    pub required: bool,
+    pub multiline: bool,
+    pub max_length: Option<i64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillOptions {
+    /// Draw new appearance streams for the filled text and choice fields.
+    /// When off, the form is marked NeedAppearances so viewers redraw them.
+    #[serde(default = "default_true")]
+    pub regenerate_appearances: bool,
+    /// Burn every field into the page content and remove the form, so the
+    /// output is no longer fillable. Signature fields are kept.
+    #[serde(default)]
+    pub flatten: bool,
+    #[serde(default)]
+    pub mode: SaveMode,
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        FillOptions {
+            regenerate_appearances: true,
+            flatten: false,
+            mode: SaveMode::default(),
+        }
+    }
+}
+
+/// List the AcroForm fields of the PDF at `path`.
+#[tauri::command]
+pub async fn list_form_fields(path: String) -> Result<Vec<FormField>, String> {
+    list_fields(&path)
+}
+
+/// Write `values` (keyed by fully qualified field name) into the form of
+/// the PDF at `source_path` and save it to `output_path`.
+#[tauri::command]
+pub async fn fill_form_fields(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    values: BTreeMap<String, FieldValue>,
+    options: Option<FillOptions>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        fill(
+            &source_path,
+            &output_path,
+            &values,
+            &options.unwrap_or_default(),
+        )
+    })
+}
+
+pub fn list_fields(path: &str) -> Result<Vec<FormField>, String> {
+    let editor = PdfEditor::open(path, SaveMode::Rewrite)?;
+    let doc = &editor.doc;
+    let page_numbers: BTreeMap<ObjectId, u32> =
+        doc.get_pages().into_iter().map(|(n, id)| (id, n)).collect();
+
+    let mut fields = Vec::new();
+    for node in form_fields(doc) {
+        let Some(field_type) = field_type(doc, node.id) else {
+            continue;
+        };
+        let flags = field_flags(doc, node.id);
+
+        let widgets = node
+            .widgets
+            .iter()
+            .map(|&widget| {
+                let page_number = widget_page(doc, widget, &page_numbers);
+                let (x, y, width, height) = page_number
+                    .and_then(|n| doc.get_pages().get(&n).copied())
+                    .map(|page| display_rect(doc, page, widget))
+                    .unwrap_or_default();
+                FieldWidget {
+                    page_number,
+                    x,
+                    y,
+                    width,
+                    height,
+                    export_value: on_state(doc, widget),
+                }
+            })
+            .collect();
+
+        let options = match field_type {
+            FieldType::ComboBox | FieldType::ListBox => choice_options(doc, node.id),
+            FieldType::Checkbox | FieldType::Radio => {
+                let mut seen = BTreeSet::new();
+                node.widgets
+                    .iter()
+                    .filter_map(|&w| on_state(doc, w))
+                    .filter(|state| seen.insert(state.clone()))
+                    .map(|state| FieldOption {
+                        value: state.clone(),
+                        label: state,
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        fields.push(FormField {
+            name: node.name.clone(),
+            field_type,
+            value: read_value(doc, node.id, field_type),
+            options,
+            widgets,
+            read_only: flags & FF_READ_ONLY != 0,
+            required: flags & FF_REQUIRED != 0,
+            multiline: field_type == FieldType::Text && flags & FF_MULTILINE != 0,
+            max_length: field_attribute(doc, node.id, b"MaxLen").and_then(|o| o.as_i64().ok()),
+        });
+    }
+    Ok(fields)
+}
+
+/// Fill form fields and save the result.
+///
+/// Values go into each field's /V, and checkbox/radio widgets get a matching
+/// /AS. Filled text and choice fields get a fresh appearance stream built from
+/// the field's default appearance (/DA) and the form's default resources, so
+/// the values show up in viewers that don't regenerate appearances. The
+/// AcroForm itself is left in place, so the output stays fillable unless
+/// `flatten` is set.
+///
+/// ## Known limitations
+/// - Appearances are drawn with simple fonts from /DR (or Helvetica); text
+///   outside WinAnsiEncoding is dropped from the appearance, though /V keeps
+///   it. Comb fields and rich text are drawn as plain text.
+pub fn fill(
+    source_path: &str,
+    output_path: &str,
+    values: &BTreeMap<String, FieldValue>,
+    options: &FillOptions,
+) -> Result<(), CommandError> {
+    let mut editor = PdfEditor::open(source_path, options.mode)?;
+    fill_into(&mut editor.doc, values, options)?;
+    editor.save(output_path)
+}
+
+/// Fill and optionally flatten the form of an open document.
+pub(crate) fn fill_into(
+    doc: &mut Document,
+    values: &BTreeMap<String, FieldValue>,
+    options: &FillOptions,
+) -> Result<(), String> {
+    let fields = form_fields(doc);
+    let by_name: BTreeMap<&str, &FieldNode> = fields.iter().map(|f| (f.name.as_str(), f)).collect();
+
+    let mut filled = Vec::new();
+    for (name, value) in values {
+        let node = by_name
+            .get(name.as_str())
+            .ok_or_else(|| format!("No form field named \"{}\"", name))?;
+        set_value(doc, node, value)?;
+        filled.push(*node);
+    }
+
+    let mut appearances = AppearanceBuilder::default();
+    if options.flatten {
+        // Every text and choice field is burnt in, so make sure each one's
+        // appearance shows its current value.
+        for node in &fields {
+            if read_value(
+                doc,
+                node.id,
+                field_type(doc, node.id).unwrap_or(FieldType::PushButton),
+            )
+            .is_some()
+            {
+                appearances.regenerate(doc, node)?;
+            }
+        }
+        flatten_fields(doc, &fields)
+    } else if options.regenerate_appearances {
+        for node in filled {
+            appearances.regenerate(doc, node)?;
+        }
+        Ok(())
+    } else {
+        if !filled.is_empty() {
+            set_need_appearances(doc)?;
+        }
+        Ok(())
+    }
+}
+
+fn field_flags(doc: &Document, field_id: ObjectId) -> i64 {
+    field_attribute(doc, field_id, b"Ff")
+        .and_then(|o| o.as_i64().ok())
+        .unwrap_or(0)
+}
+
+fn field_type(doc: &Document, field_id: ObjectId) -> Option<FieldType> {
+    let flags = field_flags(doc, field_id);
+    let field_type = match field_attribute(doc, field_id, b"FT")?.as_name().ok()? {
+        b"Tx" => FieldType::Text,
+        b"Btn" if flags & FF_PUSH_BUTTON != 0 => FieldType::PushButton,
+        b"Btn" if flags & FF_RADIO != 0 => FieldType::Radio,
+        b"Btn" => FieldType::Checkbox,
+        b"Ch" if flags & FF_COMBO != 0 => FieldType::ComboBox,
+        b"Ch" => FieldType::ListBox,
+        b"Sig" => FieldType::Signature,
+        _ => return None,
+    };
+    Some(field_type)
+}
+
+/// A string or name object as text.
+fn object_text(object: &Object) -> Option<String> {
+    match object {
+        Object::String(bytes, _) => Some(decode_text_string(bytes)),
+        Object::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+        _ => None,
+    }
+}
+
+fn read_value(doc: &Document, field_id: ObjectId, field_type: FieldType) -> Option<FieldValue> {
+    field_value(field_attribute(doc, field_id, b"V"), field_type)
+}
+
+/// Interpret a /V entry as the value of a field of `field_type`.
+pub(crate) fn field_value(value: Option<&Object>, field_type: FieldType) -> Option<FieldValue> {
+    match field_type {
+        FieldType::Text | FieldType::ComboBox => value.and_then(object_text).map(FieldValue::Text),
+        FieldType::Checkbox => Some(FieldValue::Flag(
+            value
+                .and_then(|v| v.as_name().ok())
+                .is_some_and(|name| name != b"Off"),
+        )),
+        FieldType::Radio => value
+            .and_then(|v| v.as_name().ok())
+            .filter(|name| *name != b"Off")
+            .map(|name| FieldValue::Text(String::from_utf8_lossy(name).into_owned())),
+        FieldType::ListBox => match value? {
+            Object::Array(items) => Some(FieldValue::Choices(
+                items.iter().filter_map(object_text).collect(),
+            )),
+            other => object_text(other).map(|v| FieldValue::Choices(vec![v])),
+        },
+        FieldType::PushButton | FieldType::Signature => None,
+    }
+}
+
+/// The /Opt entries of a choice field.
+fn choice_options(doc: &Document, field_id: ObjectId) -> Vec<FieldOption> {
+    let Some(Object::Array(items)) = field_attribute(doc, field_id, b"Opt") else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| match doc.dereference(item).ok()?.1 {
+            Object::Array(pair) if pair.len() == 2 => Some(FieldOption {
+                value: object_text(&pair[0])?,
+                label: object_text(&pair[1])?,
+            }),
+            other => {
+                let text = object_text(other)?;
+                Some(FieldOption {
+                    value: text.clone(),
+                    label: text,
+                })
+            }
+        })
+        .collect()
+}
+
+/// The widget's normal appearance dictionary (/AP /N), if it has states.
+fn appearance_states(doc: &Document, widget: ObjectId) -> Option<&Dictionary> {
+    let ap = doc
+        .get_dictionary(widget)
+        .ok()?
+        .get_deref(b"AP", doc)
+        .ok()?
+        .as_dict()
+        .ok()?;
+    ap.get_deref(b"N", doc).ok()?.as_dict().ok()
+}
+
+/// The name of a checkbox/radio widget's "on" appearance state.
+fn on_state(doc: &Document, widget: ObjectId) -> Option<String> {
+    appearance_states(doc, widget)?
+        .iter()
+        .map(|(name, _)| name)
+        .find(|name| name.as_slice() != b"Off")
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+}
+
+fn set_value(doc: &mut Document, node: &FieldNode, value: &FieldValue) -> Result<(), String> {
+    let field_type = field_type(doc, node.id)
+        .ok_or_else(|| format!("Field \"{}\" has an unknown type", node.name))?;
+    let flags = field_flags(doc, node.id);
+    if flags & FF_READ_ONLY != 0 {
+        return Err(format!("Field \"{}\" is read-only", node.name));
+    }
+    let mismatch = || format!("Invalid value for field \"{}\"", node.name);
+
+    let mut selected_state: Option<Vec<u8>> = None;
+    let new_value = match (field_type, value) {
+        (FieldType::Text, FieldValue::Text(text)) => {
+            let text = match field_attribute(doc, node.id, b"MaxLen").and_then(|o| o.as_i64().ok())
+            {
+                Some(max) if max >= 0 => text.chars().take(max as usize).collect(),
+                _ => text.clone(),
+            };
+            encode_text_string(&text)
+        }
+        (FieldType::ComboBox, FieldValue::Text(text)) => {
+            // Accept an option's label as well as its export value.
+            let options = choice_options(doc, node.id);
+            let export = options
+                .iter()
+                .find(|o| &o.value == text)
+                .or_else(|| options.iter().find(|o| &o.label == text))
+                .map_or(text.as_str(), |o| o.value.as_str());
+            encode_text_string(export)
+        }
+        (FieldType::ListBox, FieldValue::Text(text)) => encode_text_string(text),
+        (FieldType::ListBox, FieldValue::Choices(choices)) => {
+            if choices.len() > 1 && flags & FF_MULTI_SELECT == 0 {
+                return Err(format!(
+                    "Field \"{}\" does not allow multiple selections",
+                    node.name
+                ));
+            }
+            match choices.as_slice() {
+                [single] => encode_text_string(single),
+                _ => Object::Array(choices.iter().map(|c| encode_text_string(c)).collect()),
+            }
+        }
+        (FieldType::Checkbox, FieldValue::Flag(checked)) => {
+            let on = node
+                .widgets
+                .iter()
+                .find_map(|&w| on_state(doc, w))
+                .unwrap_or_else(|| "Yes".to_string());
+            let state = if *checked {
+                on.into_bytes()
+            } else {
+                b"Off".to_vec()
+            };
+            selected_state = Some(state.clone());
+            Object::Name(state)
+        }
+        (FieldType::Checkbox | FieldType::Radio, FieldValue::Text(state)) => {
+            let states: BTreeSet<String> = node
+                .widgets
+                .iter()
+                .filter_map(|&w| on_state(doc, w))
+                .collect();
+            if state != "Off" && !states.contains(state) {
+                return Err(format!(
+                    "Invalid value \"{}\" for field \"{}\" (expected one of: {})",
+                    state,
+                    node.name,
+                    states.into_iter().collect::<Vec<_>>().join(", ")
+                ));
+            }
+            selected_state = Some(state.clone().into_bytes());
+            Object::Name(state.clone().into_bytes())
+        }
+        (FieldType::Radio, FieldValue::Flag(false)) => {
+            selected_state = Some(b"Off".to_vec());
+            Object::Name(b"Off".to_vec())
+        }
+        (FieldType::PushButton | FieldType::Signature, _) => {
+            return Err(format!("Field \"{}\" cannot be filled", node.name));
+        }
+        _ => return Err(mismatch()),
+    };
+
+    let field = doc
+        .get_dictionary_mut(node.id)
+        .map_err(|e| format!("Failed to update field: {}", e))?;
+    field.set("V", new_value);
+    if field_type == FieldType::ListBox {
+        // /I caches the selected indices; drop it rather than let it go stale.
+        field.remove(b"I");
+    }
+
+    // Point each button widget at the appearance for the new state.
+    if let Some(state) = selected_state {
+        for &widget in &node.widgets {
+            let has_state = appearance_states(doc, widget).is_some_and(|s| s.has(&state));
+            let appearance = if has_state {
+                state.clone()
+            } else {
+                b"Off".to_vec()
+            };
+            doc.get_dictionary_mut(widget)
+                .map_err(|e| format!("Failed to update field: {}", e))?
+                .set("AS", Object::Name(appearance));
+        }
+    }
+    Ok(())
+}
+
+/// Set /NeedAppearances so viewers redraw field appearances from /V.
+fn set_need_appearances(doc: &mut Document) -> Result<(), String> {
+    let catalog_id = doc
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|e| format!("Failed to read document catalog: {}", e))?;
+    let form_ref = doc
+        .get_dictionary(catalog_id)
+        .and_then(|c| c.get(b"AcroForm"))
+        .and_then(Object::as_reference)
+        .ok();
+    let form = match form_ref {
+        Some(id) => doc.get_dictionary_mut(id),
+        None => doc
+            .get_dictionary_mut(catalog_id)
+            .and_then(|c| c.get_mut(b"AcroForm"))
+            .and_then(Object::as_dict_mut),
+    }
+    .map_err(|e| format!("Failed to update form: {}", e))?;
+    form.set("NeedAppearances", true);
+    Ok(())
+}
+
+/// Parsed default appearance string (/DA), e.g. `/Helv 0 Tf 0 g`.
+pub(crate) struct DefaultAppearance {
+    pub font_name: Vec<u8>,
+    /// 0 means auto-size.
+    pub font_size: f32,
+    pub color: Vec<Operation>,
+}
+
+pub(crate) fn parse_default_appearance(da: &[u8]) -> DefaultAppearance {
+    let mut parsed = DefaultAppearance {
+        font_name: b"Helv".to_vec(),
+        font_size: 0.0,
+        color: vec![Operation::new("g", vec![0.into()])],
+    };
+    let Ok(content) = Content::decode(da) else {
+        return parsed;
+    };
+    for op in content.operations {
+        match op.operator.as_str() {
+            "Tf" if op.operands.len() == 2 => {
+                if let Ok(name) = op.operands[0].as_name() {
+                    parsed.font_name = name.to_vec();
+                }
+                parsed.font_size = op.operands[1].as_float().unwrap_or(0.0);
+            }
+            "g" | "rg" | "k" => parsed.color = vec![op],
+            _ => {}
+        }
+    }
+    parsed
+}
+
+/// A font usable for drawing a field appearance, with what's needed to
+/// measure and encode text.
+struct AppearanceFont {
+    name: Vec<u8>,
+    id: ObjectId,
+    first_char: i64,
+    widths: Vec<f32>,
+    ascent: f32,
+    descent: f32,
+}
+
+impl AppearanceFont {
+    fn from_dictionary(doc: &Document, name: &[u8], id: ObjectId) -> Option<Self> {
+        let font = doc.get_dictionary(id).ok()?;
+        // Composite fonts need their CMap to encode text; fall back to
+        // Helvetica for those.
+        if font.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Type0") {
+            return None;
+        }
+        let widths = font
+            .get_deref(b"Widths", doc)
+            .and_then(Object::as_array)
+            .map(|w| w.iter().map(|v| v.as_float().unwrap_or(0.0)).collect())
+            .unwrap_or_default();
+        let descriptor = font
+            .get_deref(b"FontDescriptor", doc)
+            .and_then(Object::as_dict)
+            .ok();
+        let metric = |key: &[u8], default: f32| {
+            descriptor
+                .and_then(|d| d.get(key).ok())
+                .and_then(|v| v.as_float().ok())
+                .filter(|v| *v != 0.0)
+                .unwrap_or(default)
+        };
+        Some(AppearanceFont {
+            name: name.to_vec(),
+            id,
+            first_char: font.get(b"FirstChar").and_then(Object::as_i64).unwrap_or(0),
+            widths,
+            ascent: metric(b"Ascent", 718.0),
+            descent: metric(b"Descent", -207.0),
+        })
+    }
+
+    fn encode(&self, text: &str) -> Vec<u8> {
+        Document::encode_text(&Encoding::SimpleEncoding(b"WinAnsiEncoding"), text)
+    }
+
+    /// Width of `text` in points. Fonts without /Widths (the standard 14)
+    /// are estimated at half an em per character.
+    fn width(&self, text: &str, size: f32) -> f32 {
+        let units: f32 = self
+            .encode(text)
+            .iter()
+            .map(|&code| {
+                usize::try_from(code as i64 - self.first_char)
+                    .ok()
+                    .and_then(|i| self.widths.get(i).copied())
+                    .unwrap_or(500.0)
+            })
+            .sum();
+        units * size / 1000.0
+    }
+}
+
+/// Builds appearance streams for text and choice fields, sharing one
+/// Helvetica fallback font per document.
+#[derive(Default)]
+struct AppearanceBuilder {
+    helvetica: Option<ObjectId>,
+}
+
+impl AppearanceBuilder {
+    /// Replace the normal appearance of every widget of a text or choice
+    /// field with one showing its current value.
+    fn regenerate(&mut self, doc: &mut Document, node: &FieldNode) -> Result<(), String> {
+        let Some(field_type) = field_type(doc, node.id) else {
+            return Ok(());
+        };
+        if !matches!(
+            field_type,
+            FieldType::Text | FieldType::ComboBox | FieldType::ListBox
+        ) {
+            return Ok(());
+        }
+
+        for &widget in &node.widgets {
+            let appearance = self.build(doc, node, field_type, widget)?;
+            doc.get_dictionary_mut(widget)
+                .map_err(|e| format!("Failed to update field: {}", e))?
+                .set("AP", dictionary! { "N" => appearance });
+        }
+        Ok(())
+    }
+
+    fn build(
+        &mut self,
+        doc: &mut Document,
+        node: &FieldNode,
+        field_type: FieldType,
+        widget: ObjectId,
+    ) -> Result<ObjectId, String> {
+        let flags = field_flags(doc, node.id);
+        let da = field_attribute(doc, node.id, b"DA")
+            .or_else(|| acro_form(doc).and_then(|f| f.get(b"DA").ok()))
+            .and_then(|o| o.as_str().ok())
+            .map(parse_default_appearance)
+            .unwrap_or_else(|| parse_default_appearance(b""));
+        let font = self.font(doc, &da.font_name)?;
+
+        let widget_dict = doc
+            .get_dictionary(widget)
+            .map_err(|e| format!("Failed to read field: {}", e))?;
+        let rect = rect_of(widget_dict);
+        let rotation = widget_dict
+            .get_deref(b"MK", doc)
+            .and_then(Object::as_dict)
+            .and_then(|mk| mk.get(b"R"))
+            .and_then(Object::as_i64)
+            .unwrap_or(0)
+            .rem_euclid(360);
+        let (mut width, mut height) = (rect[2] - rect[0], rect[3] - rect[1]);
+        if rotation == 90 || rotation == 270 {
+            std::mem::swap(&mut width, &mut height);
+        }
+        let alignment = field_attribute(doc, node.id, b"Q")
+            .and_then(|o| o.as_i64().ok())
+            .unwrap_or(0);
+
+        let value = read_value(doc, node.id, field_type);
+        let mut ops = vec![
+            Operation::new("BMC", vec![Object::Name(b"Tx".to_vec())]),
+            Operation::new("q", vec![]),
+            Operation::new(
+                "re",
+                vec![
+                    FIELD_PADDING.into(),
+                    FIELD_PADDING.into(),
+                    (width - 2.0 * FIELD_PADDING).max(0.0).into(),
+                    (height - 2.0 * FIELD_PADDING).max(0.0).into(),
+                ],
+            ),
+            Operation::new("W", vec![]),
+            Operation::new("n", vec![]),
+        ];
+
+        let inner_width = (width - 2.0 * FIELD_PADDING).max(1.0);
+        let line_factor = (font.ascent - font.descent) / 1000.0;
+        match field_type {
+            FieldType::ListBox => {
+                let selected: BTreeSet<String> = match value {
+                    Some(FieldValue::Choices(c)) => c.into_iter().collect(),
+                    _ => BTreeSet::new(),
+                };
+                let size = if da.font_size > 0.0 {
+                    da.font_size
+                } else {
+                    AUTO_FONT_SIZE
+                };
+                let line_height = size * line_factor;
+                for (i, option) in choice_options(doc, node.id).iter().enumerate() {
+                    let top = height - FIELD_PADDING - i as f32 * line_height;
+                    if top - line_height < 0.0 {
+                        break;
+                    }
+                    if selected.contains(&option.value) {
+                        ops.push(Operation::new(
+                            "rg",
+                            vec![0.6.into(), 0.75.into(), 0.85.into()],
+                        ));
+                        ops.push(Operation::new(
+                            "re",
+                            vec![
+                                FIELD_PADDING.into(),
+                                (top - line_height).into(),
+                                inner_width.into(),
+                                line_height.into(),
+                            ],
+                        ));
+                        ops.push(Operation::new("f", vec![]));
+                    }
+                    let baseline = top - font.ascent * size / 1000.0;
+                    ops.extend(text_line(
+                        &font,
+                        &da,
+                        &option.label,
+                        size,
+                        FIELD_PADDING,
+                        baseline,
+                    ));
+                }
+            }
+            _ => {
+                let mut text = match value {
+                    Some(FieldValue::Text(text)) => text,
+                    _ => String::new(),
+                };
+                if field_type == FieldType::ComboBox {
+                    // Show the label of the chosen option, not its export value.
+                    if let Some(option) = choice_options(doc, node.id)
+                        .into_iter()
+                        .find(|o| o.value == text)
+                    {
+                        text = option.label;
+                    }
+                }
+                if flags & FF_PASSWORD != 0 {
+                    text = "*".repeat(text.chars().count());
+                }
+
+                let multiline = field_type == FieldType::Text && flags & FF_MULTILINE != 0;
+                let (size, lines) = if multiline {
+                    let size = if da.font_size > 0.0 {
+                        da.font_size
+                    } else {
+                        AUTO_FONT_SIZE
+                    };
+                    (size, wrap_lines(&font, &text, size, inner_width))
+                } else {
+                    let size = if da.font_size > 0.0 {
+                        da.font_size
+                    } else {
+                        // Fill the height, then shrink to fit the width.
+                        let by_height = (height - 2.0 * FIELD_PADDING) / line_factor;
+                        let natural = font.width(&text, by_height);
+                        let by_width = if natural > inner_width {
+                            by_height * inner_width / natural
+                        } else {
+                            by_height
+                        };
+                        by_width.clamp(4.0, AUTO_FONT_SIZE)
+                    };
+                    (size, vec![text])
+                };
+
+                let line_height = size * line_factor;
+                let first_baseline = if multiline {
+                    height - FIELD_PADDING - font.ascent * size / 1000.0
+                } else {
+                    (height - line_height) / 2.0 - font.descent * size / 1000.0
+                };
+                for (i, line) in lines.iter().enumerate() {
+                    let line_width = font.width(line, size);
+                    let x = match alignment {
+                        1 => (width - line_width) / 2.0,
+                        2 => width - FIELD_PADDING - line_width,
+                        _ => FIELD_PADDING,
+                    };
+                    let baseline = first_baseline - i as f32 * line_height;
+                    ops.extend(text_line(&font, &da, line, size, x, baseline));
+                }
+            }
+        }
+        ops.push(Operation::new("Q", vec![]));
+        ops.push(Operation::new("EMC", vec![]));
+
+        let content = Content { operations: ops }
+            .encode()
+            .map_err(|e| format!("Failed to encode field appearance: {}", e))?;
+        let (sin, cos) = match rotation {
+            90 => (1.0, 0.0),
+            180 => (0.0, -1.0),
+            270 => (-1.0, 0.0),
+            _ => (0.0, 1.0),
+        };
+        let stream = Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+                "Matrix" => vec![
+                    Object::Real(cos),
+                    Object::Real(sin),
+                    Object::Real(-sin),
+                    Object::Real(cos),
+                    0.into(),
+                    0.into(),
+                ],
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { font.name.clone() => font.id },
+                },
+            },
+            content,
+        );
+        Ok(doc.add_object(stream))
+    }
+
+    /// Resolve the /DA font from the form's /DR, falling back to Helvetica.
+    fn font(&mut self, doc: &mut Document, name: &[u8]) -> Result<AppearanceFont, String> {
+        let from_resources = acro_form(doc)
+            .and_then(|form| form.get_deref(b"DR", doc).ok())
+            .and_then(|dr| dr.as_dict().ok())
+            .and_then(|dr| dr.get_deref(b"Font", doc).ok())
+            .and_then(|fonts| fonts.as_dict().ok())
+            .and_then(|fonts| fonts.get(name).ok())
+            .and_then(|font| font.as_reference().ok())
+            .and_then(|id| AppearanceFont::from_dictionary(doc, name, id));
+        if let Some(font) = from_resources {
+            return Ok(font);
+        }
+
+        let id = *self
+            .helvetica
+            .get_or_insert_with(|| PdfFont::helvetica(doc).id);
+        AppearanceFont::from_dictionary(doc, name, id)
+            .ok_or_else(|| "Failed to create the appearance font".to_string())
+    }
+}
+
+/// Operators drawing one line of text in the default appearance's color.
+fn text_line(
+    font: &AppearanceFont,
+    da: &DefaultAppearance,
+    text: &str,
+    size: f32,
+    x: f32,
+    baseline: f32,
+) -> Vec<Operation> {
+    let mut ops = vec![Operation::new("BT", vec![])];
+    ops.extend(da.color.iter().cloned());
+    ops.push(Operation::new(
+        "Tf",
+        vec![Object::Name(font.name.clone()), size.into()],
+    ));
+    ops.push(Operation::new("Td", vec![x.into(), baseline.into()]));
+    ops.push(Operation::new(
+        "Tj",
+        vec![Object::String(font.encode(text), StringFormat::Literal)],
+    ));
+    ops.push(Operation::new("ET", vec![]));
+    ops
+}
+
+/// Greedy word wrap to `max_width`, keeping explicit line breaks.
+fn wrap_lines(font: &AppearanceFont, text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if !line.is_empty() && font.width(&candidate, size) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn acro_form(doc: &Document) -> Option<&Dictionary> {
+    doc.catalog()
+        .ok()?
+        .get_deref(b"AcroForm", doc)
+        .ok()?
+        .as_dict()
+        .ok()
+}
+
+/// A widget's /Rect, normalized to [llx, lly, urx, ury].
+fn rect_of(widget: &Dictionary) -> [f32; 4] {
+    let values: Vec<f32> = widget
+        .get(b"Rect")
+        .and_then(Object::as_array)
+        .map(|r| r.iter().filter_map(|v| v.as_float().ok()).collect())
+        .unwrap_or_default();
+    match values.as_slice() {
+        [a, b, c, d] => [a.min(*c), b.min(*d), a.max(*c), b.max(*d)],
+        _ => [0.0; 4],
+    }
+}
+
+/// A widget's (or any annotation's) rectangle in displayed-page
+/// coordinates: (x, y, width, height).
+pub(crate) fn display_rect(
+    doc: &Document,
+    page_id: ObjectId,
+    widget: ObjectId,
+) -> (f32, f32, f32, f32) {
+    let Ok(widget) = doc.get_dictionary(widget) else {
+        return (0.0, 0.0, 0.0, 0.0);
+    };
+    PageGeometry::of(doc, page_id).to_display_rect(rect_of(widget))
+}
+
+/// Draw every non-signature field's current appearance into its page and
+/// remove those fields and widgets from the document.
+fn flatten_fields(doc: &mut Document, fields: &[FieldNode]) -> Result<(), String> {
+    let pages = doc.get_pages();
+    let page_numbers: BTreeMap<ObjectId, u32> = pages.iter().map(|(&n, &id)| (id, n)).collect();
+
+    let mut removed_widgets = BTreeSet::new();
+    let mut removed_fields = BTreeSet::new();
+    // page -> (appearance stream, widget rect)
+    let mut placements: BTreeMap<ObjectId, Vec<(ObjectId, [f32; 4])>> = BTreeMap::new();
+    for node in fields {
+        if field_type(doc, node.id) == Some(FieldType::Signature) {
+            continue;
+        }
+        removed_fields.insert(node.id);
+        for &widget in &node.widgets {
+            removed_widgets.insert(widget);
+            let Ok(widget_dict) = doc.get_dictionary(widget) else {
+                continue;
+            };
+            let flags = widget_dict.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+            if flags & ANNOT_HIDDEN != 0 {
+                continue;
+            }
+            let Some(page_id) =
+                widget_page(doc, widget, &page_numbers).and_then(|n| pages.get(&n).copied())
+            else {
+                continue;
+            };
+            if let Some(appearance) = normal_appearance(doc, widget) {
+                placements
+                    .entry(page_id)
+                    .or_default()
+                    .push((appearance, rect_of(widget_dict)));
+            }
+        }
+    }
+
+    for (page_id, items) in placements {
+        let ids: Vec<ObjectId> = items.iter().map(|(id, _)| *id).collect();
+        for &id in &ids {
+            // Do requires a form XObject; some writers leave the type off.
+            if let Ok(Object::Stream(stream)) = doc.get_object_mut(id) {
+                stream.dict.set("Type", "XObject");
+                stream.dict.set("Subtype", "Form");
+            }
+        }
+        let names = add_page_resources(doc, page_id, b"XObject", "FlatFx", &ids)?;
+        let mut ops = Vec::new();
+        for ((appearance, rect), name) in items.iter().zip(names) {
+            let matrix = appearance_placement(doc, *appearance, rect);
+            ops.push(Operation::new("q", vec![]));
+            ops.push(Operation::new(
+                "cm",
+                matrix.iter().map(|&v| v.into()).collect(),
+            ));
+            ops.push(Operation::new("Do", vec![Object::Name(name)]));
+            ops.push(Operation::new("Q", vec![]));
+        }
+        append_page_content(doc, page_id, ops)?;
+    }
+
+    for &page_id in pages.values() {
+        remove_page_annotations(doc, page_id, &removed_widgets)?;
+    }
+    remove_form_fields(doc, &removed_fields)
+}
+
+/// The stream a widget shows in its current state: /AP /N directly, or the
+/// /AS entry of /AP /N for buttons.
+fn normal_appearance(doc: &Document, widget: ObjectId) -> Option<ObjectId> {
+    let widget_dict = doc.get_dictionary(widget).ok()?;
+    let ap = widget_dict.get_deref(b"AP", doc).ok()?.as_dict().ok()?;
+    match ap.get(b"N").ok()? {
+        Object::Reference(id) => match doc.get_object(*id).ok()? {
+            Object::Stream(_) => Some(*id),
+            Object::Dictionary(states) => {
+                let state = widget_dict.get(b"AS").and_then(Object::as_name).ok()?;
+                states.get(state).and_then(Object::as_reference).ok()
+            }
+            _ => None,
+        },
+        Object::Dictionary(states) => {
+            let state = widget_dict.get(b"AS").and_then(Object::as_name).ok()?;
+            states.get(state).and_then(Object::as_reference).ok()
+        }
+        _ => None,
+    }
+}
+
+/// The `cm` matrix that maps a form XObject onto a widget rectangle
+/// (ISO 32000-1, 12.5.5): its BBox, transformed by its Matrix, is scaled
+/// and translated to fill the rectangle.
+fn appearance_placement(doc: &Document, appearance: ObjectId, rect: &[f32; 4]) -> [f32; 6] {
+    let dict = match doc.get_object(appearance) {
+        Ok(Object::Stream(stream)) => &stream.dict,
+        _ => return [1.0, 0.0, 0.0, 1.0, rect[0], rect[1]],
+    };
+    let numbers = |key: &[u8]| -> Vec<f32> {
+        dict.get(key)
+            .and_then(Object::as_array)
+            .map(|a| a.iter().filter_map(|v| v.as_float().ok()).collect())
+            .unwrap_or_default()
+    };
+    let bbox = match numbers(b"BBox").as_slice() {
+        [a, b, c, d] => [*a, *b, *c, *d],
+        _ => [0.0, 0.0, rect[2] - rect[0], rect[3] - rect[1]],
+    };
+    let m = match numbers(b"Matrix").as_slice() {
+        [a, b, c, d, e, f] => [*a, *b, *c, *d, *e, *f],
+        _ => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+    };
+
+    let corners = [
+        (bbox[0], bbox[1]),
+        (bbox[2], bbox[1]),
+        (bbox[0], bbox[3]),
+        (bbox[2], bbox[3]),
+    ]
+    .map(|(x, y)| (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5]));
+    let min_x = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+    let max_x = corners
+        .iter()
+        .map(|c| c.0)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min);
+    let max_y = corners
+        .iter()
+        .map(|c| c.1)
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    let scale_x = if max_x > min_x {
+        (rect[2] - rect[0]) / (max_x - min_x)
+    } else {
+        1.0
+    };
+    let scale_y = if max_y > min_y {
+        (rect[3] - rect[1]) / (max_y - min_y)
+    } else {
+        1.0
+    };
+    [
+        scale_x,
+        0.0,
+        0.0,
+        scale_y,
+        rect[0] - min_x * scale_x,
+        rect[1] - min_y * scale_y,
+    ]
+}
+
+/// Remove the given annotations from a page's /Annots.
+pub(crate) fn remove_page_annotations(
+    doc: &mut Document,
+    page_id: ObjectId,
+    annotations: &BTreeSet<ObjectId>,
+) -> Result<(), String> {
+    let annots = doc
+        .get_dictionary(page_id)
+        .map_err(|e| format!("Failed to read page: {}", e))?
+        .get(b"Annots")
+        .ok()
+        .cloned();
+    let keep = |items: &mut Vec<Object>| {
+        items.retain(|a| {
+            a.as_reference()
+                .map_or(true, |id| !annotations.contains(&id))
+        })
+    };
+    match annots {
+        Some(Object::Reference(id)) => {
+            if let Ok(items) = doc.get_object_mut(id).and_then(Object::as_array_mut) {
+                keep(items);
+            }
+        }
+        Some(Object::Array(mut items)) => {
+            keep(&mut items);
+            doc.get_dictionary_mut(page_id)
+                .map_err(|e| format!("Failed to update page: {}", e))?
+                .set("Annots", items);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Detach widget annotations (e.g. those of deleted pages) from their
+/// fields, dropping fields that are left without any widget.
+pub(crate) fn remove_widgets(
+    doc: &mut Document,
+    widgets: &BTreeSet<ObjectId>,
+) -> Result<(), String> {
+    let mut removed_fields = BTreeSet::new();
+    for node in form_fields(doc) {
+        let remaining = node.widgets.iter().filter(|w| !widgets.contains(w)).count();
+        if remaining == node.widgets.len() {
+            continue;
+        }
+        if remaining == 0 {
+            removed_fields.insert(node.id);
+            continue;
+        }
+        let field = doc
+            .get_dictionary_mut(node.id)
+            .map_err(|e| format!("Failed to update field: {}", e))?;
+        if let Ok(Object::Array(kids)) = field.get_mut(b"Kids") {
+            kids.retain(|kid| kid.as_reference().map_or(true, |id| !widgets.contains(&id)));
+        }
+    }
+    if removed_fields.is_empty() {
+        return Ok(());
+    }
+    remove_form_fields(doc, &removed_fields)
+}
+
+/// Drop removed terminal fields from the AcroForm, removing the form
+/// entirely when no fields remain.
+fn remove_form_fields(doc: &mut Document, removed: &BTreeSet<ObjectId>) -> Result<(), String> {
+    let catalog_id = doc
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|e| format!("Failed to read document catalog: {}", e))?;
+    let fields: Vec<Object> = acro_form(doc)
+        .and_then(|form| form.get_deref(b"Fields", doc).ok())
+        .and_then(|fields| fields.as_array().ok())
+        .cloned()
+        .unwrap_or_default();
+    let remaining: Vec<Object> = fields
+        .into_iter()
+        .filter(|f| {
+            f.as_reference()
+                .map_or(true, |id| prune_field(doc, id, removed, 0))
+        })
+        .collect();
+
+    if remaining.is_empty() {
+        doc.get_dictionary_mut(catalog_id)
+            .map_err(|e| format!("Failed to update document catalog: {}", e))?
+            .remove(b"AcroForm");
+        return Ok(());
+    }
+
+    let form_ref = doc
+        .get_dictionary(catalog_id)
+        .and_then(|c| c.get(b"AcroForm"))
+        .and_then(Object::as_reference)
+        .ok();
+    let form = match form_ref {
+        Some(id) => doc.get_dictionary_mut(id),
+        None => doc
+            .get_dictionary_mut(catalog_id)
+            .and_then(|c| c.get_mut(b"AcroForm"))
+            .and_then(Object::as_dict_mut),
+    }
+    .map_err(|e| format!("Failed to update form: {}", e))?;
+    form.set("Fields", remaining);
+    Ok(())
+}
+
+/// Remove the given terminal fields from a field subtree, rewriting the
+/// /Kids of parents that keep some of their children. Returns whether
+/// anything of the subtree is left.
+fn prune_field(
+    doc: &mut Document,
+    id: ObjectId,
+    removed: &BTreeSet<ObjectId>,
+    depth: usize,
+) -> bool {
+    if removed.contains(&id) {
+        return false;
+    }
+    // Guard against malformed, cyclic field trees.
+    if depth > 32 {
+        return true;
+    }
+    let Ok(field) = doc.get_dictionary(id) else {
+        return true;
+    };
+    let kids: Vec<ObjectId> = field
+        .get(b"Kids")
+        .and_then(Object::as_array)
+        .map(|kids| kids.iter().filter_map(|k| k.as_reference().ok()).collect())
+        .unwrap_or_default();
+    let (child_fields, widgets): (Vec<ObjectId>, Vec<ObjectId>) =
+        kids.into_iter().partition(|&kid| {
+            doc.get_dictionary(kid)
+                .map(|k| k.has(b"T"))
+                .unwrap_or(false)
+        });
+    if child_fields.is_empty() {
+        return true;
+    }
+
+    let dropped: BTreeSet<ObjectId> = child_fields
+        .into_iter()
+        .filter(|&kid| !prune_field(doc, kid, removed, depth + 1))
+        .collect();
+    if dropped.is_empty() {
+        return true;
+    }
+    let mut left_any = !widgets.is_empty();
+    if let Ok(Object::Array(kids)) = doc
+        .get_dictionary_mut(id)
+        .and_then(|field| field.get_mut(b"Kids"))
+    {
+        kids.retain(|kid| {
+            kid.as_reference()
+                .map_or(true, |kid| !dropped.contains(&kid))
+        });
+        left_any |= !kids.is_empty();
+    }
+    left_any
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{path_in, save, temp_dir, text_pdf};
+
+    /// A widget dictionary on `page`, with `states` as its /AP /N entries.
+    fn widget(doc: &mut Document, page: ObjectId, x: i64, states: &[&str]) -> Dictionary {
+        let mut widget = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Widget",
+            "Rect" => vec![x.into(), 600.into(), (x + 100).into(), 620.into()],
+            "P" => page,
+        };
+        if !states.is_empty() {
+            let mut normal = Dictionary::new();
+            for state in states {
+                normal.set(
+                    *state,
+                    doc.add_object(Stream::new(dictionary! {}, Vec::new())),
+                );
+            }
+            widget.set("AP", dictionary! { "N" => normal });
+        }
+        widget
+    }
+
+    /// One page with a text field nested under `applicant`, a checkbox, a
+    /// two-button radio group, a combo box and a multi-select list box.
+    fn form_pdf() -> Document {
+        let mut doc = text_pdf(&[&["Application"]]);
+        let page = doc.get_pages()[&1];
+        let da = Object::string_literal("/Helv 0 Tf 0 g");
+        let mut annots = Vec::new();
+        let mut add = |doc: &mut Document, dict: Dictionary| {
+            let id = doc.add_object(dict);
+            annots.push(Object::Reference(id));
+            id
+        };
+
+        let applicant = doc.new_object_id();
+        let mut name = widget(&mut doc, page, 72, &[]);
+        name.extend(&dictionary! {
+            "FT" => "Tx",
+            "T" => Object::string_literal("name"),
+            "DA" => da.clone(),
+            "Parent" => applicant,
+        });
+        let name = add(&mut doc, name);
+        doc.objects.insert(
+            applicant,
+            Object::Dictionary(dictionary! {
+                "T" => Object::string_literal("applicant"),
+                "Kids" => vec![name.into()],
+            }),
+        );
+
+        let mut agree = widget(&mut doc, page, 72, &["Yes", "Off"]);
+        agree.extend(&dictionary! { "FT" => "Btn", "T" => Object::string_literal("agree") });
+        let agree = add(&mut doc, agree);
+
+        let color = doc.new_object_id();
+        let mut buttons = Vec::new();
+        for (x, state) in [(72, "Red"), (200, "Blue")] {
+            let mut button = widget(&mut doc, page, x, &[state, "Off"]);
+            button.set("Parent", color);
+            buttons.push(Object::Reference(add(&mut doc, button)));
+        }
+        doc.objects.insert(
+            color,
+            Object::Dictionary(dictionary! {
+                "FT" => "Btn",
+                "Ff" => FF_RADIO,
+                "T" => Object::string_literal("color"),
+                "Kids" => buttons,
+            }),
+        );
+
+        let mut country = widget(&mut doc, page, 72, &[]);
+        country.extend(&dictionary! {
+            "FT" => "Ch",
+            "Ff" => FF_COMBO,
+            "T" => Object::string_literal("country"),
+            "DA" => da.clone(),
+            "Opt" => vec![
+                vec![Object::string_literal("ca"), Object::string_literal("Canada")].into(),
+                vec![Object::string_literal("fr"), Object::string_literal("France")].into(),
+            ],
+        });
+        let country = add(&mut doc, country);
+
+        let mut toppings = widget(&mut doc, page, 72, &[]);
+        toppings.extend(&dictionary! {
+            "FT" => "Ch",
+            "Ff" => FF_MULTI_SELECT,
+            "T" => Object::string_literal("toppings"),
+            "DA" => da,
+            "Opt" => vec![
+                Object::string_literal("Cheese"),
+                Object::string_literal("Olives"),
+                Object::string_literal("Ham"),
+            ],
+        });
+        let toppings = add(&mut doc, toppings);
+
+        doc.get_dictionary_mut(page).unwrap().set("Annots", annots);
+        let acro_form = doc.add_object(dictionary! {
+            "Fields" => vec![
+                applicant.into(),
+                agree.into(),
+                color.into(),
+                country.into(),
+                toppings.into(),
+            ],
+        });
+        let catalog = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+        doc.get_dictionary_mut(catalog)
+            .unwrap()
+            .set("AcroForm", acro_form);
+        doc
+    }
+
+    fn fill_and_list(values: &[(&str, FieldValue)]) -> BTreeMap<String, FormField> {
+        let dir = temp_dir("forms");
+        let source = save(&mut form_pdf(), &dir, "form.pdf");
+        let filled = path_in(&dir, "filled.pdf");
+        let values = values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        fill(&source, &filled, &values, &FillOptions::default()).unwrap();
+        list_fields(&filled)
+            .unwrap()
+            .into_iter()
+            .map(|field| (field.name.clone(), field))
+            .collect()
+    }
+
+    #[test]
+    fn text_values_read_back_under_their_qualified_name() {
+        let fields = fill_and_list(&[("applicant.name", FieldValue::Text("Zoë".into()))]);
+        let name = &fields["applicant.name"];
+        assert_eq!(name.field_type, FieldType::Text);
+        assert_eq!(name.value, Some(FieldValue::Text("Zoë".into())));
+        assert_eq!(name.widgets[0].page_number, Some(1));
+    }
+
+    #[test]
+    fn checkbox_values_read_back() {
+        let fields = fill_and_list(&[("agree", FieldValue::Flag(true))]);
+        assert_eq!(fields["agree"].value, Some(FieldValue::Flag(true)));
+
+        let fields = fill_and_list(&[("agree", FieldValue::Flag(false))]);
+        assert_eq!(fields["agree"].value, Some(FieldValue::Flag(false)));
+    }
+
+    #[test]
+    fn radio_values_read_back_and_select_one_button() {
+        let dir = temp_dir("forms-radio");
+        let source = save(&mut form_pdf(), &dir, "form.pdf");
+        let filled = path_in(&dir, "filled.pdf");
+        let values = BTreeMap::from([("color".to_string(), FieldValue::Text("Blue".into()))]);
+        fill(&source, &filled, &values, &FillOptions::default()).unwrap();
+
+        let color = list_fields(&filled)
+            .unwrap()
+            .into_iter()
+            .find(|f| f.name == "color")
+            .unwrap();
+        assert_eq!(color.field_type, FieldType::Radio);
+        assert_eq!(color.value, Some(FieldValue::Text("Blue".into())));
+
+        let doc = Document::load(&filled).unwrap();
+        let node = form_fields(&doc)
+            .into_iter()
+            .find(|f| f.name == "color")
+            .unwrap();
+        let states: Vec<&[u8]> = node
+            .widgets
+            .iter()
+            .map(|&w| doc.get_dictionary(w).unwrap().get(b"AS").unwrap())
+            .map(|state| state.as_name().unwrap())
+            .collect();
+        assert_eq!(states, [b"Off".as_slice(), b"Blue".as_slice()]);
+    }
+
+    #[test]
+    fn choice_values_read_back() {
+        let fields = fill_and_list(&[
+            // A combo box accepts an option's label and stores its value.
+            ("country", FieldValue::Text("France".into())),
+            (
+                "toppings",
+                FieldValue::Choices(vec!["Cheese".into(), "Ham".into()]),
+            ),
+        ]);
+        assert_eq!(fields["country"].field_type, FieldType::ComboBox);
+        assert_eq!(fields["country"].value, Some(FieldValue::Text("fr".into())));
+        assert_eq!(fields["toppings"].field_type, FieldType::ListBox);
+        assert_eq!(
+            fields["toppings"].value,
+            Some(FieldValue::Choices(vec!["Cheese".into(), "Ham".into()]))
+        );
+    }
+
+    #[test]
+    fn values_a_field_cannot_take_are_rejected() {
+        let mut doc = form_pdf();
+        let values = BTreeMap::from([("color".to_string(), FieldValue::Text("Green".into()))]);
+        let err = fill_into(&mut doc, &values, &FillOptions::default()).unwrap_err();
+        assert!(err.contains("expected one of: Blue, Red"), "{}", err);
+
+        let values = BTreeMap::from([("missing".to_string(), FieldValue::Flag(true))]);
+        assert!(fill_into(&mut doc, &values, &FillOptions::default()).is_err());
+    }
+}
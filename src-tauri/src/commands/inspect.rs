@@ -0,0 +1,635 @@
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use lopdf::Document;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// How much of a file is looked at to recognise text formats.
+const SNIFF_LENGTH: u64 = 8 * 1024;
+
+/// `%PDF-` may follow some junk; readers accept it within the first 1 KB.
+const PDF_HEADER_WINDOW: usize = 1024;
+
+/// How much of the end of a PDF is searched for the trailer's /ID.
+const PDF_TRAILER_WINDOW: u64 = 16 * 1024;
+
+const OLE2_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// The main part's content type in `[Content_Types].xml` of each Office Open
+/// XML format, with the format's usual extension.
+const OOXML_FORMATS: &[(&str, &str, &str)] = &[
+    ("wordprocessingml.document.main+xml", "word", "docx"),
+    ("wordprocessingml.template.main+xml", "word", "dotx"),
+    ("ms-word.document.macroEnabled.main+xml", "word", "docm"),
+    (
+        "ms-word.template.macroEnabledTemplate.main+xml",
+        "word",
+        "dotm",
+    ),
+    ("spreadsheetml.sheet.main+xml", "excel", "xlsx"),
+    ("spreadsheetml.template.main+xml", "excel", "xltx"),
+    ("ms-excel.sheet.macroEnabled.main+xml", "excel", "xlsm"),
+    ("ms-excel.sheet.binary.macroEnabled.main", "excel", "xlsb"),
+    ("presentationml.presentation.main+xml", "powerpoint", "pptx"),
+    ("presentationml.slideshow.main+xml", "powerpoint", "ppsx"),
+    ("presentationml.template.main+xml", "powerpoint", "potx"),
+    (
+        "ms-powerpoint.presentation.macroEnabled.main+xml",
+        "powerpoint",
+        "pptm",
+    ),
+];
+
+/// The `mimetype` entry of each OpenDocument format.
+const ODF_FORMATS: &[(&str, &str, &str)] = &[
+    ("application/vnd.oasis.opendocument.text", "word", "odt"),
+    (
+        "application/vnd.oasis.opendocument.spreadsheet",
+        "excel",
+        "ods",
+    ),
+    (
+        "application/vnd.oasis.opendocument.presentation",
+        "powerpoint",
+        "odp",
+    ),
+];
+
+/// Property ids in the OLE summary information streams.
+const PIDSI_PAGECOUNT: u32 = 14;
+const PIDDSI_SLIDECOUNT: u32 = 7;
+
+/// BIFF8 records in an Excel 97-2003 workbook stream.
+const BIFF_EOF: u16 = 0x000A;
+const BIFF_FILEPASS: u16 = 0x002F;
+const BIFF_BOUNDSHEET: u16 = 0x0085;
+
+/// What a file really is, judged by its contents rather than its name.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    /// The kind of document, as FileType in src/types/document.ts: "pdf",
+    /// "word", "excel", "powerpoint" or "unknown".
+    pub file_type: String,
+    /// The actual format, named by its usual extension ("pdf", "docx",
+    /// "doc", "rtf", "xlsx", "csv", "pptx", "odt", ...), or "html", "text",
+    /// "zip", "ole" or "unknown".
+    pub format: String,
+    /// The PDF version, the RTF version, or the version of the application
+    /// that last saved an Office document, when known.
+    pub version: Option<String>,
+    /// Whether the contents are encrypted (opening may need a password).
+    pub encrypted: bool,
+    pub page_count: Option<u32>,
+    pub sheet_count: Option<u32>,
+    pub slide_count: Option<u32>,
+    /// SHA-256 of the contents, which identifies the document wherever it
+    /// is moved or copied to.
+    pub fingerprint: Option<String>,
+    /// The permanent part of a PDF's trailer /ID (as hex), which survives
+    /// edits made by most PDF writers.
+    pub pdf_id: Option<String>,
+}
+
+impl FileInfo {
+    fn new(file_type: &str, format: &str) -> Self {
+        FileInfo {
+            file_type: file_type.to_string(),
+            format: format.to_string(),
+            version: None,
+            encrypted: false,
+            page_count: None,
+            sheet_count: None,
+            slide_count: None,
+            fingerprint: None,
+            pdf_id: None,
+        }
+    }
+}
+
+/// Identify the file at `path` from its contents.
+#[tauri::command]
+pub async fn inspect_file(path: String) -> Result<FileInfo, String> {
+    inspect(Path::new(&path))
+}
+
+pub fn inspect(path: &Path) -> Result<FileInfo, String> {
+    let mut info = inspect_format(path)?;
+    info.fingerprint = Some(fingerprint(path)?);
+    Ok(info)
+}
+
+/// SHA-256 of the file's contents, as hex.
+pub fn fingerprint(path: &Path) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    fs::File::open(path)
+        .and_then(|mut file| io::copy(&mut file, &mut hasher))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+fn inspect_format(path: &Path) -> Result<FileInfo, String> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .and_then(|file| file.take(SNIFF_LENGTH).read_to_end(&mut head))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let window = &head[..head.len().min(PDF_HEADER_WINDOW)];
+    if let Some(start) = find(window, b"%PDF-") {
+        return Ok(inspect_pdf(path, &head[start..]));
+    }
+    if head.starts_with(OLE2_MAGIC) {
+        return inspect_ole(path);
+    }
+    if head.starts_with(ZIP_MAGIC) {
+        return inspect_zip(path);
+    }
+    Ok(inspect_text(&head))
+}
+
+fn inspect_pdf(path: &Path, header: &[u8]) -> FileInfo {
+    let mut info = FileInfo::new("pdf", "pdf");
+    info.pdf_id = pdf_file_id(path);
+    match Document::load_metadata(path) {
+        Ok(metadata) => {
+            info.version = Some(metadata.version);
+            info.encrypted = metadata.encrypted;
+            info.page_count = Some(metadata.page_count);
+        }
+        // Damaged, but pdf.js may still manage: keep what the header says
+        Err(_) => {
+            let version: String = header[5..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit() || **b == b'.')
+                .map(|&b| b as char)
+                .collect();
+            info.version = (!version.is_empty()).then_some(version);
+        }
+    }
+    info
+}
+
+/// The first string of the last /ID near the end of the file: the trailer
+/// of the latest revision (or its cross-reference stream's dictionary).
+/// Only hex strings are recognised, which is what PDF writers use.
+fn pdf_file_id(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(PDF_TRAILER_WINDOW)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+
+    let mut rest = &tail[..];
+    let mut id = None;
+    while let Some(start) = find(rest, b"/ID") {
+        rest = &rest[start + 3..];
+        // Not the start of a longer name
+        if rest.first().is_some_and(|b| b.is_ascii_alphanumeric()) {
+            continue;
+        }
+        let Some(value) = skip_whitespace(rest)
+            .strip_prefix(b"[")
+            .and_then(|array| skip_whitespace(array).strip_prefix(b"<"))
+        else {
+            continue;
+        };
+        let hex: String = value
+            .iter()
+            .take_while(|b| **b != b'>')
+            .filter(|b| !b.is_ascii_whitespace())
+            .map(|&b| (b as char).to_ascii_uppercase())
+            .collect();
+        if !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            id = Some(hex);
+        }
+    }
+    id
+}
+
+/// Office 97-2003 documents, and Office Open XML documents encrypted with a
+/// password, are OLE2 compound files; the streams inside say which.
+fn inspect_ole(path: &Path) -> Result<FileInfo, String> {
+    let mut comp = cfb::open(path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    if comp.is_stream("/EncryptionInfo") && comp.is_stream("/EncryptedPackage") {
+        // The package, and so its type, is only readable with the password
+        let mut info = match extension_format(path) {
+            Some((file_type, format)) => FileInfo::new(file_type, format),
+            None => FileInfo::new("unknown", "ole"),
+        };
+        info.encrypted = true;
+        return Ok(info);
+    }
+
+    if comp.is_stream("/WordDocument") {
+        let mut info = FileInfo::new("word", "doc");
+        info.version = Some("97-2003".to_string());
+        // The FIB at the start of the stream: flags at 0x0A, fEncrypted = 0x0100
+        let mut fib = [0u8; 12];
+        if read_stream(&mut comp, "/WordDocument", &mut fib).is_ok() {
+            info.encrypted = u16::from_le_bytes([fib[10], fib[11]]) & 0x0100 != 0;
+        }
+        info.page_count = summary_count(&mut comp, "/\u{5}SummaryInformation", PIDSI_PAGECOUNT);
+        return Ok(info);
+    }
+
+    for name in ["/Workbook", "/Book"] {
+        if comp.is_stream(name) {
+            let mut info = FileInfo::new("excel", "xls");
+            info.version = Some(if name == "/Book" { "5.0/95" } else { "97-2003" }.to_string());
+            let mut data = Vec::new();
+            comp.open_stream(name)
+                .and_then(|mut s| s.read_to_end(&mut data))
+                .map_err(|e| format!("Failed to read workbook: {}", e))?;
+            let (sheets, encrypted) = biff_globals(&data);
+            info.sheet_count = Some(sheets);
+            info.encrypted = encrypted;
+            return Ok(info);
+        }
+    }
+
+    if comp.is_stream("/PowerPoint Document") {
+        let mut info = FileInfo::new("powerpoint", "ppt");
+        info.version = Some("97-2003".to_string());
+        info.encrypted = comp.is_stream("/EncryptedSummary");
+        info.slide_count = summary_count(
+            &mut comp,
+            "/\u{5}DocumentSummaryInformation",
+            PIDDSI_SLIDECOUNT,
+        );
+        return Ok(info);
+    }
+
+    Ok(FileInfo::new("unknown", "ole"))
+}
+
+/// Office Open XML and OpenDocument files are ZIP packages, told apart by
+/// their content types; anything else is a plain archive.
+fn inspect_zip(path: &Path) -> Result<FileInfo, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let Ok(mut archive) = zip::ZipArchive::new(file) else {
+        return Ok(FileInfo::new("unknown", "zip"));
+    };
+
+    if let Some(mimetype) = zip_text(&mut archive, "mimetype") {
+        if let Some(&(_, file_type, format)) =
+            ODF_FORMATS.iter().find(|(m, _, _)| mimetype.trim() == *m)
+        {
+            return Ok(FileInfo::new(file_type, format));
+        }
+    }
+
+    let Some(content_types) = zip_text(&mut archive, "[Content_Types].xml") else {
+        return Ok(FileInfo::new("unknown", "zip"));
+    };
+    let Some(&(_, file_type, format)) = OOXML_FORMATS
+        .iter()
+        .find(|(content_type, _, _)| content_types.contains(content_type))
+    else {
+        return Ok(FileInfo::new("unknown", "zip"));
+    };
+
+    let mut info = FileInfo::new(file_type, format);
+    let app = zip_text(&mut archive, "docProps/app.xml").unwrap_or_default();
+    info.version = element_text(&app, "AppVersion").map(str::to_string);
+    match file_type {
+        "word" => info.page_count = element_text(&app, "Pages").and_then(|n| n.parse().ok()),
+        // Every sheet and slide is a part listed in the content types
+        "excel" => info.sheet_count = Some(count(&content_types, "spreadsheetml.worksheet+xml")),
+        _ => info.slide_count = Some(count(&content_types, "presentationml.slide+xml")),
+    }
+    Ok(info)
+}
+
+/// Files without a signature: RTF, HTML, delimited text or plain text.
+fn inspect_text(head: &[u8]) -> FileInfo {
+    let text = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    if text.starts_with(b"{\\rtf") {
+        let mut info = FileInfo::new("word", "rtf");
+        info.version = text
+            .get(5)
+            .filter(|b| b.is_ascii_digit())
+            .map(|&b| (b as char).to_string());
+        return info;
+    }
+    if text.contains(&0) {
+        return FileInfo::new("unknown", "unknown");
+    }
+
+    let lower = String::from_utf8_lossy(text).to_ascii_lowercase();
+    let start = lower.trim_start();
+    if start.starts_with("<!doctype html") || start.starts_with("<html") || lower.contains("<html")
+    {
+        return FileInfo::new("unknown", "html");
+    }
+    if is_delimited(&lower) {
+        return FileInfo::new("excel", "csv");
+    }
+    FileInfo::new("unknown", "text")
+}
+
+/// Whether the text looks like a table: the first lines (at least two)
+/// split into the same number of fields, more than one, on one of the
+/// usual delimiters. The last line may be cut short by the sniff window.
+fn is_delimited(text: &str) -> bool {
+    let mut lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() > 2 {
+        lines.pop();
+    }
+    let lines = &lines[..lines.len().min(20)];
+    if lines.len() < 2 {
+        return false;
+    }
+    [',', ';', '\t', '|'].iter().any(|&delimiter| {
+        let fields = field_count(lines[0], delimiter);
+        fields > 1 && lines.iter().all(|l| field_count(l, delimiter) == fields)
+    })
+}
+
+/// Fields on a line, not counting delimiters inside double quotes.
+fn field_count(line: &str, delimiter: char) -> usize {
+    let mut quoted = false;
+    let mut fields = 1;
+    for c in line.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == delimiter && !quoted {
+            fields += 1;
+        }
+    }
+    fields
+}
+
+/// The number of sheets in an Excel 97-2003 workbook stream, and whether it
+/// is encrypted: BOUNDSHEET and FILEPASS records in the globals substream,
+/// which ends at the first EOF record.
+fn biff_globals(data: &[u8]) -> (u32, bool) {
+    let mut sheets = 0;
+    let mut encrypted = false;
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let kind = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let len = usize::from(u16::from_le_bytes([data[pos + 2], data[pos + 3]]));
+        match kind {
+            BIFF_BOUNDSHEET => sheets += 1,
+            BIFF_FILEPASS => encrypted = true,
+            BIFF_EOF => break,
+            _ => {}
+        }
+        pos += 4 + len;
+    }
+    (sheets, encrypted)
+}
+
+/// An integer property from an OLE property set stream (the summary
+/// information of Office 97-2003 documents), from its first section.
+fn summary_count<F: Read + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+    stream: &str,
+    property_id: u32,
+) -> Option<u32> {
+    let mut data = Vec::new();
+    comp.open_stream(stream).ok()?.read_to_end(&mut data).ok()?;
+    let u32_at = |pos: usize| -> Option<u32> {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    // Header (28 bytes), then the first section's FMTID (16) and offset
+    let section = u32_at(44)? as usize;
+    let properties = u32_at(section + 4)? as usize;
+    for i in 0..properties.min(1024) {
+        let entry = section + 8 + i * 8;
+        if u32_at(entry)? != property_id {
+            continue;
+        }
+        let value = section + u32_at(entry + 4)? as usize;
+        // The value follows its 4-byte type, which should be VT_I4 (3)
+        if u32_at(value)? != 3 {
+            return None;
+        }
+        return u32_at(value + 4);
+    }
+    None
+}
+
+fn read_stream<F: Read + Seek>(
+    comp: &mut cfb::CompoundFile<F>,
+    name: &str,
+    buf: &mut [u8],
+) -> std::io::Result<()> {
+    comp.open_stream(name)?.read_exact(buf)
+}
+
+fn zip_text<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Option<String> {
+    let mut text = String::new();
+    archive.by_name(name).ok()?.read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+/// The text of the first `<name>` element in a simple XML document.
+fn element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..end].trim())
+}
+
+fn count(haystack: &str, needle: &str) -> u32 {
+    haystack.matches(needle).count() as u32
+}
+
+fn skip_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// The type an encrypted Office Open XML file most likely has, going by its
+/// name, since the contents can't be read without the password.
+fn extension_format(path: &Path) -> Option<(&'static str, &'static str)> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    OOXML_FORMATS
+        .iter()
+        .find(|(_, _, format)| *format == ext)
+        .map(|&(_, file_type, format)| (file_type, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+    use crate::commands::test_support::{save, temp_dir, text_pdf};
+
+    fn sniff(name: &str, data: &[u8]) -> FileInfo {
+        let dir = temp_dir("inspect");
+        let path = dir.join(name);
+        fs::write(&path, data).unwrap();
+        inspect(&path).unwrap()
+    }
+
+    fn zip_file(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn ole_file(name: &str, streams: &[(&str, &[u8])]) -> FileInfo {
+        let dir = temp_dir("inspect-ole");
+        let path = dir.join(name);
+        let mut comp = cfb::create(&path).unwrap();
+        for (stream, data) in streams {
+            comp.create_stream(stream).unwrap().write_all(data).unwrap();
+        }
+        comp.flush().unwrap();
+        drop(comp);
+        inspect(&path).unwrap()
+    }
+
+    #[test]
+    fn recognises_pdfs_after_leading_junk() {
+        let dir = temp_dir("inspect-pdf");
+        let path = save(&mut text_pdf(&[&["One"], &["Two"]]), &dir, "doc.pdf");
+        let mut data = b"junk before the header\n".to_vec();
+        data.extend(fs::read(&path).unwrap());
+
+        let info = sniff("renamed.txt", &data);
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("pdf", "pdf")
+        );
+        assert_eq!(info.version.as_deref(), Some("1.7"));
+        assert!(info.fingerprint.is_some());
+    }
+
+    #[test]
+    fn reads_the_pdf_id_of_the_latest_trailer() {
+        let dir = temp_dir("inspect-pdf-id");
+        let mut doc = text_pdf(&[&["Text"]]);
+        let id = lopdf::Object::String(vec![0xAB, 0xCD], lopdf::StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+        let path = save(&mut doc, &dir, "doc.pdf");
+        assert_eq!(
+            inspect(Path::new(&path)).unwrap().pdf_id.as_deref(),
+            Some("ABCD")
+        );
+    }
+
+    #[test]
+    fn recognises_office_open_xml_and_opendocument_packages() {
+        let docx = zip_file(&[
+            (
+                "[Content_Types].xml",
+                "<Override ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>",
+            ),
+            (
+                "docProps/app.xml",
+                "<Properties><Pages>3</Pages><AppVersion>16.0000</AppVersion></Properties>",
+            ),
+        ]);
+        let info = sniff("report.bin", &docx);
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("word", "docx")
+        );
+        assert_eq!(info.page_count, Some(3));
+        assert_eq!(info.version.as_deref(), Some("16.0000"));
+
+        let ods = zip_file(&[("mimetype", "application/vnd.oasis.opendocument.spreadsheet")]);
+        let info = sniff("sheet.zip", &ods);
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("excel", "ods")
+        );
+
+        let info = sniff("archive.docx", &zip_file(&[("readme.txt", "hello")]));
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("unknown", "zip")
+        );
+    }
+
+    #[test]
+    fn recognises_office_97_2003_compound_files() {
+        let mut fib = [0u8; 12];
+        fib[11] = 0x01; // fEncrypted
+        let info = ole_file("letter.dat", &[("/WordDocument", &fib)]);
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("word", "doc")
+        );
+        assert!(info.encrypted);
+
+        // Two BOUNDSHEET records, then EOF
+        let mut workbook = Vec::new();
+        for kind in [BIFF_BOUNDSHEET, BIFF_BOUNDSHEET, BIFF_EOF] {
+            workbook.extend(kind.to_le_bytes());
+            workbook.extend(0u16.to_le_bytes());
+        }
+        let info = ole_file("book.dat", &[("/Workbook", &workbook)]);
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("excel", "xls")
+        );
+        assert_eq!(info.sheet_count, Some(2));
+        assert!(!info.encrypted);
+
+        let info = ole_file("slides.dat", &[("/PowerPoint Document", b"")]);
+        assert_eq!(info.format, "ppt");
+
+        // A password-protected .xlsx is a compound file too
+        let info = ole_file(
+            "budget.xlsx",
+            &[("/EncryptionInfo", b""), ("/EncryptedPackage", b"")],
+        );
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("excel", "xlsx")
+        );
+        assert!(info.encrypted);
+    }
+
+    #[test]
+    fn recognises_text_formats() {
+        let info = sniff("a.doc", b"\xEF\xBB\xBF{\\rtf1\\ansi Hello}");
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("word", "rtf")
+        );
+        assert_eq!(info.version.as_deref(), Some("1"));
+
+        let info = sniff("a.xls", b"  <!DOCTYPE html><html><table></table></html>");
+        assert_eq!(info.format, "html");
+
+        let info = sniff("a.txt", b"name;amount\n\"Smith; J\";10\nDoe;20\n");
+        assert_eq!(
+            (info.file_type.as_str(), info.format.as_str()),
+            ("excel", "csv")
+        );
+
+        assert_eq!(
+            sniff("a.csv", b"Just a note,\nwritten down.\n").format,
+            "text"
+        );
+        assert_eq!(sniff("a.pdf", b"\x00\x01\x02binary").format, "unknown");
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::Manager;
+
+use super::documents::canonical_path;
+use super::security::needs_password;
+use super::text::extract_text;
+
+/// The app database the frontend keeps recent documents in (`DB_NAME` in
+/// src/constants.ts). tauri-plugin-sql resolves it against the app config
+/// directory.
+const DATABASE_FILE: &str = "officetools.db";
+
+/// How long to wait when the frontend's connection holds the write lock.
+pub(crate) const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Length of a result snippet, in tokens.
+const SNIPPET_TOKENS: i64 = 24;
+
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+/// Marks the start and end of a match in the snippets FTS5 builds. Control
+/// characters never appear in indexed text, so they can't be confused with
+/// document content.
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+/// The index lives in the app database. `document_text` holds one row per
+/// PDF page (one per Word document, with no page number); `document_index`
+/// records which version of each file was indexed. Both are keyed by the
+/// resolved path, as backups and version history are.
+const SCHEMA: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS document_text USING fts5(
+        file_path UNINDEXED,
+        page_number UNINDEXED,
+        content,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    CREATE TABLE IF NOT EXISTS document_index (
+        file_path TEXT PRIMARY KEY,
+        file_size INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        indexed_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+";
+
+/// A piece of a result snippet; matched pieces are the ones to highlight.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetPart {
+    pub text: String,
+    pub matched: bool,
+}
+
+/// A page (or Word document) matching a library search, best match first.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryHit {
+    pub file_path: String,
+    pub file_name: String,
+    /// 1-based; None for Word documents, which have no fixed pages.
+    pub page_number: Option<u32>,
+    pub snippet: Vec<SnippetPart>,
+}
+
+/// Add the text of the document at `path` to the library index. Resolves
+/// to false when the indexed copy is already up to date.
+#[tauri::command]
+pub async fn index_document(app: tauri::AppHandle, path: String) -> Result<bool, String> {
+    Library::open(&database_path(&app)?)?.index(&path)
+}
+
+/// Index every document in `paths` (the recent list, most recent first)
+/// that changed since it was last indexed, and drop every other document
+/// from the index. Resolves to the number of documents (re)indexed.
+#[tauri::command]
+pub async fn index_library(app: tauri::AppHandle, paths: Vec<String>) -> Result<usize, String> {
+    Library::open(&database_path(&app)?)?.index_all(&paths)
+}
+
+/// Search the text of all recent documents.
+#[tauri::command]
+pub async fn search_library(
+    app: tauri::AppHandle,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<LibraryHit>, String> {
+    Library::open(&database_path(&app)?)?.search(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+}
+
+pub(crate) fn database_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(DATABASE_FILE))
+        .map_err(|e| format!("Failed to get app config dir: {}", e))
+}
+
+/// A connection to the app database with the index tables in place.
+pub struct Library {
+    conn: Connection,
+}
+
+impl Library {
+    pub fn open(db_path: &Path) -> Result<Self, String> {
+        if let Some(dir) = db_path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create app dir: {}", e))?;
+        }
+        let conn =
+            Connection::open(db_path).map_err(|e| format!("Failed to open database: {}", e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .and_then(|_| conn.execute_batch(SCHEMA))
+            .map_err(|e| format!("Failed to prepare search index: {}", e))?;
+        Ok(Library { conn })
+    }
+
+    /// Index one file unless the index already has this version of it.
+    pub fn index(&mut self, path: &str) -> Result<bool, String> {
+        let metadata = fs::metadata(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let key = canonical_path(Path::new(path));
+        let size = metadata.len() as i64;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+
+        let indexed: Option<(i64, i64)> = self
+            .conn
+            .query_row(
+                "SELECT file_size, modified FROM document_index WHERE file_path = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read search index: {}", e))?;
+        if indexed == Some((size, modified)) {
+            return Ok(false);
+        }
+
+        let pages = document_text(path)?;
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+        tx.execute(
+            "DELETE FROM document_text WHERE file_path = ?1",
+            params![key],
+        )
+        .map_err(|e| format!("Failed to update search index: {}", e))?;
+        for (page_number, content) in &pages {
+            if content.trim().is_empty() {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO document_text (file_path, page_number, content) VALUES (?1, ?2, ?3)",
+                params![key, page_number, content],
+            )
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+        }
+        tx.execute(
+            "INSERT INTO document_index (file_path, file_size, modified, indexed_at)
+             VALUES (?1, ?2, ?3, datetime('now'))
+             ON CONFLICT(file_path) DO UPDATE SET
+               file_size = excluded.file_size,
+               modified = excluded.modified,
+               indexed_at = excluded.indexed_at",
+            params![key, size, modified],
+        )
+        .map_err(|e| format!("Failed to update search index: {}", e))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+        Ok(true)
+    }
+
+    /// Bring the index in line with `paths`: index the ones that changed and
+    /// drop every other file. Files that have moved or can't be read are
+    /// skipped; they stay searchable under their last indexed text until
+    /// they are left out of `paths`.
+    pub fn index_all(&mut self, paths: &[String]) -> Result<usize, String> {
+        let keep: BTreeSet<String> = paths
+            .iter()
+            .map(|path| canonical_path(Path::new(path)))
+            .collect();
+        let indexed: Vec<String> = self
+            .conn
+            .prepare(
+                "SELECT file_path FROM document_index
+                 UNION SELECT DISTINCT file_path FROM document_text",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<Result<_, _>>()
+            })
+            .map_err(|e| format!("Failed to read search index: {}", e))?;
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+        for path in indexed.iter().filter(|p| !keep.contains(*p)) {
+            tx.execute(
+                "DELETE FROM document_text WHERE file_path = ?1",
+                params![path],
+            )
+            .and_then(|_| {
+                tx.execute(
+                    "DELETE FROM document_index WHERE file_path = ?1",
+                    params![path],
+                )
+            })
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+
+        let mut count = 0;
+        for path in paths.iter().filter(|p| document_kind(p).is_some()) {
+            if let Ok(true) = self.index(path) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Find pages containing every word of `query`, best match first. A
+    /// query wrapped in double quotes matches as an exact phrase.
+    pub fn search(&self, query: &str, limit: u32) -> Result<Vec<LibraryHit>, String> {
+        let Some(expression) = match_expression(query) else {
+            return Err("Search query is empty".to_string());
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT file_path, page_number, snippet(document_text, 2, ?2, ?3, '…', ?4)
+                 FROM document_text
+                 WHERE document_text MATCH ?1
+                 ORDER BY document_text.rank
+                 LIMIT ?5",
+            )
+            .map_err(|e| format!("Failed to search library: {}", e))?;
+        let rows = stmt
+            .query_map(
+                params![
+                    expression,
+                    MATCH_START.to_string(),
+                    MATCH_END.to_string(),
+                    SNIPPET_TOKENS,
+                    limit
+                ],
+                |row| {
+                    let file_path: String = row.get(0)?;
+                    Ok(LibraryHit {
+                        file_name: Path::new(&file_path).file_name().map_or_else(
+                            || file_path.clone(),
+                            |n| n.to_string_lossy().into_owned(),
+                        ),
+                        file_path,
+                        page_number: row.get(1)?,
+                        snippet: snippet_parts(&row.get::<_, String>(2)?),
+                    })
+                },
+            )
+            .map_err(|e| format!("Failed to search library: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to search library: {}", e))
+    }
+}
+
+enum DocumentKind {
+    Pdf,
+    Word,
+}
+
+fn document_kind(path: &str) -> Option<DocumentKind> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "pdf" => Some(DocumentKind::Pdf),
+        "docx" => Some(DocumentKind::Word),
+        _ => None,
+    }
+}
+
+/// The text to index for a file, as (page number, text) pairs.
+fn document_text(path: &str) -> Result<Vec<(Option<u32>, String)>, String> {
+    match document_kind(path) {
+        // Their text would be readable in the index without the password
+        Some(DocumentKind::Pdf) if needs_password(path)? => {
+            Err("Password-protected PDFs are not indexed".to_string())
+        }
+        Some(DocumentKind::Pdf) => Ok(extract_text(path, None)?
+            .into_iter()
+            .map(|page| (Some(page.page_number), page.text))
+            .collect()),
+        Some(DocumentKind::Word) => Ok(vec![(None, docx_text(path)?)]),
+        None => Err("Only PDF and Word documents can be indexed".to_string()),
+    }
+}
+
+/// Read the body text of a .docx file.
+fn docx_text(path: &str) -> Result<String, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to open Word document: {}", e))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| format!("Failed to open Word document: {}", e))?
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read Word document: {}", e))?;
+    Ok(wordml_text(&xml))
+}
+
+/// The text of a WordprocessingML body: the contents of `<w:t>` runs, with
+/// paragraph ends, breaks and tabs turned into whitespace. Deleted text in
+/// tracked changes is in `<w:delText>` and is left out.
+fn wordml_text(xml: &str) -> String {
+    let mut text = String::new();
+    let mut in_run_text = false;
+    // Tab stops in paragraph properties are also `<w:tab>` elements.
+    let mut in_properties = false;
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        if in_run_text {
+            text.push_str(&unescape_xml(&rest[..start]));
+        }
+        let Some(length) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start + 1..start + length];
+        rest = &rest[start + length + 1..];
+
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        match name {
+            "w:t" => in_run_text = !closing && !self_closing,
+            "w:pPr" | "w:rPr" if !self_closing => in_properties = !closing,
+            "w:tab" if !closing && !in_properties => text.push('\t'),
+            "w:br" | "w:cr" if !closing => text.push('\n'),
+            "w:p" if closing => text.push('\n'),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Replace the predefined XML entities and character references.
+fn unescape_xml(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Turn a user's query into an FTS5 match expression. Every word is quoted
+/// so punctuation and FTS5 keywords (AND, NEAR, ...) are searched for as
+/// text instead of being parsed as query syntax.
+fn match_expression(query: &str) -> Option<String> {
+    let query = query.trim();
+    let phrase = query.len() >= 2 && query.starts_with('"') && query.ends_with('"');
+    let words: Vec<String> = query
+        .split(|c: char| c.is_whitespace() || c == '"')
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"", w))
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+    Some(words.join(if phrase { " + " } else { " " }))
+}
+
+/// Split an FTS5 snippet into plain and matched parts.
+fn snippet_parts(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut matched = false;
+    for piece in snippet.split([MATCH_START, MATCH_END]) {
+        if !piece.is_empty() {
+            // Page text keeps its line breaks; a snippet reads as one line.
+            let mut text = String::with_capacity(piece.len());
+            for c in piece.chars() {
+                if !c.is_whitespace() {
+                    text.push(c);
+                } else if !text.ends_with(' ') {
+                    text.push(' ');
+                }
+            }
+            parts.push(SnippetPart { text, matched });
+        }
+        matched = !matched;
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{path_in, save, temp_dir, text_pdf};
+
+    #[test]
+    fn keeps_only_the_documents_it_is_given() {
+        let dir = temp_dir("library");
+        let invoice = save(
+            &mut text_pdf(&[&["Invoice for paper"]]),
+            &dir,
+            "invoice.pdf",
+        );
+        let letter = save(
+            &mut text_pdf(&[&["Letter about paper"]]),
+            &dir,
+            "letter.pdf",
+        );
+        let mut library = Library::open(&dir.join("test.db")).unwrap();
+
+        assert_eq!(library.index_all(&[invoice.clone(), letter]).unwrap(), 2);
+        assert_eq!(library.search("paper", 10).unwrap().len(), 2);
+
+        assert_eq!(library.index_all(&[invoice]).unwrap(), 0);
+        let hits = library.search("paper", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].file_name, "invoice.pdf");
+        assert_eq!(hits[0].page_number, Some(1));
+    }
+
+    #[test]
+    fn indexes_a_file_once_however_its_path_is_spelled() {
+        let dir = temp_dir("library-paths");
+        fs::create_dir(dir.join("sub")).unwrap();
+        let path = save(&mut text_pdf(&[&["Quarterly report"]]), &dir, "report.pdf");
+        let other_spelling = path_in(&dir.join("sub"), "../report.pdf");
+        let mut library = Library::open(&dir.join("test.db")).unwrap();
+
+        assert!(library.index(&path).unwrap());
+        assert!(!library.index(&other_spelling).unwrap());
+        assert_eq!(library.index_all(&[other_spelling]).unwrap(), 0);
+        assert_eq!(library.search("quarterly", 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use super::error::{CommandError, ErrorCode};
+
+/// A document being edited has an owner file next to it, named like the
+/// ones Microsoft Office creates: `~$` followed by the document's name.
+/// Anyone opening the document on a shared drive sees who has it open.
+const LOCK_PREFIX: &str = "~$";
+
+/// Word keeps its owner files' names short: `~$` replaces the first
+/// character of a 7-character name and the first two of a longer one
+/// (`Document.docx` has `~$cument.docx`). Excel and PowerPoint prefix the
+/// whole name.
+const WORD_EXTENSIONS: &[&str] = &["doc", "docx", "docm", "dot", "dotx", "dotm", "rtf"];
+
+/// Office writes the owner's name as a length byte followed by the name,
+/// padded to this many bytes.
+const OFFICE_NAME_LENGTH: usize = 54;
+
+/// The owner files this app holds, by resolved document path, with the
+/// label of the window that holds each. Managed as Tauri state.
+#[derive(Default)]
+pub struct Locks {
+    held: Mutex<HashMap<PathBuf, Held>>,
+}
+
+struct Held {
+    lock_path: PathBuf,
+    window: String,
+}
+
+/// Who has a document open, as recorded in its owner file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockOwner {
+    pub user: String,
+    /// Empty for owner files written by Office.
+    pub host: String,
+    /// Milliseconds since the Unix epoch; `None` for Office's owner files.
+    pub locked_at: Option<u64>,
+}
+
+/// Take the lock on `path` for this window. Returns `None` once the lock is
+/// held, or the owner if someone else has the document open, including
+/// another window of this app: it should then be opened read-only.
+#[tauri::command]
+pub async fn lock_document(
+    window: tauri::WebviewWindow,
+    locks: tauri::State<'_, Locks>,
+    path: String,
+) -> Result<Option<LockOwner>, String> {
+    let path = Path::new(&path);
+    let mut held = locks.held.lock().unwrap();
+    if let Some(other) = held.get(&key(path)) {
+        if other.window == window.label() {
+            return Ok(None);
+        }
+        return Ok(Some(
+            read_owner(&other.lock_path).unwrap_or_else(current_owner),
+        ));
+    }
+    let lock_paths = lock_paths(path)?;
+    // An owner file this user left behind on this machine (after a crash,
+    // say) is stale. Any other, including one that can't be read yet, is
+    // someone else's.
+    for lock_path in lock_paths.iter().filter(|p| p.exists()) {
+        match read_owner(lock_path) {
+            Some(owner) if is_current_user(&owner) => remove(lock_path),
+            Some(owner) => return Ok(Some(owner)),
+            None => return Ok(Some(unknown_owner())),
+        }
+    }
+    let lock_path = lock_paths[0].clone();
+    let owner = LockOwner {
+        locked_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .ok(),
+        ..current_owner()
+    };
+    let json = serde_json::to_vec(&owner).map_err(|e| format!("Failed to lock file: {}", e))?;
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Hidden, as Office's owner files are
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        options.attributes(FILE_ATTRIBUTE_HIDDEN);
+    }
+    match options
+        .open(&lock_path)
+        .and_then(|mut file| file.write_all(&json))
+    {
+        Ok(()) => {}
+        // Someone else took the lock since the check above
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Ok(Some(read_owner(&lock_path).unwrap_or_else(unknown_owner)));
+        }
+        Err(e) => return Err(format!("Failed to lock file: {}", e)),
+    }
+
+    held.insert(
+        key(path),
+        Held {
+            lock_path,
+            window: window.label().to_string(),
+        },
+    );
+    Ok(None)
+}
+
+/// Give up the lock on `path`, if this window holds it.
+#[tauri::command]
+pub async fn unlock_document(
+    window: tauri::WebviewWindow,
+    locks: tauri::State<'_, Locks>,
+    path: String,
+) -> Result<(), String> {
+    let mut held = locks.held.lock().unwrap();
+    let key = key(Path::new(&path));
+    if held.get(&key).is_some_and(|h| h.window == window.label()) {
+        if let Some(h) = held.remove(&key) {
+            remove(&h.lock_path);
+        }
+    }
+    Ok(())
+}
+
+/// Who holds the lock on `path`, if anyone (including this user).
+#[tauri::command]
+pub async fn get_lock_owner(path: String) -> Result<Option<LockOwner>, String> {
+    Ok(lock_paths(Path::new(&path))?
+        .iter()
+        .find_map(|lock_path| read_owner(lock_path)))
+}
+
+/// Fail if someone else has `path` open, so a save can't replace their
+/// copy.
+pub fn ensure_not_locked(path: &Path) -> Result<(), CommandError> {
+    match other_owner(path) {
+        Some(owner) => Err(CommandError::new(
+            ErrorCode::FileLocked,
+            format!("{} is locked for editing by {}", path.display(), owner.user),
+        )
+        .with_path(path)),
+        None => Ok(()),
+    }
+}
+
+/// Release the locks held by a window that was closed.
+pub fn release_window(app: &tauri::AppHandle, label: &str) {
+    let locks = app.state::<Locks>();
+    locks.held.lock().unwrap().retain(|_, held| {
+        if held.window != label {
+            return true;
+        }
+        remove(&held.lock_path);
+        false
+    });
+}
+
+/// Release every lock, at exit.
+pub fn release_all(app: &tauri::AppHandle) {
+    let locks = app.state::<Locks>();
+    for (_, held) in locks.held.lock().unwrap().drain() {
+        remove(&held.lock_path);
+    }
+}
+
+/// The owner of the lock on `path` if it is someone else. A lock left
+/// behind by this user on this machine (after a crash, say) doesn't count.
+fn other_owner(path: &Path) -> Option<LockOwner> {
+    lock_paths(path)
+        .ok()?
+        .iter()
+        .filter_map(|lock_path| read_owner(lock_path))
+        .find(|owner| !is_current_user(owner))
+}
+
+fn is_current_user(owner: &LockOwner) -> bool {
+    let me = current_owner();
+    owner.user == me.user && owner.host == me.host
+}
+
+/// The owner of an owner file that can't be read: one still being written,
+/// or in a format not recognised here.
+fn unknown_owner() -> LockOwner {
+    LockOwner {
+        user: "Unknown user".to_string(),
+        host: String::new(),
+        locked_at: None,
+    }
+}
+
+fn read_owner(lock_path: &Path) -> Option<LockOwner> {
+    let data = fs::read(lock_path).ok()?;
+    serde_json::from_slice(&data)
+        .ok()
+        .or_else(|| office_owner(&data))
+}
+
+/// Read the owner from an owner file written by Office.
+fn office_owner(data: &[u8]) -> Option<LockOwner> {
+    let len = usize::from(*data.first()?).min(OFFICE_NAME_LENGTH);
+    let name = data.get(1..1 + len)?;
+    let user: String = name.iter().map(|&b| b as char).collect();
+    let user = user.trim().to_string();
+    if user.is_empty() {
+        return None;
+    }
+    Some(LockOwner {
+        user,
+        host: String::new(),
+        locked_at: None,
+    })
+}
+
+fn current_owner() -> LockOwner {
+    let user = env::var("USERNAME")
+        .or_else(|_| env::var("USER"))
+        .unwrap_or_else(|_| "Unknown user".to_string());
+    let host = env::var("COMPUTERNAME")
+        .or_else(|_| env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .unwrap_or_default();
+    LockOwner {
+        user,
+        host,
+        locked_at: None,
+    }
+}
+
+/// The owner files `path` may have, the one this app writes first: named
+/// as Office names them for the document's type, then `~$` and the whole
+/// name and, for Word documents, every other shortening Word uses.
+fn lock_paths(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?
+        .to_string_lossy();
+    let stem_length = Path::new(name.as_ref())
+        .file_stem()
+        .map_or(0, |stem| stem.to_string_lossy().chars().count());
+    let is_word = Path::new(name.as_ref())
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .is_some_and(|ext| WORD_EXTENSIONS.contains(&ext.as_str()));
+
+    let written = match stem_length {
+        _ if !is_word => 0,
+        0..=6 => 0,
+        7 => 1,
+        _ => 2,
+    };
+    let mut dropped = vec![written, 0];
+    if is_word {
+        dropped.extend(1..stem_length.min(3));
+    }
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for n in dropped {
+        let lock_name: String = name.chars().skip(n).collect();
+        let lock_path = path.with_file_name(format!("{}{}", LOCK_PREFIX, lock_name));
+        if !paths.contains(&lock_path) {
+            paths.push(lock_path);
+        }
+    }
+    Ok(paths)
+}
+
+fn remove(lock_path: &Path) {
+    if let Err(e) = fs::remove_file(lock_path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to unlock file: {}", e);
+        }
+    }
+}
+
+/// The same document reached through a different spelling of its path has
+/// one lock.
+fn key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_names(name: &str) -> Vec<String> {
+        lock_paths(&Path::new("/docs").join(name))
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn owner_files_are_named_as_office_names_them() {
+        assert_eq!(lock_names("Document.docx")[0], "~$cument.docx");
+        assert_eq!(lock_names("Letter7.docx")[0], "~$etter7.docx");
+        assert_eq!(lock_names("Memo.doc")[0], "~$Memo.doc");
+        assert_eq!(
+            lock_names("Quarterly budget.xlsx"),
+            ["~$Quarterly budget.xlsx"]
+        );
+        assert_eq!(lock_names("Slides.pdf"), ["~$Slides.pdf"]);
+    }
+
+    #[test]
+    fn every_spelling_word_uses_is_checked() {
+        assert_eq!(
+            lock_names("Report.docx"),
+            ["~$Report.docx", "~$eport.docx", "~$port.docx"]
+        );
+        assert_eq!(
+            lock_names("Document.docx"),
+            ["~$cument.docx", "~$Document.docx", "~$ocument.docx"]
+        );
+    }
+}
@@ -1,2 +1,24 @@
+pub mod annotations;
+pub mod backups;
 pub mod documents;
+pub mod error;
+pub mod exchange;
+pub mod forms;
+pub mod inspect;
+pub mod library;
+pub mod locks;
+pub mod optimize;
+pub mod pages;
+pub mod pdf;
+pub mod recovery;
+pub mod redaction;
+pub mod security;
 pub mod settings;
+pub mod signing;
+#[cfg(test)]
+mod test_support;
+pub mod text;
+pub mod verification;
+pub mod versions;
+pub mod watcher;
+pub mod workspace;
@@ -0,0 +1,762 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::mem;
+use std::path::Path;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use jpeg_encoder::{ColorType, Encoder};
+use lopdf::xref::XrefType;
+use lopdf::{Dictionary, Document, Object, ObjectId, SaveOptions, Stream};
+use serde::{Deserialize, Serialize};
+use zune_jpeg::zune_core::bytestream::ZCursor;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::JpegDecoder;
+
+use super::documents::{overwrite, write_atomic};
+use super::error::CommandError;
+use super::pdf::{save_document_with, PdfEditor, SaveMode};
+use super::redaction::color_components;
+use super::security;
+use super::text::{walk_page, PaintEvent};
+
+/// Images are resampled only when they are this much sharper than the
+/// target, so ones just above it aren't blurred for a small saving.
+const DOWNSAMPLE_THRESHOLD: f32 = 1.5;
+
+/// Filters whose data we can decode and store as Flate instead without
+/// losing anything. Image codecs (DCT, JPX, JBIG2, CCITT) aren't among them;
+/// JPEG images are only re-encoded when they are downsampled.
+const LOSSLESS_FILTERS: [&[u8]; 5] = [
+    b"FlateDecode",
+    b"LZWDecode",
+    b"ASCII85Decode",
+    b"ASCIIHexDecode",
+    b"RunLengthDecode",
+];
+
+/// What the optimized file is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OptimizePreset {
+    /// Smallest file: images down to 150 dpi, JPEGs at quality 60.
+    Email,
+    /// Images down to 300 dpi, enough for office printers, JPEGs at
+    /// quality 85.
+    Print,
+    /// Lossless only, and no object or cross-reference streams, which
+    /// PDF/A-1 doesn't allow.
+    Archive,
+}
+
+impl OptimizePreset {
+    fn image_dpi(self) -> Option<u32> {
+        match self {
+            OptimizePreset::Email => Some(150),
+            OptimizePreset::Print => Some(300),
+            OptimizePreset::Archive => None,
+        }
+    }
+
+    /// The quality (1-100) JPEG images are saved at once downsampled.
+    fn jpeg_quality(self) -> u8 {
+        match self {
+            OptimizePreset::Email => 60,
+            OptimizePreset::Print => 85,
+            OptimizePreset::Archive => 95,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeOptions {
+    pub preset: OptimizePreset,
+    /// Overrides the preset's image resolution, in dots per inch; 0 leaves
+    /// images alone.
+    pub image_dpi: Option<u32>,
+}
+
+/// What `optimize_pdf` did, with the sizes in bytes.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeReport {
+    pub original_size: u64,
+    pub optimized_size: u64,
+    pub duplicates_removed: usize,
+    /// Duplicate fonts among `duplicates_removed`.
+    pub fonts_merged: usize,
+    pub streams_recompressed: usize,
+    pub images_downsampled: usize,
+    /// Images above the target resolution that were left as they are,
+    /// since they are JPEG 2000, JBIG2, CCITT or CMYK JPEG data, or have a
+    /// sample layout we don't resample.
+    pub images_skipped: usize,
+}
+
+/// Shrink the PDF at `source_path` and save it to `output_path`.
+#[tauri::command]
+pub async fn optimize_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    options: OptimizeOptions,
+) -> Result<OptimizeReport, CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        optimize(&source_path, &output_path, &options)
+    })
+}
+
+/// Optimize a PDF for size.
+///
+/// In order: images drawn above the target resolution are downsampled
+/// (JPEGs are re-encoded at the preset's quality), every stream stored uncompressed or with a lossless filter is deflated
+/// at the best compression level (kept only if smaller), objects nothing
+/// refers to are dropped, and identical objects, fonts included, are
+/// stored once. The result is written with object streams and a
+/// cross-reference stream, except for the archive preset.
+///
+/// ## Known limitations
+/// - JPEG 2000, JBIG2 and CCITT images, and JPEGs in CMYK, keep their
+///   resolution; they're counted in `images_skipped`.
+/// - Images only drawn by annotations, or inline in content streams, are
+///   left alone.
+/// - Encrypted PDFs are saved without object streams, which lopdf doesn't
+///   encrypt, so one that used them can come out larger.
+/// - The file is rewritten in full, so existing digital signatures no
+///   longer verify.
+pub fn optimize(
+    source_path: &str,
+    output_path: &str,
+    options: &OptimizeOptions,
+) -> Result<OptimizeReport, CommandError> {
+    let mut report = OptimizeReport {
+        original_size: fs::metadata(source_path)
+            .map_err(|e| format!("Failed to read file: {}", e))?
+            .len(),
+        ..OptimizeReport::default()
+    };
+    let mut editor = PdfEditor::open(source_path, SaveMode::Rewrite)?;
+    let doc = &mut editor.doc;
+
+    let dpi = options
+        .image_dpi
+        .or(options.preset.image_dpi())
+        .filter(|dpi| *dpi > 0);
+    if let Some(dpi) = dpi {
+        downsample_images(doc, dpi as f32, options.preset.jpeg_quality(), &mut report);
+    }
+    for object in doc.objects.values_mut() {
+        if let Object::Stream(stream) = object {
+            if recompress(stream) {
+                report.streams_recompressed += 1;
+            }
+        }
+    }
+    doc.prune_objects();
+    deduplicate(doc, &mut report);
+
+    let compact = options.preset != OptimizePreset::Archive;
+    if !compact {
+        doc.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+        // A trailer read from a cross-reference stream still has the
+        // stream's entries, which lopdf would copy into the table's trailer
+        for key in [
+            "Type",
+            "W",
+            "Index",
+            "Filter",
+            "DecodeParms",
+            "Length",
+            "Prev",
+        ] {
+            doc.trailer.remove(key.as_bytes());
+        }
+    }
+    let save_options = SaveOptions::builder()
+        .use_object_streams(compact)
+        .use_xref_streams(compact)
+        .compression_level(9)
+        .build();
+
+    let password = editor.output_password();
+    let bytes = save_document_with(&mut editor.doc, save_options)?;
+    write_atomic(Path::new(output_path), &bytes)?;
+    security::remember_password(Path::new(output_path), password.as_deref());
+    report.optimized_size = bytes.len() as u64;
+    Ok(report)
+}
+
+/// Resample the images drawn on the pages at more than `dpi` (times
+/// `DOWNSAMPLE_THRESHOLD`) down to `dpi`. An image's resolution is taken
+/// from the largest size it's drawn at. JPEGs are saved at `jpeg_quality`.
+fn downsample_images(doc: &mut Document, dpi: f32, jpeg_quality: u8, report: &mut OptimizeReport) {
+    // Largest drawn width and height of each image, in points
+    let mut drawn: BTreeMap<ObjectId, (f32, f32)> = BTreeMap::new();
+    for page_id in doc.page_iter().collect::<Vec<_>>() {
+        let _ = walk_page(doc, page_id, &mut |event| {
+            if let PaintEvent::Image {
+                id: Some(id), ctm, ..
+            } = event
+            {
+                let [a, b, c, d, _, _] = ctm.0;
+                let size = drawn.entry(id).or_insert((0.0, 0.0));
+                size.0 = size.0.max(a.hypot(b));
+                size.1 = size.1.max(c.hypot(d));
+            }
+        });
+    }
+
+    // Soft masks can be shared between images; each is resampled once
+    let mut resampled: BTreeSet<ObjectId> = BTreeSet::new();
+    for (id, (drawn_width, drawn_height)) in drawn {
+        if resampled.contains(&id) {
+            continue;
+        }
+        let Ok(image) = doc.get_object(id).and_then(Object::as_stream) else {
+            continue;
+        };
+        let (Ok(width), Ok(height)) = (
+            image.dict.get(b"Width").and_then(Object::as_i64),
+            image.dict.get(b"Height").and_then(Object::as_i64),
+        ) else {
+            continue;
+        };
+        if drawn_width <= 0.0 || drawn_height <= 0.0 {
+            continue;
+        }
+        let resolution =
+            (width as f32 * 72.0 / drawn_width).min(height as f32 * 72.0 / drawn_height);
+        if resolution <= dpi * DOWNSAMPLE_THRESHOLD {
+            continue;
+        }
+
+        let scale = dpi / resolution;
+        let Some(image_resampled) = downsample(doc, image, scale, jpeg_quality) else {
+            report.images_skipped += 1;
+            continue;
+        };
+        let soft_mask = image
+            .dict
+            .get(b"SMask")
+            .and_then(Object::as_reference)
+            .ok()
+            .filter(|mask_id| !resampled.contains(mask_id))
+            .and_then(|mask_id| {
+                let mask = doc.get_object(mask_id).and_then(Object::as_stream).ok()?;
+                Some((mask_id, downsample(doc, mask, scale, jpeg_quality)?))
+            });
+        doc.objects.insert(id, Object::Stream(image_resampled));
+        resampled.insert(id);
+        if let Some((mask_id, mask)) = soft_mask {
+            doc.objects.insert(mask_id, Object::Stream(mask));
+            resampled.insert(mask_id);
+        }
+        report.images_downsampled += 1;
+    }
+}
+
+/// A copy of an image with its width and height multiplied by `scale`.
+/// Only 8-bit images are resampled: pixels are averaged, or for indexed
+/// colour the nearest one is picked. Ones stored losslessly come out
+/// Flate-compressed, and gray or RGB JPEGs as JPEGs of `jpeg_quality`.
+fn downsample(doc: &Document, image: &Stream, scale: f32, jpeg_quality: u8) -> Option<Stream> {
+    let dict = &image.dict;
+    if dict.get(b"ImageMask").and_then(Object::as_bool).ok() == Some(true)
+        || dict.get(b"BitsPerComponent").and_then(Object::as_i64).ok() != Some(8)
+    {
+        return None;
+    }
+    let filters = stream_filters(image);
+    if filters == [b"DCTDecode"] {
+        return downsample_jpeg(doc, image, scale, jpeg_quality);
+    }
+    if !filters.iter().all(|f| LOSSLESS_FILTERS.contains(f)) {
+        return None;
+    }
+    let width = usize::try_from(dict.get(b"Width").and_then(Object::as_i64).ok()?).ok()?;
+    let height = usize::try_from(dict.get(b"Height").and_then(Object::as_i64).ok()?).ok()?;
+    let (components, indexed) = match dict.get(b"ColorSpace") {
+        Ok(color_space) => {
+            let indexed = doc
+                .dereference(color_space)
+                .ok()
+                .and_then(|(_, cs)| cs.as_array().ok()?.first()?.as_name().ok())
+                == Some(b"Indexed".as_slice());
+            (color_components(doc, color_space)?, indexed)
+        }
+        // Soft masks are DeviceGray without saying so
+        Err(_) => (1, false),
+    };
+
+    let data = decoded(image)?;
+    if width == 0 || height == 0 || data.len() < width * height * components {
+        return None;
+    }
+    let new_width = ((width as f32 * scale).round() as usize).clamp(1, width);
+    let new_height = ((height as f32 * scale).round() as usize).clamp(1, height);
+    let samples = resample(
+        &data,
+        components,
+        (width, height),
+        (new_width, new_height),
+        !indexed,
+    );
+
+    let mut dict = dict.clone();
+    dict.set("Width", new_width as i64);
+    dict.set("Height", new_height as i64);
+    dict.set("Filter", "FlateDecode");
+    dict.remove(b"DecodeParms");
+    Some(Stream::new(dict, deflate(&samples)?))
+}
+
+/// `downsample` for a baseline or progressive JPEG in gray or RGB. CMYK
+/// JPEGs, and ones with decode parameters (which can override the colour
+/// transform the JPEG declares), are left alone.
+fn downsample_jpeg(doc: &Document, image: &Stream, scale: f32, quality: u8) -> Option<Stream> {
+    let dict = &image.dict;
+    if dict.has(b"DecodeParms") {
+        return None;
+    }
+    let mut decoder = JpegDecoder::new(ZCursor::new(&image.content));
+    decoder.decode_headers().ok()?;
+    let (components, color_type, output) = match decoder.input_colorspace()? {
+        ColorSpace::Luma => (1, ColorType::Luma, ColorSpace::Luma),
+        ColorSpace::YCbCr | ColorSpace::RGB => (3, ColorType::Rgb, ColorSpace::RGB),
+        _ => return None,
+    };
+    // The image's colour space has to agree, or the samples mean something else
+    let declared = match dict.get(b"ColorSpace") {
+        Ok(color_space) => color_components(doc, color_space)?,
+        Err(_) => components,
+    };
+    if declared != components {
+        return None;
+    }
+    decoder.set_options(decoder.options().jpeg_set_out_colorspace(output));
+    let data = decoder.decode().ok()?;
+    let (width, height) = decoder.dimensions()?;
+    if width == 0 || height == 0 || data.len() < width * height * components {
+        return None;
+    }
+
+    let new_width = ((width as f32 * scale).round() as usize).clamp(1, width);
+    let new_height = ((height as f32 * scale).round() as usize).clamp(1, height);
+    let samples = resample(
+        &data,
+        components,
+        (width, height),
+        (new_width, new_height),
+        true,
+    );
+    let mut encoded = Vec::new();
+    Encoder::new(&mut encoded, quality)
+        .encode(
+            &samples,
+            u16::try_from(new_width).ok()?,
+            u16::try_from(new_height).ok()?,
+            color_type,
+        )
+        .ok()?;
+
+    let mut dict = dict.clone();
+    dict.set("Width", new_width as i64);
+    dict.set("Height", new_height as i64);
+    Some(Stream::new(dict, encoded).with_compression(false))
+}
+
+/// Scale 8-bit samples of `components` channels from `from` to `to`
+/// pixels (each no larger), averaging the source pixels under each target
+/// pixel, or taking the first of them when `average` is false.
+fn resample(
+    data: &[u8],
+    components: usize,
+    from: (usize, usize),
+    to: (usize, usize),
+    average: bool,
+) -> Vec<u8> {
+    let (width, height) = from;
+    let (new_width, new_height) = to;
+    let mut out = Vec::with_capacity(new_width * new_height * components);
+    let mut sums = vec![0u32; components];
+    for ty in 0..new_height {
+        let y0 = ty * height / new_height;
+        let y1 = ((ty + 1) * height / new_height).max(y0 + 1);
+        for tx in 0..new_width {
+            let x0 = tx * width / new_width;
+            let x1 = ((tx + 1) * width / new_width).max(x0 + 1);
+            if !average {
+                let start = (y0 * width + x0) * components;
+                out.extend_from_slice(&data[start..start + components]);
+                continue;
+            }
+            sums.fill(0);
+            for y in y0..y1 {
+                let row = &data[(y * width + x0) * components..(y * width + x1) * components];
+                for pixel in row.chunks_exact(components) {
+                    for (sum, sample) in sums.iter_mut().zip(pixel) {
+                        *sum += u32::from(*sample);
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u32;
+            out.extend(sums.iter().map(|sum| ((sum + count / 2) / count) as u8));
+        }
+    }
+    out
+}
+
+/// Store a stream with the best Flate compression if it is uncompressed or
+/// uses only lossless filters, and that makes it smaller. XMP metadata is
+/// left readable, as PDF/A asks.
+fn recompress(stream: &mut Stream) -> bool {
+    let filters = stream_filters(stream);
+    if !stream.allows_compression
+        || stream.dict.has_type(b"Metadata")
+        || !filters.iter().all(|f| LOSSLESS_FILTERS.contains(f))
+    {
+        return false;
+    }
+    let Some(compressed) = decoded(stream).and_then(|data| deflate(&data)) else {
+        return false;
+    };
+    if compressed.len() >= stream.content.len() {
+        return false;
+    }
+    stream.dict.set("Filter", "FlateDecode");
+    stream.dict.remove(b"DecodeParms");
+    stream.set_content(compressed);
+    true
+}
+
+fn stream_filters(stream: &Stream) -> Vec<&[u8]> {
+    stream.filters().unwrap_or_default()
+}
+
+/// The decoded data of a stream, unless its parameters are per filter,
+/// which lopdf doesn't apply.
+fn decoded(stream: &Stream) -> Option<Vec<u8>> {
+    if let Ok(Object::Array(_)) = stream.dict.get(b"DecodeParms") {
+        return None;
+    }
+    if stream.dict.get(b"Filter").is_err() {
+        return Some(stream.content.clone());
+    }
+    stream.decompressed_content().ok()
+}
+
+fn deflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+/// Store identical objects once and point every reference at the copy
+/// kept. Repeats until nothing changes, since merging two fonts' files
+/// makes their descriptors identical, then the fonts themselves.
+///
+/// Pages, annotations, form fields, structure elements and signatures are
+/// never merged: they're told apart by their object, even when equal.
+fn deduplicate(doc: &mut Document, report: &mut OptimizeReport) {
+    loop {
+        let mut candidates: BTreeMap<u64, Vec<ObjectId>> = BTreeMap::new();
+        for (id, object) in &doc.objects {
+            if mergeable(object) {
+                let mut hasher = DefaultHasher::new();
+                hash_object(object, &mut hasher);
+                candidates.entry(hasher.finish()).or_default().push(*id);
+            }
+        }
+
+        let mut replaced: BTreeMap<ObjectId, ObjectId> = BTreeMap::new();
+        for ids in candidates.values().filter(|ids| ids.len() > 1) {
+            let mut kept: Vec<ObjectId> = Vec::new();
+            for id in ids {
+                let object = &doc.objects[id];
+                match kept.iter().find(|k| &doc.objects[*k] == object) {
+                    Some(original) => {
+                        replaced.insert(*id, *original);
+                    }
+                    None => kept.push(*id),
+                }
+            }
+        }
+        if replaced.is_empty() {
+            return;
+        }
+
+        for id in replaced.keys() {
+            if let Some(object) = doc.objects.remove(id) {
+                let is_font = object.as_dict().is_ok_and(|dict| dict.has_type(b"Font"));
+                report.fonts_merged += usize::from(is_font);
+            }
+        }
+        report.duplicates_removed += replaced.len();
+        for object in doc.objects.values_mut() {
+            redirect(object, &replaced);
+        }
+        for (_, value) in doc.trailer.iter_mut() {
+            redirect(value, &replaced);
+        }
+    }
+}
+
+fn mergeable(object: &Object) -> bool {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &stream.dict,
+        Object::Array(_) | Object::String(..) | Object::Integer(_) | Object::Real(_) => {
+            return true
+        }
+        _ => return false,
+    };
+    let is_annotation = dict.has(b"Subtype") && dict.has(b"Rect");
+    let is_field = dict.has(b"FT") || dict.has(b"T");
+    !is_annotation
+        && !is_field
+        && ![
+            b"Catalog".as_slice(),
+            b"Pages",
+            b"Page",
+            b"StructElem",
+            b"Sig",
+            b"ObjStm",
+            b"XRef",
+        ]
+        .iter()
+        .any(|t| dict.has_type(t))
+}
+
+/// Point references to replaced objects at the ones kept.
+fn redirect(object: &mut Object, replaced: &BTreeMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if let Some(kept) = replaced.get(id) {
+                *id = *kept;
+            }
+        }
+        Object::Array(items) => items.iter_mut().for_each(|item| redirect(item, replaced)),
+        Object::Dictionary(dict) => redirect_dict(dict, replaced),
+        Object::Stream(stream) => redirect_dict(&mut stream.dict, replaced),
+        _ => {}
+    }
+}
+
+fn redirect_dict(dict: &mut Dictionary, replaced: &BTreeMap<ObjectId, ObjectId>) {
+    for (_, value) in dict.iter_mut() {
+        redirect(value, replaced);
+    }
+}
+
+/// Hash an object so equal objects hash the same: dictionary entries are
+/// hashed in key order, as equality ignores their order.
+fn hash_object(object: &Object, state: &mut impl Hasher) {
+    mem::discriminant(object).hash(state);
+    match object {
+        Object::Null => {}
+        Object::Boolean(value) => value.hash(state),
+        Object::Integer(value) => value.hash(state),
+        Object::Real(value) => value.to_bits().hash(state),
+        Object::Name(name) => name.hash(state),
+        Object::String(bytes, format) => {
+            bytes.hash(state);
+            mem::discriminant(format).hash(state);
+        }
+        Object::Array(items) => {
+            items.len().hash(state);
+            for item in items {
+                hash_object(item, state);
+            }
+        }
+        Object::Dictionary(dict) => hash_dict(dict, state),
+        Object::Stream(stream) => {
+            hash_dict(&stream.dict, state);
+            stream.content.hash(state);
+        }
+        Object::Reference(id) => id.hash(state),
+    }
+}
+
+fn hash_dict(dict: &Dictionary, state: &mut impl Hasher) {
+    let mut entries: Vec<(&Vec<u8>, &Object)> = dict.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries.len().hash(state);
+    for (key, value) in entries {
+        key.hash(state);
+        hash_object(value, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{image_pdf, image_stream, text_pdf};
+    use lopdf::content::{Content, Operation};
+    use lopdf::dictionary;
+
+    #[test]
+    fn resample_averages_or_picks_the_first_pixel() {
+        let gray = [10, 20, 30, 40, 50, 60, 70, 80];
+        assert_eq!(resample(&gray, 1, (4, 2), (2, 1), true), [35, 55]);
+        assert_eq!(resample(&gray, 1, (4, 2), (2, 1), false), [10, 30]);
+
+        let rgb = [0, 0, 0, 255, 255, 255];
+        assert_eq!(resample(&rgb, 3, (2, 1), (1, 1), true), [128, 128, 128]);
+    }
+
+    #[test]
+    fn downsamples_images_and_their_soft_mask() {
+        let mut image = image_stream(100, 100, "DeviceGray", vec![200; 100 * 100]);
+        let mut mask = image_stream(100, 100, "DeviceGray", vec![255; 100 * 100]);
+        mask.dict.remove(b"ColorSpace");
+        // 100 pixels over 50pt is 144 dpi
+        let (mut doc, image_id) = image_pdf(image.clone(), [100.0, 100.0, 50.0, 50.0]);
+        let mask_id = doc.add_object(mask);
+        image.dict.set("SMask", mask_id);
+        doc.objects.insert(image_id, Object::Stream(image));
+
+        let mut report = OptimizeReport::default();
+        downsample_images(&mut doc, 72.0, 85, &mut report);
+
+        assert_eq!(report.images_downsampled, 1);
+        for id in [image_id, mask_id] {
+            let stream = doc.get_object(id).unwrap().as_stream().unwrap();
+            assert_eq!(stream.dict.get(b"Width").unwrap().as_i64().unwrap(), 50);
+            assert_eq!(stream.dict.get(b"Height").unwrap().as_i64().unwrap(), 50);
+        }
+        let image = doc.get_object(image_id).unwrap().as_stream().unwrap();
+        assert!(decoded(image).unwrap().iter().all(|&v| v == 200));
+    }
+
+    #[test]
+    fn downsamples_jpegs_as_jpegs() {
+        let mut jpeg = Vec::new();
+        let pixels: Vec<u8> = [200, 40, 40].repeat(100 * 100);
+        Encoder::new(&mut jpeg, 90)
+            .encode(&pixels, 100, 100, ColorType::Rgb)
+            .unwrap();
+        let mut image = image_stream(100, 100, "DeviceRGB", jpeg);
+        image.dict.set("Filter", "DCTDecode");
+        let (mut doc, image_id) = image_pdf(image, [100.0, 100.0, 50.0, 50.0]);
+
+        let mut report = OptimizeReport::default();
+        downsample_images(&mut doc, 72.0, 85, &mut report);
+
+        assert_eq!(report.images_downsampled, 1);
+        assert_eq!(report.images_skipped, 0);
+        let image = doc.get_object(image_id).unwrap().as_stream().unwrap();
+        assert_eq!(
+            image.dict.get(b"Filter").unwrap().as_name().unwrap(),
+            b"DCTDecode"
+        );
+        assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 50);
+        let mut decoder = JpegDecoder::new(ZCursor::new(&image.content));
+        let samples = decoder.decode().unwrap();
+        assert_eq!(decoder.dimensions(), Some((50, 50)));
+        for pixel in samples.chunks_exact(3) {
+            for (sample, expected) in pixel.iter().zip([200, 40, 40]) {
+                assert!(sample.abs_diff(expected) <= 8, "{:?}", pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn resamples_a_shared_soft_mask_once() {
+        let mask = image_stream(100, 100, "DeviceGray", vec![255; 100 * 100]);
+        let (mut doc, first) = image_pdf(
+            image_stream(100, 100, "DeviceGray", vec![0; 100 * 100]),
+            [0.0, 0.0, 50.0, 50.0],
+        );
+        let mask_id = doc.add_object(mask);
+        let mut second = image_stream(100, 100, "DeviceGray", vec![0; 100 * 100]);
+        second.dict.set("SMask", mask_id);
+        let second = doc.add_object(second);
+        doc.get_object_mut(first)
+            .and_then(Object::as_stream_mut)
+            .unwrap()
+            .dict
+            .set("SMask", mask_id);
+
+        // Draw the second image too, at a quarter of its pixel size
+        let page_id = doc.page_iter().next().unwrap();
+        let mut content = Content::decode(&doc.get_page_content(page_id)).unwrap();
+        content.operations.extend([
+            Operation::new(
+                "cm",
+                vec![
+                    25.into(),
+                    0.into(),
+                    0.into(),
+                    25.into(),
+                    200.into(),
+                    200.into(),
+                ],
+            ),
+            Operation::new("Do", vec![Object::Name(b"Im2".to_vec())]),
+        ]);
+        doc.change_page_content(page_id, content.encode().unwrap())
+            .unwrap();
+        let page = doc.get_dictionary_mut(page_id).unwrap();
+        let resources = page.get_mut(b"Resources").unwrap().as_dict_mut().unwrap();
+        resources
+            .get_mut(b"XObject")
+            .unwrap()
+            .as_dict_mut()
+            .unwrap()
+            .set("Im2", second);
+
+        let mut report = OptimizeReport::default();
+        downsample_images(&mut doc, 72.0, 85, &mut report);
+
+        assert_eq!(report.images_downsampled, 2);
+        let width = |id| {
+            doc.get_object(id)
+                .and_then(Object::as_stream)
+                .unwrap()
+                .dict
+                .get(b"Width")
+                .and_then(Object::as_i64)
+                .unwrap()
+        };
+        assert_eq!(width(first), 50);
+        assert_eq!(width(second), 25);
+        // Resampled with the first image, not a second time after it
+        assert_eq!(width(mask_id), 50);
+    }
+
+    #[test]
+    fn stores_duplicate_fonts_once() {
+        let mut doc = text_pdf(&[&["One"], &["Two"]]);
+        let pages: Vec<ObjectId> = doc.page_iter().collect();
+        let font = |doc: &Document, page: ObjectId| {
+            doc.get_dictionary(page)
+                .unwrap()
+                .get(b"Resources")
+                .and_then(Object::as_dict)
+                .unwrap()
+                .get(b"Font")
+                .and_then(Object::as_dict)
+                .unwrap()
+                .get(b"F1")
+                .and_then(Object::as_reference)
+                .unwrap()
+        };
+        let copy = doc.get_object(font(&doc, pages[0])).unwrap().clone();
+        let copy_id = doc.add_object(copy);
+        doc.get_dictionary_mut(pages[1]).unwrap().set(
+            "Resources",
+            dictionary! { "Font" => dictionary! { "F1" => copy_id } },
+        );
+
+        let mut report = OptimizeReport::default();
+        deduplicate(&mut doc, &mut report);
+
+        assert_eq!(report.fonts_merged, 1);
+        assert!(report.duplicates_removed >= 1);
+        assert_eq!(font(&doc, pages[0]), font(&doc, pages[1]));
+        assert_eq!(doc.get_pages().len(), 2);
+    }
+}
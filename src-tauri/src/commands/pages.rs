@@ -0,0 +1,1074 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId};
+use serde::Deserialize;
+use tauri_plugin_dialog::DialogExt;
+
+use super::documents::{overwrite, write_atomic};
+use super::error::CommandError;
+use super::forms::remove_widgets;
+use super::pdf::{inherited_attribute, save_document, PdfEditor, SaveMode};
+use super::security;
+
+/// Page attributes a page may inherit from its ancestors in the page tree
+/// (ISO 32000-1, table 30). They're copied onto each page before the tree is
+/// rebuilt, since the intermediate nodes go away.
+const INHERITABLE_PAGE_KEYS: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// An inclusive, 1-based range of pages.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// How to split a document: one output per range, or a new output every
+/// `pages` pages.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "by", rename_all = "camelCase")]
+pub enum SplitMode {
+    Ranges { ranges: Vec<PageRange> },
+    Every { pages: u32 },
+}
+
+/// Ask the user for one or more PDFs, e.g. to merge.
+#[tauri::command]
+pub async fn open_pdfs_dialog(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let files = app
+        .dialog()
+        .file()
+        .add_filter("PDF Files", &["pdf"])
+        .blocking_pick_files();
+
+    Ok(files
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| path.to_string())
+        .collect())
+}
+
+/// Ask the user for a folder to write split documents into.
+#[tauri::command]
+pub async fn pick_folder_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let folder = app.dialog().file().blocking_pick_folder();
+    Ok(folder.map(|path| path.to_string()))
+}
+
+/// Concatenate the PDFs at `source_paths`, in order, into `output_path`.
+#[tauri::command]
+pub async fn merge_pdfs(
+    app: tauri::AppHandle,
+    source_paths: Vec<String>,
+    output_path: String,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        merge(&source_paths, &output_path)
+    })
+}
+
+/// Split the PDF at `source_path` into several files in `output_dir`.
+/// Returns the paths written.
+#[tauri::command]
+pub async fn split_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_dir: String,
+    mode: SplitMode,
+) -> Result<Vec<String>, CommandError> {
+    split(&source_path, &output_dir, &mode, |path, bytes| {
+        overwrite(&app, path, || write_atomic(path, bytes))
+    })
+}
+
+/// Rearrange the pages of a PDF. `order` lists every page number once, in
+/// the new order.
+#[tauri::command]
+pub async fn reorder_pages(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    order: Vec<u32>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        reorder(&source_path, &output_path, &order)
+    })
+}
+
+/// Rotate the given pages clockwise by `degrees` (a multiple of 90).
+#[tauri::command]
+pub async fn rotate_pages(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    pages: Vec<u32>,
+    degrees: i64,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        rotate(&source_path, &output_path, &pages, degrees)
+    })
+}
+
+#[tauri::command]
+pub async fn delete_pages(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    pages: Vec<u32>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        delete(&source_path, &output_path, &pages)
+    })
+}
+
+/// Merge PDFs into one document.
+///
+/// Each document's objects are renumbered into the first one, and its
+/// pages appended to a single flat page tree. Bookmarks are appended to the
+/// first document's outline and links keep working, since they point at
+/// page objects that are carried over. Named destinations are resolved to
+/// explicit ones first, so equal names in different documents can't clash.
+///
+/// ## Known limitations
+/// - Form fields with the same name in different documents are kept as
+///   separate fields, which viewers may fill together.
+/// - Document-level metadata (title, page labels, JavaScript) comes from the
+///   first document only.
+/// - So does the encryption: the output is protected only if the first
+///   document is.
+pub fn merge(source_paths: &[String], output_path: &str) -> Result<(), CommandError> {
+    let (first, rest) = match source_paths {
+        [first, rest @ ..] if !rest.is_empty() => (first, rest),
+        _ => return Err("Select at least two PDFs to merge".to_string().into()),
+    };
+
+    let first = PdfEditor::open(first, SaveMode::Rewrite)?;
+    let password = first.output_password();
+    let mut merged = first.doc;
+    resolve_named_destinations(&mut merged);
+    inline_inherited_attributes(&mut merged);
+    let mut pages: Vec<ObjectId> = merged.page_iter().collect();
+    let mut outline_items = outline_children(&merged, outline_root(&merged));
+
+    for path in rest {
+        let mut doc = PdfEditor::open(path, SaveMode::Rewrite)?.doc;
+        resolve_named_destinations(&mut doc);
+        inline_inherited_attributes(&mut doc);
+        doc.renumber_objects_with(merged.max_id + 1);
+
+        pages.extend(doc.page_iter());
+        outline_items.extend(outline_children(&doc, outline_root(&doc)));
+        let form = form_parts(&doc);
+
+        merged.max_id = doc.max_id;
+        merged.objects.extend(doc.objects);
+        if let Some(form) = form {
+            append_form_fields(&mut merged, form)?;
+        }
+    }
+
+    set_page_tree(&mut merged, &pages)?;
+    if !outline_items.is_empty() {
+        let root = match outline_root(&merged) {
+            Some(root) => root,
+            None => {
+                let root = merged.add_object(dictionary! { "Type" => "Outlines" });
+                catalog_mut(&mut merged)?.set("Outlines", root);
+                root
+            }
+        };
+        link_outline_items(&mut merged, root, &outline_items)?;
+        update_outline_counts(&mut merged, root);
+    }
+    merged.prune_objects();
+    write_document(&mut merged, output_path, password.as_deref())
+}
+
+/// Split a PDF into one file per range, named after the source with the
+/// pages each contains (e.g. `invoices_p1-3.pdf`). A name that's already
+/// taken gets a number instead of replacing the file there
+/// (`invoices_p1-3 (2).pdf`). Each part's bytes are handed to `write`.
+pub fn split(
+    source_path: &str,
+    output_dir: &str,
+    mode: &SplitMode,
+    mut write: impl FnMut(&Path, &[u8]) -> Result<(), CommandError>,
+) -> Result<Vec<String>, CommandError> {
+    let editor = PdfEditor::open(source_path, SaveMode::Rewrite)?;
+    let password = editor.output_password();
+    let page_count = editor.doc.get_pages().len() as u32;
+
+    let ranges = match mode {
+        SplitMode::Ranges { ranges } => {
+            for range in ranges {
+                if range.start == 0 || range.start > range.end {
+                    return Err(format!("Invalid page range {}-{}", range.start, range.end).into());
+                }
+                check_page_number(range.end, page_count)?;
+            }
+            ranges.clone()
+        }
+        SplitMode::Every { pages: 0 } => {
+            return Err("Split size must be at least one page".to_string().into())
+        }
+        SplitMode::Every { pages } => (1..=page_count)
+            .step_by(*pages as usize)
+            .map(|start| PageRange {
+                start,
+                end: start.saturating_add(pages - 1).min(page_count),
+            })
+            .collect(),
+    };
+    if ranges.is_empty() {
+        return Err("No page ranges to split into".to_string().into());
+    }
+
+    let stem = Path::new(source_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "document".to_string());
+    let mut written = Vec::new();
+    for range in ranges {
+        let mut part = editor.doc.clone();
+        let keep: Vec<u32> = (range.start..=range.end).collect();
+        keep_pages(&mut part, &keep)?;
+
+        let name = if range.start == range.end {
+            format!("{}_p{}", stem, range.start)
+        } else {
+            format!("{}_p{}-{}", stem, range.start, range.end)
+        };
+        let path = free_path(Path::new(output_dir), &name);
+        write(&path, &save_document(&mut part)?)?;
+        security::remember_password(&path, password.as_deref());
+        written.push(path.to_string_lossy().into_owned());
+    }
+    Ok(written)
+}
+
+pub fn reorder(source_path: &str, output_path: &str, order: &[u32]) -> Result<(), CommandError> {
+    let mut editor = PdfEditor::open(source_path, SaveMode::Rewrite)?;
+    let page_count = editor.doc.get_pages().len() as u32;
+    let mut seen = BTreeSet::new();
+    for &page in order {
+        check_page_number(page, page_count)?;
+        if !seen.insert(page) {
+            return Err(format!("Page {} appears more than once", page).into());
+        }
+    }
+    if seen.len() as u32 != page_count {
+        return Err(format!(
+            "The new order must list all {} pages exactly once",
+            page_count
+        )
+        .into());
+    }
+
+    keep_pages(&mut editor.doc, order)?;
+    editor.save(output_path)
+}
+
+pub fn rotate(
+    source_path: &str,
+    output_path: &str,
+    pages: &[u32],
+    degrees: i64,
+) -> Result<(), CommandError> {
+    if degrees % 90 != 0 {
+        return Err("Pages can only be rotated in steps of 90 degrees"
+            .to_string()
+            .into());
+    }
+    let mut editor = PdfEditor::open(source_path, SaveMode::Rewrite)?;
+    let page_ids = editor.doc.get_pages();
+    for &page in pages {
+        check_page_number(page, page_ids.len() as u32)?;
+        let page_id = page_ids[&page];
+        let current = inherited_attribute(&editor.doc, page_id, b"Rotate")
+            .and_then(|r| r.as_i64().ok())
+            .unwrap_or(0);
+        editor
+            .doc
+            .get_dictionary_mut(page_id)
+            .map_err(|e| format!("Failed to update page: {}", e))?
+            .set("Rotate", (current + degrees).rem_euclid(360));
+    }
+    editor.save(output_path)
+}
+
+pub fn delete(source_path: &str, output_path: &str, pages: &[u32]) -> Result<(), CommandError> {
+    let mut editor = PdfEditor::open(source_path, SaveMode::Rewrite)?;
+    let page_count = editor.doc.get_pages().len() as u32;
+    for &page in pages {
+        check_page_number(page, page_count)?;
+    }
+    let removed: BTreeSet<u32> = pages.iter().copied().collect();
+    let keep: Vec<u32> = (1..=page_count).filter(|p| !removed.contains(p)).collect();
+    if keep.is_empty() {
+        return Err("Cannot delete every page of a document".to_string().into());
+    }
+
+    keep_pages(&mut editor.doc, &keep)?;
+    editor.save(output_path)
+}
+
+fn check_page_number(page: u32, page_count: u32) -> Result<(), String> {
+    if page == 0 || page > page_count {
+        return Err(format!(
+            "Page {} does not exist (the document has {} pages)",
+            page, page_count
+        ));
+    }
+    Ok(())
+}
+
+/// `dir/<name>.pdf`, or `dir/<name> (2).pdf` and so on if that exists.
+fn free_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.pdf", name));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).pdf", name, n));
+        n += 1;
+    }
+    path
+}
+
+fn write_document(
+    doc: &mut Document,
+    path: &str,
+    password: Option<&str>,
+) -> Result<(), CommandError> {
+    let bytes = save_document(doc)?;
+    write_atomic(Path::new(path), &bytes)?;
+    security::remember_password(Path::new(path), password);
+    Ok(())
+}
+
+fn catalog_mut(doc: &mut Document) -> Result<&mut Dictionary, String> {
+    doc.catalog_mut()
+        .map_err(|e| format!("Failed to read document catalog: {}", e))
+}
+
+/// Rebuild the document with only the given pages (1-based), in the given
+/// order. Bookmarks, links and named destinations pointing at dropped pages
+/// are removed, as are form fields whose widgets were all on them.
+fn keep_pages(doc: &mut Document, pages: &[u32]) -> Result<(), String> {
+    let page_ids = doc.get_pages();
+    let kept: Vec<ObjectId> = pages.iter().map(|n| page_ids[n]).collect();
+    let kept_set: BTreeSet<ObjectId> = kept.iter().copied().collect();
+    let removed: BTreeSet<ObjectId> = page_ids
+        .values()
+        .copied()
+        .filter(|id| !kept_set.contains(id))
+        .collect();
+
+    inline_inherited_attributes(doc);
+    set_page_tree(doc, &kept)?;
+    if !removed.is_empty() {
+        // Resolve names first so links to dropped pages are found however
+        // they're written.
+        resolve_named_destinations(doc);
+        remove_links_to(doc, &kept, &removed);
+        if let Some(root) = outline_root(doc) {
+            prune_outline(doc, root, &removed, 0);
+            update_outline_counts(doc, root);
+        }
+        remove_named_destinations_to(doc, &removed);
+        remove_page_widgets(doc, &removed)?;
+
+        let open_action_dead = doc
+            .catalog()
+            .ok()
+            .and_then(|c| c.get(b"OpenAction").ok())
+            .and_then(|action| action_target(doc, action))
+            .is_some_and(|page| removed.contains(&page));
+        if open_action_dead {
+            catalog_mut(doc)?.remove(b"OpenAction");
+        }
+    }
+    doc.prune_objects();
+    Ok(())
+}
+
+/// Copy inheritable attributes down onto every page.
+fn inline_inherited_attributes(doc: &mut Document) {
+    let pages: Vec<ObjectId> = doc.page_iter().collect();
+    for page_id in pages {
+        for key in INHERITABLE_PAGE_KEYS {
+            let Some(value) = inherited_raw(doc, page_id, key) else {
+                continue;
+            };
+            if let Ok(page) = doc.get_dictionary_mut(page_id) {
+                if !page.has(key) {
+                    page.set(key, value);
+                }
+            }
+        }
+    }
+}
+
+/// Like `inherited_attribute`, but without dereferencing the value, so
+/// shared resources stay shared when copied.
+fn inherited_raw(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+    let mut dict = doc.get_dictionary(page_id).ok()?;
+    // Guard against malformed, cyclic page trees.
+    for _ in 0..64 {
+        if let Ok(value) = dict.get(key) {
+            return Some(value.clone());
+        }
+        let parent = dict.get(b"Parent").and_then(Object::as_reference).ok()?;
+        dict = doc.get_dictionary(parent).ok()?;
+    }
+    None
+}
+
+/// Replace the page tree with a single node listing `pages` in order.
+/// Intermediate nodes of the old tree become unreferenced, for the caller
+/// to prune.
+fn set_page_tree(doc: &mut Document, pages: &[ObjectId]) -> Result<(), String> {
+    let root = doc
+        .catalog()
+        .and_then(|c| c.get(b"Pages"))
+        .and_then(Object::as_reference)
+        .map_err(|e| format!("Failed to read page tree: {}", e))?;
+    for &page in pages {
+        doc.get_dictionary_mut(page)
+            .map_err(|e| format!("Failed to update page: {}", e))?
+            .set("Parent", root);
+    }
+    let tree = doc
+        .get_dictionary_mut(root)
+        .map_err(|e| format!("Failed to update page tree: {}", e))?;
+    for key in INHERITABLE_PAGE_KEYS {
+        tree.remove(key);
+    }
+    tree.set(
+        "Kids",
+        pages
+            .iter()
+            .map(|&id| Object::Reference(id))
+            .collect::<Vec<_>>(),
+    );
+    tree.set("Count", pages.len() as i64);
+    Ok(())
+}
+
+/// The page an explicit destination points at (ISO 32000-1, 12.3.2.2).
+fn destination_page(doc: &Document, dest: &Object) -> Option<ObjectId> {
+    match doc.dereference(dest).ok()?.1 {
+        Object::Array(dest) => dest.first()?.as_reference().ok(),
+        Object::Dictionary(dict) => destination_page(doc, dict.get(b"D").ok()?),
+        _ => None,
+    }
+}
+
+/// The page a GoTo action jumps to.
+fn action_target(doc: &Document, action: &Object) -> Option<ObjectId> {
+    let action = doc.dereference(action).ok()?.1;
+    match action {
+        Object::Array(_) => destination_page(doc, action),
+        Object::Dictionary(dict) => {
+            if dict.get(b"S").and_then(Object::as_name).ok() != Some(b"GoTo") {
+                return None;
+            }
+            destination_page(doc, dict.get(b"D").ok()?)
+        }
+        _ => None,
+    }
+}
+
+/// The page an outline item or link annotation jumps to, through /Dest or
+/// a GoTo action.
+fn link_target(doc: &Document, dict: &Dictionary) -> Option<ObjectId> {
+    match dict.get(b"Dest") {
+        Ok(dest) => destination_page(doc, dest),
+        Err(_) => action_target(doc, dict.get(b"A").ok()?),
+    }
+}
+
+/// Collect every named destination, from the catalog's /Dests dictionary
+/// and the /Names /Dests name tree.
+fn named_destinations(doc: &Document) -> BTreeMap<Vec<u8>, Object> {
+    let mut names = BTreeMap::new();
+    let Ok(catalog) = doc.catalog() else {
+        return names;
+    };
+    if let Ok(dests) = catalog.get_deref(b"Dests", doc).and_then(Object::as_dict) {
+        for (name, dest) in dests.iter() {
+            names.insert(name.clone(), dest.clone());
+        }
+    }
+    let tree = catalog
+        .get_deref(b"Names", doc)
+        .and_then(Object::as_dict)
+        .and_then(|n| n.get_deref(b"Dests", doc))
+        .and_then(Object::as_dict);
+    let mut stack: Vec<&Dictionary> = tree.into_iter().collect();
+    // Bound the walk to guard against cyclic name trees.
+    let mut budget = 10_000;
+    while let Some(node) = stack.pop() {
+        budget -= 1;
+        if budget == 0 {
+            break;
+        }
+        if let Ok(pairs) = node.get_deref(b"Names", doc).and_then(Object::as_array) {
+            for pair in pairs.chunks(2) {
+                if let [Object::String(name, _), dest] = pair {
+                    names.insert(name.clone(), dest.clone());
+                }
+            }
+        }
+        if let Ok(kids) = node.get_deref(b"Kids", doc).and_then(Object::as_array) {
+            stack.extend(kids.iter().filter_map(|kid| {
+                doc.dereference(kid)
+                    .ok()
+                    .and_then(|(_, kid)| kid.as_dict().ok())
+            }));
+        }
+    }
+
+    // Normalize each entry to its destination array.
+    names
+        .into_iter()
+        .filter_map(|(name, dest)| {
+            let explicit = match doc.dereference(&dest).ok()?.1 {
+                Object::Dictionary(dict) => doc.dereference(dict.get(b"D").ok()?).ok()?.1,
+                other => other,
+            };
+            matches!(explicit, Object::Array(_)).then(|| (name, explicit.clone()))
+        })
+        .collect()
+}
+
+/// Replace named destinations in outline items, links and GoTo actions with
+/// the explicit destinations they name.
+fn resolve_named_destinations(doc: &mut Document) {
+    let names = named_destinations(doc);
+    if names.is_empty() {
+        return;
+    }
+    let resolve = |value: &mut Object| {
+        let name = match value {
+            Object::Name(name) | Object::String(name, _) => name,
+            _ => return,
+        };
+        if let Some(dest) = names.get(name.as_slice()) {
+            *value = dest.clone();
+        }
+    };
+    let resolve_dict = |dict: &mut Dictionary| {
+        if let Ok(dest) = dict.get_mut(b"Dest") {
+            resolve(dest);
+        }
+        if dict.get(b"S").and_then(Object::as_name).ok() == Some(b"GoTo") {
+            if let Ok(dest) = dict.get_mut(b"D") {
+                resolve(dest);
+            }
+        }
+    };
+    for object in doc.objects.values_mut() {
+        if let Object::Dictionary(dict) = object {
+            resolve_dict(dict);
+            // Actions are often inline in the outline item or annotation.
+            if let Ok(Object::Dictionary(action)) = dict.get_mut(b"A") {
+                resolve_dict(action);
+            }
+        }
+    }
+}
+
+/// Remove link annotations on the kept pages that jump to removed pages.
+fn remove_links_to(doc: &mut Document, kept: &[ObjectId], removed: &BTreeSet<ObjectId>) {
+    for &page_id in kept {
+        let Ok(annots) = doc
+            .get_dictionary(page_id)
+            .and_then(|page| page.get_deref(b"Annots", doc))
+            .and_then(Object::as_array)
+        else {
+            continue;
+        };
+        let dead: BTreeSet<ObjectId> = annots
+            .iter()
+            .filter_map(|annot| annot.as_reference().ok())
+            .filter(|&id| {
+                doc.get_dictionary(id).is_ok_and(|annot| {
+                    annot.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Link")
+                        && link_target(doc, annot).is_some_and(|page| removed.contains(&page))
+                })
+            })
+            .collect();
+        if dead.is_empty() {
+            continue;
+        }
+
+        let annots_ref = doc
+            .get_dictionary(page_id)
+            .and_then(|page| page.get(b"Annots"))
+            .and_then(Object::as_reference)
+            .ok();
+        let annots = match annots_ref {
+            Some(id) => doc.get_object_mut(id).and_then(Object::as_array_mut),
+            None => doc
+                .get_dictionary_mut(page_id)
+                .and_then(|page| page.get_mut(b"Annots"))
+                .and_then(Object::as_array_mut),
+        };
+        if let Ok(annots) = annots {
+            annots.retain(|a| a.as_reference().map_or(true, |id| !dead.contains(&id)));
+        }
+    }
+}
+
+/// Detach widgets that lived on removed pages from the form.
+fn remove_page_widgets(doc: &mut Document, removed: &BTreeSet<ObjectId>) -> Result<(), String> {
+    let mut widgets = BTreeSet::new();
+    for &page_id in removed {
+        let annots = doc
+            .get_dictionary(page_id)
+            .and_then(|page| page.get_deref(b"Annots", doc))
+            .and_then(Object::as_array);
+        if let Ok(annots) = annots {
+            widgets.extend(annots.iter().filter_map(|a| a.as_reference().ok()));
+        }
+    }
+    if widgets.is_empty() {
+        return Ok(());
+    }
+    remove_widgets(doc, &widgets)
+}
+
+/// Drop named destinations that point at removed pages.
+fn remove_named_destinations_to(doc: &mut Document, removed: &BTreeSet<ObjectId>) {
+    let dead = |doc: &Document, dest: &Object| {
+        destination_page(doc, dest).is_some_and(|page| removed.contains(&page))
+    };
+
+    let dests_ref = doc
+        .catalog()
+        .and_then(|c| c.get(b"Dests"))
+        .and_then(Object::as_reference)
+        .ok();
+    if let Some(id) = dests_ref {
+        let names: Vec<Vec<u8>> = doc
+            .get_dictionary(id)
+            .map(|dests| {
+                dests
+                    .iter()
+                    .filter(|(_, dest)| dead(doc, dest))
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Ok(dests) = doc.get_dictionary_mut(id) {
+            for name in names {
+                dests.remove(&name);
+            }
+        }
+    }
+
+    // Name tree nodes are indirect objects in practice; filter each leaf's
+    // /Names array in place.
+    let tree_root = doc
+        .catalog()
+        .and_then(|c| c.get_deref(b"Names", doc))
+        .and_then(Object::as_dict)
+        .and_then(|n| n.get(b"Dests"))
+        .and_then(Object::as_reference)
+        .ok();
+    let mut stack: Vec<ObjectId> = tree_root.into_iter().collect();
+    let mut visited = BTreeSet::new();
+    while let Some(node_id) = stack.pop() {
+        if !visited.insert(node_id) {
+            continue;
+        }
+        let Ok(node) = doc.get_dictionary(node_id) else {
+            continue;
+        };
+        if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+            stack.extend(kids.iter().filter_map(|kid| kid.as_reference().ok()));
+        }
+        let Ok(pairs) = node.get(b"Names").and_then(Object::as_array) else {
+            continue;
+        };
+        let filtered: Vec<Object> = pairs
+            .chunks(2)
+            .filter(|pair| pair.len() == 2 && !dead(doc, &pair[1]))
+            .flatten()
+            .cloned()
+            .collect();
+        if filtered.len() != pairs.len() {
+            if let Ok(node) = doc.get_dictionary_mut(node_id) {
+                node.set("Names", filtered);
+            }
+        }
+    }
+}
+
+fn outline_root(doc: &Document) -> Option<ObjectId> {
+    doc.catalog()
+        .ok()?
+        .get(b"Outlines")
+        .and_then(Object::as_reference)
+        .ok()
+}
+
+/// The items directly under an outline node, following /First and /Next.
+fn outline_children(doc: &Document, parent: Option<ObjectId>) -> Vec<ObjectId> {
+    let mut items = Vec::new();
+    let mut next = parent
+        .and_then(|id| doc.get_dictionary(id).ok())
+        .and_then(|node| node.get(b"First").and_then(Object::as_reference).ok());
+    let mut seen = BTreeSet::new();
+    while let Some(id) = next {
+        if !seen.insert(id) {
+            break;
+        }
+        items.push(id);
+        next = doc
+            .get_dictionary(id)
+            .ok()
+            .and_then(|item| item.get(b"Next").and_then(Object::as_reference).ok());
+    }
+    items
+}
+
+/// Make `items` the children of outline node `parent`, in order.
+fn link_outline_items(
+    doc: &mut Document,
+    parent: ObjectId,
+    items: &[ObjectId],
+) -> Result<(), String> {
+    for (i, &id) in items.iter().enumerate() {
+        let item = doc
+            .get_dictionary_mut(id)
+            .map_err(|e| format!("Failed to update bookmark: {}", e))?;
+        item.set("Parent", parent);
+        match i.checked_sub(1).map(|prev| items[prev]) {
+            Some(prev) => item.set("Prev", prev),
+            None => {
+                item.remove(b"Prev");
+            }
+        }
+        match items.get(i + 1) {
+            Some(&next) => item.set("Next", next),
+            None => {
+                item.remove(b"Next");
+            }
+        }
+    }
+
+    let node = doc
+        .get_dictionary_mut(parent)
+        .map_err(|e| format!("Failed to update bookmarks: {}", e))?;
+    match (items.first(), items.last()) {
+        (Some(&first), Some(&last)) => {
+            node.set("First", first);
+            node.set("Last", last);
+        }
+        _ => {
+            node.remove(b"First");
+            node.remove(b"Last");
+            node.remove(b"Count");
+        }
+    }
+    Ok(())
+}
+
+/// Remove outline items that jump to removed pages. Items that still have
+/// children are kept as plain headings, without a destination.
+fn prune_outline(doc: &mut Document, parent: ObjectId, removed: &BTreeSet<ObjectId>, depth: usize) {
+    // Guard against malformed, cyclic outlines.
+    if depth > 64 {
+        return;
+    }
+    let mut kept = Vec::new();
+    for item in outline_children(doc, Some(parent)) {
+        prune_outline(doc, item, removed, depth + 1);
+        let Ok(dict) = doc.get_dictionary(item) else {
+            continue;
+        };
+        let has_children = dict.has(b"First");
+        if link_target(doc, dict).is_some_and(|page| removed.contains(&page)) {
+            if !has_children {
+                continue;
+            }
+            if let Ok(dict) = doc.get_dictionary_mut(item) {
+                dict.remove(b"Dest");
+                dict.remove(b"A");
+            }
+        }
+        kept.push(item);
+    }
+    // Items come from the document, so relinking them can't fail.
+    let _ = link_outline_items(doc, parent, &kept);
+}
+
+/// Recompute /Count throughout an outline (ISO 32000-1, 12.3.3): the number
+/// of descendants visible when a node is open, negated for closed items.
+/// Returns that number for `node`.
+fn update_outline_counts(doc: &mut Document, node: ObjectId) -> i64 {
+    fn visit(doc: &mut Document, node: ObjectId, depth: usize) -> i64 {
+        if depth > 64 {
+            return 0;
+        }
+        let mut visible = 0;
+        for item in outline_children(doc, Some(node)) {
+            let descendants = visit(doc, item, depth + 1);
+            let open = doc
+                .get_dictionary(item)
+                .ok()
+                .and_then(|d| d.get(b"Count").and_then(Object::as_i64).ok())
+                .is_some_and(|count| count > 0);
+            if let Ok(dict) = doc.get_dictionary_mut(item) {
+                if descendants == 0 {
+                    dict.remove(b"Count");
+                } else {
+                    dict.set("Count", if open { descendants } else { -descendants });
+                }
+            }
+            visible += 1 + if open { descendants } else { 0 };
+        }
+        visible
+    }
+    let total = visit(doc, node, 0);
+    if let Ok(root) = doc.get_dictionary_mut(node) {
+        if total > 0 {
+            root.set("Count", total);
+        }
+    }
+    total
+}
+
+/// The parts of a document's form carried over when merging.
+#[derive(Default)]
+struct FormParts {
+    fields: Vec<Object>,
+    fonts: Dictionary,
+    default_appearance: Option<Object>,
+}
+
+/// The top-level field references, default fonts and default appearance
+/// of a document's form.
+fn form_parts(doc: &Document) -> Option<FormParts> {
+    let form = doc
+        .catalog()
+        .ok()?
+        .get_deref(b"AcroForm", doc)
+        .ok()?
+        .as_dict()
+        .ok()?;
+    let fields = form
+        .get_deref(b"Fields", doc)
+        .and_then(Object::as_array)
+        .ok()?
+        .clone();
+    let fonts = form
+        .get_deref(b"DR", doc)
+        .and_then(Object::as_dict)
+        .and_then(|dr| dr.get_deref(b"Font", doc))
+        .and_then(Object::as_dict)
+        .cloned()
+        .unwrap_or_default();
+    Some(FormParts {
+        fields,
+        fonts,
+        default_appearance: form.get(b"DA").ok().cloned(),
+    })
+}
+
+/// Add another document's fields (already merged into `doc`) to the form,
+/// along with any default fonts the form doesn't have yet.
+fn append_form_fields(doc: &mut Document, parts: FormParts) -> Result<(), String> {
+    if parts.fields.is_empty() {
+        return Ok(());
+    }
+    let existing = form_parts(doc);
+    let form_id = match doc.catalog().and_then(|c| c.get(b"AcroForm")) {
+        Ok(Object::Reference(id)) => *id,
+        Ok(Object::Dictionary(form)) => {
+            let form = form.clone();
+            let id = doc.add_object(form);
+            catalog_mut(doc)?.set("AcroForm", id);
+            id
+        }
+        _ => {
+            let id = doc.add_object(Dictionary::new());
+            catalog_mut(doc)?.set("AcroForm", id);
+            id
+        }
+    };
+
+    let mut merged = existing.unwrap_or_default();
+    merged.fields.extend(parts.fields);
+    for (name, font) in parts.fonts.iter() {
+        if !merged.fonts.has(name) {
+            merged.fonts.set(name.clone(), font.clone());
+        }
+    }
+    let mut resources = doc
+        .get_dictionary(form_id)
+        .and_then(|form| form.get_deref(b"DR", doc))
+        .and_then(Object::as_dict)
+        .cloned()
+        .unwrap_or_default();
+    resources.set("Font", merged.fonts);
+
+    let form = doc
+        .get_dictionary_mut(form_id)
+        .map_err(|e| format!("Failed to update form: {}", e))?;
+    form.set("Fields", merged.fields);
+    form.set("DR", resources);
+    if let Some(da) = merged.default_appearance.or(parts.default_appearance) {
+        form.set("DA", da);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{path_in, save, temp_dir, text_pdf};
+    use crate::commands::text::extract_text;
+
+    /// The text of each page of the PDF at `path`, in page order.
+    fn page_texts(path: &str) -> Vec<String> {
+        extract_text(path, None)
+            .unwrap()
+            .into_iter()
+            .map(|page| page.text.trim().to_string())
+            .collect()
+    }
+
+    /// The effective /Rotate of each page of the PDF at `path`.
+    fn rotations(path: &str) -> Vec<i64> {
+        let doc = Document::load(path).unwrap();
+        doc.page_iter()
+            .map(|page| {
+                inherited_attribute(&doc, page, b"Rotate")
+                    .and_then(|r| r.as_i64().ok())
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// Pages "One", "Two" and "Three", all rotated by `rotation` through
+    /// the page tree root, as some producers do.
+    fn three_pages(dir: &Path, rotation: i64) -> String {
+        let mut doc = text_pdf(&[&["One"], &["Two"], &["Three"]]);
+        let root = doc
+            .catalog()
+            .unwrap()
+            .get(b"Pages")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        doc.get_dictionary_mut(root)
+            .unwrap()
+            .set("Rotate", rotation);
+        save(&mut doc, dir, "doc.pdf")
+    }
+
+    fn split_into(source: &str, dir: &Path) -> Vec<String> {
+        let mode = SplitMode::Every { pages: 1 };
+        split(source, &dir.to_string_lossy(), &mode, |path, bytes| {
+            write_atomic(path, bytes)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn split_keeps_existing_parts() {
+        let dir = temp_dir("split");
+        let source = save(&mut text_pdf(&[&["One"], &["Two"]]), &dir, "doc.pdf");
+
+        let first = split_into(&source, &dir);
+        let second = split_into(&source, &dir);
+
+        let names = |paths: &[String]| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| {
+                    Path::new(p)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+        assert_eq!(names(&first), ["doc_p1.pdf", "doc_p2.pdf"]);
+        assert_eq!(names(&second), ["doc_p1 (2).pdf", "doc_p2 (2).pdf"]);
+        for path in first.iter().chain(&second) {
+            assert_eq!(Document::load(path).unwrap().get_pages().len(), 1);
+        }
+    }
+
+    #[test]
+    fn merge_appends_documents_in_order() {
+        let dir = temp_dir("merge");
+        let first = save(&mut text_pdf(&[&["One"], &["Two"]]), &dir, "first.pdf");
+        let second = three_pages(&dir, 90);
+        let output = path_in(&dir, "merged.pdf");
+
+        merge(&[first, second], &output).unwrap();
+        assert_eq!(page_texts(&output), ["One", "Two", "One", "Two", "Three"]);
+        assert_eq!(rotations(&output), [0, 0, 90, 90, 90]);
+    }
+
+    #[test]
+    fn reorder_moves_pages_with_their_rotation() {
+        let dir = temp_dir("reorder");
+        let source = three_pages(&dir, 180);
+        let output = path_in(&dir, "reordered.pdf");
+
+        reorder(&source, &output, &[3, 1, 2]).unwrap();
+        assert_eq!(page_texts(&output), ["Three", "One", "Two"]);
+        assert_eq!(rotations(&output), [180, 180, 180]);
+
+        assert!(reorder(&source, &output, &[1, 2]).is_err());
+        assert!(reorder(&source, &output, &[1, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn rotate_adds_to_the_inherited_rotation() {
+        let dir = temp_dir("rotate");
+        let source = three_pages(&dir, 90);
+        let output = path_in(&dir, "rotated.pdf");
+
+        rotate(&source, &output, &[1, 3], 180).unwrap();
+        assert_eq!(page_texts(&output), ["One", "Two", "Three"]);
+        assert_eq!(rotations(&output), [270, 90, 270]);
+
+        rotate(&source, &output, &[2], -90).unwrap();
+        assert_eq!(rotations(&output), [90, 0, 90]);
+        assert!(rotate(&source, &output, &[1], 45).is_err());
+    }
+
+    #[test]
+    fn delete_keeps_the_other_pages_in_order() {
+        let dir = temp_dir("delete");
+        let source = three_pages(&dir, 270);
+        let output = path_in(&dir, "deleted.pdf");
+
+        delete(&source, &output, &[2]).unwrap();
+        assert_eq!(page_texts(&output), ["One", "Three"]);
+        assert_eq!(rotations(&output), [270, 270]);
+
+        assert!(delete(&source, &output, &[1, 2, 3]).is_err());
+        assert!(delete(&source, &output, &[4]).is_err());
+    }
+
+    #[test]
+    fn split_sizes_beyond_the_page_count_give_one_part() {
+        let dir = temp_dir("split-large");
+        let source = three_pages(&dir, 0);
+        let mode = SplitMode::Every { pages: u32::MAX };
+        let parts = split(&source, &dir.to_string_lossy(), &mode, |path, bytes| {
+            write_atomic(path, bytes)
+        })
+        .unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(page_texts(&parts[0]), ["One", "Two", "Three"]);
+    }
+}
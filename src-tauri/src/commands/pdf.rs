@@ -0,0 +1,1210 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{
+    dictionary, Dictionary, Document, Encoding, IncrementalDocument, Object, ObjectId, SaveOptions,
+    Stream, StringFormat,
+};
+use serde::{Deserialize, Serialize};
+
+use super::annotations;
+use super::documents::{overwrite, write_atomic};
+use super::error::CommandError;
+use super::security;
+use super::text::HELVETICA_WIDTHS;
+
+/// Font size used when drawing signatures. Keep in sync with
+/// SIGNATURE_DEFAULT_FONT_SIZE in src/constants.ts so the flattened output
+/// matches the on-screen overlay.
+pub(crate) const SIGNATURE_FONT_SIZE: f32 = 32.0;
+
+/// Fallback for text annotations saved without a font size
+/// (TEXT_DEFAULT_FONT_SIZE in src/constants.ts).
+pub(crate) const TEXT_DEFAULT_FONT_SIZE: f32 = 14.0;
+
+/// Helvetica ascender/descender from the standard AFM metrics (1000 units/em).
+const HELVETICA_ASCENT: f32 = 718.0;
+const HELVETICA_DESCENT: f32 = -207.0;
+
+/// Bundled signature fonts keyed by the family name stored in the
+/// `signatures` table. Compiled into the binary so flattening never depends
+/// on the WebView's asset server.
+const SIGNATURE_FONTS: &[(&str, &[u8])] = &[
+    (
+        "Dancing Script",
+        include_bytes!("../../../resources/fonts/DancingScript-Regular.ttf"),
+    ),
+    (
+        "Great Vibes",
+        include_bytes!("../../../resources/fonts/GreatVibes-Regular.ttf"),
+    ),
+    (
+        "Sacramento",
+        include_bytes!("../../../resources/fonts/Sacramento-Regular.ttf"),
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationType {
+    Text,
+    Signature,
+}
+
+/// A text or signature overlay, with the same fields as a row of the
+/// `annotations` table (minus the `document_*` columns).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub id: String,
+    pub page_number: u32,
+    #[serde(rename = "type")]
+    pub annotation_type: AnnotationType,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub text_content: Option<String>,
+    pub font_size: Option<f32>,
+    pub color: Option<String>,
+    pub signature_id: Option<i64>,
+}
+
+/// How an edited PDF is written to disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveMode {
+    /// Rewrite the whole file. Produces the most compact output but
+    /// invalidates any existing digital signatures.
+    #[default]
+    Rewrite,
+    /// Append only the changed objects, a new xref section and trailer after
+    /// the original bytes (a PDF incremental update). Every byte an existing
+    /// signature covers stays untouched, so those signatures remain valid.
+    Incremental,
+}
+
+/// A saved signature style from the `signatures` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureStyle {
+    pub id: i64,
+    pub name: String,
+    pub font_family: String,
+    pub color: String,
+}
+
+/// Flatten text and signature annotations into the PDF at `source_path`
+/// and write the result to `output_path`. The document is loaded, edited and
+/// saved entirely in Rust, so the bytes never cross the IPC boundary.
+///
+/// `removed` lists overlays read from the PDF's own annotations (see
+/// `annotations::read_annotations`) that have since been deleted.
+#[tauri::command]
+pub async fn flatten_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    annotations: Vec<Annotation>,
+    signatures: Vec<SignatureStyle>,
+    removed: Option<Vec<String>>,
+    output_path: String,
+    mode: Option<SaveMode>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        flatten(
+            &source_path,
+            &annotations,
+            &signatures,
+            &removed.unwrap_or_default(),
+            &output_path,
+            mode.unwrap_or_default(),
+        )
+    })
+}
+
+/// Flatten all annotations (text + signature) into the PDF, producing a new
+/// PDF with embedded text.
+///
+/// ## Font embedding
+/// Text annotations use the standard Helvetica font with WinAnsiEncoding;
+/// characters outside that encoding are dropped. Signature fonts are
+/// embedded as CIDFont Type2 (composite fonts) with Identity-H encoding and a
+/// ToUnicode CMap for text selection/search. The full TTF program is embedded
+/// (no subsetting); the bundled script fonts are all under 500 KB.
+///
+/// ## Content stream behavior
+/// The existing page content is wrapped in a q/Q pair so any unbalanced
+/// graphics state it leaves behind cannot leak into the new drawing
+/// operators, which are appended as a separate content stream. Form fields,
+/// bookmarks, links and attachments are untouched.
+///
+/// ## Digital signatures
+/// With `SaveMode::Rewrite` the document is fully rewritten, so any existing
+/// digital signatures WILL be INVALIDATED. `SaveMode::Incremental` appends the
+/// new content instead and keeps them valid; viewers will still report that
+/// the document changed after it was signed.
+///
+/// ## Known limitations
+/// - Text overflow: text is not clipped or wrapped -- long text that exceeds
+///   the annotation width will extend beyond the visible overlay boundary.
+///
+/// ## Coordinate system
+/// Annotations store x,y as point offsets from the top-left of the rendered
+/// page at pdf.js scale=1 (which accounts for page rotation). PDF content is
+/// drawn in the unrotated MediaBox coordinate system with origin at
+/// bottom-left, so positions go through `to_media_box_coords`.
+///
+/// ## Annotations read from the PDF
+/// Overlays that came from the document's own FreeText and signature
+/// annotations replace them: those annotations are removed, along with the
+/// ones listed in `removed`, so nothing is drawn twice.
+pub fn flatten(
+    source_path: &str,
+    annotations: &[Annotation],
+    signatures: &[SignatureStyle],
+    removed: &[String],
+    output_path: &str,
+    mode: SaveMode,
+) -> Result<(), CommandError> {
+    let mut editor = PdfEditor::open(source_path, mode)?;
+    annotations::remove_replaced(&mut editor.doc, annotations, removed)?;
+    flatten_into(&mut editor.doc, annotations, signatures)?;
+    editor.save(output_path)
+}
+
+/// A PDF opened for editing. Keeps the original bytes and an untouched copy
+/// of the parsed document when saving incrementally, so the edits can be
+/// diffed and appended as a new revision.
+///
+/// Encrypted documents are decrypted with the password they were unlocked
+/// with (see `security`) and encrypted again the same way when saved.
+pub(crate) struct PdfEditor {
+    pub doc: Document,
+    /// The user password that opens the saved file; remembered for the
+    /// output path while `doc.encryption_state` is set.
+    pub password: Option<String>,
+    mode: SaveMode,
+    original: Vec<u8>,
+    pristine: Option<Document>,
+}
+
+impl PdfEditor {
+    /// Load a PDF from disk, decrypting it with the password entered for it.
+    pub fn open(path: &str, mode: SaveMode) -> Result<Self, String> {
+        let original = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let (doc, password) = security::load_pdf(&original, Path::new(path))?;
+        let pristine = (mode == SaveMode::Incremental).then(|| doc.clone());
+        Ok(PdfEditor {
+            doc,
+            password,
+            mode,
+            original,
+            pristine,
+        })
+    }
+
+    /// Serialize the edited document according to the save mode.
+    pub fn into_bytes(mut self) -> Result<Vec<u8>, String> {
+        match (self.mode, self.pristine) {
+            (SaveMode::Incremental, Some(pristine)) => {
+                incremental_update(self.original, pristine, &self.doc)
+            }
+            _ => save_document(&mut self.doc),
+        }
+    }
+
+    /// The password to remember for files saved from this document: none
+    /// unless it will be encrypted.
+    pub fn output_password(&self) -> Option<String> {
+        self.doc
+            .encryption_state
+            .as_ref()
+            .and(self.password.clone())
+    }
+
+    pub fn save(self, output_path: &str) -> Result<(), CommandError> {
+        let password = self.output_password();
+        let bytes = self.into_bytes()?;
+        write_atomic(Path::new(output_path), &bytes)?;
+        security::remember_password(Path::new(output_path), password.as_deref());
+        Ok(())
+    }
+}
+
+/// Serialize a whole document, encrypting it first if it was decrypted on
+/// load (or given new encryption), so saving never drops the protection.
+pub(crate) fn save_document(doc: &mut Document) -> Result<Vec<u8>, String> {
+    save_document_with(doc, SaveOptions::default())
+}
+
+/// `save_document` with object streams or a cross-reference stream, as
+/// `options` asks. Encrypted documents never get object streams.
+pub(crate) fn save_document_with(
+    doc: &mut Document,
+    options: SaveOptions,
+) -> Result<Vec<u8>, String> {
+    if let Some(state) = doc.encryption_state.take() {
+        security::encrypt_document(doc, &state)?;
+    }
+    let mut bytes = Vec::new();
+    doc.save_with_options(&mut bytes, options)
+        .map_err(|e| format!("Failed to save PDF: {}", e))?;
+    Ok(bytes)
+}
+
+/// Build an incremental update: the original bytes followed by every object
+/// of `edited` that is new or differs from `pristine`, then a new
+/// cross-reference section and a trailer whose /Prev points at the previous
+/// one (ISO 32000-1, 7.5.6).
+///
+/// The appended objects of an encrypted document are encrypted here rather
+/// than by lopdf, which would encrypt a new signature's /Contents too.
+pub(crate) fn incremental_update(
+    original: Vec<u8>,
+    mut pristine: Document,
+    edited: &Document,
+) -> Result<Vec<u8>, String> {
+    let encryption = pristine.encryption_state.take();
+    let mut update = IncrementalDocument::create_from(original, pristine);
+
+    let prev = update.get_prev_documents();
+    let changed: Vec<ObjectId> = edited
+        .objects
+        .iter()
+        .filter(|(id, object)| prev.objects.get(id) != Some(object))
+        .map(|(id, _)| *id)
+        .collect();
+    let trailer_changes: Vec<&[u8]> = [b"Root".as_slice(), b"Info", b"ID"]
+        .into_iter()
+        .filter(|key| prev.trailer.get(key).ok() != edited.trailer.get(key).ok())
+        .collect();
+
+    for id in changed {
+        let mut object = edited.objects[&id].clone();
+        if let Some(state) = &encryption {
+            security::encrypt_object(state, id, &mut object)?;
+        }
+        update.new_document.objects.insert(id, object);
+    }
+    if let Some(state) = &encryption {
+        let encrypt_id = state
+            .encrypt_object_id()
+            .ok_or("Failed to save PDF: its encryption dictionary was not found")?;
+        update.new_document.trailer.set("Encrypt", encrypt_id);
+    }
+    for key in trailer_changes {
+        match edited.trailer.get(key) {
+            Ok(value) => update.new_document.trailer.set(key, value.clone()),
+            Err(_) => {
+                update.new_document.trailer.remove(key);
+            }
+        }
+    }
+    update.new_document.max_id = update.new_document.max_id.max(edited.max_id);
+
+    let mut bytes = Vec::new();
+    update
+        .save_to(&mut bytes)
+        .map_err(|e| format!("Failed to save PDF: {}", e))?;
+    Ok(bytes)
+}
+
+/// Which font a piece of text is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum FontKey {
+    Helvetica,
+    Embedded(&'static str),
+}
+
+/// A single run of text to draw, resolved from an annotation.
+struct TextRun<'a> {
+    page_number: u32,
+    x: f32,
+    y: f32,
+    text: &'a str,
+    font: FontKey,
+    size: f32,
+    color: [f32; 3],
+}
+
+/// Draw every annotation into the page content of `doc`.
+pub(crate) fn flatten_into(
+    doc: &mut Document,
+    annotations: &[Annotation],
+    signatures: &[SignatureStyle],
+) -> Result<(), String> {
+    let pages = doc.get_pages();
+
+    let mut runs = Vec::new();
+    for annotation in annotations {
+        if !pages.contains_key(&annotation.page_number) {
+            continue;
+        }
+        match annotation.annotation_type {
+            AnnotationType::Text => {
+                let text = annotation.text_content.as_deref().unwrap_or("");
+                if text.is_empty() {
+                    continue;
+                }
+                runs.push(TextRun {
+                    page_number: annotation.page_number,
+                    x: annotation.x,
+                    y: annotation.y,
+                    text,
+                    font: FontKey::Helvetica,
+                    size: annotation.font_size.unwrap_or(TEXT_DEFAULT_FONT_SIZE),
+                    color: hex_to_rgb(annotation.color.as_deref().unwrap_or("#000000")),
+                });
+            }
+            AnnotationType::Signature => {
+                let Some(sig) = signatures
+                    .iter()
+                    .find(|s| Some(s.id) == annotation.signature_id)
+                else {
+                    continue;
+                };
+                runs.push(TextRun {
+                    page_number: annotation.page_number,
+                    x: annotation.x,
+                    y: annotation.y,
+                    text: &sig.name,
+                    font: signature_font_key(&sig.font_family),
+                    size: SIGNATURE_FONT_SIZE,
+                    color: hex_to_rgb(&sig.color),
+                });
+            }
+        }
+    }
+
+    if runs.is_empty() {
+        return Ok(());
+    }
+
+    // Embed each font once, covering every character drawn with it.
+    let mut fonts: BTreeMap<FontKey, PdfFont> = BTreeMap::new();
+    for key in runs.iter().map(|r| r.font) {
+        if fonts.contains_key(&key) {
+            continue;
+        }
+        let font = match key {
+            FontKey::Helvetica => PdfFont::helvetica(doc),
+            FontKey::Embedded(family) => {
+                let text: String = runs
+                    .iter()
+                    .filter(|r| r.font == key)
+                    .map(|r| r.text)
+                    .collect();
+                PdfFont::embed(doc, family, signature_font_bytes(family), &text)?
+            }
+        };
+        fonts.insert(key, font);
+    }
+
+    for (page_number, page_id) in pages {
+        let page_runs: Vec<&TextRun> = runs
+            .iter()
+            .filter(|r| r.page_number == page_number)
+            .collect();
+        if page_runs.is_empty() {
+            continue;
+        }
+
+        let geometry = PageGeometry::of(doc, page_id);
+        let mut used: Vec<FontKey> = page_runs.iter().map(|r| r.font).collect();
+        used.sort();
+        used.dedup();
+        let names = add_page_fonts(
+            doc,
+            page_id,
+            &used.iter().map(|k| fonts[k].id).collect::<Vec<_>>(),
+        )?;
+        let names: BTreeMap<FontKey, Vec<u8>> = used.into_iter().zip(names).collect();
+
+        let mut operations = Vec::new();
+        for run in page_runs {
+            let font = &fonts[&run.font];
+            let ascent = font.ascent * run.size / 1000.0;
+            let (pdf_x, pdf_y, rotation) = geometry.to_media_box_coords(run.x, run.y, ascent);
+            operations.extend(font.text_operations(
+                &names[&run.font],
+                run.text,
+                run.size,
+                run.color,
+                pdf_x,
+                pdf_y,
+                rotation,
+            ));
+        }
+        append_page_content(doc, page_id, operations)?;
+    }
+
+    Ok(())
+}
+
+fn signature_font_key(family: &str) -> FontKey {
+    SIGNATURE_FONTS
+        .iter()
+        .find(|(name, _)| *name == family)
+        .map(|(name, _)| FontKey::Embedded(name))
+        .unwrap_or(FontKey::Helvetica)
+}
+
+pub(crate) fn signature_font_bytes(family: &str) -> &'static [u8] {
+    SIGNATURE_FONTS
+        .iter()
+        .find(|(name, _)| *name == family)
+        .map(|(_, bytes)| *bytes)
+        .unwrap_or_default()
+}
+
+/// Map hex color string to DeviceRGB components.
+pub(crate) fn hex_to_rgb(hex: &str) -> [f32; 3] {
+    let h = hex.trim_start_matches('#');
+    let channel = |i: usize| {
+        h.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .unwrap_or(0) as f32
+            / 255.0
+    };
+    [channel(0), channel(2), channel(4)]
+}
+
+/// Decode a PDF text string: UTF-16BE with a BOM, otherwise treated as
+/// Latin-1 (close enough to PDFDocEncoding for names and form values).
+pub(crate) fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Encode a PDF text string, as Latin-1 when possible and UTF-16BE with a
+/// BOM otherwise.
+pub(crate) fn encode_text_string(text: &str) -> Object {
+    if text.chars().all(|c| (c as u32) < 0x80 || ('\u{a0}'..='\u{ff}').contains(&c)) {
+        Object::String(text.chars().map(|c| c as u8).collect(), StringFormat::Literal)
+    } else {
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        Object::String(bytes, StringFormat::Hexadecimal)
+    }
+}
+
+/// Walk up the page tree to find an inheritable page attribute
+/// (MediaBox, Rotate, Resources, ...).
+pub(crate) fn inherited_attribute<'a>(
+    doc: &'a Document,
+    page_id: ObjectId,
+    key: &[u8],
+) -> Option<&'a Object> {
+    let mut dict = doc.get_dictionary(page_id).ok()?;
+    // Guard against malformed, cyclic page trees.
+    for _ in 0..64 {
+        if let Ok(value) = dict.get(key) {
+            return doc.dereference(value).ok().map(|(_, object)| object);
+        }
+        let parent = dict.get(b"Parent").and_then(Object::as_reference).ok()?;
+        dict = doc.get_dictionary(parent).ok()?;
+    }
+    None
+}
+
+/// A terminal field: one with a value, as opposed to a node that only
+/// groups child fields.
+#[derive(Debug, Clone)]
+pub(crate) struct FieldNode {
+    pub(crate) id: ObjectId,
+    pub(crate) name: String,
+    pub(crate) widgets: Vec<ObjectId>,
+}
+
+/// Walk the AcroForm field tree collecting terminal fields with their fully
+/// qualified names and widget annotations.
+pub(crate) fn form_fields(doc: &Document) -> Vec<FieldNode> {
+    let roots = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get_deref(b"AcroForm", doc).ok())
+        .and_then(|form| form.as_dict().ok())
+        .and_then(|form| form.get_deref(b"Fields", doc).ok())
+        .and_then(|fields| fields.as_array().ok())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut found = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut stack: Vec<(ObjectId, String)> = roots
+        .iter()
+        .rev()
+        .filter_map(|f| f.as_reference().ok())
+        .map(|id| (id, String::new()))
+        .collect();
+    while let Some((id, parent_name)) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let Ok(field) = doc.get_dictionary(id) else {
+            continue;
+        };
+        let name = match field.get(b"T").and_then(Object::as_str) {
+            Ok(t) if parent_name.is_empty() => decode_text_string(t),
+            Ok(t) => format!("{}.{}", parent_name, decode_text_string(t)),
+            Err(_) => parent_name.clone(),
+        };
+
+        // Kids with a /T are child fields; kids without one are this field's
+        // widgets.
+        let kids: Vec<ObjectId> = field
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .map(|kids| kids.iter().filter_map(|k| k.as_reference().ok()).collect())
+            .unwrap_or_default();
+        let (child_fields, widgets): (Vec<ObjectId>, Vec<ObjectId>) =
+            kids.into_iter().partition(|&kid| {
+                doc.get_dictionary(kid)
+                    .map(|k| k.has(b"T"))
+                    .unwrap_or(false)
+            });
+        if !child_fields.is_empty() {
+            for kid in child_fields.into_iter().rev() {
+                stack.push((kid, name.clone()));
+            }
+            continue;
+        }
+
+        let is_widget = field.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget");
+        let widgets = if widgets.is_empty() && is_widget {
+            vec![id]
+        } else {
+            widgets
+        };
+        found.push(FieldNode { id, name, widgets });
+    }
+    found
+}
+
+/// Look up an inheritable field attribute (FT, Ff, V, DA, Q, Opt, MaxLen)
+/// on the field or its ancestors. Field trees chain through /Parent just
+/// like the page tree does.
+pub(crate) fn field_attribute<'a>(
+    doc: &'a Document,
+    field_id: ObjectId,
+    key: &[u8],
+) -> Option<&'a Object> {
+    inherited_attribute(doc, field_id, key)
+}
+
+/// Page number of a widget, from its /P entry or by searching page /Annots.
+pub(crate) fn widget_page(
+    doc: &Document,
+    widget: ObjectId,
+    page_numbers: &BTreeMap<ObjectId, u32>,
+) -> Option<u32> {
+    let from_parent = doc
+        .get_dictionary(widget)
+        .ok()
+        .and_then(|w| w.get(b"P").and_then(Object::as_reference).ok())
+        .and_then(|page| page_numbers.get(&page).copied());
+    from_parent.or_else(|| {
+        page_numbers.iter().find_map(|(&page_id, &number)| {
+            let annots = doc
+                .get_dictionary(page_id)
+                .ok()?
+                .get_deref(b"Annots", doc)
+                .ok()?;
+            annots
+                .as_array()
+                .ok()?
+                .iter()
+                .any(|a| a.as_reference().ok() == Some(widget))
+                .then_some(number)
+        })
+    })
+}
+
+/// The parts of a page's geometry needed to place overlays.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PageGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Page rotation normalized to 0, 90, 180 or 270.
+    pub rotation: i64,
+}
+
+impl PageGeometry {
+    pub fn of(doc: &Document, page_id: ObjectId) -> Self {
+        let media_box: Vec<f32> = inherited_attribute(doc, page_id, b"MediaBox")
+            .and_then(|o| o.as_array().ok())
+            .map(|a| a.iter().filter_map(|v| v.as_float().ok()).collect())
+            .unwrap_or_default();
+        // US Letter is the PDF default when MediaBox is missing or malformed.
+        let [x0, y0, x1, y1] = match media_box.as_slice() {
+            [a, b, c, d] => [*a, *b, *c, *d],
+            _ => [0.0, 0.0, 612.0, 792.0],
+        };
+        let rotation = inherited_attribute(doc, page_id, b"Rotate")
+            .and_then(|o| o.as_i64().ok())
+            .unwrap_or(0);
+
+        PageGeometry {
+            x: x0.min(x1),
+            y: y0.min(y1),
+            width: (x1 - x0).abs(),
+            height: (y1 - y0).abs(),
+            rotation: rotation.rem_euclid(360),
+        }
+    }
+
+    /// Convert a point from the displayed (rotated) frame to the unrotated
+    /// MediaBox frame.
+    ///
+    /// Annotation x,y are captured from pdf.js which applies page rotation,
+    /// so they're relative to the top-left of the *displayed* page. Page
+    /// content is drawn in the unrotated MediaBox coordinate system (origin
+    /// at bottom-left). `ascent` moves the point from the top of the text
+    /// box down to the baseline.
+    ///
+    /// Returns `(pdf_x, pdf_y, text_rotation_degrees)`.
+    pub fn to_media_box_coords(self, x: f32, y: f32, ascent: f32) -> (f32, f32, f32) {
+        // pdf.js renders from viewport (0,0) regardless of MediaBox origin,
+        // so we offset by the MediaBox origin.
+        match self.rotation {
+            // Page rotated 90deg CW for display. Displayed size = height x width.
+            // Text runs up the MediaBox, so the baseline is further right.
+            90 => (self.x + y + ascent, self.y + x, 90.0),
+            // Page rotated 180deg. Displayed size = width x height.
+            180 => (self.x + self.width - x, self.y + y + ascent, 180.0),
+            // Page rotated 270deg CW (= 90 CCW). Displayed size = height x width.
+            270 => (
+                self.x + self.width - y - ascent,
+                self.y + self.height - x,
+                270.0,
+            ),
+            // No rotation (or a non-standard one): flip from top-origin to
+            // bottom-origin and offset by ascent for the baseline.
+            _ => (self.x + x, self.y + self.height - y - ascent, 0.0),
+        }
+    }
+
+    /// Convert a box in the displayed frame to a MediaBox rectangle
+    /// `[x0, y0, x1, y1]`, with the rotation its contents need to appear
+    /// upright (as in `to_media_box_coords`).
+    pub fn to_media_box_rect(self, x: f32, y: f32, width: f32, height: f32) -> ([f32; 4], f32) {
+        let (x0, y0, rotation) = self.to_media_box_coords(x, y, 0.0);
+        let (x1, y1, _) = self.to_media_box_coords(x + width, y + height, 0.0);
+        ([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)], rotation)
+    }
+
+    /// Convert a MediaBox rectangle `[x0, y0, x1, y1]` to a box in the
+    /// displayed frame: `(x, y, width, height)`.
+    pub fn to_display_rect(self, rect: [f32; 4]) -> (f32, f32, f32, f32) {
+        let (ax, ay) = self.to_display_coords(rect[0], rect[1]);
+        let (bx, by) = self.to_display_coords(rect[2], rect[3]);
+        (ax.min(bx), ay.min(by), (ax - bx).abs(), (ay - by).abs())
+    }
+
+    /// Inverse of `to_media_box_coords` (with no ascent): convert a MediaBox
+    /// point to the displayed frame annotations use.
+    pub fn to_display_coords(self, pdf_x: f32, pdf_y: f32) -> (f32, f32) {
+        match self.rotation {
+            90 => (pdf_y - self.y, pdf_x - self.x),
+            180 => (self.x + self.width - pdf_x, pdf_y - self.y),
+            270 => (self.y + self.height - pdf_y, self.x + self.width - pdf_x),
+            _ => (pdf_x - self.x, self.y + self.height - pdf_y),
+        }
+    }
+}
+
+/// A font object added to the document, with the metrics needed to lay out
+/// text drawn with it.
+pub(crate) struct PdfFont {
+    pub id: ObjectId,
+    /// Ascent/descent in 1000 units/em.
+    pub ascent: f32,
+    pub descent: f32,
+    /// Glyph ids for embedded TrueType fonts; `None` for Helvetica.
+    glyphs: Option<BTreeMap<char, u16>>,
+    /// Advance widths (1000 units/em) of the characters embedded fonts were
+    /// created for. Helvetica uses the standard metrics.
+    advances: BTreeMap<char, f32>,
+}
+
+impl PdfFont {
+    pub fn helvetica(doc: &mut Document) -> Self {
+        let id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        PdfFont {
+            id,
+            ascent: HELVETICA_ASCENT,
+            descent: HELVETICA_DESCENT,
+            glyphs: None,
+            advances: BTreeMap::new(),
+        }
+    }
+
+    /// Embed a TrueType font as a CIDFontType2 with Identity-H encoding,
+    /// with widths and ToUnicode entries for every character in `text`.
+    pub fn embed(
+        doc: &mut Document,
+        family: &str,
+        font_bytes: &[u8],
+        text: &str,
+    ) -> Result<Self, String> {
+        let face = ttf_parser::Face::parse(font_bytes, 0)
+            .map_err(|e| format!("Failed to parse font \"{}\": {}", family, e))?;
+        let scale = 1000.0 / face.units_per_em() as f32;
+        let scaled = |v: i16| (v as f32 * scale).round() as i64;
+
+        let mut glyphs = BTreeMap::new();
+        for c in text.chars() {
+            let gid = face.glyph_index(c).map(|g| g.0).unwrap_or(0);
+            glyphs.insert(c, gid);
+        }
+
+        let base_font = face
+            .names()
+            .into_iter()
+            .find(|n| n.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .and_then(|n| n.to_string())
+            .unwrap_or_else(|| family.replace(' ', ""));
+
+        let mut widths: BTreeMap<u16, i64> = BTreeMap::new();
+        let mut advances = BTreeMap::new();
+        for (&c, &gid) in &glyphs {
+            let advance = face
+                .glyph_hor_advance(ttf_parser::GlyphId(gid))
+                .unwrap_or(0);
+            widths.insert(gid, (advance as f32 * scale).round() as i64);
+            advances.insert(c, advance as f32 * scale);
+        }
+        let w: Vec<Object> = widths
+            .iter()
+            .flat_map(|(&gid, &width)| {
+                [
+                    Object::Integer(gid as i64),
+                    Object::Array(vec![Object::Integer(width)]),
+                ]
+            })
+            .collect();
+
+        let bbox = face.global_bounding_box();
+        let ascent = face.ascender();
+        let descent = face.descender();
+
+        let mut font_file = Stream::new(
+            dictionary! { "Length1" => font_bytes.len() as i64 },
+            font_bytes.to_vec(),
+        );
+        let _ = font_file.compress();
+        let font_file_id = doc.add_object(font_file);
+
+        let descriptor_id = doc.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => Object::Name(base_font.clone().into_bytes()),
+            "Flags" => 4,
+            "FontBBox" => vec![
+                scaled(bbox.x_min).into(),
+                scaled(bbox.y_min).into(),
+                scaled(bbox.x_max).into(),
+                scaled(bbox.y_max).into(),
+            ],
+            "ItalicAngle" => face.italic_angle().round() as i64,
+            "Ascent" => scaled(ascent),
+            "Descent" => scaled(descent),
+            "CapHeight" => scaled(face.capital_height().unwrap_or(ascent)),
+            "StemV" => 80,
+            "FontFile2" => font_file_id,
+        });
+
+        let cid_font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => Object::Name(base_font.clone().into_bytes()),
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::string_literal("Adobe"),
+                "Ordering" => Object::string_literal("Identity"),
+                "Supplement" => 0,
+            },
+            "FontDescriptor" => descriptor_id,
+            "CIDToGIDMap" => "Identity",
+            "W" => w,
+        });
+
+        let mut to_unicode = Stream::new(dictionary! {}, to_unicode_cmap(&glyphs).into_bytes());
+        let _ = to_unicode.compress();
+        let to_unicode_id = doc.add_object(to_unicode);
+
+        let id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => Object::Name(base_font.into_bytes()),
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![cid_font_id.into()],
+            "ToUnicode" => to_unicode_id,
+        });
+
+        Ok(PdfFont {
+            id,
+            ascent: ascent as f32 * scale,
+            descent: descent as f32 * scale,
+            glyphs: Some(glyphs),
+            advances,
+        })
+    }
+
+    /// Width in points of a line of text drawn at `size`.
+    pub fn width(&self, text: &str, size: f32) -> f32 {
+        let units: f32 = text
+            .chars()
+            .map(|c| match self.glyphs {
+                Some(_) => self.advances.get(&c).copied().unwrap_or(0.0),
+                None => (c as usize)
+                    .checked_sub(32)
+                    .and_then(|i| HELVETICA_WIDTHS.get(i))
+                    .map_or(556.0, |&w| f32::from(w)),
+            })
+            .sum();
+        units * size / 1000.0
+    }
+
+    /// Encode a line of text as a PDF string for this font.
+    pub fn encode(&self, text: &str) -> Object {
+        match &self.glyphs {
+            Some(glyphs) => {
+                let bytes = text
+                    .chars()
+                    .flat_map(|c| glyphs.get(&c).copied().unwrap_or(0).to_be_bytes())
+                    .collect();
+                Object::String(bytes, StringFormat::Hexadecimal)
+            }
+            None => Object::String(
+                Document::encode_text(&Encoding::SimpleEncoding(b"WinAnsiEncoding"), text),
+                StringFormat::Literal,
+            ),
+        }
+    }
+
+    /// Operators that draw `text` with its top-left corner's baseline at
+    /// (x, y), rotated counter-clockwise by `rotation` degrees. Embedded
+    /// newlines start a new line one line-height further down.
+    #[allow(clippy::too_many_arguments)]
+    pub fn text_operations(
+        &self,
+        font_name: &[u8],
+        text: &str,
+        size: f32,
+        color: [f32; 3],
+        x: f32,
+        y: f32,
+        rotation: f32,
+    ) -> Vec<Operation> {
+        // Exact values for the quarter turns page rotation produces, so the
+        // text matrix doesn't pick up float noise like 4.37e-8.
+        let (sin, cos) = match rotation as i64 {
+            0 => (0.0, 1.0),
+            90 => (1.0, 0.0),
+            180 => (0.0, -1.0),
+            270 => (-1.0, 0.0),
+            _ => rotation.to_radians().sin_cos(),
+        };
+        let line_height = (self.ascent - self.descent) * size / 1000.0;
+
+        let mut ops = vec![
+            Operation::new("q", vec![]),
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![Object::Name(font_name.to_vec()), size.into()]),
+            Operation::new("rg", color.iter().map(|&c| c.into()).collect()),
+            Operation::new("TL", vec![line_height.into()]),
+            Operation::new(
+                "Tm",
+                vec![
+                    cos.into(),
+                    sin.into(),
+                    (-sin).into(),
+                    cos.into(),
+                    x.into(),
+                    y.into(),
+                ],
+            ),
+        ];
+        for (i, line) in text.lines().enumerate() {
+            if i > 0 {
+                ops.push(Operation::new("T*", vec![]));
+            }
+            ops.push(Operation::new("Tj", vec![self.encode(line)]));
+        }
+        ops.push(Operation::new("ET", vec![]));
+        ops.push(Operation::new("Q", vec![]));
+        ops
+    }
+}
+
+/// Build a ToUnicode CMap mapping each glyph id back to its character.
+fn to_unicode_cmap(glyphs: &BTreeMap<char, u16>) -> String {
+    let mut entries: BTreeMap<u16, char> = BTreeMap::new();
+    for (&c, &gid) in glyphs {
+        if gid != 0 {
+            entries.entry(gid).or_insert(c);
+        }
+    }
+
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n\
+         12 dict begin\n\
+         begincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /Adobe-Identity-UCS def\n\
+         /CMapType 2 def\n\
+         1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let entries: Vec<(u16, char)> = entries.into_iter().collect();
+    // bfchar blocks are limited to 100 entries each.
+    for chunk in entries.chunks(100) {
+        cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+        for (gid, c) in chunk {
+            let mut units = [0u16; 2];
+            let utf16: String = c
+                .encode_utf16(&mut units)
+                .iter()
+                .map(|u| format!("{:04X}", u))
+                .collect();
+            cmap.push_str(&format!("<{:04X}> <{}>\n", gid, utf16));
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+/// Register `font_ids` in the page's font resources, returning the resource
+/// name assigned to each.
+pub(crate) fn add_page_fonts(
+    doc: &mut Document,
+    page_id: ObjectId,
+    font_ids: &[ObjectId],
+) -> Result<Vec<Vec<u8>>, String> {
+    add_page_resources(doc, page_id, b"Font", "OTF", font_ids)
+}
+
+/// Register objects under one category of the page's resources (`Font`,
+/// `XObject`, ...), returning the resource name assigned to each.
+/// Inherited or shared (indirect) resources are copied onto the page first
+/// so the additions don't leak to other pages.
+pub(crate) fn add_page_resources(
+    doc: &mut Document,
+    page_id: ObjectId,
+    category: &[u8],
+    prefix: &str,
+    ids: &[ObjectId],
+) -> Result<Vec<Vec<u8>>, String> {
+    let mut resources = inherited_attribute(doc, page_id, b"Resources")
+        .and_then(|o| o.as_dict().ok())
+        .cloned()
+        .unwrap_or_default();
+    let mut category_dict = resources
+        .get_deref(category, doc)
+        .and_then(Object::as_dict)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut names = Vec::with_capacity(ids.len());
+    for &id in ids {
+        let name = unique_resource_name(&category_dict, prefix);
+        category_dict.set(name.clone(), id);
+        names.push(name);
+    }
+
+    resources.set(category, category_dict);
+    doc.get_dictionary_mut(page_id)
+        .map_err(|e| format!("Failed to update page: {}", e))?
+        .set("Resources", resources);
+    Ok(names)
+}
+
+/// Pick a resource name with the given prefix that isn't already in `dict`.
+pub(crate) fn unique_resource_name(dict: &Dictionary, prefix: &str) -> Vec<u8> {
+    (1..)
+        .map(|n| format!("{}{}", prefix, n).into_bytes())
+        .find(|name| !dict.has(name))
+        .expect("unbounded range always yields a free name")
+}
+
+/// Append drawing operators to a page. The original content is wrapped in
+/// q/Q first so it can't alter the graphics state the new operators run in.
+pub(crate) fn append_page_content(
+    doc: &mut Document,
+    page_id: ObjectId,
+    operations: Vec<Operation>,
+) -> Result<(), String> {
+    let content = Content { operations }
+        .encode()
+        .map_err(|e| format!("Failed to encode page content: {}", e))?;
+
+    let existing = doc
+        .get_dictionary(page_id)
+        .map_err(|e| format!("Failed to read page: {}", e))?
+        .get(b"Contents")
+        .ok()
+        .cloned();
+
+    let mut contents = match existing {
+        Some(Object::Reference(id)) => vec![Object::Reference(id)],
+        Some(Object::Array(items)) => items,
+        _ => Vec::new(),
+    };
+
+    let mut body = Vec::new();
+    if !contents.is_empty() {
+        let open_id = doc.add_object(Stream::new(dictionary! {}, b"q\n".to_vec()));
+        contents.insert(0, open_id.into());
+        body.extend_from_slice(b"Q\n");
+    }
+    body.extend(content);
+    let mut stream = Stream::new(dictionary! {}, body);
+    let _ = stream.compress();
+    let stream_id = doc.add_object(stream);
+    contents.push(stream_id.into());
+
+    doc.get_dictionary_mut(page_id)
+        .map_err(|e| format!("Failed to update page: {}", e))?
+        .set("Contents", contents);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{path_in, save, temp_dir, text_pdf};
+    use crate::commands::text::extract_text;
+
+    fn text_annotation(page_number: u32, x: f32, y: f32, text: &str) -> Annotation {
+        Annotation {
+            id: format!("{}-{}", page_number, text),
+            page_number,
+            annotation_type: AnnotationType::Text,
+            x,
+            y,
+            width: 200.0,
+            height: 20.0,
+            text_content: Some(text.to_string()),
+            font_size: Some(12.0),
+            color: None,
+            signature_id: None,
+        }
+    }
+
+    /// Two pages sharing one indirect /Resources dictionary, the first
+    /// rotated by `rotation`.
+    fn shared_resources_pdf(rotation: i64) -> Document {
+        let mut doc = text_pdf(&[&["First"], &["Second"]]);
+        let pages = doc.get_pages();
+        let shared = doc
+            .get_dictionary(pages[&1])
+            .unwrap()
+            .get(b"Resources")
+            .unwrap()
+            .clone();
+        let shared = doc.add_object(shared);
+        for page in pages.values() {
+            doc.get_dictionary_mut(*page)
+                .unwrap()
+                .set("Resources", shared);
+        }
+        doc.get_dictionary_mut(pages[&1])
+            .unwrap()
+            .set("Rotate", rotation);
+        doc
+    }
+
+    fn font_names(doc: &Document, page: ObjectId) -> Vec<Vec<u8>> {
+        inherited_attribute(doc, page, b"Resources")
+            .and_then(|r| r.as_dict().ok())
+            .and_then(|r| r.get_deref(b"Font", doc).ok())
+            .and_then(|f| f.as_dict().ok())
+            .map(|f| f.iter().map(|(name, _)| name.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn flattened_text_lands_where_it_was_placed_on_rotated_pages() {
+        for rotation in [90, 180, 270] {
+            let dir = temp_dir("flatten-rotated");
+            let source = save(&mut shared_resources_pdf(rotation), &dir, "source.pdf");
+            let output = path_in(&dir, "flat.pdf");
+            let annotations = [text_annotation(1, 100.0, 50.0, "Stamped")];
+            flatten(&source, &annotations, &[], &[], &output, SaveMode::Rewrite).unwrap();
+
+            let page = &extract_text(&output, Some(&[1])).unwrap()[0];
+            assert!(page.text.contains("Stamped"), "{}: {}", rotation, page.text);
+            let start = page.text.find("Stamped").unwrap();
+            let first = page.glyphs.iter().find(|g| g.offset == start).unwrap();
+            // The glyph box starts at the annotation's top-left corner, give
+            // or take the font's internal leading.
+            assert!(
+                (first.rect.x - 100.0).abs() < 2.0 && (first.rect.y - 50.0).abs() < 2.0,
+                "rotation {}: glyph at ({}, {})",
+                rotation,
+                first.rect.x,
+                first.rect.y
+            );
+        }
+    }
+
+    #[test]
+    fn display_coordinates_match_the_pdfjs_viewport() {
+        // A Letter page's MediaBox corners (0, 0) and (612, 792), as pdf.js
+        // places them at each rotation.
+        let expected = [
+            (0, (0.0, 792.0), (612.0, 0.0)),
+            (90, (0.0, 0.0), (792.0, 612.0)),
+            (180, (612.0, 0.0), (0.0, 792.0)),
+            (270, (792.0, 612.0), (0.0, 0.0)),
+        ];
+        for (rotation, origin, corner) in expected {
+            let geometry = PageGeometry {
+                x: 0.0,
+                y: 0.0,
+                width: 612.0,
+                height: 792.0,
+                rotation,
+            };
+            assert_eq!(geometry.to_display_coords(0.0, 0.0), origin, "{}", rotation);
+            assert_eq!(
+                geometry.to_display_coords(612.0, 792.0),
+                corner,
+                "{}",
+                rotation
+            );
+            let (x, y, _) = geometry.to_media_box_coords(corner.0, corner.1, 0.0);
+            assert_eq!((x, y), (612.0, 792.0), "{}", rotation);
+        }
+    }
+
+    #[test]
+    fn added_fonts_do_not_leak_into_shared_resources() {
+        let dir = temp_dir("flatten-shared");
+        let source = save(&mut shared_resources_pdf(90), &dir, "source.pdf");
+        let output = path_in(&dir, "flat.pdf");
+        let annotations = [text_annotation(1, 100.0, 50.0, "Stamped")];
+        flatten(&source, &annotations, &[], &[], &output, SaveMode::Rewrite).unwrap();
+
+        let doc = Document::load(&output).unwrap();
+        let pages = doc.get_pages();
+        assert_eq!(font_names(&doc, pages[&1]).len(), 2);
+        assert_eq!(font_names(&doc, pages[&2]), [b"F1".to_vec()]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use super::documents::write_atomic;
+use super::error::CommandError;
+
+/// Unsaved work is snapshotted to `{app_data}/recovery/<run>-<window>.json`,
+/// where `<run>` identifies the process. A window that closes normally
+/// removes its snapshot, so any left from another run belong to a session
+/// that crashed.
+const RECOVERY_DIR: &str = "recovery";
+
+/// This run's id and the snapshots found at startup from earlier runs.
+/// Managed as Tauri state.
+pub struct Recovery {
+    run_id: String,
+    orphaned: Mutex<Vec<RecoverableSession>>,
+}
+
+impl Default for Recovery {
+    fn default() -> Self {
+        Recovery {
+            run_id: now_millis().to_string(),
+            orphaned: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// The unsaved state of a window's document. `content` is up to the
+/// frontend: the editor's HTML for Word documents, the overlays as JSON for
+/// PDFs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverySnapshot {
+    /// `None` for a document that was never saved.
+    pub file_path: Option<String>,
+    pub file_name: String,
+    pub file_type: String,
+    pub content: String,
+}
+
+/// A snapshot left behind by a crashed session, without its content.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableSession {
+    /// Pass to `open_recoverable_session` and `discard_recoverable_session`.
+    pub id: String,
+    pub file_path: Option<String>,
+    pub file_name: String,
+    pub file_type: String,
+    /// Milliseconds since the Unix epoch.
+    pub saved_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredSnapshot {
+    saved_at: u64,
+    #[serde(flatten)]
+    snapshot: RecoverySnapshot,
+}
+
+/// Replace this window's snapshot. Called periodically while the document
+/// has unsaved changes.
+#[tauri::command]
+pub async fn save_recovery_snapshot(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    snapshot: RecoverySnapshot,
+) -> Result<(), CommandError> {
+    let path = snapshot_path(&app, window.label())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create recovery folder: {}", e))?;
+    }
+    let stored = StoredSnapshot {
+        saved_at: now_millis(),
+        snapshot,
+    };
+    let json = serde_json::to_vec(&stored)
+        .map_err(|e| format!("Failed to save recovery snapshot: {}", e))?;
+    write_atomic(&path, &json)
+}
+
+/// Drop this window's snapshot, once its document is saved or closed.
+#[tauri::command]
+pub async fn clear_recovery_snapshot(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+) -> Result<(), String> {
+    remove(&snapshot_path(&app, window.label())?)
+}
+
+/// Snapshots left by sessions that didn't exit cleanly, newest first.
+#[tauri::command]
+pub fn list_recoverable_sessions(recovery: tauri::State<'_, Recovery>) -> Vec<RecoverableSession> {
+    recovery.orphaned.lock().unwrap().clone()
+}
+
+/// The unsaved state kept in a recoverable session.
+#[tauri::command]
+pub async fn open_recoverable_session(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<RecoverySnapshot, String> {
+    let path = orphaned_path(&app, &id)?;
+    let data = fs::read(&path).map_err(|e| format!("Failed to read recovery snapshot: {}", e))?;
+    serde_json::from_slice::<StoredSnapshot>(&data)
+        .map(|stored| stored.snapshot)
+        .map_err(|e| format!("Failed to read recovery snapshot: {}", e))
+}
+
+/// Delete a recoverable session, recovered or not.
+#[tauri::command]
+pub async fn discard_recoverable_session(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    remove(&orphaned_path(&app, &id)?)?;
+    let recovery = app.state::<Recovery>();
+    recovery.orphaned.lock().unwrap().retain(|s| s.id != id);
+    Ok(())
+}
+
+/// Collect the snapshots earlier runs left behind. Called once at startup,
+/// before any window can write one of its own.
+pub fn find_orphaned(app: &tauri::AppHandle) -> Result<(), String> {
+    let dir = recovery_dir(app)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to list recovery snapshots: {}", e)),
+    };
+    let recovery = app.state::<Recovery>();
+    let mut sessions = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(id) = snapshot_id(&path) else {
+            continue;
+        };
+        if id.starts_with(&format!("{}-", recovery.run_id)) {
+            continue;
+        }
+        // Unreadable snapshots are skipped rather than offered
+        let Some(stored) = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<StoredSnapshot>(&data).ok())
+        else {
+            continue;
+        };
+        sessions.push(RecoverableSession {
+            id,
+            file_path: stored.snapshot.file_path,
+            file_name: stored.snapshot.file_name,
+            file_type: stored.snapshot.file_type,
+            saved_at: stored.saved_at,
+        });
+    }
+    sessions.sort_by_key(|s| Reverse(s.saved_at));
+    *recovery.orphaned.lock().unwrap() = sessions;
+    Ok(())
+}
+
+/// Remove the snapshot of a window that was closed normally.
+pub fn discard_window(app: &tauri::AppHandle, label: &str) {
+    if let Err(e) = snapshot_path(app, label).and_then(|path| remove(&path)) {
+        log::warn!("{}", e);
+    }
+}
+
+fn snapshot_path(app: &tauri::AppHandle, label: &str) -> Result<PathBuf, String> {
+    let recovery = app.state::<Recovery>();
+    Ok(recovery_dir(app)?.join(format!("{}-{}.json", recovery.run_id, label)))
+}
+
+/// The file of a session found at startup. Ids come from the frontend, so
+/// only those in the list are accepted.
+fn orphaned_path(app: &tauri::AppHandle, id: &str) -> Result<PathBuf, String> {
+    let recovery = app.state::<Recovery>();
+    if !recovery.orphaned.lock().unwrap().iter().any(|s| s.id == id) {
+        return Err(format!("No recoverable session {}", id));
+    }
+    Ok(recovery_dir(app)?.join(format!("{}.json", id)))
+}
+
+fn snapshot_id(path: &Path) -> Option<String> {
+    if path.extension().and_then(|e| e.to_str()) != Some("json") {
+        return None;
+    }
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .map(str::to_string)
+}
+
+fn recovery_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(RECOVERY_DIR))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+fn remove(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove recovery snapshot: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
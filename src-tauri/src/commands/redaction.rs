@@ -0,0 +1,1320 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::Path;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document, LoadOptions, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+
+use super::documents::{overwrite, write_atomic};
+use super::error::CommandError;
+use super::forms::remove_widgets;
+use super::pdf::{
+    append_page_content, inherited_attribute, unique_resource_name, PageGeometry, PdfEditor,
+    SaveMode,
+};
+use super::security;
+use super::text::{
+    form_content, form_matrix, is_subtype, page_operations, walk_page, xobject, ContentEvent,
+    Matrix, PaintEvent, PlacedGlyph, Rect, TextLayout, MAX_FORM_DEPTH,
+};
+
+/// Marked-content properties holding replacement or alternate text for the
+/// content they enclose (ISO 32000-1, 14.9). Search and copy use them
+/// instead of the glyphs, so they go along with redacted text.
+const ALTERNATE_TEXT_KEYS: [&[u8]; 3] = [b"ActualText", b"Alt", b"E"];
+
+/// The post-redaction check shrinks each area by this much (in points) so
+/// rounding in the rewritten content can't flag glyphs that end right at the
+/// edge of an area.
+const CHECK_TOLERANCE: f32 = 0.01;
+
+/// A rectangle to redact, in the displayed page frame annotations use
+/// (top-left origin, PDF points).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionArea {
+    pub page_number: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// What a redaction removed, for reporting back to the user.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionSummary {
+    pub glyphs_removed: usize,
+    /// Images whose pixels under the areas were blacked out.
+    pub images_redacted: usize,
+    /// Images and drawings that couldn't be edited and were removed whole.
+    pub graphics_removed: usize,
+    pub annotations_removed: usize,
+}
+
+impl RedactionSummary {
+    fn add(&mut self, other: &RedactionSummary) {
+        self.glyphs_removed += other.glyphs_removed;
+        self.images_redacted += other.images_redacted;
+        self.graphics_removed += other.graphics_removed;
+        self.annotations_removed += other.annotations_removed;
+    }
+}
+
+/// Permanently remove the content under `areas` and write the result to
+/// `output_path`.
+#[tauri::command]
+pub async fn redact_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    areas: Vec<RedactionArea>,
+) -> Result<RedactionSummary, CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        redact(&source_path, &output_path, &areas)
+    })
+}
+
+/// Redact a PDF.
+///
+/// Unlike drawing a box over the page, this edits the content streams:
+/// glyphs under an area are taken out of their text operators (the text
+/// that remains keeps its position), image pixels under an area are
+/// blacked out, and annotations overlapping an area are deleted. Invisible
+/// OCR text is removed the same way as visible text, along with any
+/// marked-content /ActualText covering it. A black box is then drawn over
+/// each area.
+///
+/// The output is always a full rewrite with unreferenced objects dropped,
+/// so neither earlier revisions nor the original streams survive. Before
+/// anything is written, the result is reloaded and the areas are checked
+/// again, including a scan of the raw text operators that doesn't depend on
+/// the fonts; if any text, image data or annotation is still found there
+/// the redaction fails.
+///
+/// ## Known limitations
+/// - Text drawn as vector outlines, or with fonts whose widths are missing,
+///   is located approximately or not at all.
+/// - Images in formats that can't be decoded here (JPEG, JPEG 2000, CCITT,
+///   JBIG2) are removed whole when they overlap an area.
+/// - Alternate text in the structure tree or in named property lists is not
+///   edited.
+pub fn redact(
+    source_path: &str,
+    output_path: &str,
+    areas: &[RedactionArea],
+) -> Result<RedactionSummary, CommandError> {
+    if areas.is_empty() {
+        return Err("No areas to redact".to_string().into());
+    }
+
+    let mut editor = PdfEditor::open(source_path, SaveMode::Rewrite)?;
+    let regions = page_regions(&editor.doc, areas)?;
+
+    let mut summary = RedactionSummary::default();
+    for (page_number, (page_id, rects)) in &regions {
+        redact_page(&mut editor.doc, *page_id, rects, &mut summary)
+            .map_err(|e| format!("Failed to redact page {}: {}", page_number, e))?;
+    }
+    editor.doc.prune_objects();
+
+    let password = editor.output_password();
+    let bytes = editor.into_bytes()?;
+    verify_redaction(&bytes, password.as_deref(), &regions)?;
+    write_atomic(Path::new(output_path), &bytes)?;
+    security::remember_password(Path::new(output_path), password.as_deref());
+    Ok(summary)
+}
+
+/// Group the areas by page, converted to each page's default user space.
+fn page_regions(
+    doc: &Document,
+    areas: &[RedactionArea],
+) -> Result<BTreeMap<u32, (ObjectId, Vec<Rect>)>, String> {
+    let pages = doc.get_pages();
+    let mut regions: BTreeMap<u32, (ObjectId, Vec<Rect>)> = BTreeMap::new();
+    for area in areas {
+        let page_id = *pages.get(&area.page_number).ok_or_else(|| {
+            format!(
+                "Page {} does not exist (the document has {} pages)",
+                area.page_number,
+                pages.len()
+            )
+        })?;
+        if !(area.width > 0.0 && area.height > 0.0) {
+            return Err("Redaction areas must have a width and height".to_string());
+        }
+        let geometry = PageGeometry::of(doc, page_id);
+        let (x0, y0, _) = geometry.to_media_box_coords(area.x, area.y, 0.0);
+        let (x1, y1, _) =
+            geometry.to_media_box_coords(area.x + area.width, area.y + area.height, 0.0);
+        regions
+            .entry(area.page_number)
+            .or_insert_with(|| (page_id, Vec::new()))
+            .1
+            .push(Rect::new(x0, y0, x1, y1));
+    }
+    Ok(regions)
+}
+
+fn hits(bounds: &Rect, rects: &[Rect]) -> bool {
+    rects.iter().any(|rect| rect.intersects(bounds))
+}
+
+fn redact_page(
+    doc: &mut Document,
+    page_id: ObjectId,
+    rects: &[Rect],
+    summary: &mut RedactionSummary,
+) -> Result<(), String> {
+    let operations = page_operations(doc, page_id)?;
+    let (edits, mut resources) = {
+        let resources =
+            inherited_attribute(doc, page_id, b"Resources").and_then(|o| o.as_dict().ok());
+        let mut layout = TextLayout::new(doc);
+        let edits = plan_edits(
+            &mut layout,
+            doc,
+            resources,
+            &operations,
+            Matrix::IDENTITY,
+            rects,
+            0,
+        )?;
+        (edits, resources.cloned().unwrap_or_default())
+    };
+
+    if !edits.is_empty() {
+        summary.add(&edits.summary);
+        let operations = apply_edits(doc, &operations, edits, &mut resources)?;
+        let mut stream = Stream::new(dictionary! {}, encode_operations(&operations)?);
+        let _ = stream.compress();
+        let content_id = doc.add_object(stream);
+        // The page gets its own resources, since they may be shared with
+        // pages that keep drawing the original XObjects.
+        let page = doc
+            .get_dictionary_mut(page_id)
+            .map_err(|e| format!("Failed to update page: {}", e))?;
+        page.set("Contents", content_id);
+        page.set("Resources", resources);
+    }
+
+    summary.annotations_removed += remove_annotations(doc, page_id, rects)?;
+
+    // The thumbnail is a small picture of the unredacted page.
+    doc.get_dictionary_mut(page_id)
+        .map_err(|e| format!("Failed to update page: {}", e))?
+        .remove(b"Thumb");
+
+    let mut boxes = vec![
+        Operation::new("q", vec![]),
+        Operation::new("g", vec![0.into()]),
+    ];
+    for rect in rects {
+        boxes.push(Operation::new(
+            "re",
+            vec![
+                rect.x0.into(),
+                rect.y0.into(),
+                (rect.x1 - rect.x0).into(),
+                (rect.y1 - rect.y0).into(),
+            ],
+        ));
+    }
+    boxes.push(Operation::new("f", vec![]));
+    boxes.push(Operation::new("Q", vec![]));
+    append_page_content(doc, page_id, boxes)
+}
+
+/// Edits to one content stream, worked out before the document is
+/// modified.
+#[derive(Default)]
+struct StreamEdits {
+    /// Replacements for text-showing operators, by operation index.
+    text: BTreeMap<usize, Vec<Operation>>,
+    /// XObjects to swap out or drop, by the index of their `Do`.
+    xobjects: BTreeMap<usize, XObjectEdit>,
+    /// Inline images to drop.
+    removed: BTreeSet<usize>,
+    /// BDC operators whose alternate text must go.
+    marked_content: BTreeSet<usize>,
+    summary: RedactionSummary,
+}
+
+impl StreamEdits {
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.xobjects.is_empty()
+            && self.removed.is_empty()
+            && self.marked_content.is_empty()
+    }
+}
+
+enum XObjectEdit {
+    Remove,
+    Image(Stream),
+    Form(Box<FormEdit>),
+}
+
+/// A Form XObject to replace with a redacted copy.
+struct FormEdit {
+    form: Stream,
+    resources: Dictionary,
+    operations: Vec<Operation>,
+    edits: StreamEdits,
+}
+
+fn plan_edits<'a>(
+    layout: &mut TextLayout<'a>,
+    doc: &'a Document,
+    resources: Option<&'a Dictionary>,
+    operations: &[Operation],
+    ctm: Matrix,
+    rects: &[Rect],
+    depth: usize,
+) -> Result<StreamEdits, String> {
+    let mut events = Vec::new();
+    layout.walk(resources, operations, ctm, &mut |index, event| {
+        events.push((index, event))
+    });
+
+    let mut edits = StreamEdits::default();
+    let mut unreadable_inline_image = false;
+    for (index, event) in events {
+        match event {
+            ContentEvent::Text(glyphs) => {
+                let removed = glyphs.iter().filter(|g| hits(&g.bounds, rects)).count();
+                if removed > 0 {
+                    edits
+                        .text
+                        .insert(index, rewrite_text(&operations[index], &glyphs, rects));
+                    edits.summary.glyphs_removed += removed;
+                }
+            }
+            ContentEvent::InlineImage { ctm } => {
+                if hits(&ctm.transform_rect(Rect::UNIT), rects) {
+                    edits.removed.insert(index);
+                    edits.summary.graphics_removed += 1;
+                } else if operations[index].operands.is_empty() {
+                    unreadable_inline_image = true;
+                }
+            }
+            ContentEvent::XObject { name, ctm } => {
+                let Some((_, stream)) = xobject(doc, resources, &name) else {
+                    continue;
+                };
+                let edit = plan_xobject(layout, doc, resources, stream, ctm, rects, depth)?;
+                match &edit {
+                    Some(XObjectEdit::Remove) => edits.summary.graphics_removed += 1,
+                    Some(XObjectEdit::Image(_)) => edits.summary.images_redacted += 1,
+                    Some(XObjectEdit::Form(form)) => edits.summary.add(&form.edits.summary),
+                    None => {}
+                }
+                if let Some(edit) = edit {
+                    edits.xobjects.insert(index, edit);
+                }
+            }
+        }
+    }
+
+    if edits.is_empty() {
+        return Ok(edits);
+    }
+    // lopdf can't read compressed inline images back, so rewriting a stream
+    // holding one would lose it.
+    if unreadable_inline_image {
+        return Err(
+            "The page contains a compressed inline image that can't be preserved".to_string(),
+        );
+    }
+    edits.marked_content = alternate_text_spans(operations, &edits.text);
+    Ok(edits)
+}
+
+fn plan_xobject<'a>(
+    layout: &mut TextLayout<'a>,
+    doc: &'a Document,
+    resources: Option<&'a Dictionary>,
+    stream: &'a Stream,
+    ctm: Matrix,
+    rects: &[Rect],
+    depth: usize,
+) -> Result<Option<XObjectEdit>, String> {
+    if is_subtype(stream, b"Image") {
+        if !hits(&ctm.transform_rect(Rect::UNIT), rects) {
+            return Ok(None);
+        }
+        return Ok(Some(match redact_image(doc, stream, ctm, rects) {
+            Some(image) => XObjectEdit::Image(image),
+            None => XObjectEdit::Remove,
+        }));
+    }
+    if !is_subtype(stream, b"Form") {
+        return Ok(None);
+    }
+
+    let matrix = form_matrix(stream).then(ctm);
+    let overlaps = || {
+        stream
+            .dict
+            .get(b"BBox")
+            .ok()
+            .and_then(Rect::from_object)
+            .map_or(true, |bbox| hits(&matrix.transform_rect(bbox), rects))
+    };
+    let content = match form_content(doc, stream, resources) {
+        Ok(content) if depth < MAX_FORM_DEPTH => content,
+        // A form we can't look inside is dropped if it might reach an area.
+        _ => return Ok(overlaps().then_some(XObjectEdit::Remove)),
+    };
+    let (operations, form_resources) = content;
+    let edits = plan_edits(
+        layout,
+        doc,
+        form_resources,
+        &operations,
+        matrix,
+        rects,
+        depth + 1,
+    )?;
+    if edits.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(XObjectEdit::Form(Box::new(FormEdit {
+        form: stream.clone(),
+        resources: form_resources.cloned().unwrap_or_default(),
+        operations,
+        edits,
+    }))))
+}
+
+/// Rebuild a text-showing operator without the glyphs under `rects`.
+/// Removed glyphs become TJ adjustments of the same width, so the text that
+/// remains stays exactly where it was.
+fn rewrite_text(op: &Operation, glyphs: &[PlacedGlyph], rects: &[Rect]) -> Vec<Operation> {
+    let operands = op.operands.as_slice();
+    let (mut replacement, elements) = match op.operator.as_str() {
+        "TJ" => (
+            Vec::new(),
+            operands
+                .first()
+                .and_then(|o| o.as_array().ok())
+                .map_or(&[][..], |a| a.as_slice()),
+        ),
+        "'" => (
+            vec![Operation::new("T*", vec![])],
+            operands.get(..1).unwrap_or(&[]),
+        ),
+        "\"" => (
+            vec![
+                Operation::new("Tw", operands.get(..1).unwrap_or(&[]).to_vec()),
+                Operation::new("Tc", operands.get(1..2).unwrap_or(&[]).to_vec()),
+                Operation::new("T*", vec![]),
+            ],
+            operands.get(2..3).unwrap_or(&[]),
+        ),
+        _ => (Vec::new(), operands.get(..1).unwrap_or(&[])),
+    };
+
+    let mut array = Vec::new();
+    let mut glyphs = glyphs.iter().peekable();
+    // Adjustments for consecutive removed glyphs are merged and rounded, so
+    // the output reveals only the width of the gap (which the box shows
+    // anyway) and not the width of each glyph that was in it.
+    let mut gap: Option<f32> = None;
+    for (element, item) in elements.iter().enumerate() {
+        let Object::String(_, format) = item else {
+            match (gap.as_mut(), item.as_float()) {
+                (Some(gap), Ok(adjustment)) => *gap += adjustment,
+                _ => array.push(item.clone()),
+            }
+            continue;
+        };
+        let mut kept = Vec::new();
+        while let Some(glyph) = glyphs.next_if(|g| g.element == element) {
+            if hits(&glyph.bounds, rects) {
+                if !kept.is_empty() {
+                    array.push(Object::String(std::mem::take(&mut kept), *format));
+                }
+                *gap.get_or_insert(0.0) += glyph.adjustment;
+            } else {
+                if let Some(width) = gap.take() {
+                    array.push(Object::Real(width.round()));
+                }
+                kept.extend_from_slice(&glyph.code);
+            }
+        }
+        if !kept.is_empty() {
+            array.push(Object::String(kept, *format));
+        }
+    }
+    if let Some(width) = gap {
+        array.push(Object::Real(width.round()));
+    }
+
+    replacement.push(Operation::new("TJ", vec![Object::Array(array)]));
+    replacement
+}
+
+/// Indices of the BDC operators that enclose a rewritten text operator and
+/// carry alternate text inline.
+fn alternate_text_spans(
+    operations: &[Operation],
+    rewritten: &BTreeMap<usize, Vec<Operation>>,
+) -> BTreeSet<usize> {
+    let mut spans = BTreeSet::new();
+    let mut open = Vec::new();
+    for (index, op) in operations.iter().enumerate() {
+        match op.operator.as_str() {
+            "BMC" => open.push(None),
+            "BDC" => open.push(Some(index)),
+            "EMC" => {
+                open.pop();
+            }
+            _ if rewritten.contains_key(&index) => spans.extend(open.iter().flatten()),
+            _ => {}
+        }
+    }
+    spans.retain(|index: &usize| {
+        operations[*index]
+            .operands
+            .get(1)
+            .and_then(|o| o.as_dict().ok())
+            .is_some_and(|props| ALTERNATE_TEXT_KEYS.iter().any(|key| props.has(key)))
+    });
+    spans
+}
+
+/// Apply planned edits to a stream's operations. `resources` is the
+/// stream's own copy of its resources: new XObjects are filed under it, and
+/// the entries of replaced ones it no longer draws are dropped so the
+/// originals don't stay in the file.
+fn apply_edits(
+    doc: &mut Document,
+    operations: &[Operation],
+    mut edits: StreamEdits,
+    resources: &mut Dictionary,
+) -> Result<Vec<Operation>, String> {
+    let mut xobjects = resources
+        .get(b"XObject")
+        .ok()
+        .and_then(|o| doc.dereference(o).ok())
+        .and_then(|(_, o)| o.as_dict().ok())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut replaced = BTreeMap::new();
+    for (index, edit) in std::mem::take(&mut edits.xobjects) {
+        let id = match edit {
+            XObjectEdit::Remove => None,
+            XObjectEdit::Image(image) => Some(doc.add_object(image)),
+            XObjectEdit::Form(form) => Some(add_redacted_form(doc, *form)?),
+        };
+        let name = id.map(|id| {
+            let name = unique_resource_name(&xobjects, "Redacted");
+            xobjects.set(name.clone(), id);
+            name
+        });
+        replaced.insert(index, name);
+    }
+
+    let mut output = Vec::with_capacity(operations.len());
+    for (index, op) in operations.iter().enumerate() {
+        if let Some(replacement) = edits.text.remove(&index) {
+            output.extend(replacement);
+        } else if let Some(name) = replaced.get(&index) {
+            if let Some(name) = name {
+                output.push(Operation::new("Do", vec![Object::Name(name.clone())]));
+            }
+        } else if edits.removed.contains(&index) {
+            continue;
+        } else if edits.marked_content.contains(&index) {
+            let mut op = op.clone();
+            if let Some(Object::Dictionary(props)) = op.operands.get_mut(1) {
+                for key in ALTERNATE_TEXT_KEYS {
+                    props.remove(key);
+                }
+            }
+            output.push(op);
+        } else {
+            output.push(op.clone());
+        }
+    }
+
+    let drawn: BTreeSet<&[u8]> = output
+        .iter()
+        .filter(|op| op.operator == "Do")
+        .filter_map(|op| op.operands.first()?.as_name().ok())
+        .collect();
+    for index in replaced.keys() {
+        let original = operations[*index]
+            .operands
+            .first()
+            .and_then(|o| o.as_name().ok());
+        if let Some(name) = original.filter(|name| !drawn.contains(name)) {
+            xobjects.remove(name);
+        }
+    }
+    resources.set("XObject", xobjects);
+    Ok(output)
+}
+
+/// Add a copy of a Form XObject with its content redacted. The original,
+/// which other pages may draw, is left alone.
+fn add_redacted_form(doc: &mut Document, edit: FormEdit) -> Result<ObjectId, String> {
+    let FormEdit {
+        mut form,
+        mut resources,
+        operations,
+        edits,
+    } = edit;
+    let operations = apply_edits(doc, &operations, edits, &mut resources)?;
+
+    form.dict.set("Resources", resources);
+    form.dict.remove(b"Filter");
+    form.dict.remove(b"DecodeParms");
+    form.set_content(encode_operations(&operations)?);
+    let _ = form.compress();
+    Ok(doc.add_object(form))
+}
+
+/// The sample layout of an image XObject.
+struct ImageLayout {
+    width: usize,
+    height: usize,
+    bits_per_pixel: usize,
+    row_bytes: usize,
+}
+
+impl ImageLayout {
+    fn of(doc: &Document, image: &Stream) -> Option<Self> {
+        let dict = &image.dict;
+        let width = usize::try_from(dict.get(b"Width").and_then(Object::as_i64).ok()?).ok()?;
+        let height = usize::try_from(dict.get(b"Height").and_then(Object::as_i64).ok()?).ok()?;
+        let is_mask = dict
+            .get(b"ImageMask")
+            .and_then(Object::as_bool)
+            .unwrap_or(false);
+        let bits_per_pixel = if is_mask {
+            1
+        } else {
+            let bits = dict
+                .get(b"BitsPerComponent")
+                .and_then(Object::as_i64)
+                .ok()?;
+            usize::try_from(bits).ok()? * color_components(doc, dict.get(b"ColorSpace").ok()?)?
+        };
+        if width == 0 || height == 0 || bits_per_pixel == 0 {
+            return None;
+        }
+        Some(ImageLayout {
+            width,
+            height,
+            bits_per_pixel,
+            row_bytes: (width * bits_per_pixel).div_ceil(8),
+        })
+    }
+
+    /// Byte ranges of the sample data under `rects`, for an image painted
+    /// with `ctm`. Partially covered bytes are included.
+    fn spans(&self, ctm: Matrix, rects: &[Rect]) -> Vec<Range<usize>> {
+        let Some(inverse) = ctm.invert() else {
+            return Vec::new();
+        };
+        let (width, height) = (self.width as f32, self.height as f32);
+        let mut spans = Vec::new();
+        for rect in rects {
+            // In image space the image fills the unit square, with its first
+            // row at the top.
+            let area = inverse.transform_rect(*rect);
+            let col0 = (area.x0 * width).floor().clamp(0.0, width) as usize;
+            let col1 = (area.x1 * width).ceil().clamp(0.0, width) as usize;
+            let row0 = ((1.0 - area.y1) * height).floor().clamp(0.0, height) as usize;
+            let row1 = ((1.0 - area.y0) * height).ceil().clamp(0.0, height) as usize;
+            if col0 >= col1 || row0 >= row1 {
+                continue;
+            }
+            let start = col0 * self.bits_per_pixel / 8;
+            let end = (col1 * self.bits_per_pixel).div_ceil(8);
+            for row in row0..row1 {
+                spans.push(row * self.row_bytes + start..row * self.row_bytes + end);
+            }
+        }
+        spans
+    }
+}
+
+/// Number of color components of an image color space, for the ones we
+/// can edit samples of.
+pub(crate) fn color_components(doc: &Document, color_space: &Object) -> Option<usize> {
+    let (_, color_space) = doc.dereference(color_space).ok()?;
+    match color_space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceGray" | b"CalGray" => Some(1),
+            b"DeviceRGB" | b"CalRGB" | b"Lab" => Some(3),
+            b"DeviceCMYK" => Some(4),
+            _ => None,
+        },
+        Object::Array(items) => match items.first()?.as_name().ok()? {
+            b"CalGray" | b"Indexed" | b"Separation" => Some(1),
+            b"CalRGB" | b"Lab" => Some(3),
+            b"ICCBased" => {
+                let (_, profile) = doc.dereference(items.get(1)?).ok()?;
+                let n = profile
+                    .as_stream()
+                    .ok()?
+                    .dict
+                    .get(b"N")
+                    .and_then(Object::as_i64);
+                usize::try_from(n.ok()?).ok()
+            }
+            b"DeviceN" => {
+                let (_, names) = doc.dereference(items.get(1)?).ok()?;
+                Some(names.as_array().ok()?.len())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Black out the pixels of an image under the areas. Returns None when the
+/// samples can't be decoded (JPEG, JPEG 2000, CCITT and JBIG2 data), in
+/// which case the caller drops the image instead.
+fn redact_image(doc: &Document, image: &Stream, ctm: Matrix, rects: &[Rect]) -> Option<Stream> {
+    let layout = ImageLayout::of(doc, image)?;
+    let mut data = image.decompressed_content().ok()?;
+    if data.len() < layout.row_bytes * layout.height {
+        return None;
+    }
+    for span in layout.spans(ctm, rects) {
+        data[span].fill(0);
+    }
+
+    let mut dict = image.dict.clone();
+    dict.remove(b"Filter");
+    dict.remove(b"DecodeParms");
+    let mut stream = Stream::new(dict, data);
+    let _ = stream.compress();
+    Some(stream)
+}
+
+/// Whether every sample of an image under the areas is zero.
+fn image_is_blank(doc: &Document, image: &Stream, ctm: Matrix, rects: &[Rect]) -> bool {
+    let Some(layout) = ImageLayout::of(doc, image) else {
+        return false;
+    };
+    let Ok(data) = image.decompressed_content() else {
+        return false;
+    };
+    layout.spans(ctm, rects).into_iter().all(|span| {
+        data.get(span)
+            .is_some_and(|bytes| bytes.iter().all(|b| *b == 0))
+    })
+}
+
+/// Delete the page's annotations overlapping an area, with their pop-ups.
+/// Widgets are detached from their form fields as well, since a field's
+/// value is as readable as the page text.
+fn remove_annotations(
+    doc: &mut Document,
+    page_id: ObjectId,
+    rects: &[Rect],
+) -> Result<usize, String> {
+    let annots: Vec<Object> = doc
+        .get_dictionary(page_id)
+        .map_err(|e| format!("Failed to read page: {}", e))?
+        .get_deref(b"Annots", doc)
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut removed = BTreeSet::new();
+    let mut inline_removed = 0;
+    for annot in &annots {
+        let Some((id, dict)) = annotation(doc, annot) else {
+            continue;
+        };
+        let overlaps = dict
+            .get(b"Rect")
+            .ok()
+            .and_then(Rect::from_object)
+            .is_some_and(|rect| hits(&rect, rects));
+        match (overlaps, id) {
+            (true, Some(id)) => {
+                removed.insert(id);
+            }
+            (true, None) => inline_removed += 1,
+            _ => {}
+        }
+    }
+    // Pop-ups show their parent's text wherever they are on the page.
+    for annot in &annots {
+        if let Some((Some(id), dict)) = annotation(doc, annot) {
+            let parent = dict.get(b"Parent").and_then(Object::as_reference);
+            if parent.is_ok_and(|parent| removed.contains(&parent)) {
+                removed.insert(id);
+            }
+        }
+    }
+    if removed.is_empty() && inline_removed == 0 {
+        return Ok(0);
+    }
+
+    let widgets: BTreeSet<ObjectId> = removed
+        .iter()
+        .copied()
+        .filter(|id| {
+            doc.get_dictionary(*id)
+                .and_then(|d| d.get(b"Subtype"))
+                .and_then(Object::as_name)
+                .is_ok_and(|subtype| subtype == b"Widget")
+        })
+        .collect();
+    let remaining: Vec<Object> = annots
+        .into_iter()
+        .filter(|annot| match annot {
+            Object::Reference(id) => !removed.contains(id),
+            Object::Dictionary(dict) => !dict
+                .get(b"Rect")
+                .ok()
+                .and_then(Rect::from_object)
+                .is_some_and(|rect| hits(&rect, rects)),
+            _ => true,
+        })
+        .collect();
+
+    let page = doc
+        .get_dictionary_mut(page_id)
+        .map_err(|e| format!("Failed to update page: {}", e))?;
+    if remaining.is_empty() {
+        page.remove(b"Annots");
+    } else {
+        page.set("Annots", remaining);
+    }
+    remove_widgets(doc, &widgets)?;
+    Ok(removed.len() + inline_removed)
+}
+
+fn annotation<'a>(
+    doc: &'a Document,
+    annot: &'a Object,
+) -> Option<(Option<ObjectId>, &'a Dictionary)> {
+    let (id, object) = doc.dereference(annot).ok()?;
+    Some((id, object.as_dict().ok()?))
+}
+
+/// Serialize operations like `Content::encode`, except for inline images:
+/// lopdf parses those into a stream operand that it can't write back.
+fn encode_operations(operations: &[Operation]) -> Result<Vec<u8>, String> {
+    let encode = |operations: &[Operation]| {
+        Content { operations }
+            .encode()
+            .map_err(|e| format!("Failed to encode content: {}", e))
+    };
+
+    let mut out = Vec::new();
+    let mut start = 0;
+    for (index, op) in operations.iter().enumerate() {
+        if op.operator != "BI" {
+            continue;
+        }
+        out.extend(encode(&operations[start..index])?);
+        out.push(b'\n');
+        start = index + 1;
+        let Some(Object::Stream(image)) = op.operands.first() else {
+            continue;
+        };
+        out.extend_from_slice(b"BI");
+        for (key, value) in image.dict.iter() {
+            if key == b"Length" {
+                continue;
+            }
+            out.push(b' ');
+            out.extend(encode_object(&Object::Name(key.clone()))?);
+            out.push(b' ');
+            out.extend(encode_object(value)?);
+        }
+        out.extend_from_slice(b" ID ");
+        out.extend_from_slice(&image.content);
+        out.extend_from_slice(b"\nEI\n");
+    }
+    out.extend(encode(&operations[start..])?);
+    Ok(out)
+}
+
+fn encode_object(object: &Object) -> Result<Vec<u8>, String> {
+    // An operand is written followed by a space, then the (empty) operator.
+    let mut bytes = Content {
+        operations: vec![Operation::new("", vec![object.clone()])],
+    }
+    .encode()
+    .map_err(|e| format!("Failed to encode content: {}", e))?;
+    bytes.pop();
+    Ok(bytes)
+}
+
+/// Reload the redacted document and look for anything readable left under
+/// the areas. `password` opens it if it was saved encrypted.
+///
+/// The error says what kind of content was found and where, never what it
+/// says: it is shown to the user, logged and printed by the CLI.
+fn verify_redaction(
+    bytes: &[u8],
+    password: Option<&str>,
+    regions: &BTreeMap<u32, (ObjectId, Vec<Rect>)>,
+) -> Result<(), String> {
+    let options = password.map(LoadOptions::with_password).unwrap_or_default();
+    let doc = Document::load_mem_with_options(bytes, options)
+        .map_err(|e| format!("Failed to reload redacted PDF: {}", e))?;
+    let pages = doc.get_pages();
+
+    for (page_number, (_, rects)) in regions {
+        let page_id = *pages
+            .get(page_number)
+            .ok_or_else(|| format!("Redaction check failed: page {} is missing", page_number))?;
+        let rects: Vec<Rect> = rects
+            .iter()
+            .map(|r| {
+                Rect::new(
+                    r.x0 + CHECK_TOLERANCE,
+                    r.y0 + CHECK_TOLERANCE,
+                    r.x1 - CHECK_TOLERANCE,
+                    r.y1 - CHECK_TOLERANCE,
+                )
+            })
+            .collect();
+
+        let mut glyphs_left = 0;
+        let mut text_bounds: Option<Rect> = None;
+        let mut leaks = Vec::new();
+        walk_page(&doc, page_id, &mut |event| match event {
+            PaintEvent::Text(glyphs) => {
+                for glyph in glyphs.iter().filter(|g| hits(&g.bounds, &rects)) {
+                    glyphs_left += 1;
+                    text_bounds = Some(match text_bounds {
+                        Some(bounds) => bounds.union(&glyph.bounds),
+                        None => glyph.bounds,
+                    });
+                }
+            }
+            PaintEvent::Image { stream, ctm, .. } => {
+                if hits(&ctm.transform_rect(Rect::UNIT), &rects)
+                    && !image_is_blank(&doc, stream, ctm, &rects)
+                {
+                    leaks.push("image data".to_string());
+                }
+            }
+            PaintEvent::InlineImage { ctm } => {
+                if hits(&ctm.transform_rect(Rect::UNIT), &rects) {
+                    leaks.push("an inline image".to_string());
+                }
+            }
+        })
+        .map_err(|e| format!("Redaction check failed: {}", e))?;
+        if let Some(bounds) = text_bounds {
+            leaks.insert(
+                0,
+                format!(
+                    "{} glyph(s) of text within [{:.0} {:.0} {:.0} {:.0}]",
+                    glyphs_left, bounds.x0, bounds.y0, bounds.x1, bounds.y1
+                ),
+            );
+        } else {
+            // Glyphs the layout can't place (an unknown font, say) are
+            // invisible to the redactor and the check above alike, so look
+            // at the raw operators too.
+            let mut origins = Vec::new();
+            let operations = page_operations(&doc, page_id)
+                .map_err(|e| format!("Redaction check failed: {}", e))?;
+            let resources =
+                inherited_attribute(&doc, page_id, b"Resources").and_then(|o| o.as_dict().ok());
+            text_origins(
+                &doc,
+                resources,
+                &operations,
+                Matrix::IDENTITY,
+                0,
+                &mut origins,
+            );
+            let inside: Vec<&(f32, f32)> = origins
+                .iter()
+                .filter(|&&(x, y)| hits(&Rect::new(x, y, x, y), &rects))
+                .collect();
+            if !inside.is_empty() {
+                leaks.insert(0, format!("{} run(s) of text", inside.len()));
+            }
+        }
+
+        let annotations = doc
+            .get_dictionary(page_id)
+            .and_then(|page| page.get_deref(b"Annots", &doc))
+            .and_then(Object::as_array)
+            .map(|annots| {
+                annots
+                    .iter()
+                    .filter_map(|a| doc.dereference(a).ok()?.1.as_dict().ok())
+                    .filter_map(|a| Rect::from_object(a.get(b"Rect").ok()?))
+                    .filter(|rect| hits(rect, &rects))
+                    .count()
+            })
+            .unwrap_or(0);
+        if annotations > 0 {
+            leaks.push(format!("{} annotation(s)", annotations));
+        }
+
+        if !leaks.is_empty() {
+            return Err(format!(
+                "Redaction check failed: {} still present in the redacted area on page {}",
+                leaks.join(", "),
+                page_number
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Where each text-showing operator starts drawing, in page space, worked
+/// out from the raw operators alone: no fonts are loaded, so this sees text
+/// that `TextLayout` can't lay out. Only operators whose start is known
+/// without glyph widths are reported, i.e. the first one after each
+/// positioning operator.
+fn text_origins(
+    doc: &Document,
+    resources: Option<&Dictionary>,
+    operations: &[Operation],
+    ctm: Matrix,
+    depth: usize,
+    origins: &mut Vec<(f32, f32)>,
+) {
+    let mut ctm = ctm;
+    let mut stack = Vec::new();
+    let mut line_matrix = Matrix::IDENTITY;
+    // Font size, horizontal scaling, leading and rise.
+    let (mut size, mut scaling, mut leading, mut rise) = (0.0, 1.0, 0.0, 0.0);
+    // Whether text has been shown since the last positioning operator, so
+    // the current position depends on glyph widths.
+    let mut moved = true;
+
+    for op in operations {
+        let operands = op.operands.as_slice();
+        let number = |i: usize| operands.get(i).and_then(|o| o.as_float().ok());
+        match op.operator.as_str() {
+            "q" => stack.push(ctm),
+            "Q" => ctm = stack.pop().unwrap_or(ctm),
+            "cm" => {
+                if let Some(m) = Matrix::from_operands(operands) {
+                    ctm = m.then(ctm);
+                }
+            }
+            "BT" => {
+                line_matrix = Matrix::IDENTITY;
+                moved = true;
+            }
+            "Tf" => size = number(1).unwrap_or(0.0),
+            "Tz" => scaling = number(0).unwrap_or(100.0) / 100.0,
+            "TL" => leading = number(0).unwrap_or(0.0),
+            "Ts" => rise = number(0).unwrap_or(0.0),
+            "Td" | "TD" => {
+                let (tx, ty) = (number(0).unwrap_or(0.0), number(1).unwrap_or(0.0));
+                if op.operator == "TD" {
+                    leading = -ty;
+                }
+                line_matrix = Matrix::translate(tx, ty).then(line_matrix);
+                moved = true;
+            }
+            "Tm" => {
+                if let Some(m) = Matrix::from_operands(operands) {
+                    line_matrix = m;
+                    moved = true;
+                }
+            }
+            "T*" => {
+                line_matrix = Matrix::translate(0.0, -leading).then(line_matrix);
+                moved = true;
+            }
+            "Tj" | "'" | "\"" | "TJ" => {
+                if op.operator != "Tj" && op.operator != "TJ" {
+                    line_matrix = Matrix::translate(0.0, -leading).then(line_matrix);
+                    moved = true;
+                }
+                let elements: &[Object] = match op.operator.as_str() {
+                    "TJ" => operands
+                        .first()
+                        .and_then(|o| o.as_array().ok())
+                        .map_or(&[], |a| a.as_slice()),
+                    "\"" => operands.get(2).map_or(&[], std::slice::from_ref),
+                    _ => operands.first().map_or(&[], std::slice::from_ref),
+                };
+                // Adjustments before the first string move it without
+                // needing any widths.
+                let mut offset = 0.0;
+                for item in elements {
+                    match item {
+                        Object::String(bytes, _) if !bytes.is_empty() => {
+                            if moved {
+                                let start =
+                                    Matrix::translate(offset, rise).then(line_matrix).then(ctm);
+                                origins.push(start.apply(0.0, 0.0));
+                            }
+                            break;
+                        }
+                        other => {
+                            if let Ok(adjustment) = other.as_float() {
+                                offset -= adjustment / 1000.0 * size * scaling;
+                            }
+                        }
+                    }
+                }
+                if !elements.is_empty() {
+                    moved = false;
+                }
+            }
+            "Do" => {
+                let Some(name) = operands.first().and_then(|o| o.as_name().ok()) else {
+                    continue;
+                };
+                let Some((_, stream)) = xobject(doc, resources, name) else {
+                    continue;
+                };
+                if !is_subtype(stream, b"Form") || depth >= MAX_FORM_DEPTH {
+                    continue;
+                }
+                if let Ok((operations, form_resources)) = form_content(doc, stream, resources) {
+                    text_origins(
+                        doc,
+                        form_resources,
+                        &operations,
+                        form_matrix(stream).then(ctm),
+                        depth + 1,
+                        origins,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{
+        image_pdf, image_stream, path_in, save, temp_dir, text_pdf,
+    };
+    use crate::commands::text::extract_text;
+
+    /// Covers the second line of `text_pdf` (baseline y = 700) and nothing
+    /// of the first (baseline y = 720).
+    fn second_line() -> RedactionArea {
+        RedactionArea {
+            page_number: 1,
+            x: 60.0,
+            y: 792.0 - 712.0,
+            width: 300.0,
+            height: 14.0,
+        }
+    }
+
+    #[test]
+    fn removes_text_under_the_area_only() {
+        let dir = temp_dir("redact-text");
+        let mut doc = text_pdf(&[&["Public line", "Secret 1234"]]);
+        let source = save(&mut doc, &dir, "source.pdf");
+        let output = path_in(&dir, "redacted.pdf");
+
+        let summary = redact(&source, &output, &[second_line()]).unwrap();
+        assert_eq!(summary.glyphs_removed, "Secret 1234".len());
+
+        let text = &extract_text(&output, None).unwrap()[0].text;
+        assert!(text.contains("Public line"), "{:?}", text);
+        assert!(
+            !text.contains("Secret") && !text.contains("1234"),
+            "{:?}",
+            text
+        );
+
+        // Nowhere in the file either, not even in an unreferenced stream
+        let redacted = Document::load(&output).unwrap();
+        for object in redacted.objects.values() {
+            if let Object::Stream(stream) = object {
+                let content = stream
+                    .decompressed_content()
+                    .unwrap_or_else(|_| stream.content.clone());
+                assert!(!content.windows(6).any(|w| w == b"Secret"));
+            }
+        }
+    }
+
+    #[test]
+    fn blacks_out_image_pixels_under_the_area() {
+        let dir = temp_dir("redact-image");
+        // A 10x10 gray image drawn over x 100..200, y 600..700
+        let image = image_stream(10, 10, "DeviceGray", vec![200; 100]);
+        let (mut doc, _) = image_pdf(image, [100.0, 600.0, 100.0, 100.0]);
+        let source = save(&mut doc, &dir, "source.pdf");
+        let output = path_in(&dir, "redacted.pdf");
+        let left_half = RedactionArea {
+            page_number: 1,
+            x: 100.0,
+            y: 792.0 - 700.0,
+            width: 50.0,
+            height: 100.0,
+        };
+
+        let summary = redact(&source, &output, &[left_half]).unwrap();
+        assert_eq!(summary.images_redacted, 1);
+        assert_eq!(summary.graphics_removed, 0);
+
+        let redacted = Document::load(&output).unwrap();
+        let page_id = redacted.page_iter().next().unwrap();
+        let mut samples = Vec::new();
+        walk_page(&redacted, page_id, &mut |event| {
+            if let PaintEvent::Image { stream, .. } = event {
+                samples = stream
+                    .decompressed_content()
+                    .unwrap_or_else(|_| stream.content.clone());
+            }
+        })
+        .unwrap();
+        assert_eq!(samples.len(), 100);
+        for row in samples.chunks(10) {
+            assert!(row[..4].iter().all(|s| *s == 0), "{:?}", row);
+            assert!(row[6..].iter().all(|s| *s == 200), "{:?}", row);
+        }
+    }
+
+    #[test]
+    fn drops_annotations_under_the_area() {
+        let dir = temp_dir("redact-annotations");
+        let mut doc = text_pdf(&[&["Public line", "Secret 1234"]]);
+        let page_id = doc.page_iter().next().unwrap();
+        let note = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Square",
+            "Rect" => vec![70.into(), 698.into(), 150.into(), 710.into()],
+            "Contents" => Object::string_literal("Secret 1234"),
+        });
+        let kept = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Square",
+            "Rect" => vec![70.into(), 100.into(), 150.into(), 120.into()],
+        });
+        doc.get_dictionary_mut(page_id)
+            .unwrap()
+            .set("Annots", vec![note.into(), kept.into()]);
+        let source = save(&mut doc, &dir, "source.pdf");
+        let output = path_in(&dir, "redacted.pdf");
+
+        let summary = redact(&source, &output, &[second_line()]).unwrap();
+        assert_eq!(summary.annotations_removed, 1);
+
+        let redacted = Document::load(&output).unwrap();
+        let page_id = redacted.page_iter().next().unwrap();
+        let annots = redacted
+            .get_dictionary(page_id)
+            .unwrap()
+            .get_deref(b"Annots", &redacted)
+            .and_then(Object::as_array)
+            .unwrap();
+        assert_eq!(annots.len(), 1);
+    }
+
+    /// A one-page `text_pdf` whose content is replaced by `content`.
+    fn pdf_with_content(content: &str) -> Document {
+        let mut doc = text_pdf(&[&[]]);
+        let page_id = doc.page_iter().next().unwrap();
+        let stream = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+        doc.get_dictionary_mut(page_id)
+            .unwrap()
+            .set("Contents", stream);
+        doc
+    }
+
+    fn contains(doc: &Document, needle: &[u8]) -> bool {
+        doc.objects.values().any(|object| match object {
+            Object::Stream(stream) => stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone())
+                .windows(needle.len())
+                .any(|w| w == needle),
+            Object::String(bytes, _) => bytes.windows(needle.len()).any(|w| w == needle),
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn removes_alternate_text_of_redacted_text() {
+        let dir = temp_dir("redact-actual-text");
+        let mut doc = pdf_with_content(
+            "BT /F1 12 Tf 72 720 Td (Public line) Tj ET \
+             /Span << /ActualText (Secret 1234) >> BDC \
+             BT /F1 12 Tf 72 700 Td (S3cr3t) Tj ET EMC",
+        );
+        let source = save(&mut doc, &dir, "source.pdf");
+        let output = path_in(&dir, "redacted.pdf");
+
+        redact(&source, &output, &[second_line()]).unwrap();
+        let redacted = Document::load(&output).unwrap();
+        assert!(!contains(&redacted, b"Secret"));
+        assert!(!contains(&redacted, b"S3cr3t"));
+        assert!(contains(&redacted, b"Public line"));
+    }
+
+    #[test]
+    fn text_after_a_redacted_run_does_not_trip_the_check() {
+        let dir = temp_dir("redact-same-line");
+        // "Secret" ends around x = 107; the kept text follows on the line.
+        let mut doc = pdf_with_content("BT /F1 12 Tf 72 700 Td (Secret) Tj ( kept) Tj ET");
+        let source = save(&mut doc, &dir, "source.pdf");
+        let output = path_in(&dir, "redacted.pdf");
+        let area = RedactionArea {
+            width: 45.0,
+            ..second_line()
+        };
+
+        let summary = redact(&source, &output, &[area]).unwrap();
+        assert_eq!(summary.glyphs_removed, "Secret".len());
+        let text = &extract_text(&output, None).unwrap()[0].text;
+        assert_eq!(text.trim(), "kept");
+    }
+
+    #[test]
+    fn text_the_layout_cannot_place_fails_the_check() {
+        // /F9 isn't in the page's resources, so no glyph widths are known,
+        // but viewers still draw the text with a substitute font.
+        let dir = temp_dir("redact-unknown-font");
+        let mut doc = pdf_with_content("BT /F9 12 Tf 72 700 Td (Secret 1234) Tj ET");
+        let source = save(&mut doc, &dir, "source.pdf");
+        let output = path_in(&dir, "redacted.pdf");
+
+        let error = redact(&source, &output, &[second_line()]).unwrap_err();
+        assert!(
+            error.message.contains("1 run(s) of text"),
+            "{}",
+            error.message
+        );
+        assert!(!error.message.contains("Secret"), "{}", error.message);
+        assert!(!Path::new(&output).exists());
+    }
+
+    #[test]
+    fn verification_reports_leaks_without_their_text() {
+        let mut doc = text_pdf(&[&["Public line", "Secret 1234"]]);
+        let page_id = doc.page_iter().next().unwrap();
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        let regions = page_regions(&doc, &[second_line()]).unwrap();
+        assert_eq!(regions[&1].0, page_id);
+
+        let error = verify_redaction(&bytes, None, &regions).unwrap_err();
+        assert!(error.contains("page 1"), "{}", error);
+        assert!(error.contains("11 glyph(s)"), "{}", error);
+        assert!(
+            !error.contains("Secret") && !error.contains("1234"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,637 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use lopdf::encryption::crypt_filters::{Aes128CryptFilter, Aes256CryptFilter, CryptFilter};
+use lopdf::{
+    encryption, Dictionary, Document, EncryptionState, EncryptionVersion, LoadOptions, Object,
+    ObjectId, Permissions, StringFormat,
+};
+use serde::{Deserialize, Serialize};
+
+use super::documents::overwrite;
+use super::error::CommandError;
+use super::pdf::{PdfEditor, SaveMode};
+
+/// Returned by every command that reads a PDF whose password hasn't been
+/// entered this session.
+pub(crate) const PASSWORD_REQUIRED: &str =
+    "This PDF is password-protected. Enter its password first";
+
+/// Name of the crypt filter written for AES and RC4 (V4/V5) encryption.
+const STANDARD_CRYPT_FILTER: &[u8] = b"StdCF";
+
+/// Passwords that unlocked encrypted PDFs this session, keyed by resolved
+/// path. Global rather than Tauri state so the command cores (and the CLI)
+/// can open such documents through `PdfEditor::open` without passing a
+/// password around. Never written to disk.
+static PASSWORDS: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+/// How a PDF is encrypted, and what its password allows.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionInfo {
+    pub encrypted: bool,
+    /// `RC4-40`, `RC4-128`, `AES-128` or `AES-256`; `None` when not
+    /// encrypted or the security handler isn't the standard one.
+    pub method: Option<String>,
+    /// Whether a password must be entered (with `unlock_pdf`) before the
+    /// document can be read.
+    pub needs_password: bool,
+    /// Whether the password entered (or the empty one) is the owner
+    /// password, which is needed to change or remove the protection.
+    pub owner_unlocked: bool,
+    pub permissions: PdfPermissions,
+}
+
+/// What readers opening the document with the user password may do. The
+/// owner password always allows everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfPermissions {
+    pub print: bool,
+    pub copy: bool,
+    /// Editing pages, annotating, filling forms and assembling pages.
+    pub modify: bool,
+}
+
+impl Default for PdfPermissions {
+    fn default() -> Self {
+        PdfPermissions {
+            print: true,
+            copy: true,
+            modify: true,
+        }
+    }
+}
+
+/// Encryption of the standard security handler to save with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EncryptionMethod {
+    /// RC4 with a 128-bit key (PDF 1.4), for very old readers only.
+    Rc4,
+    /// AES-128 (PDF 1.6).
+    Aes128,
+    /// AES-256 (PDF 2.0).
+    #[default]
+    Aes256,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtectOptions {
+    /// Needed to open the document; may be empty, so anyone can open it
+    /// but the permissions still apply.
+    #[serde(default)]
+    pub user_password: String,
+    /// Needed to change the permissions or the protection. Defaults to the
+    /// user password.
+    pub owner_password: Option<String>,
+    #[serde(default)]
+    pub permissions: PdfPermissions,
+    #[serde(default)]
+    pub method: EncryptionMethod,
+}
+
+/// Report whether the PDF at `path` is encrypted, and how.
+#[tauri::command]
+pub async fn get_pdf_encryption(path: String) -> Result<EncryptionInfo, String> {
+    encryption_info(&path)
+}
+
+/// Check `password` against the PDF at `path` and remember it for the rest
+/// of the session, so the other commands can read the document. Rejects a
+/// wrong password.
+#[tauri::command]
+pub async fn unlock_pdf(path: String, password: String) -> Result<EncryptionInfo, String> {
+    unlock(&path, &password)
+}
+
+/// Save the PDF at `source_path` to `output_path` encrypted with new
+/// passwords and permissions. If it is already encrypted, the owner
+/// password is needed: `current_password`, or the one it was unlocked with.
+#[tauri::command]
+pub async fn protect_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    options: ProtectOptions,
+    current_password: Option<String>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        protect(
+            &source_path,
+            &output_path,
+            &options,
+            current_password.as_deref(),
+        )
+    })
+}
+
+/// Save the PDF at `source_path` to `output_path` without encryption. Needs
+/// the owner password, as for `protect_pdf`.
+#[tauri::command]
+pub async fn remove_pdf_password(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    current_password: Option<String>,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        remove_protection(&source_path, &output_path, current_password.as_deref())
+    })
+}
+
+/// Parse a PDF, decrypting it with the password remembered for `path` (or
+/// the empty password). Errors with `PASSWORD_REQUIRED` if that doesn't
+/// open it. Returns the password used, if any.
+pub(crate) fn load_pdf(bytes: &[u8], path: &Path) -> Result<(Document, Option<String>), String> {
+    let password = remembered_password(path);
+    let mut doc = load_with_password(bytes, password.as_deref())?;
+    if doc.is_encrypted() {
+        return Err(PASSWORD_REQUIRED.to_string());
+    }
+    if doc.encryption_state.is_some() {
+        doc = reload_signatures(doc, bytes, password.as_deref())?;
+    }
+    Ok((doc, password))
+}
+
+/// Encrypt one object with `state` the way lopdf does, except for the
+/// /Contents of a signature dictionary: ISO 32000-1 (7.6.1) keeps it in
+/// the clear, so the signature can be written into the file afterwards.
+pub(crate) fn encrypt_object(
+    state: &EncryptionState,
+    id: ObjectId,
+    object: &mut Object,
+) -> Result<(), String> {
+    match object {
+        Object::Dictionary(dict) if is_signature(dict) => dict
+            .iter_mut()
+            .filter(|(key, _)| key.as_slice() != b"Contents")
+            .try_for_each(|(_, value)| encryption::encrypt_object(state, id, value)),
+        _ => encryption::encrypt_object(state, id, object),
+    }
+    .map_err(|e| format!("Failed to encrypt PDF: {}", e))
+}
+
+/// `Document::encrypt`, leaving signatures' /Contents unencrypted.
+pub(crate) fn encrypt_document(doc: &mut Document, state: &EncryptionState) -> Result<(), String> {
+    for (&id, object) in doc.objects.iter_mut() {
+        encrypt_object(state, id, object)?;
+    }
+    let dict = state
+        .encode()
+        .map_err(|e| format!("Failed to encrypt PDF: {}", e))?;
+    let dict_id = doc.add_object(dict);
+    doc.trailer.set("Encrypt", dict_id);
+    Ok(())
+}
+
+/// A signature dictionary, signed or waiting for its signature.
+fn is_signature(dict: &Dictionary) -> bool {
+    dict.has(b"ByteRange") || dict.has_type(b"Sig") || dict.has_type(b"DocTimeStamp")
+}
+
+/// Load a decrypted document again if it is signed, so its signatures'
+/// /Contents are the bytes in the file. lopdf decrypts them like any other
+/// string, which garbles them, and with AES fails on them and leaves the
+/// rest of the dictionary encrypted. For the second load each one is
+/// blanked out in place, so no offset moves, and then put back.
+fn reload_signatures(
+    doc: Document,
+    bytes: &[u8],
+    password: Option<&str>,
+) -> Result<Document, String> {
+    let signatures: Vec<(ObjectId, Range<usize>)> = doc
+        .objects
+        .iter()
+        .filter_map(|(id, object)| {
+            let dict = object.as_dict().ok().filter(|dict| is_signature(dict))?;
+            Some((*id, signature_contents(dict, bytes)?))
+        })
+        .collect();
+    if signatures.is_empty() {
+        return Ok(doc);
+    }
+
+    let mut blanked = bytes.to_vec();
+    for (_, contents) in &signatures {
+        blanked[contents.start + 1..contents.end].fill(b' ');
+        blanked[contents.start + 1] = b'>';
+    }
+    let mut doc = load_with_password(&blanked, password)?;
+    for (id, contents) in signatures {
+        let Ok(dict) = doc.get_dictionary_mut(id) else {
+            continue;
+        };
+        let hex = &bytes[contents.start + 1..contents.end - 1];
+        dict.set(
+            "Contents",
+            Object::String(decode_hex(hex), StringFormat::Hexadecimal),
+        );
+    }
+    Ok(doc)
+}
+
+/// Where a signature's /Contents hex string is in the file: the gap
+/// between the two parts of its /ByteRange, angle brackets included.
+fn signature_contents(dict: &Dictionary, bytes: &[u8]) -> Option<Range<usize>> {
+    let range = dict.get(b"ByteRange").and_then(Object::as_array).ok()?;
+    let offset = |index: usize| usize::try_from(range.get(index)?.as_i64().ok()?).ok();
+    let (start, end) = (offset(1)?, offset(2)?);
+    (start + 2 <= end && end <= bytes.len() && bytes[start] == b'<' && bytes[end - 1] == b'>')
+        .then_some(start..end)
+}
+
+/// Bytes of the hex digits in `hex`, skipping whitespace; a missing final
+/// digit is taken as 0 (ISO 32000-1, 7.3.4.3).
+pub(crate) fn decode_hex(hex: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = hex
+        .iter()
+        .filter_map(|c| (*c as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Whether opening the PDF at `path` takes a password, entered this session
+/// or not.
+pub(crate) fn needs_password(path: &str) -> Result<bool, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(load_with_password(&bytes, None)?.is_encrypted())
+}
+
+/// Remember the password that opens the PDF at `path`, or forget it.
+pub(crate) fn remember_password(path: &Path, password: Option<&str>) {
+    let mut passwords = PASSWORDS.lock().unwrap();
+    match password {
+        Some(password) if !password.is_empty() => {
+            passwords.insert(key(path), password.to_string());
+        }
+        _ => {
+            passwords.remove(&key(path));
+        }
+    }
+}
+
+fn remembered_password(path: &Path) -> Option<String> {
+    PASSWORDS.lock().unwrap().get(&key(path)).cloned()
+}
+
+fn key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Load without failing on a wrong password: the document then stays
+/// encrypted (`is_encrypted()`), with its /Encrypt dictionary in place.
+fn load_with_password(bytes: &[u8], password: Option<&str>) -> Result<Document, String> {
+    let options = match password {
+        Some(password) => LoadOptions::with_password(password),
+        None => LoadOptions::default(),
+    };
+    match Document::load_mem_with_options(bytes, options) {
+        // Loading without a password always succeeds
+        Err(lopdf::Error::InvalidPassword) => Document::load_mem(bytes),
+        result => result,
+    }
+    .map_err(|e| format!("Failed to load PDF: {}", e))
+}
+
+pub fn encryption_info(path: &str) -> Result<EncryptionInfo, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let password = remembered_password(Path::new(path));
+    let doc = load_with_password(&bytes, password.as_deref())?;
+    Ok(describe(&doc, password.as_deref().unwrap_or("")))
+}
+
+pub fn unlock(path: &str, password: &str) -> Result<EncryptionInfo, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let doc = load_with_password(&bytes, Some(password))?;
+    if doc.is_encrypted() {
+        return Err("Incorrect password".to_string());
+    }
+    remember_password(Path::new(path), Some(password));
+    Ok(describe(&doc, password))
+}
+
+pub fn protect(
+    source_path: &str,
+    output_path: &str,
+    options: &ProtectOptions,
+    current_password: Option<&str>,
+) -> Result<(), CommandError> {
+    let mut editor = open_as_owner(source_path, current_password)?;
+    ensure_file_id(&mut editor.doc)?;
+
+    let owner_password = options
+        .owner_password
+        .as_deref()
+        .filter(|password| !password.is_empty())
+        .unwrap_or(&options.user_password);
+    let permissions = permission_flags(options.permissions);
+    let filters =
+        |filter: Arc<dyn CryptFilter>| BTreeMap::from([(STANDARD_CRYPT_FILTER.to_vec(), filter)]);
+    let file_key = random_bytes::<32>()?;
+    let version = match options.method {
+        EncryptionMethod::Rc4 => EncryptionVersion::V2 {
+            document: &editor.doc,
+            owner_password,
+            user_password: &options.user_password,
+            key_length: 128,
+            permissions,
+        },
+        EncryptionMethod::Aes128 => EncryptionVersion::V4 {
+            document: &editor.doc,
+            encrypt_metadata: true,
+            crypt_filters: filters(Arc::new(Aes128CryptFilter)),
+            stream_filter: STANDARD_CRYPT_FILTER.to_vec(),
+            string_filter: STANDARD_CRYPT_FILTER.to_vec(),
+            owner_password,
+            user_password: &options.user_password,
+            permissions,
+        },
+        EncryptionMethod::Aes256 => EncryptionVersion::V5 {
+            encrypt_metadata: true,
+            crypt_filters: filters(Arc::new(Aes256CryptFilter)),
+            file_encryption_key: &file_key,
+            stream_filter: STANDARD_CRYPT_FILTER.to_vec(),
+            string_filter: STANDARD_CRYPT_FILTER.to_vec(),
+            owner_password,
+            user_password: &options.user_password,
+            permissions,
+        },
+    };
+    let state =
+        EncryptionState::try_from(version).map_err(|e| format!("Failed to encrypt PDF: {}", e))?;
+
+    editor.doc.encryption_state = Some(state);
+    editor.password = Some(options.user_password.clone());
+    editor.save(output_path)
+}
+
+pub fn remove_protection(
+    source_path: &str,
+    output_path: &str,
+    current_password: Option<&str>,
+) -> Result<(), CommandError> {
+    let mut editor = open_as_owner(source_path, current_password)?;
+    if editor.doc.encryption_state.is_none() {
+        return Err("This PDF is not password-protected".to_string().into());
+    }
+    editor.doc.encryption_state = None;
+    editor.password = None;
+    editor.save(output_path)
+}
+
+/// Open a PDF for changing its security, which takes the owner password if
+/// it is encrypted. The document is rewritten in full, as nothing of the
+/// old encryption may be left in it.
+fn open_as_owner(path: &str, current_password: Option<&str>) -> Result<PdfEditor, String> {
+    if let Some(password) = current_password {
+        unlock(path, password)?;
+    }
+    let editor = PdfEditor::open(path, SaveMode::Rewrite)?;
+    if let Some(state) = &editor.doc.encryption_state {
+        let password = editor.password.as_deref().unwrap_or("");
+        if !is_owner_password(&editor.doc, state, password) {
+            return Err(
+                "Enter the owner password to change this PDF's password or permissions".to_string(),
+            );
+        }
+    }
+    Ok(editor)
+}
+
+fn describe(doc: &Document, password: &str) -> EncryptionInfo {
+    if doc.is_encrypted() {
+        return match doc.get_encrypted() {
+            Ok(dict) => EncryptionInfo {
+                encrypted: true,
+                method: method_name(dict),
+                needs_password: true,
+                owner_unlocked: false,
+                permissions: permissions(dict),
+            },
+            Err(_) => EncryptionInfo {
+                encrypted: true,
+                needs_password: true,
+                ..EncryptionInfo::default()
+            },
+        };
+    }
+    match &doc.encryption_state {
+        Some(state) => {
+            let dict = state.encode().unwrap_or_default();
+            EncryptionInfo {
+                encrypted: true,
+                method: method_name(&dict),
+                needs_password: false,
+                owner_unlocked: is_owner_password(doc, state, password),
+                permissions: permissions(&dict),
+            }
+        }
+        None => EncryptionInfo {
+            owner_unlocked: true,
+            ..EncryptionInfo::default()
+        },
+    }
+}
+
+/// Whether `password` is the owner password of a decrypted document. The
+/// loaded document no longer has its /Encrypt dictionary, so it is checked
+/// against one rebuilt from the decryption state, with the same /ID.
+fn is_owner_password(doc: &Document, state: &EncryptionState, password: &str) -> bool {
+    let Ok(encrypt) = state.encode() else {
+        return false;
+    };
+    let mut probe = Document::new();
+    if let Ok(id) = doc.trailer.get(b"ID") {
+        probe.trailer.set("ID", id.clone());
+    }
+    let encrypt_id = probe.add_object(encrypt);
+    probe.trailer.set("Encrypt", encrypt_id);
+    probe.authenticate_owner_password(password).is_ok()
+}
+
+/// The encryption named after its cipher and key length, from the /V and
+/// /Length of an /Encrypt dictionary and the method of its crypt filter.
+fn method_name(dict: &Dictionary) -> Option<String> {
+    if dict.get(b"Filter").and_then(Object::as_name).ok() != Some(b"Standard".as_slice()) {
+        return None;
+    }
+    let version = dict.get(b"V").and_then(Object::as_i64).unwrap_or(0);
+    let name = match version {
+        1 => "RC4-40".to_string(),
+        2 | 3 => {
+            let bits = dict.get(b"Length").and_then(Object::as_i64).unwrap_or(40);
+            format!("RC4-{}", bits)
+        }
+        4 | 5 => {
+            let filter = dict
+                .get(b"StmF")
+                .and_then(Object::as_name)
+                .unwrap_or(b"Identity");
+            let method = dict
+                .get(b"CF")
+                .and_then(Object::as_dict)
+                .and_then(|filters| filters.get(filter))
+                .and_then(Object::as_dict)
+                .and_then(|filter| filter.get(b"CFM"))
+                .and_then(Object::as_name)
+                .unwrap_or(b"None");
+            match method {
+                b"AESV2" => "AES-128".to_string(),
+                b"AESV3" => "AES-256".to_string(),
+                b"V2" => "RC4-128".to_string(),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The /P entry of an /Encrypt dictionary as our three permissions.
+fn permissions(dict: &Dictionary) -> PdfPermissions {
+    let Ok(bits) = dict.get(b"P").and_then(Object::as_i64) else {
+        return PdfPermissions::default();
+    };
+    // A 32-bit field, usually stored as a negative number
+    let flags = Permissions::from_bits_truncate(bits as u32 as u64);
+    PdfPermissions {
+        print: flags.contains(Permissions::PRINTABLE),
+        copy: flags.contains(Permissions::COPYABLE),
+        modify: flags.contains(Permissions::MODIFIABLE),
+    }
+}
+
+fn permission_flags(permissions: PdfPermissions) -> Permissions {
+    // Copying for screen readers is always allowed (ISO 32000-2, 7.6.4.2)
+    let mut flags = Permissions::COPYABLE_FOR_ACCESSIBILITY;
+    if permissions.print {
+        flags |= Permissions::PRINTABLE | Permissions::PRINTABLE_IN_HIGH_QUALITY;
+    }
+    if permissions.copy {
+        flags |= Permissions::COPYABLE;
+    }
+    if permissions.modify {
+        flags |= Permissions::MODIFIABLE
+            | Permissions::ANNOTABLE
+            | Permissions::FILLABLE
+            | Permissions::ASSEMBLABLE;
+    }
+    flags
+}
+
+/// Give the document a file identifier if it has none: RC4 and AES-128 keys
+/// are derived from it.
+fn ensure_file_id(doc: &mut Document) -> Result<(), String> {
+    if doc.trailer.get(b"ID").is_ok() {
+        return Ok(());
+    }
+    let id = random_bytes::<16>()?.to_vec();
+    doc.trailer.set(
+        "ID",
+        vec![
+            Object::String(id.clone(), StringFormat::Hexadecimal),
+            Object::String(id, StringFormat::Hexadecimal),
+        ],
+    );
+    Ok(())
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate a key: {}", e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::pdf::{flatten, SaveMode};
+    use crate::commands::signing::{sign, SignOptions};
+    use crate::commands::test_support::{
+        path_in, save, temp_dir, text_pdf, SIGNER_P12, SIGNER_PASSWORD,
+    };
+    use crate::commands::verification::{verify, IntegrityStatus};
+
+    fn protect_options(method: EncryptionMethod) -> ProtectOptions {
+        ProtectOptions {
+            user_password: "open".to_string(),
+            owner_password: Some("owner".to_string()),
+            permissions: PdfPermissions::default(),
+            method,
+        }
+    }
+
+    #[test]
+    fn signs_encrypted_pdfs_and_verifies_them() {
+        for method in [
+            EncryptionMethod::Rc4,
+            EncryptionMethod::Aes128,
+            EncryptionMethod::Aes256,
+        ] {
+            let dir = temp_dir("sign-encrypted");
+            let plain = save(&mut text_pdf(&[&["Contract"]]), &dir, "plain.pdf");
+            let protected = path_in(&dir, "protected.pdf");
+            protect(&plain, &protected, &protect_options(method), None).unwrap();
+            assert!(needs_password(&protected).unwrap());
+            unlock(&protected, "open").unwrap();
+
+            let identity = dir.join("signer.p12");
+            fs::write(&identity, SIGNER_P12).unwrap();
+            let options = SignOptions {
+                identity_path: identity.to_string_lossy().into_owned(),
+                password: SIGNER_PASSWORD.to_string(),
+                placement: None,
+                style: None,
+                reason: Some("Approved".to_string()),
+                location: None,
+                contact_info: None,
+            };
+            let signed = path_in(&dir, "signed.pdf");
+            sign(&protected, &signed, &options).unwrap();
+
+            let reports = verify(&signed, &[]).unwrap();
+            assert_eq!(reports.len(), 1, "{:?}", method);
+            assert_eq!(reports[0].integrity, IntegrityStatus::Valid, "{:?}", method);
+            assert_eq!(reports[0].reason.as_deref(), Some("Approved"));
+            assert!(encryption_info(&signed).unwrap().encrypted);
+
+            // An incremental edit afterwards keeps the signature intact
+            let edited = path_in(&dir, "edited.pdf");
+            flatten(&signed, &[], &[], &[], &edited, SaveMode::Incremental).unwrap();
+            let reports = verify(&edited, &[]).unwrap();
+            assert_eq!(reports[0].integrity, IntegrityStatus::Valid, "{:?}", method);
+            assert!(reports[0].modified_after_signing);
+        }
+    }
+
+    #[test]
+    fn wrong_password_is_refused() {
+        let dir = temp_dir("wrong-password");
+        let plain = save(&mut text_pdf(&[&["Secret"]]), &dir, "plain.pdf");
+        let protected = path_in(&dir, "protected.pdf");
+        protect(
+            &plain,
+            &protected,
+            &protect_options(EncryptionMethod::Aes256),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            unlock(&protected, "guess").unwrap_err(),
+            "Incorrect password"
+        );
+        let owner = unlock(&protected, "owner").unwrap();
+        assert!(owner.owner_unlocked);
+    }
+}
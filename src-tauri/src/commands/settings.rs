@@ -1,9 +1,11 @@
 use tauri::Manager;
 
+use super::error::CommandError;
+
 #[tauri::command]
-pub fn get_app_data_dir(app: tauri::AppHandle) -> Result<String, String> {
+pub fn get_app_data_dir(app: tauri::AppHandle) -> Result<String, CommandError> {
     app.path()
         .app_data_dir()
         .map(|p| p.to_string_lossy().to_string())
-        .map_err(|e| format!("Failed to get app data dir: {}", e))
+        .map_err(|e| CommandError::from(format!("Failed to get app data dir: {}", e)))
 }
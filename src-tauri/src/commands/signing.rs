@@ -0,0 +1,926 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::SystemTime;
+
+use cms::builder::{create_signing_time_attribute, SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::content_info::ContentInfo;
+use cms::encrypted_data::EncryptedData;
+use cms::signed_data::{EncapsulatedContentInfo, SignerIdentifier};
+use der::asn1::{ObjectIdentifier, OctetString};
+use der::{Any, Decode, Encode};
+use hmac::{Hmac, Mac};
+use lopdf::content::Operation;
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use pkcs12::cert_type::CertBag;
+use pkcs12::kdf::{derive_key_utf8, Pkcs12KeyType};
+use pkcs12::pbe_params::{EncryptedPrivateKeyInfo, Pkcs12PbeParams};
+use pkcs12::pfx::Pfx;
+use pkcs12::safe_bag::SafeContents;
+use pkcs8::spki::{AlgorithmIdentifierOwned, EncodePublicKey, SubjectPublicKeyInfoOwned};
+use pkcs8::DecodePrivateKey;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tauri_plugin_dialog::DialogExt;
+use x509_cert::name::Name;
+use x509_cert::Certificate;
+
+use super::documents::{overwrite, write_atomic};
+use super::error::CommandError;
+use super::pdf::{
+    encode_text_string, hex_to_rgb, PageGeometry, PdfEditor, PdfFont, SaveMode, SignatureStyle,
+    SIGNATURE_FONT_SIZE,
+};
+use super::security;
+
+/// Bytes reserved for the DER-encoded CMS signature in /Contents. Enough for
+/// a 4096-bit RSA signature plus a typical three-certificate chain.
+const SIGNATURE_CONTENTS_SIZE: usize = 8192;
+
+/// Written into /ByteRange before the final offsets are known. Ten digits
+/// per entry leaves room for files up to ~9 GB.
+const BYTE_RANGE_PLACEHOLDER: [i64; 4] = [0, 9_999_999_999, 9_999_999_999, 9_999_999_999];
+
+/// Font size of the "Digitally signed by" lines under the signature.
+const DETAILS_FONT_SIZE: f32 = 7.0;
+
+/// Padding between the appearance border and its contents, in points.
+const APPEARANCE_PADDING: f32 = 2.0;
+
+pub(crate) const ID_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.1");
+const ID_ENCRYPTED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.6");
+pub(crate) const ID_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+pub(crate) const ID_SHA256: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const ID_PBES2: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.5.13");
+pub(crate) const ID_COMMON_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+
+/// Where the visible signature goes, in the same displayed-page coordinates
+/// as annotations (points from the top-left at pdf.js scale=1).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignaturePlacement {
+    pub page_number: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignOptions {
+    /// Path to the .p12/.pfx file holding the private key and certificate.
+    pub identity_path: String,
+    pub password: String,
+    /// Visible signature box. `None` adds an invisible signature.
+    pub placement: Option<SignaturePlacement>,
+    /// Saved signature style drawn in the box; the certificate's common
+    /// name in Helvetica is used without one.
+    pub style: Option<SignatureStyle>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+    pub contact_info: Option<String>,
+}
+
+/// Let the user pick a PKCS#12 digital ID to sign with.
+#[tauri::command]
+pub async fn open_identity_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let file = app
+        .dialog()
+        .file()
+        .add_filter("Digital IDs", &["p12", "pfx"])
+        .add_filter("All Files", &["*"])
+        .blocking_pick_file();
+
+    Ok(file.map(|path| path.to_string()))
+}
+
+/// Digitally sign the PDF at `source_path` and write it to `output_path`.
+#[tauri::command]
+pub async fn sign_pdf(
+    app: tauri::AppHandle,
+    source_path: String,
+    output_path: String,
+    options: SignOptions,
+) -> Result<(), CommandError> {
+    overwrite(&app, Path::new(&output_path), || {
+        sign(&source_path, &output_path, &options)
+    })
+}
+
+/// Sign a PDF with a certificate from a PKCS#12 identity.
+///
+/// The signature is always appended as an incremental update, so earlier
+/// signatures stay valid and the new one covers the whole file as saved.
+/// A `/Sig` dictionary (adbe.pkcs7.detached) is referenced from a new
+/// signature field whose widget carries the visible appearance.
+///
+/// ## Byte range
+/// The file is first written with a fixed-width /ByteRange placeholder and a
+/// zero-filled /Contents hex string. The real offsets are then patched in,
+/// the two ranges either side of /Contents are hashed with SHA-256, and the
+/// DER-encoded CMS SignedData is hex-encoded over the zeros. Nothing else
+/// moves, so the xref offsets lopdf wrote stay correct. In an encrypted PDF
+/// /Contents is the one string left unencrypted, so the same works there.
+///
+/// ## Identities
+/// RSA (signed with PKCS#1 v1.5) and P-256 ECDSA keys are supported. Both
+/// PBES2 (OpenSSL 3 default) and the legacy PKCS#12 3DES/RC2 encryption
+/// (OpenSSL 1.x, Windows exports) can be read. Every certificate in the file
+/// is embedded in the signature so verifiers can build the chain.
+pub fn sign(
+    source_path: &str,
+    output_path: &str,
+    options: &SignOptions,
+) -> Result<(), CommandError> {
+    let identity_bytes = std::fs::read(&options.identity_path)
+        .map_err(|e| format!("Failed to read digital ID: {}", e))?;
+    let identity = SigningIdentity::from_pkcs12(&identity_bytes, &options.password)?;
+
+    let mut editor = PdfEditor::open(source_path, SaveMode::Incremental)?;
+    add_signature_field(&mut editor.doc, &identity, options)?;
+    let password = editor.output_password();
+    let mut bytes = editor.into_bytes()?;
+
+    let (contents_start, contents_end) = patch_byte_range(&mut bytes)?;
+    let mut hasher = Sha256::new();
+    hasher.update(&bytes[..contents_start]);
+    hasher.update(&bytes[contents_end..]);
+    let digest = hasher.finalize();
+
+    let signature = identity.sign_digest(&digest)?;
+    let hex: String = signature.iter().map(|b| format!("{:02X}", b)).collect();
+    if hex.len() > contents_end - contents_start - 2 {
+        return Err(format!(
+            "Signature is too large ({} bytes, {} reserved)",
+            signature.len(),
+            SIGNATURE_CONTENTS_SIZE
+        )
+        .into());
+    }
+    bytes[contents_start + 1..contents_start + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+
+    write_atomic(Path::new(output_path), &bytes)?;
+    security::remember_password(Path::new(output_path), password.as_deref());
+    Ok(())
+}
+
+/// Add the signature dictionary, its field and widget, and the appearance
+/// stream to the document.
+fn add_signature_field(
+    doc: &mut Document,
+    identity: &SigningIdentity,
+    options: &SignOptions,
+) -> Result<(), String> {
+    let pages = doc.get_pages();
+    let page_number = options.placement.as_ref().map_or(1, |p| p.page_number);
+    let page_id = *pages
+        .get(&page_number)
+        .ok_or_else(|| format!("Page {} does not exist", page_number))?;
+
+    let signer_name = identity.common_name();
+    let mut signature = dictionary! {
+        "Type" => "Sig",
+        "Filter" => "Adobe.PPKLite",
+        "SubFilter" => "adbe.pkcs7.detached",
+        "ByteRange" => BYTE_RANGE_PLACEHOLDER.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+        "Contents" => Object::String(vec![0; SIGNATURE_CONTENTS_SIZE], StringFormat::Hexadecimal),
+        "M" => Object::string_literal(pdf_date(SystemTime::now())?),
+        "Name" => encode_text_string(&signer_name),
+    };
+    for (key, value) in [
+        ("Reason", &options.reason),
+        ("Location", &options.location),
+        ("ContactInfo", &options.contact_info),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+            signature.set(key, encode_text_string(value));
+        }
+    }
+    let signature_id = doc.add_object(signature);
+
+    let field_name = unique_field_name(doc);
+    let mut widget = dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Widget",
+        "FT" => "Sig",
+        "T" => Object::string_literal(field_name),
+        "V" => signature_id,
+        // Print | Locked
+        "F" => 132,
+        "P" => page_id,
+    };
+    match &options.placement {
+        Some(placement) => {
+            let geometry = PageGeometry::of(doc, page_id);
+            let (rect, rotation) = placement_rect(geometry, placement);
+            let appearance = appearance_stream(
+                doc,
+                placement,
+                rotation,
+                &signer_name,
+                options.style.as_ref(),
+            )?;
+            widget.set(
+                "Rect",
+                rect.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+            );
+            widget.set("AP", dictionary! { "N" => appearance });
+        }
+        None => widget.set("Rect", vec![0.into(), 0.into(), 0.into(), 0.into()]),
+    }
+    let widget_id = doc.add_object(widget);
+
+    add_page_annotation(doc, page_id, widget_id)?;
+    add_acro_form_field(doc, widget_id)
+}
+
+/// MediaBox rectangle covering the displayed placement box, and the
+/// counter-clockwise rotation its contents need to appear upright.
+fn placement_rect(geometry: PageGeometry, placement: &SignaturePlacement) -> ([f32; 4], f32) {
+    geometry.to_media_box_rect(placement.x, placement.y, placement.width, placement.height)
+}
+
+/// Build the widget's normal appearance: the signature name in its saved
+/// script font with the signer and date in small print underneath.
+fn appearance_stream(
+    doc: &mut Document,
+    placement: &SignaturePlacement,
+    rotation: f32,
+    signer_name: &str,
+    style: Option<&SignatureStyle>,
+) -> Result<ObjectId, String> {
+    let (width, height) = (placement.width, placement.height);
+    let date = display_date(SystemTime::now())?;
+    let details = format!("Digitally signed by {}\nDate: {}", signer_name, date);
+
+    let (name, font, color) = match style {
+        Some(style) => {
+            let font_bytes = super::pdf::signature_font_bytes(&style.font_family);
+            let font = PdfFont::embed(doc, &style.font_family, font_bytes, &style.name)?;
+            (style.name.as_str(), font, hex_to_rgb(&style.color))
+        }
+        None => (signer_name, PdfFont::helvetica(doc), [0.0, 0.0, 0.0]),
+    };
+    let helvetica = PdfFont::helvetica(doc);
+
+    let details_size = DETAILS_FONT_SIZE.min(height / 6.0);
+    let details_line = (helvetica.ascent - helvetica.descent) * details_size / 1000.0;
+    let details_top = APPEARANCE_PADDING + 2.0 * details_line;
+
+    let name_area = (height - details_top - 2.0 * APPEARANCE_PADDING).max(1.0);
+    let name_size = SIGNATURE_FONT_SIZE.min(name_area * 1000.0 / (font.ascent - font.descent));
+    let name_baseline = height - APPEARANCE_PADDING - font.ascent * name_size / 1000.0;
+
+    let mut operations: Vec<Operation> = font.text_operations(
+        b"F1",
+        name,
+        name_size,
+        color,
+        APPEARANCE_PADDING,
+        name_baseline,
+        0.0,
+    );
+    operations.extend(helvetica.text_operations(
+        b"F2",
+        &details,
+        details_size,
+        [0.3, 0.3, 0.3],
+        APPEARANCE_PADDING,
+        details_top - helvetica.ascent * details_size / 1000.0,
+        0.0,
+    ));
+    let content = lopdf::content::Content { operations }
+        .encode()
+        .map_err(|e| format!("Failed to encode signature appearance: {}", e))?;
+
+    let (sin, cos) = match rotation as i64 {
+        90 => (1.0, 0.0),
+        180 => (0.0, -1.0),
+        270 => (-1.0, 0.0),
+        _ => (0.0, 1.0),
+    };
+    let mut stream = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Matrix" => vec![
+                Object::Real(cos),
+                Object::Real(sin),
+                Object::Real(-sin),
+                Object::Real(cos),
+                0.into(),
+                0.into(),
+            ],
+            "Resources" => dictionary! {
+                "Font" => dictionary! {
+                    "F1" => font.id,
+                    "F2" => helvetica.id,
+                },
+            },
+        },
+        content,
+    );
+    let _ = stream.compress();
+    Ok(doc.add_object(stream))
+}
+
+/// Append an annotation to the page's /Annots array, which may be inline or
+/// an indirect object.
+pub(crate) fn add_page_annotation(
+    doc: &mut Document,
+    page_id: ObjectId,
+    annotation_id: ObjectId,
+) -> Result<(), String> {
+    let annots = doc
+        .get_dictionary(page_id)
+        .map_err(|e| format!("Failed to read page: {}", e))?
+        .get(b"Annots")
+        .ok()
+        .cloned();
+    match annots {
+        Some(Object::Reference(id)) => doc
+            .get_object_mut(id)
+            .and_then(Object::as_array_mut)
+            .map_err(|e| format!("Failed to read page annotations: {}", e))?
+            .push(annotation_id.into()),
+        Some(Object::Array(mut items)) => {
+            items.push(annotation_id.into());
+            doc.get_dictionary_mut(page_id)
+                .map_err(|e| format!("Failed to update page: {}", e))?
+                .set("Annots", items);
+        }
+        _ => doc
+            .get_dictionary_mut(page_id)
+            .map_err(|e| format!("Failed to update page: {}", e))?
+            .set("Annots", vec![annotation_id.into()]),
+    }
+    Ok(())
+}
+
+/// Register a field in the catalog's AcroForm, creating the form if needed,
+/// and mark the document as signed (SignaturesExist | AppendOnly).
+fn add_acro_form_field(doc: &mut Document, field_id: ObjectId) -> Result<(), String> {
+    let catalog_id = doc
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|e| format!("Failed to read document catalog: {}", e))?;
+    let acro_form = doc
+        .get_dictionary(catalog_id)
+        .map_err(|e| format!("Failed to read document catalog: {}", e))?
+        .get(b"AcroForm")
+        .ok()
+        .cloned();
+
+    let (form_id, mut form) = match acro_form {
+        Some(Object::Reference(id)) => (
+            Some(id),
+            doc.get_dictionary(id).cloned().unwrap_or_default(),
+        ),
+        Some(Object::Dictionary(dict)) => (None, dict),
+        _ => (None, Dictionary::new()),
+    };
+
+    match form.get(b"Fields").ok().cloned() {
+        Some(Object::Reference(id)) => doc
+            .get_object_mut(id)
+            .and_then(Object::as_array_mut)
+            .map_err(|e| format!("Failed to read form fields: {}", e))?
+            .push(field_id.into()),
+        Some(Object::Array(mut fields)) => {
+            fields.push(field_id.into());
+            form.set("Fields", fields);
+        }
+        _ => form.set("Fields", vec![field_id.into()]),
+    }
+    form.set("SigFlags", 3);
+
+    match form_id {
+        Some(id) => {
+            doc.objects.insert(id, Object::Dictionary(form));
+        }
+        None => {
+            doc.get_dictionary_mut(catalog_id)
+                .map_err(|e| format!("Failed to update document catalog: {}", e))?
+                .set("AcroForm", form);
+        }
+    }
+    Ok(())
+}
+
+/// Pick a "SignatureN" name not used by any existing top-level form field.
+fn unique_field_name(doc: &Document) -> String {
+    let fields = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get_deref(b"AcroForm", doc).ok())
+        .and_then(|form| form.as_dict().ok())
+        .and_then(|form| form.get_deref(b"Fields", doc).ok())
+        .and_then(|fields| fields.as_array().ok());
+    let taken: BTreeSet<Vec<u8>> = fields
+        .into_iter()
+        .flatten()
+        .filter_map(|field| doc.dereference(field).ok())
+        .filter_map(|(_, field)| field.as_dict().ok())
+        .filter_map(|field| field.get(b"T").and_then(Object::as_str).ok())
+        .map(|name| name.to_vec())
+        .collect();
+    (1..)
+        .map(|n| format!("Signature{}", n))
+        .find(|name| !taken.contains(name.as_bytes()))
+        .expect("unbounded range always yields a free name")
+}
+
+/// Replace the /ByteRange placeholder in the appended revision with the real
+/// offsets, returning the span of the /Contents hex string (including its
+/// angle brackets).
+fn patch_byte_range(bytes: &mut [u8]) -> Result<(usize, usize), String> {
+    let placeholder = format!("<{}>", "0".repeat(SIGNATURE_CONTENTS_SIZE * 2));
+    let contents_start =
+        rfind(bytes, placeholder.as_bytes()).ok_or("Failed to locate the signature placeholder")?;
+    let contents_end = contents_start + placeholder.len();
+
+    let range_text = BYTE_RANGE_PLACEHOLDER
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let range_start =
+        rfind(bytes, range_text.as_bytes()).ok_or("Failed to locate the signature byte range")?;
+
+    let actual = format!(
+        "0 {} {} {}",
+        contents_start,
+        contents_end,
+        bytes.len() - contents_end
+    );
+    // Pad to the placeholder's width so no offsets after it shift.
+    let padded = format!("{:<width$}", actual, width = range_text.len());
+    bytes[range_start..range_start + padded.len()].copy_from_slice(padded.as_bytes());
+    Ok((contents_start, contents_end))
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// PDF date string (ISO 32000-1, 7.9.4) in UTC, e.g. `D:20240131120000Z`.
+pub(crate) fn pdf_date(time: SystemTime) -> Result<String, String> {
+    let t = der::DateTime::from_system_time(time).map_err(|e| format!("Invalid time: {}", e))?;
+    Ok(format!(
+        "D:{:04}{:02}{:02}{:02}{:02}{:02}Z",
+        t.year(),
+        t.month(),
+        t.day(),
+        t.hour(),
+        t.minutes(),
+        t.seconds()
+    ))
+}
+
+/// Human-readable UTC timestamp for the appearance, e.g. `2024-01-31 12:00 UTC`.
+fn display_date(time: SystemTime) -> Result<String, String> {
+    let t = der::DateTime::from_system_time(time).map_err(|e| format!("Invalid time: {}", e))?;
+    Ok(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        t.year(),
+        t.month(),
+        t.day(),
+        t.hour(),
+        t.minutes()
+    ))
+}
+
+/// A private key from a PKCS#12 file.
+enum PrivateKey {
+    Rsa(Box<rsa::pkcs1v15::SigningKey<Sha256>>),
+    EcdsaP256(p256::ecdsa::SigningKey),
+}
+
+/// The signer's key, its certificate and any other certificates that came
+/// with it (usually the issuing chain).
+pub(crate) struct SigningIdentity {
+    key: PrivateKey,
+    pub(crate) certificate: Certificate,
+    chain: Vec<Certificate>,
+}
+
+impl SigningIdentity {
+    /// Parse a PKCS#12 (.p12/.pfx) file (RFC 7292), checking its MAC first
+    /// so a wrong password is reported as such rather than as a decrypt error.
+    pub(crate) fn from_pkcs12(bytes: &[u8], password: &str) -> Result<Self, String> {
+        let pfx = Pfx::from_der(bytes).map_err(|e| format!("Invalid digital ID file: {}", e))?;
+        if pfx.auth_safe.content_type != ID_DATA {
+            return Err(
+                "Unsupported digital ID: only password-protected files are supported".into(),
+            );
+        }
+        let auth_safe = pfx
+            .auth_safe
+            .content
+            .decode_as::<OctetString>()
+            .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+        if let Some(mac_data) = &pfx.mac_data {
+            verify_mac(mac_data, auth_safe.as_bytes(), password)?;
+        }
+
+        let mut keys = Vec::new();
+        let mut certificates = Vec::new();
+        let contents = Vec::<ContentInfo>::from_der(auth_safe.as_bytes())
+            .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+        for content in contents {
+            let safe_contents = if content.content_type == ID_DATA {
+                content
+                    .content
+                    .decode_as::<OctetString>()
+                    .map_err(|e| format!("Invalid digital ID file: {}", e))?
+                    .into_bytes()
+            } else if content.content_type == ID_ENCRYPTED_DATA {
+                let encrypted = content
+                    .content
+                    .decode_as::<EncryptedData>()
+                    .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+                let info = encrypted.enc_content_info;
+                let data = info
+                    .encrypted_content
+                    .ok_or("Invalid digital ID file: missing encrypted content")?;
+                decrypt(&info.content_enc_alg, data.as_bytes(), password)?
+            } else {
+                continue;
+            };
+
+            let bags = SafeContents::from_der(&safe_contents)
+                .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+            for bag in bags {
+                // bag_value is the raw [0] EXPLICIT wrapper.
+                let value = Any::from_der(&bag.bag_value)
+                    .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+                if bag.bag_id == pkcs12::PKCS_12_PKCS8_KEY_BAG_OID {
+                    let shrouded = EncryptedPrivateKeyInfo::from_der(value.value())
+                        .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+                    keys.push(decrypt(
+                        &shrouded.encryption_algorithm,
+                        shrouded.encrypted_data.as_bytes(),
+                        password,
+                    )?);
+                } else if bag.bag_id == pkcs12::PKCS_12_KEY_BAG_OID {
+                    keys.push(value.value().to_vec());
+                } else if bag.bag_id == pkcs12::PKCS_12_CERT_BAG_OID {
+                    let cert_bag = CertBag::from_der(value.value())
+                        .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+                    if cert_bag.cert_id == pkcs12::PKCS_12_X509_CERT_OID {
+                        certificates.push(
+                            Certificate::from_der(cert_bag.cert_value.as_bytes())
+                                .map_err(|e| format!("Invalid certificate: {}", e))?,
+                        );
+                    }
+                }
+            }
+        }
+
+        let key_der = keys
+            .into_iter()
+            .next()
+            .ok_or("The digital ID does not contain a private key")?;
+        let (key, public_key) = parse_private_key(&key_der)?;
+
+        let position = certificates
+            .iter()
+            .position(|cert| {
+                cert.tbs_certificate
+                    .subject_public_key_info
+                    .subject_public_key
+                    == public_key.subject_public_key
+            })
+            .ok_or("The digital ID does not contain a certificate for its private key")?;
+        let certificate = certificates.remove(position);
+
+        Ok(SigningIdentity {
+            key,
+            certificate,
+            chain: certificates,
+        })
+    }
+
+    fn common_name(&self) -> String {
+        common_name(&self.certificate.tbs_certificate.subject)
+    }
+
+    /// Build a detached CMS SignedData over a document digest, with the
+    /// signing time and every certificate from the identity file.
+    pub(crate) fn sign_digest(&self, digest: &[u8]) -> Result<Vec<u8>, String> {
+        let content = EncapsulatedContentInfo {
+            econtent_type: ID_DATA,
+            econtent: None,
+        };
+        let digest_algorithm = AlgorithmIdentifierOwned {
+            oid: ID_SHA256,
+            parameters: None,
+        };
+        let signer_id = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: self.certificate.tbs_certificate.issuer.clone(),
+            serial_number: self.certificate.tbs_certificate.serial_number.clone(),
+        });
+        let cms_error = |e: cms::builder::Error| format!("Failed to build signature: {}", e);
+        let signing_time = create_signing_time_attribute().map_err(cms_error)?;
+
+        let mut builder = SignedDataBuilder::new(&content);
+        builder
+            .add_digest_algorithm(digest_algorithm.clone())
+            .map_err(cms_error)?;
+        for certificate in std::iter::once(&self.certificate).chain(&self.chain) {
+            builder
+                .add_certificate(CertificateChoices::Certificate(certificate.clone()))
+                .map_err(cms_error)?;
+        }
+
+        match &self.key {
+            PrivateKey::Rsa(key) => {
+                let mut signer = SignerInfoBuilder::new(
+                    key.as_ref(),
+                    signer_id,
+                    digest_algorithm,
+                    &content,
+                    Some(digest),
+                )
+                .map_err(cms_error)?;
+                signer
+                    .add_signed_attribute(signing_time)
+                    .map_err(cms_error)?;
+                builder
+                    .add_signer_info::<_, rsa::pkcs1v15::Signature>(signer)
+                    .map_err(cms_error)?;
+            }
+            PrivateKey::EcdsaP256(key) => {
+                let mut signer = SignerInfoBuilder::new(
+                    key,
+                    signer_id,
+                    digest_algorithm,
+                    &content,
+                    Some(digest),
+                )
+                .map_err(cms_error)?;
+                signer
+                    .add_signed_attribute(signing_time)
+                    .map_err(cms_error)?;
+                builder
+                    .add_signer_info::<_, p256::ecdsa::DerSignature>(signer)
+                    .map_err(cms_error)?;
+            }
+        }
+
+        builder
+            .build()
+            .and_then(|info| Ok(info.to_der()?))
+            .map_err(cms_error)
+    }
+}
+
+/// A certificate name's common name (CN), or the full name if it has none.
+pub(crate) fn common_name(name: &Name) -> String {
+    name.0
+        .iter()
+        .flat_map(|rdn| rdn.0.iter())
+        .find(|atv| atv.oid == ID_COMMON_NAME)
+        .and_then(|atv| std::str::from_utf8(atv.value.value()).ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Load a PKCS#8 private key, returning it with its public key so the
+/// matching certificate can be found.
+fn parse_private_key(der: &[u8]) -> Result<(PrivateKey, SubjectPublicKeyInfoOwned), String> {
+    let (key, public_key) = if let Ok(key) = rsa::RsaPrivateKey::from_pkcs8_der(der) {
+        let public_key = key.to_public_key().to_public_key_der();
+        (
+            PrivateKey::Rsa(Box::new(rsa::pkcs1v15::SigningKey::new(key))),
+            public_key,
+        )
+    } else if let Ok(key) = p256::SecretKey::from_pkcs8_der(der) {
+        let public_key = key.public_key().to_public_key_der();
+        (PrivateKey::EcdsaP256(key.into()), public_key)
+    } else {
+        return Err("Unsupported private key: only RSA and P-256 keys can sign".into());
+    };
+    let public_key = public_key.map_err(|e| format!("Invalid private key: {}", e))?;
+    let public_key = SubjectPublicKeyInfoOwned::from_der(public_key.as_bytes())
+        .map_err(|e| format!("Invalid private key: {}", e))?;
+    Ok((key, public_key))
+}
+
+/// Check the PKCS#12 integrity MAC (RFC 7292, appendix B).
+fn verify_mac(
+    mac_data: &pkcs12::mac_data::MacData,
+    data: &[u8],
+    password: &str,
+) -> Result<(), String> {
+    let salt = mac_data.mac_salt.as_bytes();
+    let expected = mac_data.mac.digest.as_bytes();
+    let algorithm = mac_data.mac.algorithm.oid;
+    let kdf_error = |e: der::Error| format!("Invalid password: {}", e);
+
+    let valid = if algorithm == ID_SHA1 {
+        let key = derive_key_utf8::<sha1::Sha1>(
+            password,
+            salt,
+            Pkcs12KeyType::Mac,
+            mac_data.iterations,
+            20,
+        )
+        .map_err(kdf_error)?;
+        let mut mac = Hmac::<sha1::Sha1>::new_from_slice(&key).map_err(|e| e.to_string())?;
+        mac.update(data);
+        mac.verify_slice(expected).is_ok()
+    } else if algorithm == ID_SHA256 {
+        let key =
+            derive_key_utf8::<Sha256>(password, salt, Pkcs12KeyType::Mac, mac_data.iterations, 32)
+                .map_err(kdf_error)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key).map_err(|e| e.to_string())?;
+        mac.update(data);
+        mac.verify_slice(expected).is_ok()
+    } else {
+        return Err(format!(
+            "Unsupported digital ID integrity algorithm {}",
+            algorithm
+        ));
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err("Incorrect password for the digital ID".to_string())
+    }
+}
+
+/// Decrypt a PKCS#12 encrypted bag or safe with the given password.
+fn decrypt(
+    algorithm: &AlgorithmIdentifierOwned,
+    data: &[u8],
+    password: &str,
+) -> Result<Vec<u8>, String> {
+    use cbc::cipher::block_padding::Pkcs7;
+    use cbc::cipher::{BlockDecryptMut, InnerIvInit, KeyIvInit};
+
+    let decrypt_error = |_| "Failed to decrypt digital ID: incorrect password?".to_string();
+
+    if algorithm.oid == ID_PBES2 {
+        let encoded = algorithm
+            .to_der()
+            .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+        let scheme = pkcs8::pkcs5::EncryptionScheme::from_der(&encoded)
+            .map_err(|e| format!("Unsupported digital ID encryption: {}", e))?;
+        return scheme
+            .decrypt(password.as_bytes(), data)
+            .map_err(|_| "Failed to decrypt digital ID: incorrect password?".to_string());
+    }
+
+    let params = algorithm
+        .parameters
+        .as_ref()
+        .ok_or("Invalid digital ID file: missing encryption parameters")?
+        .decode_as::<Pkcs12PbeParams>()
+        .map_err(|e| format!("Invalid digital ID file: {}", e))?;
+    let derive = |id, len| {
+        derive_key_utf8::<sha1::Sha1>(password, params.salt.as_bytes(), id, params.iterations, len)
+            .map_err(|e| format!("Invalid password: {}", e))
+    };
+    let mut buffer = data.to_vec();
+
+    let plain = if algorithm.oid == pkcs12::PKCS_12_PBE_WITH_SHAAND3_KEY_TRIPLE_DES_CBC {
+        let key = derive(Pkcs12KeyType::EncryptionKey, 24)?;
+        let iv = derive(Pkcs12KeyType::Iv, 8)?;
+        cbc::Decryptor::<des::TdesEde3>::new_from_slices(&key, &iv)
+            .map_err(|e| e.to_string())?
+            .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+            .map_err(decrypt_error)?
+    } else if algorithm.oid == pkcs12::PKCS_12_PBEWITH_SHAAND40_BIT_RC2_CBC
+        || algorithm.oid == pkcs12::PKCS_12_PBE_WITH_SHAAND128_BIT_RC2_CBC
+    {
+        let key_len = if algorithm.oid == pkcs12::PKCS_12_PBEWITH_SHAAND40_BIT_RC2_CBC {
+            5
+        } else {
+            16
+        };
+        let key = derive(Pkcs12KeyType::EncryptionKey, key_len)?;
+        let iv = derive(Pkcs12KeyType::Iv, 8)?;
+        let cipher = rc2::Rc2::new_with_eff_key_len(&key, key_len * 8);
+        cbc::Decryptor::<rc2::Rc2>::inner_iv_slice_init(cipher, &iv)
+            .map_err(|e| e.to_string())?
+            .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+            .map_err(decrypt_error)?
+    } else {
+        return Err(format!(
+            "Unsupported digital ID encryption {}",
+            algorithm.oid
+        ));
+    };
+    Ok(plain.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::commands::test_support::{
+        path_in, save, temp_dir, text_pdf, SIGNER_P12, SIGNER_PASSWORD,
+    };
+    use crate::commands::verification::{verify, IntegrityStatus};
+
+    fn sign_options(dir: &Path, placement: Option<SignaturePlacement>) -> SignOptions {
+        let identity = dir.join("signer.p12");
+        fs::write(&identity, SIGNER_P12).unwrap();
+        SignOptions {
+            identity_path: identity.to_string_lossy().into_owned(),
+            password: SIGNER_PASSWORD.to_string(),
+            placement,
+            style: None,
+            reason: Some("Approved".to_string()),
+            location: Some("Office".to_string()),
+            contact_info: None,
+        }
+    }
+
+    #[test]
+    fn signatures_verify() {
+        let dir = temp_dir("sign");
+        let source = save(&mut text_pdf(&[&["Contract"]]), &dir, "source.pdf");
+        let signed = path_in(&dir, "signed.pdf");
+        let placement = SignaturePlacement {
+            page_number: 1,
+            x: 72.0,
+            y: 600.0,
+            width: 200.0,
+            height: 50.0,
+        };
+        sign(&source, &signed, &sign_options(&dir, Some(placement))).unwrap();
+
+        let reports = verify(&signed, &[]).unwrap();
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert!(report.signed);
+        assert_eq!(
+            report.integrity,
+            IntegrityStatus::Valid,
+            "{:?}",
+            report.messages
+        );
+        assert_eq!(report.signer_name.as_deref(), Some("Test Signer"));
+        assert_eq!(report.reason.as_deref(), Some("Approved"));
+        assert_eq!(report.location.as_deref(), Some("Office"));
+        assert_eq!(report.page_number, Some(1));
+        assert!(report.covers_whole_document);
+        assert!(!report.modified_after_signing);
+    }
+
+    #[test]
+    fn a_second_signature_keeps_the_first_valid() {
+        let dir = temp_dir("sign-twice");
+        let source = save(&mut text_pdf(&[&["Contract"]]), &dir, "source.pdf");
+        let once = path_in(&dir, "once.pdf");
+        let twice = path_in(&dir, "twice.pdf");
+        sign(&source, &once, &sign_options(&dir, None)).unwrap();
+        sign(&once, &twice, &sign_options(&dir, None)).unwrap();
+
+        let reports = verify(&twice, &[]).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert!(reports
+            .iter()
+            .all(|r| r.integrity == IntegrityStatus::Valid));
+        assert_eq!(
+            reports.iter().filter(|r| r.modified_after_signing).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn changed_bytes_break_the_signature() {
+        let dir = temp_dir("sign-tamper");
+        let source = save(&mut text_pdf(&[&["Contract"]]), &dir, "source.pdf");
+        let signed = path_in(&dir, "signed.pdf");
+        sign(&source, &signed, &sign_options(&dir, None)).unwrap();
+
+        let mut bytes = fs::read(&signed).unwrap();
+        let at = bytes.windows(8).position(|w| w == b"Contract").unwrap();
+        bytes[at] = b'K';
+        fs::write(&signed, bytes).unwrap();
+
+        let reports = verify(&signed, &[]).unwrap();
+        assert_eq!(reports[0].integrity, IntegrityStatus::Invalid);
+    }
+
+    #[test]
+    fn wrong_identity_password_is_refused() {
+        let dir = temp_dir("sign-password");
+        let source = save(&mut text_pdf(&[&["Contract"]]), &dir, "source.pdf");
+        let options = SignOptions {
+            password: "wrong".to_string(),
+            ..sign_options(&dir, None)
+        };
+        assert!(sign(&source, &path_in(&dir, "signed.pdf"), &options).is_err());
+        assert!(!dir.join("signed.pdf").exists());
+    }
+}
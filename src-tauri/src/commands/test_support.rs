@@ -0,0 +1,147 @@
+//! Small in-memory PDFs and scratch directories for the unit tests.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+
+/// A self-signed RSA-2048 identity ("CN=Test Signer"), password `test`.
+pub(crate) const SIGNER_P12: &[u8] = include_bytes!("../../tests/fixtures/signer.p12");
+pub(crate) const SIGNER_PASSWORD: &str = "test";
+
+/// A new empty directory under the system temp directory.
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "office-tools-test-{}-{}-{}",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A path in `dir` as the `&str` the command cores take.
+pub(crate) fn path_in(dir: &std::path::Path, name: &str) -> String {
+    dir.join(name).to_string_lossy().into_owned()
+}
+
+/// Letter-size pages, each with its lines of text drawn in 12pt Helvetica
+/// from (72, 720) down, 20pt apart.
+pub(crate) fn text_pdf(pages: &[&[&str]]) -> Document {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let mut kids = Vec::new();
+    for lines in pages {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            Operation::new("TL", vec![20.into()]),
+            Operation::new("Td", vec![72.into(), 720.into()]),
+        ];
+        for line in *lines {
+            operations.push(Operation::new("Tj", vec![Object::string_literal(*line)]));
+            operations.push(Operation::new("T*", vec![]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+        let resources = dictionary! { "Font" => dictionary! { "F1" => font_id } };
+        kids.push(add_page(&mut doc, pages_id, operations, resources).into());
+    }
+    finish(&mut doc, pages_id, kids);
+    doc
+}
+
+/// One page drawing the image stream `image` as `/Im1` over the rectangle
+/// `[x y width height]`, in points.
+pub(crate) fn image_pdf(image: Stream, rect: [f32; 4]) -> (Document, ObjectId) {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let image_id = doc.add_object(image);
+    let [x, y, width, height] = rect;
+    let operations = vec![
+        Operation::new("q", vec![]),
+        Operation::new(
+            "cm",
+            vec![
+                width.into(),
+                0.into(),
+                0.into(),
+                height.into(),
+                x.into(),
+                y.into(),
+            ],
+        ),
+        Operation::new("Do", vec![Object::Name(b"Im1".to_vec())]),
+        Operation::new("Q", vec![]),
+    ];
+    let resources = dictionary! { "XObject" => dictionary! { "Im1" => image_id } };
+    let page = add_page(&mut doc, pages_id, operations, resources);
+    finish(&mut doc, pages_id, vec![page.into()]);
+    (doc, image_id)
+}
+
+/// An uncompressed 8-bit image with the given samples.
+pub(crate) fn image_stream(width: i64, height: i64, color_space: &str, samples: Vec<u8>) -> Stream {
+    Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width,
+            "Height" => height,
+            "ColorSpace" => color_space,
+            "BitsPerComponent" => 8,
+        },
+        samples,
+    )
+}
+
+/// Save `doc` into `dir` as `name`, returning its path.
+pub(crate) fn save(doc: &mut Document, dir: &std::path::Path, name: &str) -> String {
+    let path = path_in(dir, name);
+    doc.save(&path).unwrap();
+    path
+}
+
+fn add_page(
+    doc: &mut Document,
+    pages_id: ObjectId,
+    operations: Vec<Operation>,
+    resources: lopdf::Dictionary,
+) -> ObjectId {
+    let content = Content { operations }.encode().unwrap();
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+    doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        "Contents" => content_id,
+        "Resources" => resources,
+    })
+}
+
+fn finish(doc: &mut Document, pages_id: ObjectId, kids: Vec<Object>) {
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+}
@@ -0,0 +1,1264 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Encoding, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+
+use super::pdf::{PageGeometry, PdfEditor, SaveMode};
+
+/// How deep we follow Form XObjects that draw other forms. Real documents
+/// rarely nest more than two or three levels; the limit guards against
+/// cyclic resources.
+pub(crate) const MAX_FORM_DEPTH: usize = 8;
+
+/// Ascent/descent (1000 units/em) for fonts that declare neither a
+/// descriptor nor a bounding box.
+const FALLBACK_ASCENT: f32 = 800.0;
+const FALLBACK_DESCENT: f32 = -200.0;
+
+/// Helvetica advance widths for codes 32-126 from the standard AFM metrics.
+/// Used for standard 14 fonts saved without a /Widths array; other fonts in
+/// that family are close enough for locating text on the page.
+pub(crate) const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// A gap between two glyphs on a line wider than this fraction of the line
+/// height is read as a space. Most fonts' word spaces are 0.25-0.3 em.
+const SPACE_GAP_RATIO: f32 = 0.15;
+
+/// How many characters of the surrounding text a search hit carries on
+/// either side of the match.
+const CONTEXT_CHARS: usize = 40;
+
+/// A rectangle in the displayed page frame annotations use (top-left
+/// origin, PDF points at pdf.js scale=1).
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A glyph on a page and the text it stands for.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextGlyph {
+    /// Usually one character; empty for glyphs without a Unicode mapping
+    /// and several characters for ligatures.
+    pub text: String,
+    /// Where the glyph's text starts in `PageText::text`, in UTF-16 code
+    /// units so it can index JavaScript strings directly.
+    pub offset: usize,
+    #[serde(flatten)]
+    pub rect: TextRect,
+}
+
+/// The text of one page, in content-stream order with spaces and line
+/// breaks inferred from glyph positions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageText {
+    pub page_number: u32,
+    pub text: String,
+    pub glyphs: Vec<TextGlyph>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only match where the query isn't part of a longer word.
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+/// One occurrence of a search query.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub page_number: u32,
+    /// The matched text as it appears on the page.
+    pub text: String,
+    /// The match with some surrounding text, for listing results.
+    pub context: String,
+    /// One rectangle per line the match spans.
+    pub rects: Vec<TextRect>,
+}
+
+/// Extract the text of the PDF at `path` with the position of every glyph.
+/// `pages` selects 1-based page numbers; all pages when omitted.
+#[tauri::command]
+pub async fn extract_pdf_text(
+    path: String,
+    pages: Option<Vec<u32>>,
+) -> Result<Vec<PageText>, String> {
+    extract_text(&path, pages.as_deref())
+}
+
+/// Find every occurrence of `query` in the PDF at `path`.
+#[tauri::command]
+pub async fn search_pdf(
+    path: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<Vec<SearchHit>, String> {
+    search(&path, &query, &options.unwrap_or_default())
+}
+
+pub fn extract_text(path: &str, pages: Option<&[u32]>) -> Result<Vec<PageText>, String> {
+    let editor = PdfEditor::open(path, SaveMode::Rewrite)?;
+    let doc = &editor.doc;
+    let all_pages = doc.get_pages();
+    let selected: Vec<u32> = match pages {
+        Some(pages) => pages.to_vec(),
+        None => all_pages.keys().copied().collect(),
+    };
+
+    let mut result = Vec::with_capacity(selected.len());
+    for page_number in selected {
+        let page_id = *all_pages.get(&page_number).ok_or_else(|| {
+            format!(
+                "Page {} does not exist (the document has {} pages)",
+                page_number,
+                all_pages.len()
+            )
+        })?;
+        let page = PageChars::read(doc, page_id)
+            .map_err(|e| format!("Failed to read text of page {}: {}", page_number, e))?;
+        result.push(page.into_page_text(page_number));
+    }
+    Ok(result)
+}
+
+/// Search the text of every page. Runs of whitespace in the query match
+/// any whitespace or line break on the page, so phrases are found across
+/// lines.
+pub fn search(path: &str, query: &str, options: &SearchOptions) -> Result<Vec<SearchHit>, String> {
+    let needle = searchable(
+        query.trim().chars().map(|c| (c, ())),
+        options.case_sensitive,
+    );
+    if needle.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let needle: Vec<char> = needle.into_iter().map(|(c, _)| c).collect();
+
+    let editor = PdfEditor::open(path, SaveMode::Rewrite)?;
+    let doc = &editor.doc;
+    let mut hits = Vec::new();
+    for (page_number, page_id) in doc.get_pages() {
+        let page = PageChars::read(doc, page_id)
+            .map_err(|e| format!("Failed to read text of page {}: {}", page_number, e))?;
+        let haystack = searchable(
+            page.chars.iter().copied().enumerate().map(|(i, c)| (c, i)),
+            options.case_sensitive,
+        );
+
+        let mut start = 0;
+        while start + needle.len() <= haystack.len() {
+            let end = start + needle.len();
+            let matches = haystack[start..end]
+                .iter()
+                .zip(&needle)
+                .all(|((c, _), n)| c == n);
+            let bounded = !options.whole_word
+                || (!continues_word(needle[0], start.checked_sub(1).map(|i| haystack[i].0))
+                    && !continues_word(needle[needle.len() - 1], haystack.get(end).map(|h| h.0)));
+            if !(matches && bounded) {
+                start += 1;
+                continue;
+            }
+            let first = haystack[start].1;
+            let last = haystack[end - 1].1;
+            hits.push(page.hit(page_number, first..last + 1));
+            start = end;
+        }
+    }
+    Ok(hits)
+}
+
+/// Lower-case (unless `case_sensitive`) and collapse whitespace runs to a
+/// single space, keeping a tag for where each character came from.
+fn searchable<T: Copy>(
+    chars: impl Iterator<Item = (char, T)>,
+    case_sensitive: bool,
+) -> Vec<(char, T)> {
+    let mut result: Vec<(char, T)> = Vec::new();
+    for (c, tag) in chars {
+        if c.is_whitespace() {
+            if result.last().is_some_and(|(last, _)| *last != ' ') {
+                result.push((' ', tag));
+            }
+        } else if case_sensitive {
+            result.push((c, tag));
+        } else {
+            result.extend(c.to_lowercase().map(|lower| (lower, tag)));
+        }
+    }
+    if result.last().is_some_and(|(last, _)| *last == ' ') {
+        result.pop();
+    }
+    result
+}
+
+/// Whether `neighbour` would make a match that ends (or starts) with `edge`
+/// part of a longer word.
+fn continues_word(edge: char, neighbour: Option<char>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    is_word(edge) && neighbour.is_some_and(is_word)
+}
+
+/// A page's text as characters, each tagged with the glyph it came from.
+/// Inferred spaces and line breaks have no glyph.
+struct PageChars {
+    chars: Vec<char>,
+    owners: Vec<Option<usize>>,
+    glyphs: Vec<PageGlyph>,
+}
+
+struct PageGlyph {
+    text: String,
+    rect: TextRect,
+    /// Quarter turns clockwise from left-to-right text, as displayed.
+    turns: u8,
+    /// `rect` turned back by `turns`, so the text runs along +x and the
+    /// next line is further down +y whichever way the glyph is drawn.
+    line_rect: TextRect,
+}
+
+impl PageGlyph {
+    fn new(geometry: PageGeometry, glyph: PlacedGlyph) -> Self {
+        let rect = display_rect(geometry, glyph.bounds);
+        let (ox, oy) = geometry.to_display_coords(0.0, 0.0);
+        let (dx, dy) = geometry.to_display_coords(glyph.direction.0, glyph.direction.1);
+        let (dx, dy) = (dx - ox, dy - oy);
+        let turns = if dx.abs() >= dy.abs() {
+            if dx >= 0.0 {
+                0
+            } else {
+                2
+            }
+        } else if dy > 0.0 {
+            1
+        } else {
+            3
+        };
+        let TextRect {
+            x,
+            y,
+            width,
+            height,
+        } = rect;
+        let (lx, ly, lw, lh) = match turns {
+            1 => (y, -(x + width), height, width),
+            2 => (-(x + width), -(y + height), width, height),
+            3 => (-(y + height), x, height, width),
+            _ => (x, y, width, height),
+        };
+        PageGlyph {
+            text: glyph.text,
+            rect,
+            turns,
+            line_rect: TextRect {
+                x: lx,
+                y: ly,
+                width: lw,
+                height: lh,
+            },
+        }
+    }
+
+    /// Whether `next` continues the line this glyph is on: both run the same
+    /// way and overlap across the line by at least half the smaller height,
+    /// and `next` doesn't start back before this glyph (a new column or
+    /// table cell).
+    fn same_line(&self, next: &PageGlyph) -> bool {
+        let (a, b) = (&self.line_rect, &next.line_rect);
+        let overlap = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+        self.turns == next.turns && overlap >= 0.5 * a.height.min(b.height) && b.x + b.width > a.x
+    }
+
+    /// Whether the gap between this glyph and `next` on the same line is
+    /// wide enough to be a word space.
+    fn space_before(&self, next: &PageGlyph) -> bool {
+        let (a, b) = (&self.line_rect, &next.line_rect);
+        b.x - (a.x + a.width) > SPACE_GAP_RATIO * a.height.max(b.height)
+    }
+}
+
+impl PageChars {
+    fn read(doc: &Document, page_id: ObjectId) -> Result<Self, String> {
+        let geometry = PageGeometry::of(doc, page_id);
+        let mut glyphs = Vec::new();
+        walk_page(doc, page_id, &mut |event| {
+            if let PaintEvent::Text(placed) = event {
+                glyphs.extend(
+                    placed
+                        .into_iter()
+                        .filter(|g| !g.text.is_empty())
+                        .map(|g| PageGlyph::new(geometry, g)),
+                );
+            }
+        })?;
+
+        let mut page = PageChars {
+            chars: Vec::new(),
+            owners: Vec::new(),
+            glyphs: Vec::new(),
+        };
+        for (index, glyph) in glyphs.iter().enumerate() {
+            if let Some(previous) = index.checked_sub(1).map(|i| &glyphs[i]) {
+                let after_space = page.chars.last().is_some_and(|c| c.is_whitespace());
+                let before_space = glyph.text.starts_with(char::is_whitespace);
+                if !previous.same_line(glyph) {
+                    if !after_space {
+                        page.push('\n', None);
+                    }
+                } else if !after_space && !before_space && previous.space_before(glyph) {
+                    page.push(' ', None);
+                }
+            }
+            for c in glyph.text.chars() {
+                page.push(c, Some(index));
+            }
+        }
+        page.glyphs = glyphs;
+        Ok(page)
+    }
+
+    fn push(&mut self, c: char, owner: Option<usize>) {
+        self.chars.push(c);
+        self.owners.push(owner);
+    }
+
+    fn into_page_text(self, page_number: u32) -> PageText {
+        let mut offsets = vec![None; self.glyphs.len()];
+        let mut offset = 0;
+        for (c, owner) in self.chars.iter().zip(&self.owners) {
+            if let Some(owner) = owner {
+                offsets[*owner].get_or_insert(offset);
+            }
+            offset += c.len_utf16();
+        }
+        PageText {
+            page_number,
+            text: self.chars.iter().collect(),
+            glyphs: self
+                .glyphs
+                .into_iter()
+                .zip(offsets)
+                .map(|(glyph, offset)| TextGlyph {
+                    text: glyph.text,
+                    offset: offset.unwrap_or_default(),
+                    rect: glyph.rect,
+                })
+                .collect(),
+        }
+    }
+
+    /// A search hit covering the characters in `range`.
+    fn hit(&self, page_number: u32, range: Range<usize>) -> SearchHit {
+        let context_start = range.start.saturating_sub(CONTEXT_CHARS);
+        let context_end = (range.end + CONTEXT_CHARS).min(self.chars.len());
+        let context: String = self.chars[context_start..context_end]
+            .iter()
+            .map(|c| if c.is_whitespace() { ' ' } else { *c })
+            .collect();
+
+        // Ligatures own several characters; each glyph counts once.
+        let mut owners: Vec<usize> = self.owners[range.clone()]
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        owners.dedup();
+        let mut rects: Vec<TextRect> = Vec::new();
+        for (i, owner) in owners.iter().enumerate() {
+            let glyph = &self.glyphs[*owner];
+            let previous = i.checked_sub(1).map(|p| &self.glyphs[owners[p]]);
+            match (previous, rects.last_mut()) {
+                (Some(previous), Some(line)) if previous.same_line(glyph) => {
+                    *line = union(line, &glyph.rect)
+                }
+                _ => rects.push(glyph.rect),
+            }
+        }
+
+        SearchHit {
+            page_number,
+            text: self.chars[range].iter().collect(),
+            context: context.trim().to_string(),
+            rects,
+        }
+    }
+}
+
+fn display_rect(geometry: PageGeometry, bounds: Rect) -> TextRect {
+    let (ax, ay) = geometry.to_display_coords(bounds.x0, bounds.y0);
+    let (bx, by) = geometry.to_display_coords(bounds.x1, bounds.y1);
+    TextRect {
+        x: ax.min(bx),
+        y: ay.min(by),
+        width: (ax - bx).abs(),
+        height: (ay - by).abs(),
+    }
+}
+
+fn union(a: &TextRect, b: &TextRect) -> TextRect {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    TextRect {
+        x,
+        y,
+        width: (a.x + a.width).max(b.x + b.width) - x,
+        height: (a.y + a.height).max(b.y + b.height) - y,
+    }
+}
+
+/// An affine transformation `[a b c d e f]`, as used by `cm` and `Tm`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Matrix(pub [f32; 6]);
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    pub fn translate(tx: f32, ty: f32) -> Self {
+        Matrix([1.0, 0.0, 0.0, 1.0, tx, ty])
+    }
+
+    pub fn from_operands(operands: &[Object]) -> Option<Self> {
+        let values: Vec<f32> = operands.iter().filter_map(|o| o.as_float().ok()).collect();
+        <[f32; 6]>::try_from(values).ok().map(Matrix)
+    }
+
+    /// The transformation that applies `self` first, then `other`.
+    pub fn then(self, other: Matrix) -> Matrix {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+        Matrix([
+            a * a2 + b * c2,
+            a * b2 + b * d2,
+            c * a2 + d * c2,
+            c * b2 + d * d2,
+            e * a2 + f * c2 + e2,
+            e * b2 + f * d2 + f2,
+        ])
+    }
+
+    pub fn apply(self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    pub fn invert(self) -> Option<Matrix> {
+        let [a, b, c, d, e, f] = self.0;
+        let det = a * d - b * c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        Some(Matrix([
+            d / det,
+            -b / det,
+            -c / det,
+            a / det,
+            (c * f - d * e) / det,
+            (b * e - a * f) / det,
+        ]))
+    }
+
+    /// Bounding box of `rect` after transformation.
+    pub fn transform_rect(self, rect: Rect) -> Rect {
+        let corners = [
+            self.apply(rect.x0, rect.y0),
+            self.apply(rect.x1, rect.y0),
+            self.apply(rect.x0, rect.y1),
+            self.apply(rect.x1, rect.y1),
+        ];
+        let xs = corners.map(|(x, _)| x);
+        let ys = corners.map(|(_, y)| y);
+        Rect {
+            x0: xs.into_iter().fold(f32::INFINITY, f32::min),
+            y0: ys.into_iter().fold(f32::INFINITY, f32::min),
+            x1: xs.into_iter().fold(f32::NEG_INFINITY, f32::max),
+            y1: ys.into_iter().fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+}
+
+/// An axis-aligned rectangle in PDF user space (bottom-left origin).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Rect {
+    pub x0: f32,
+    pub y0: f32,
+    pub x1: f32,
+    pub y1: f32,
+}
+
+impl Rect {
+    /// Build a rectangle from two opposite corners in any order.
+    pub fn new(x0: f32, y0: f32, x1: f32, y1: f32) -> Self {
+        Rect {
+            x0: x0.min(x1),
+            y0: y0.min(y1),
+            x1: x0.max(x1),
+            y1: y0.max(y1),
+        }
+    }
+
+    /// The unit square images are painted into.
+    pub const UNIT: Rect = Rect {
+        x0: 0.0,
+        y0: 0.0,
+        x1: 1.0,
+        y1: 1.0,
+    };
+
+    /// Read a `[x0 y0 x1 y1]` array such as an annotation's /Rect.
+    pub fn from_object(object: &Object) -> Option<Self> {
+        let values: Vec<f32> = object
+            .as_array()
+            .ok()?
+            .iter()
+            .filter_map(|o| o.as_float().ok())
+            .collect();
+        match values.as_slice() {
+            [x0, y0, x1, y1] => Some(Rect::new(*x0, *y0, *x1, *y1)),
+            _ => None,
+        }
+    }
+
+    /// Whether the two rectangles overlap. Rectangles that merely touch
+    /// don't.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x0 < other.x1 && other.x0 < self.x1 && self.y0 < other.y1 && other.y0 < self.y1
+    }
+
+    /// The smallest rectangle containing both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+}
+
+/// A glyph painted by a text-showing operator.
+#[derive(Debug, Clone)]
+pub(crate) struct PlacedGlyph {
+    /// The character code as it appears in the string operand.
+    pub code: Vec<u8>,
+    /// The Unicode text the glyph stands for; may be empty or hold several
+    /// characters (ligatures).
+    pub text: String,
+    /// Bounds of the glyph in the space the walk started in (page space for
+    /// page content).
+    pub bounds: Rect,
+    /// The TJ adjustment that moves the text position exactly as far as
+    /// painting this glyph does.
+    pub adjustment: f32,
+    /// Index of the string operand the glyph came from: the position in a
+    /// TJ array, or 0 for Tj, ' and ".
+    pub element: usize,
+    /// The direction the text advances in, in the same space as `bounds`.
+    pub direction: (f32, f32),
+}
+
+/// Something a content stream paints, reported with the index of the
+/// operation that painted it.
+pub(crate) enum ContentEvent {
+    Text(Vec<PlacedGlyph>),
+    XObject { name: Vec<u8>, ctm: Matrix },
+    InlineImage { ctm: Matrix },
+}
+
+/// Something a page paints, with Form XObjects already followed.
+pub(crate) enum PaintEvent<'a> {
+    Text(Vec<PlacedGlyph>),
+    Image {
+        id: Option<ObjectId>,
+        stream: &'a Stream,
+        ctm: Matrix,
+    },
+    InlineImage {
+        ctm: Matrix,
+    },
+}
+
+/// The metrics and encoding of a font, as far as they're needed to place
+/// its glyphs.
+pub(crate) struct FontInfo<'a> {
+    /// Type0 fonts use two-byte codes; everything else one byte per glyph.
+    two_byte: bool,
+    /// Advance widths in glyph space, keyed by character code (or CID).
+    widths: BTreeMap<u32, f32>,
+    default_width: f32,
+    /// Maps glyph space to text space: 1/1000 for all but Type3 fonts.
+    font_matrix: Matrix,
+    ascent: f32,
+    descent: f32,
+    encoding: Option<Encoding<'a>>,
+}
+
+impl<'a> FontInfo<'a> {
+    pub fn load(doc: &'a Document, font: &'a Dictionary) -> Self {
+        let subtype = font
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .unwrap_or(b"");
+        let two_byte = subtype == b"Type0";
+        let metrics_dict = if two_byte {
+            font.get_deref(b"DescendantFonts", doc)
+                .and_then(Object::as_array)
+                .ok()
+                .and_then(|fonts| fonts.first())
+                .and_then(|f| doc.dereference(f).ok())
+                .and_then(|(_, f)| f.as_dict().ok())
+                .unwrap_or(font)
+        } else {
+            font
+        };
+        let descriptor = metrics_dict
+            .get_deref(b"FontDescriptor", doc)
+            .and_then(Object::as_dict)
+            .ok();
+
+        let font_matrix = if subtype == b"Type3" {
+            font.get(b"FontMatrix")
+                .and_then(Object::as_array)
+                .ok()
+                .and_then(|m| Matrix::from_operands(m))
+                .unwrap_or(Matrix([0.001, 0.0, 0.0, 0.001, 0.0, 0.0]))
+        } else {
+            Matrix([0.001, 0.0, 0.0, 0.001, 0.0, 0.0])
+        };
+
+        let (widths, default_width) = if two_byte {
+            cid_widths(doc, metrics_dict)
+        } else {
+            simple_widths(doc, font, descriptor)
+        };
+
+        let bbox = font
+            .get(b"FontBBox")
+            .ok()
+            .or_else(|| descriptor.and_then(|d| d.get(b"FontBBox").ok()))
+            .and_then(|o| doc.dereference(o).ok())
+            .and_then(|(_, o)| Rect::from_object(o));
+        let declared = |key: &[u8]| {
+            descriptor
+                .and_then(|d| d.get(key).ok())
+                .and_then(|v| v.as_float().ok())
+                .filter(|v| *v != 0.0)
+        };
+        let (ascent, descent) = match (declared(b"Ascent"), declared(b"Descent"), bbox) {
+            (Some(ascent), Some(descent), _) if subtype != b"Type3" => (ascent, descent),
+            (_, _, Some(bbox)) if bbox.y1 > bbox.y0 => (bbox.y1, bbox.y0),
+            _ => (FALLBACK_ASCENT, FALLBACK_DESCENT),
+        };
+
+        FontInfo {
+            two_byte,
+            widths,
+            default_width,
+            font_matrix,
+            ascent,
+            descent,
+            encoding: font_encoding(doc, font),
+        }
+    }
+
+    /// Split a string operand into character codes.
+    pub fn codes<'s>(&self, bytes: &'s [u8]) -> std::slice::Chunks<'s, u8> {
+        bytes.chunks(if self.two_byte { 2 } else { 1 })
+    }
+
+    /// Advance width of a code in glyph space.
+    pub fn width(&self, code: &[u8]) -> f32 {
+        let key = code.iter().fold(0u32, |acc, b| acc << 8 | u32::from(*b));
+        self.widths.get(&key).copied().unwrap_or(self.default_width)
+    }
+
+    pub fn decode(&self, code: &[u8]) -> String {
+        self.encoding
+            .as_ref()
+            .and_then(|e| e.bytes_to_string(code).ok())
+            .unwrap_or_else(|| "\u{FFFD}".to_string())
+    }
+}
+
+/// Widths of a simple font: /Widths from /FirstChar, then /MissingWidth.
+/// Standard 14 fonts may omit /Widths entirely.
+fn simple_widths(
+    doc: &Document,
+    font: &Dictionary,
+    descriptor: Option<&Dictionary>,
+) -> (BTreeMap<u32, f32>, f32) {
+    let missing = descriptor
+        .and_then(|d| d.get(b"MissingWidth").ok())
+        .and_then(|v| v.as_float().ok())
+        .unwrap_or(0.0);
+    if let Ok(widths) = font.get_deref(b"Widths", doc).and_then(Object::as_array) {
+        let first = font
+            .get(b"FirstChar")
+            .and_then(Object::as_i64)
+            .unwrap_or(0)
+            .max(0) as u32;
+        let widths = widths
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let width = doc
+                    .dereference(w)
+                    .ok()
+                    .and_then(|(_, w)| w.as_float().ok())
+                    .unwrap_or(missing);
+                (first + i as u32, width)
+            })
+            .collect();
+        return (widths, missing);
+    }
+
+    let base_font = font
+        .get(b"BaseFont")
+        .and_then(Object::as_name)
+        .unwrap_or(b"");
+    if base_font.starts_with(b"Courier") {
+        return (BTreeMap::new(), 600.0);
+    }
+    let widths = HELVETICA_WIDTHS
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (32 + i as u32, f32::from(*w)))
+        .collect();
+    (widths, 556.0)
+}
+
+/// Widths of a CIDFont from /W and /DW. Codes are taken to be CIDs, which
+/// holds for the Identity encodings nearly every embedded CJK or subset
+/// font uses.
+fn cid_widths(doc: &Document, cid_font: &Dictionary) -> (BTreeMap<u32, f32>, f32) {
+    let default_width = cid_font
+        .get(b"DW")
+        .and_then(Object::as_float)
+        .unwrap_or(1000.0);
+    let mut widths = BTreeMap::new();
+    let items = cid_font
+        .get_deref(b"W", doc)
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let number = |o: &Object| doc.dereference(o).ok().and_then(|(_, o)| o.as_float().ok());
+
+    // Entries are either `c [w1 w2 ...]` or `c_first c_last w`.
+    let mut i = 0;
+    while i < items.len() {
+        let Some(first) = number(&items[i]) else {
+            break;
+        };
+        let first = first.max(0.0) as u32;
+        match items.get(i + 1).map(|o| doc.dereference(o).map(|(_, o)| o)) {
+            Some(Ok(Object::Array(run))) => {
+                for (offset, w) in run.iter().enumerate() {
+                    if let Some(w) = number(w) {
+                        widths.insert(first + offset as u32, w);
+                    }
+                }
+                i += 2;
+            }
+            Some(Ok(last)) => {
+                let (Ok(last), Some(w)) = (last.as_float(), items.get(i + 2).and_then(number))
+                else {
+                    break;
+                };
+                // Cap ranges so a corrupt entry can't allocate millions of slots.
+                let last = (last.max(0.0) as u32).min(first.saturating_add(0xFFFF));
+                for cid in first..=last {
+                    widths.insert(cid, w);
+                }
+                i += 3;
+            }
+            _ => break,
+        }
+    }
+    (widths, default_width)
+}
+
+/// The font's mapping to Unicode. `get_font_encoding` only consults
+/// /ToUnicode when /Encoding is absent, but the ToUnicode CMap is what
+/// viewers use for search and copy, so it wins whenever it's present.
+fn font_encoding<'a>(doc: &'a Document, font: &'a Dictionary) -> Option<Encoding<'a>> {
+    if let Ok(to_unicode) = font.get(b"ToUnicode") {
+        let mut only_cmap = Dictionary::new();
+        only_cmap.set("Type", Object::Name(b"Font".to_vec()));
+        only_cmap.set("ToUnicode", to_unicode.clone());
+        if let Ok(Encoding::UnicodeMapEncoding(cmap)) = only_cmap.get_font_encoding(doc) {
+            return Some(Encoding::UnicodeMapEncoding(cmap));
+        }
+    }
+    font.get_font_encoding(doc).ok()
+}
+
+#[derive(Clone)]
+struct GraphicsState<'a> {
+    ctm: Matrix,
+    font: Option<Rc<FontInfo<'a>>>,
+    font_size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scaling: f32,
+    leading: f32,
+    rise: f32,
+}
+
+/// Interprets content streams far enough to know where glyphs and images
+/// land. Fonts are cached across streams, so reuse one layout per document.
+pub(crate) struct TextLayout<'a> {
+    doc: &'a Document,
+    fonts: BTreeMap<ObjectId, Rc<FontInfo<'a>>>,
+}
+
+impl<'a> TextLayout<'a> {
+    pub fn new(doc: &'a Document) -> Self {
+        TextLayout {
+            doc,
+            fonts: BTreeMap::new(),
+        }
+    }
+
+    fn font(&mut self, resources: Option<&'a Dictionary>, name: &[u8]) -> Option<Rc<FontInfo<'a>>> {
+        let doc = self.doc;
+        let object = resources?
+            .get_deref(b"Font", doc)
+            .and_then(Object::as_dict)
+            .ok()?
+            .get(name)
+            .ok()?;
+        match object {
+            Object::Reference(id) => {
+                if let Some(font) = self.fonts.get(id) {
+                    return Some(font.clone());
+                }
+                let font = Rc::new(FontInfo::load(doc, doc.get_dictionary(*id).ok()?));
+                self.fonts.insert(*id, font.clone());
+                Some(font)
+            }
+            Object::Dictionary(dict) => Some(Rc::new(FontInfo::load(doc, dict))),
+            _ => None,
+        }
+    }
+
+    /// Walk `operations`, reporting each glyph run, XObject and inline image
+    /// with the index of the operation that painted it. `ctm` maps the
+    /// stream's user space to the space results are reported in.
+    pub fn walk(
+        &mut self,
+        resources: Option<&'a Dictionary>,
+        operations: &[Operation],
+        ctm: Matrix,
+        visit: &mut dyn FnMut(usize, ContentEvent),
+    ) {
+        let mut state = GraphicsState {
+            ctm,
+            font: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scaling: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        };
+        let mut stack = Vec::new();
+        let mut text_matrix = Matrix::IDENTITY;
+        let mut line_matrix = Matrix::IDENTITY;
+
+        for (index, op) in operations.iter().enumerate() {
+            let operands = op.operands.as_slice();
+            let number = |i: usize| operands.get(i).and_then(|o| o.as_float().ok());
+            match op.operator.as_str() {
+                "q" => stack.push(state.clone()),
+                "Q" => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+                "cm" => {
+                    if let Some(m) = Matrix::from_operands(operands) {
+                        state.ctm = m.then(state.ctm);
+                    }
+                }
+                "BT" => {
+                    text_matrix = Matrix::IDENTITY;
+                    line_matrix = Matrix::IDENTITY;
+                }
+                "Tf" => {
+                    state.font = operands
+                        .first()
+                        .and_then(|o| o.as_name().ok())
+                        .and_then(|name| self.font(resources, name));
+                    state.font_size = number(1).unwrap_or(0.0);
+                }
+                "Tc" => state.char_spacing = number(0).unwrap_or(0.0),
+                "Tw" => state.word_spacing = number(0).unwrap_or(0.0),
+                "Tz" => state.horizontal_scaling = number(0).unwrap_or(100.0) / 100.0,
+                "TL" => state.leading = number(0).unwrap_or(0.0),
+                "Ts" => state.rise = number(0).unwrap_or(0.0),
+                "Td" | "TD" => {
+                    let (tx, ty) = (number(0).unwrap_or(0.0), number(1).unwrap_or(0.0));
+                    if op.operator == "TD" {
+                        state.leading = -ty;
+                    }
+                    line_matrix = Matrix::translate(tx, ty).then(line_matrix);
+                    text_matrix = line_matrix;
+                }
+                "Tm" => {
+                    if let Some(m) = Matrix::from_operands(operands) {
+                        line_matrix = m;
+                        text_matrix = m;
+                    }
+                }
+                "T*" => {
+                    line_matrix = Matrix::translate(0.0, -state.leading).then(line_matrix);
+                    text_matrix = line_matrix;
+                }
+                "Tj" | "'" | "\"" | "TJ" => {
+                    match op.operator.as_str() {
+                        "'" => {
+                            line_matrix = Matrix::translate(0.0, -state.leading).then(line_matrix);
+                            text_matrix = line_matrix;
+                        }
+                        "\"" => {
+                            state.word_spacing = number(0).unwrap_or(state.word_spacing);
+                            state.char_spacing = number(1).unwrap_or(state.char_spacing);
+                            line_matrix = Matrix::translate(0.0, -state.leading).then(line_matrix);
+                            text_matrix = line_matrix;
+                        }
+                        _ => {}
+                    }
+                    let elements: &[Object] = match op.operator.as_str() {
+                        "TJ" => operands
+                            .first()
+                            .and_then(|o| o.as_array().ok())
+                            .map_or(&[], |a| a.as_slice()),
+                        "\"" => operands.get(2).map_or(&[], std::slice::from_ref),
+                        _ => operands.first().map_or(&[], std::slice::from_ref),
+                    };
+                    let mut glyphs = Vec::new();
+                    for (element, item) in elements.iter().enumerate() {
+                        match item {
+                            Object::String(bytes, _) => {
+                                show_string(&state, &mut text_matrix, bytes, element, &mut glyphs)
+                            }
+                            other => {
+                                if let Ok(adjustment) = other.as_float() {
+                                    let tx = -adjustment / 1000.0
+                                        * state.font_size
+                                        * state.horizontal_scaling;
+                                    text_matrix = Matrix::translate(tx, 0.0).then(text_matrix);
+                                }
+                            }
+                        }
+                    }
+                    if !glyphs.is_empty() {
+                        visit(index, ContentEvent::Text(glyphs));
+                    }
+                }
+                "Do" => {
+                    if let Some(name) = operands.first().and_then(|o| o.as_name().ok()) {
+                        visit(
+                            index,
+                            ContentEvent::XObject {
+                                name: name.to_vec(),
+                                ctm: state.ctm,
+                            },
+                        );
+                    }
+                }
+                "BI" => visit(index, ContentEvent::InlineImage { ctm: state.ctm }),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lay out the glyphs of one string operand, advancing the text matrix.
+fn show_string(
+    state: &GraphicsState,
+    text_matrix: &mut Matrix,
+    bytes: &[u8],
+    element: usize,
+    glyphs: &mut Vec<PlacedGlyph>,
+) {
+    let Some(font) = state.font.as_deref() else {
+        return;
+    };
+    let size = state.font_size;
+    let scaling = state.horizontal_scaling;
+    let (_, ascent) = font.font_matrix.apply(0.0, font.ascent);
+    let (_, descent) = font.font_matrix.apply(0.0, font.descent);
+
+    for code in font.codes(bytes) {
+        let (advance, _) = font.font_matrix.apply(font.width(code), 0.0);
+        // Word spacing applies to the single-byte code 32 only.
+        let spacing = state.char_spacing
+            + if code == [32] {
+                state.word_spacing
+            } else {
+                0.0
+            };
+        let rendering = Matrix([size * scaling, 0.0, 0.0, size, 0.0, state.rise])
+            .then(*text_matrix)
+            .then(state.ctm);
+        glyphs.push(PlacedGlyph {
+            code: code.to_vec(),
+            text: font.decode(code),
+            bounds: rendering.transform_rect(Rect::new(0.0, descent, advance, ascent)),
+            adjustment: if size != 0.0 {
+                -(advance * size + spacing) * 1000.0 / size
+            } else {
+                0.0
+            },
+            element,
+            direction: {
+                let (x0, y0) = rendering.apply(0.0, 0.0);
+                let (x1, y1) = rendering.apply(1.0, 0.0);
+                (x1 - x0, y1 - y0)
+            },
+        });
+        let tx = (advance * size + spacing) * scaling;
+        *text_matrix = Matrix::translate(tx, 0.0).then(*text_matrix);
+    }
+}
+
+/// Look up an XObject by resource name, with its object id when it's an
+/// indirect object.
+pub(crate) fn xobject<'a>(
+    doc: &'a Document,
+    resources: Option<&'a Dictionary>,
+    name: &[u8],
+) -> Option<(Option<ObjectId>, &'a Stream)> {
+    let object = resources?
+        .get_deref(b"XObject", doc)
+        .and_then(Object::as_dict)
+        .ok()?
+        .get(name)
+        .ok()?;
+    match object {
+        Object::Reference(id) => doc
+            .get_object(*id)
+            .and_then(Object::as_stream)
+            .ok()
+            .map(|s| (Some(*id), s)),
+        Object::Stream(stream) => Some((None, stream)),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_subtype(stream: &Stream, subtype: &[u8]) -> bool {
+    stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(subtype)
+}
+
+/// The operations and resources of a Form XObject. Forms without their own
+/// resources use the ones of the stream drawing them.
+pub(crate) fn form_content<'a>(
+    doc: &'a Document,
+    form: &'a Stream,
+    parent_resources: Option<&'a Dictionary>,
+) -> Result<(Vec<Operation>, Option<&'a Dictionary>), String> {
+    let data = form
+        .get_plain_content()
+        .map_err(|e| format!("Failed to decompress form content: {}", e))?;
+    let content =
+        Content::decode(&data).map_err(|e| format!("Failed to parse form content: {}", e))?;
+    let resources = form
+        .dict
+        .get_deref(b"Resources", doc)
+        .and_then(Object::as_dict)
+        .ok()
+        .or(parent_resources);
+    Ok((content.operations, resources))
+}
+
+/// The matrix mapping a Form XObject's space to the space it's drawn in.
+pub(crate) fn form_matrix(form: &Stream) -> Matrix {
+    form.dict
+        .get(b"Matrix")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|m| Matrix::from_operands(m))
+        .unwrap_or(Matrix::IDENTITY)
+}
+
+/// Decode a page's content streams into operations.
+pub(crate) fn page_operations(doc: &Document, page_id: ObjectId) -> Result<Vec<Operation>, String> {
+    let data = doc.get_page_content(page_id);
+    Content::decode(&data)
+        .map(|content| content.operations)
+        .map_err(|e| format!("Failed to parse page content: {}", e))
+}
+
+/// Walk everything a page paints, following Form XObjects, and report it
+/// in page space.
+pub(crate) fn walk_page<'a>(
+    doc: &'a Document,
+    page_id: ObjectId,
+    visit: &mut dyn FnMut(PaintEvent<'a>),
+) -> Result<(), String> {
+    let operations = page_operations(doc, page_id)?;
+    let resources =
+        super::pdf::inherited_attribute(doc, page_id, b"Resources").and_then(|o| o.as_dict().ok());
+    let mut layout = TextLayout::new(doc);
+    walk_stream(
+        &mut layout,
+        resources,
+        &operations,
+        Matrix::IDENTITY,
+        0,
+        visit,
+    )
+}
+
+fn walk_stream<'a>(
+    layout: &mut TextLayout<'a>,
+    resources: Option<&'a Dictionary>,
+    operations: &[Operation],
+    ctm: Matrix,
+    depth: usize,
+    visit: &mut dyn FnMut(PaintEvent<'a>),
+) -> Result<(), String> {
+    let mut events = Vec::new();
+    layout.walk(resources, operations, ctm, &mut |_, event| {
+        events.push(event)
+    });
+
+    let doc = layout.doc;
+    for event in events {
+        match event {
+            ContentEvent::Text(glyphs) => visit(PaintEvent::Text(glyphs)),
+            ContentEvent::InlineImage { ctm } => visit(PaintEvent::InlineImage { ctm }),
+            ContentEvent::XObject { name, ctm } => {
+                let Some((id, stream)) = xobject(doc, resources, &name) else {
+                    continue;
+                };
+                if is_subtype(stream, b"Image") {
+                    visit(PaintEvent::Image { id, stream, ctm });
+                } else if is_subtype(stream, b"Form") && depth < MAX_FORM_DEPTH {
+                    let (operations, form_resources) = form_content(doc, stream, resources)?;
+                    walk_stream(
+                        layout,
+                        form_resources,
+                        &operations,
+                        form_matrix(stream).then(ctm),
+                        depth + 1,
+                        visit,
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_support::{save, temp_dir, text_pdf};
+
+    fn search_in(pages: &[&[&str]], query: &str, options: SearchOptions) -> Vec<SearchHit> {
+        let dir = temp_dir("search");
+        let path = save(&mut text_pdf(pages), &dir, "doc.pdf");
+        search(&path, query, &options).unwrap()
+    }
+
+    #[test]
+    fn extracts_text_in_reading_order() {
+        let dir = temp_dir("extract");
+        let path = save(
+            &mut text_pdf(&[&["First line", "Second line"], &["Next page"]]),
+            &dir,
+            "doc.pdf",
+        );
+        let pages = extract_text(&path, None).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].text.trim(), "First line\nSecond line");
+        assert_eq!(pages[1].page_number, 2);
+        assert_eq!(pages[1].text.trim(), "Next page");
+
+        let only_second = extract_text(&path, Some(&[2])).unwrap();
+        assert_eq!(only_second.len(), 1);
+        assert_eq!(only_second[0].page_number, 2);
+    }
+
+    #[test]
+    fn finds_every_hit_with_its_page_and_position() {
+        let hits = search_in(
+            &[&["The cat sat", "on the mat"], &["the end"]],
+            "the",
+            SearchOptions::default(),
+        );
+        let pages: Vec<u32> = hits.iter().map(|h| h.page_number).collect();
+        assert_eq!(pages, [1, 1, 2]);
+        assert_eq!(hits[0].text, "The");
+        assert_eq!(hits[0].rects.len(), 1);
+        // The second line is 20pt below the first, which starts 72pt in
+        let (first, second) = (&hits[0].rects[0], &hits[1].rects[0]);
+        assert!((first.x - 72.0).abs() < 1.0, "{:?}", first);
+        assert!(
+            (second.y - first.y - 20.0).abs() < 1.0,
+            "{:?} {:?}",
+            first,
+            second
+        );
+    }
+
+    #[test]
+    fn finds_phrases_across_lines() {
+        let hits = search_in(
+            &[&["Please sign the", "contract today"]],
+            "the  contract",
+            SearchOptions::default(),
+        );
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].rects.len(), 2);
+        assert!(hits[0].rects[1].y > hits[0].rects[0].y);
+        assert!(hits[0].context.contains("sign"));
+    }
+
+    #[test]
+    fn case_and_whole_word_options() {
+        let lines: &[&[&str]] = &[&["Cat catalogue CAT"]];
+        assert_eq!(search_in(lines, "cat", SearchOptions::default()).len(), 3);
+        let case_sensitive = SearchOptions {
+            case_sensitive: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(search_in(lines, "cat", case_sensitive).len(), 1);
+        let whole_word = SearchOptions {
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        let hits = search_in(lines, "cat", whole_word);
+        assert_eq!(
+            hits.iter().map(|h| h.text.as_str()).collect::<Vec<_>>(),
+            ["Cat", "CAT"]
+        );
+    }
+
+    #[test]
+    fn empty_queries_are_refused() {
+        let dir = temp_dir("search-empty");
+        let path = save(&mut text_pdf(&[&["Text"]]), &dir, "doc.pdf");
+        assert!(search(&path, "  ", &SearchOptions::default()).is_err());
+    }
+}
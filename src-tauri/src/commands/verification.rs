@@ -0,0 +1,978 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::asn1::{ObjectIdentifier, OctetString};
+use der::{DateTime, Decode, Encode, SliceReader};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use pkcs8::spki::{DecodePublicKey, SubjectPublicKeyInfoOwned};
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+use x509_cert::ext::pkix::SubjectKeyIdentifier;
+use x509_cert::time::Time;
+use x509_cert::Certificate;
+
+use super::pdf::{decode_text_string, field_attribute, form_fields, widget_page};
+use super::security;
+use super::signing::{common_name, ID_DATA, ID_SHA1, ID_SHA256};
+
+const ID_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const ID_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const ID_SIGNING_TIME: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.5");
+const ID_SUBJECT_KEY_IDENTIFIER: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.14");
+
+/// Longest issuer chain followed before giving up, as a guard against
+/// certificate loops.
+const MAX_CHAIN_LENGTH: usize = 10;
+
+/// Whether the signed bytes are unchanged and the signature over them checks
+/// out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrityStatus {
+    /// The byte-range digest matches and the signature verifies.
+    Valid,
+    /// The signed bytes were altered or the signature doesn't verify.
+    Invalid,
+    /// The signature could not be checked (unsigned field, unsupported
+    /// algorithm or malformed data).
+    Unknown,
+}
+
+/// Whether the signer's certificate chains up to the local trust store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustStatus {
+    Trusted,
+    Untrusted,
+    Unknown,
+}
+
+/// The fields of a certificate shown in the signature panel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateSummary {
+    pub subject: String,
+    pub issuer: String,
+    pub common_name: String,
+    /// Serial number as uppercase hex.
+    pub serial_number: String,
+    /// RFC 3339 UTC timestamps.
+    pub not_before: String,
+    pub not_after: String,
+    /// SHA-256 of the DER encoding, as uppercase hex. Identifies the
+    /// certificate in the trust store.
+    pub fingerprint: String,
+}
+
+/// Verification result for one signature field.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureReport {
+    /// Fully qualified field name, e.g. `Signature1` or `form.approval`.
+    pub field_name: String,
+    /// 1-based page the field's widget is on, if it has one.
+    pub page_number: Option<u32>,
+    /// False for empty signature fields waiting to be signed.
+    pub signed: bool,
+    pub signer_name: Option<String>,
+    /// RFC 3339 UTC time from the CMS signing-time attribute, falling back to
+    /// the /M entry of the signature dictionary.
+    pub signing_time: Option<String>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+    pub contact_info: Option<String>,
+    pub sub_filter: Option<String>,
+    pub integrity: IntegrityStatus,
+    pub trust: TrustStatus,
+    /// The signed byte range runs to the end of the file.
+    pub covers_whole_document: bool,
+    /// Bytes were appended after the signed range (later incremental
+    /// updates). This includes signatures added afterwards.
+    pub modified_after_signing: bool,
+    /// The signer's certificate first, followed by the issuers found for it.
+    pub certificates: Vec<CertificateSummary>,
+    /// Human-readable findings explaining the statuses above.
+    pub messages: Vec<String>,
+}
+
+/// Check every signature field in the PDF at `path` against the trust store
+/// in the app data directory.
+#[tauri::command]
+pub async fn verify_pdf_signatures(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<Vec<SignatureReport>, String> {
+    let trust_store = load_trust_store(&trust_store_dir(&app)?)?;
+    verify(&path, &trust_store)
+}
+
+/// List the certificates in the local trust store.
+#[tauri::command]
+pub async fn list_trusted_certificates(
+    app: tauri::AppHandle,
+) -> Result<Vec<CertificateSummary>, String> {
+    let certificates = load_trust_store(&trust_store_dir(&app)?)?;
+    certificates.iter().map(summarize).collect()
+}
+
+/// Let the user pick a certificate file and add every certificate in it to
+/// the trust store. Returns the certificates added, or `None` if the dialog
+/// was cancelled.
+#[tauri::command]
+pub async fn import_trusted_certificate(
+    app: tauri::AppHandle,
+) -> Result<Option<Vec<CertificateSummary>>, String> {
+    let file = app
+        .dialog()
+        .file()
+        .add_filter("Certificates", &["cer", "crt", "der", "pem"])
+        .add_filter("All Files", &["*"])
+        .blocking_pick_file();
+
+    let Some(path) = file else {
+        return Ok(None);
+    };
+    let bytes =
+        fs::read(path.to_string()).map_err(|e| format!("Failed to read certificate: {}", e))?;
+    let certificates = parse_certificates(&bytes)?;
+
+    let dir = trust_store_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create trust store: {}", e))?;
+    let mut added = Vec::with_capacity(certificates.len());
+    for certificate in &certificates {
+        let summary = summarize(certificate)?;
+        let der = certificate
+            .to_der()
+            .map_err(|e| format!("Invalid certificate: {}", e))?;
+        fs::write(dir.join(format!("{}.der", summary.fingerprint)), der)
+            .map_err(|e| format!("Failed to save certificate: {}", e))?;
+        added.push(summary);
+    }
+    Ok(Some(added))
+}
+
+/// Remove a certificate from the trust store by its SHA-256 fingerprint.
+#[tauri::command]
+pub async fn remove_trusted_certificate(
+    app: tauri::AppHandle,
+    fingerprint: String,
+) -> Result<(), String> {
+    let dir = trust_store_dir(&app)?;
+    for (path, certificate) in read_trust_store_files(&dir)? {
+        if summarize(&certificate)?.fingerprint == fingerprint {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove certificate: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// The trust store lives in `{app_data}/trust_store`: one certificate file
+/// (DER or PEM) per trusted root or intermediate.
+fn trust_store_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|p| p.join("trust_store"))
+        .map_err(|e| format!("Failed to get app data dir: {}", e))
+}
+
+/// Load every certificate in the trust store directory. A missing directory
+/// is an empty trust store.
+pub fn load_trust_store(dir: &Path) -> Result<Vec<Certificate>, String> {
+    Ok(read_trust_store_files(dir)?
+        .into_iter()
+        .map(|(_, certificate)| certificate)
+        .collect())
+}
+
+fn read_trust_store_files(dir: &Path) -> Result<Vec<(PathBuf, Certificate)>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read trust store: {}", e)),
+    };
+
+    let mut certificates = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        // Skip stray files rather than failing every verification over them.
+        let Ok(bytes) = fs::read(&path) else { continue };
+        let Ok(parsed) = parse_certificates(&bytes) else {
+            continue;
+        };
+        certificates.extend(parsed.into_iter().map(|c| (path.clone(), c)));
+    }
+    Ok(certificates)
+}
+
+/// Parse a DER certificate or a PEM file holding one or more certificates.
+fn parse_certificates(bytes: &[u8]) -> Result<Vec<Certificate>, String> {
+    if bytes.starts_with(b"-----BEGIN") {
+        Certificate::load_pem_chain(bytes).map_err(|e| format!("Invalid certificate: {}", e))
+    } else {
+        Certificate::from_der(bytes)
+            .map(|c| vec![c])
+            .map_err(|e| format!("Invalid certificate: {}", e))
+    }
+}
+
+/// Verify every signature field in a PDF.
+///
+/// For each signed field this recomputes the digest of the /ByteRange, checks
+/// it against the CMS messageDigest attribute (or the encapsulated digest for
+/// adbe.pkcs7.sha1), verifies the signer's signature, and then walks issuer
+/// links through the certificates embedded in the signature and the trust
+/// store. RSA PKCS#1 v1.5 and P-256 ECDSA with SHA-1/SHA-2 are supported;
+/// anything else is reported with an `Unknown` status rather than an error.
+///
+/// Revocation (CRL/OCSP) and timestamp tokens are not checked.
+pub fn verify(path: &str, trust_store: &[Certificate]) -> Result<Vec<SignatureReport>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (doc, _) = security::load_pdf(&bytes, Path::new(path))?;
+
+    let page_numbers: BTreeMap<ObjectId, u32> =
+        doc.get_pages().into_iter().map(|(n, id)| (id, n)).collect();
+    let mut reports = Vec::new();
+    for field in signature_fields(&doc) {
+        reports.push(verify_field(
+            &doc,
+            &bytes,
+            &field,
+            &page_numbers,
+            trust_store,
+        ));
+    }
+    Ok(reports)
+}
+
+/// A signature field found in the AcroForm tree.
+struct SignatureField {
+    name: String,
+    /// The field's first widget annotation.
+    widget: Option<ObjectId>,
+    value: Option<Dictionary>,
+}
+
+/// The AcroForm fields whose (possibly inherited) /FT is /Sig.
+fn signature_fields(doc: &Document) -> Vec<SignatureField> {
+    form_fields(doc)
+        .into_iter()
+        .filter(|node| {
+            field_attribute(doc, node.id, b"FT").and_then(|ft| ft.as_name().ok()) == Some(b"Sig")
+        })
+        .map(|node| SignatureField {
+            value: doc
+                .get_dictionary(node.id)
+                .and_then(|field| field.get_deref(b"V", doc))
+                .and_then(Object::as_dict)
+                .ok()
+                .cloned(),
+            widget: node.widgets.first().copied(),
+            name: node.name,
+        })
+        .collect()
+}
+
+fn verify_field(
+    doc: &Document,
+    bytes: &[u8],
+    field: &SignatureField,
+    page_numbers: &BTreeMap<ObjectId, u32>,
+    trust_store: &[Certificate],
+) -> SignatureReport {
+    let page_number = field
+        .widget
+        .and_then(|widget| widget_page(doc, widget, page_numbers));
+    let mut report = SignatureReport {
+        field_name: field.name.clone(),
+        page_number,
+        signed: false,
+        signer_name: None,
+        signing_time: None,
+        reason: None,
+        location: None,
+        contact_info: None,
+        sub_filter: None,
+        integrity: IntegrityStatus::Unknown,
+        trust: TrustStatus::Unknown,
+        covers_whole_document: false,
+        modified_after_signing: false,
+        certificates: Vec::new(),
+        messages: Vec::new(),
+    };
+
+    let Some(signature) = &field.value else {
+        report
+            .messages
+            .push("The field has not been signed".to_string());
+        return report;
+    };
+    report.signed = true;
+    let text = |key: &[u8]| {
+        signature
+            .get(key)
+            .ok()
+            .and_then(|o| o.as_str().ok())
+            .map(decode_text_string)
+    };
+    report.signer_name = text(b"Name");
+    report.reason = text(b"Reason");
+    report.location = text(b"Location");
+    report.contact_info = text(b"ContactInfo");
+    report.signing_time = text(b"M").and_then(|m| parse_pdf_date(&m));
+    report.sub_filter = signature
+        .get(b"SubFilter")
+        .and_then(Object::as_name)
+        .ok()
+        .map(|n| String::from_utf8_lossy(n).into_owned());
+
+    if let Err(message) = check_signature(bytes, signature, trust_store, &mut report) {
+        report.messages.push(message);
+    }
+    report
+}
+
+/// Run the cryptographic checks, filling in the report as far as they get.
+fn check_signature(
+    bytes: &[u8],
+    signature: &Dictionary,
+    trust_store: &[Certificate],
+    report: &mut SignatureReport,
+) -> Result<(), String> {
+    let contents = signature
+        .get(b"Contents")
+        .and_then(Object::as_str)
+        .map_err(|_| "The signature has no /Contents".to_string())?;
+    // Bytes outside the ranges aren't digested, so anything but the
+    // signature itself there would go unnoticed.
+    let ranges = byte_ranges(signature, contents, bytes).inspect_err(|_| {
+        report.integrity = IntegrityStatus::Invalid;
+    })?;
+    let signed_end = ranges[1].0 + ranges[1].1;
+    report.covers_whole_document = signed_end == bytes.len();
+    report.modified_after_signing = signed_end < bytes.len();
+    if report.modified_after_signing {
+        report
+            .messages
+            .push("The document has been changed since it was signed".to_string());
+    }
+
+    // /Contents is zero-padded after the DER, so decode just the first value.
+    let content_info = SliceReader::new(contents)
+        .and_then(|mut reader| ContentInfo::decode(&mut reader))
+        .map_err(|e| format!("Malformed signature data: {}", e))?;
+    if content_info.content_type != ID_SIGNED_DATA {
+        return Err("Unsupported signature format".to_string());
+    }
+    let signed_data = content_info
+        .content
+        .decode_as::<SignedData>()
+        .map_err(|e| format!("Malformed signature data: {}", e))?;
+    let signer_info = signed_data
+        .signer_infos
+        .0
+        .iter()
+        .next()
+        .ok_or("The signature has no signer")?;
+
+    let embedded: Vec<Certificate> = signed_data
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .filter_map(|choice| match choice {
+            CertificateChoices::Certificate(c) => Some(c.clone()),
+            _ => None,
+        })
+        .collect();
+    let signer = embedded
+        .iter()
+        .find(|c| identifies(&signer_info.sid, c))
+        .ok_or("The signer's certificate is not included in the signature")?;
+    report.signer_name = Some(common_name(&signer.tbs_certificate.subject));
+
+    let signing_time = signed_attribute(signer_info, ID_SIGNING_TIME)
+        .and_then(|value| Time::from_der(&value.to_der().ok()?).ok())
+        .map(|time| time.to_date_time());
+    if let Some(time) = signing_time {
+        report.signing_time = Some(time.to_string());
+    }
+
+    // Integrity: digest of the signed bytes, then the signature over it.
+    let digest_oid = signer_info.digest_alg.oid;
+    let chunks: Vec<&[u8]> = ranges
+        .iter()
+        .map(|&(start, len)| &bytes[start..start + len])
+        .collect();
+    let document_digest = digest(digest_oid, &chunks)
+        .ok_or_else(|| format!("Unsupported digest algorithm {}", digest_oid))?;
+    let content_digest = match &signed_data.encap_content_info.econtent {
+        // adbe.pkcs7.sha1: the signed content is the SHA-1 of the byte range.
+        Some(econtent) if signed_data.encap_content_info.econtent_type == ID_DATA => {
+            let embedded_digest = econtent
+                .decode_as::<OctetString>()
+                .map_err(|e| format!("Malformed signature data: {}", e))?;
+            let range_digest = digest(ID_SHA1, &chunks).unwrap_or_default();
+            if embedded_digest.as_bytes() != range_digest.as_slice() {
+                report.integrity = IntegrityStatus::Invalid;
+                report
+                    .messages
+                    .push("The signed content has been altered".to_string());
+                return Ok(());
+            }
+            digest(digest_oid, &[embedded_digest.as_bytes()]).unwrap_or_default()
+        }
+        _ => document_digest,
+    };
+
+    let signed_message = match &signer_info.signed_attrs {
+        Some(_) => {
+            let message_digest = signed_attribute(signer_info, ID_MESSAGE_DIGEST)
+                .and_then(|value| value.decode_as::<OctetString>().ok())
+                .ok_or("The signature has no message digest")?;
+            if message_digest.as_bytes() != content_digest.as_slice() {
+                report.integrity = IntegrityStatus::Invalid;
+                report
+                    .messages
+                    .push("The signed content has been altered".to_string());
+                return Ok(());
+            }
+            // Verify over the attributes exactly as they were encoded: der
+            // re-sorts SET OF on decode, which would break non-DER signers.
+            raw_signed_attributes(contents).ok_or("Malformed signed attributes")?
+        }
+        None => content_digest,
+    };
+    let verified = verify_signature(
+        &signer.tbs_certificate.subject_public_key_info,
+        signer_info.signature_algorithm.oid,
+        Some(digest_oid),
+        &signed_message,
+        signer_info.signature.as_bytes(),
+        signer_info.signed_attrs.is_none(),
+    )?;
+    if verified {
+        report.integrity = IntegrityStatus::Valid;
+    } else {
+        report.integrity = IntegrityStatus::Invalid;
+        report
+            .messages
+            .push("The signature does not match the signer's certificate".to_string());
+        return Ok(());
+    }
+
+    // Trust: follow issuers through the embedded certificates and the
+    // trust store until a trusted certificate is reached.
+    let at = signing_time.unwrap_or_else(|| {
+        DateTime::from_system_time(SystemTime::now()).unwrap_or(DateTime::INFINITY)
+    });
+    let (chain, trusted) = build_chain(signer, &embedded, trust_store);
+    for certificate in &chain {
+        report.certificates.push(summarize(certificate)?);
+    }
+    report.trust = chain_trust(&chain, trusted, at, &mut report.messages);
+    Ok(())
+}
+
+/// Trust in a chain built by `build_chain`: only a chain that reached the
+/// trust store with every certificate valid at `at` is trusted.
+fn chain_trust(
+    chain: &[Certificate],
+    trusted: bool,
+    at: DateTime,
+    messages: &mut Vec<String>,
+) -> TrustStatus {
+    let mut status = TrustStatus::Trusted;
+    for certificate in chain {
+        let validity = &certificate.tbs_certificate.validity;
+        if at < validity.not_before.to_date_time() || at > validity.not_after.to_date_time() {
+            messages.push(format!(
+                "Certificate \"{}\" was not valid at signing time",
+                common_name(&certificate.tbs_certificate.subject)
+            ));
+            status = TrustStatus::Untrusted;
+        }
+    }
+    if !trusted {
+        messages
+            .push("The signer's certificate does not chain to a trusted certificate".to_string());
+        status = TrustStatus::Untrusted;
+    }
+    status
+}
+
+/// Parse /ByteRange into its two (offset, length) pairs. It must be
+/// `[0 a b len]` with nothing between `a` and `b` but the hex string of
+/// this signature's /Contents, `contents`.
+fn byte_ranges(
+    signature: &Dictionary,
+    contents: &[u8],
+    bytes: &[u8],
+) -> Result<[(usize, usize); 2], String> {
+    let values: Vec<usize> = signature
+        .get(b"ByteRange")
+        .and_then(Object::as_array)
+        .map_err(|_| "The signature has no /ByteRange".to_string())?
+        .iter()
+        .map(|v| v.as_i64().ok().and_then(|v| usize::try_from(v).ok()))
+        .collect::<Option<_>>()
+        .ok_or("The signature's /ByteRange is malformed")?;
+    let &[0, a, b, len] = values.as_slice() else {
+        return Err("The signature's /ByteRange is malformed".to_string());
+    };
+    if a >= b || b.checked_add(len).map_or(true, |end| end > bytes.len()) {
+        return Err("The signature's /ByteRange is malformed".to_string());
+    }
+
+    let gap = &bytes[a..b];
+    let hex = gap
+        .strip_prefix(b"<")
+        .and_then(|gap| gap.strip_suffix(b">"))
+        .filter(|hex| {
+            hex.iter()
+                .all(|c| c.is_ascii_hexdigit() || c.is_ascii_whitespace())
+        });
+    let before = &bytes[..a];
+    let before = match before.iter().rposition(|c| !c.is_ascii_whitespace()) {
+        Some(last) => &before[..=last],
+        None => before,
+    };
+    if hex.map(security::decode_hex).as_deref() != Some(contents) || !before.ends_with(b"/Contents")
+    {
+        return Err("The signature's /ByteRange leaves bytes unsigned".to_string());
+    }
+    Ok([(0, a), (b, len)])
+}
+
+/// Whether `certificate` is the one a SignerInfo's sid refers to.
+fn identifies(sid: &SignerIdentifier, certificate: &Certificate) -> bool {
+    match sid {
+        SignerIdentifier::IssuerAndSerialNumber(id) => {
+            id.issuer == certificate.tbs_certificate.issuer
+                && id.serial_number == certificate.tbs_certificate.serial_number
+        }
+        SignerIdentifier::SubjectKeyIdentifier(ski) => certificate
+            .tbs_certificate
+            .extensions
+            .iter()
+            .flatten()
+            .filter(|ext| ext.extn_id == ID_SUBJECT_KEY_IDENTIFIER)
+            .filter_map(|ext| SubjectKeyIdentifier::from_der(ext.extn_value.as_bytes()).ok())
+            .any(|key_id| key_id == *ski),
+    }
+}
+
+fn signed_attribute(signer_info: &SignerInfo, oid: ObjectIdentifier) -> Option<&der::Any> {
+    signer_info
+        .signed_attrs
+        .as_ref()?
+        .iter()
+        .find(|attr| attr.oid == oid)?
+        .values
+        .iter()
+        .next()
+}
+
+/// Walk from `signer` to a trusted certificate. Returns the chain (signer
+/// first) and whether it ended at a certificate in the trust store.
+fn build_chain(
+    signer: &Certificate,
+    embedded: &[Certificate],
+    trust_store: &[Certificate],
+) -> (Vec<Certificate>, bool) {
+    let mut chain = vec![signer.clone()];
+    while chain.len() <= MAX_CHAIN_LENGTH {
+        let current = chain.last().expect("chain starts non-empty");
+        if trust_store.contains(current) {
+            return (chain, true);
+        }
+        let issuer = trust_store
+            .iter()
+            .chain(embedded)
+            .find(|candidate| *candidate != current && issued(current, candidate));
+        match issuer {
+            Some(issuer) if !chain.contains(issuer) => chain.push(issuer.clone()),
+            _ => break,
+        }
+    }
+    (chain, false)
+}
+
+/// Whether `issuer` issued `certificate`: the names match and the
+/// certificate's signature verifies with the issuer's key.
+fn issued(certificate: &Certificate, issuer: &Certificate) -> bool {
+    if certificate.tbs_certificate.issuer != issuer.tbs_certificate.subject {
+        return false;
+    }
+    let Ok(tbs) = certificate.tbs_certificate.to_der() else {
+        return false;
+    };
+    let Some(signature) = certificate.signature.as_bytes() else {
+        return false;
+    };
+    verify_signature(
+        &issuer.tbs_certificate.subject_public_key_info,
+        certificate.signature_algorithm.oid,
+        None,
+        &tbs,
+        signature,
+        false,
+    )
+    .unwrap_or(false)
+}
+
+/// The key type and digest a signature algorithm OID stands for. Bare key
+/// OIDs (as some CMS signers write) leave the digest to the caller.
+fn signature_algorithm(oid: ObjectIdentifier) -> Option<(KeyType, Option<ObjectIdentifier>)> {
+    let rsa = |digest| Some((KeyType::Rsa, digest));
+    let ecdsa = |digest| Some((KeyType::Ecdsa, digest));
+    match oid.to_string().as_str() {
+        "1.2.840.113549.1.1.1" => rsa(None),
+        "1.2.840.113549.1.1.5" => rsa(Some(ID_SHA1)),
+        "1.2.840.113549.1.1.11" => rsa(Some(ID_SHA256)),
+        "1.2.840.113549.1.1.12" => rsa(Some(ID_SHA384)),
+        "1.2.840.113549.1.1.13" => rsa(Some(ID_SHA512)),
+        "1.2.840.10045.2.1" => ecdsa(None),
+        "1.2.840.10045.4.1" => ecdsa(Some(ID_SHA1)),
+        "1.2.840.10045.4.3.2" => ecdsa(Some(ID_SHA256)),
+        "1.2.840.10045.4.3.3" => ecdsa(Some(ID_SHA384)),
+        "1.2.840.10045.4.3.4" => ecdsa(Some(ID_SHA512)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum KeyType {
+    Rsa,
+    Ecdsa,
+}
+
+/// Verify `signature` over `message` with the key in `spki`. When
+/// `prehashed` is set, `message` is already the digest.
+fn verify_signature(
+    spki: &SubjectPublicKeyInfoOwned,
+    algorithm: ObjectIdentifier,
+    default_digest: Option<ObjectIdentifier>,
+    message: &[u8],
+    signature: &[u8],
+    prehashed: bool,
+) -> Result<bool, String> {
+    let (key_type, digest_oid) = signature_algorithm(algorithm)
+        .ok_or_else(|| format!("Unsupported signature algorithm {}", algorithm))?;
+    let digest_oid = digest_oid
+        .or(default_digest)
+        .ok_or_else(|| format!("Unsupported signature algorithm {}", algorithm))?;
+    let hashed = if prehashed {
+        message.to_vec()
+    } else {
+        digest(digest_oid, &[message])
+            .ok_or_else(|| format!("Unsupported digest algorithm {}", digest_oid))?
+    };
+    let spki_der = spki
+        .to_der()
+        .map_err(|e| format!("Invalid public key: {}", e))?;
+
+    match key_type {
+        KeyType::Rsa => {
+            let key = rsa::RsaPublicKey::from_public_key_der(&spki_der)
+                .map_err(|e| format!("Invalid RSA key: {}", e))?;
+            let scheme = match digest_oid.to_string().as_str() {
+                "1.3.14.3.2.26" => rsa::Pkcs1v15Sign::new::<sha1::Sha1>(),
+                "2.16.840.1.101.3.4.2.1" => rsa::Pkcs1v15Sign::new::<Sha256>(),
+                "2.16.840.1.101.3.4.2.2" => rsa::Pkcs1v15Sign::new::<Sha384>(),
+                _ => rsa::Pkcs1v15Sign::new::<Sha512>(),
+            };
+            Ok(key.verify(scheme, &hashed, signature).is_ok())
+        }
+        KeyType::Ecdsa => {
+            use p256::ecdsa::signature::hazmat::PrehashVerifier;
+            let key = p256::ecdsa::VerifyingKey::from_public_key_der(&spki_der)
+                .map_err(|_| "Unsupported elliptic curve: only P-256 is supported".to_string())?;
+            let Ok(signature) = p256::ecdsa::Signature::from_der(signature) else {
+                return Ok(false);
+            };
+            Ok(key.verify_prehash(&hashed, &signature).is_ok())
+        }
+    }
+}
+
+/// Hash `chunks` as one message with the digest named by `oid`.
+fn digest(oid: ObjectIdentifier, chunks: &[&[u8]]) -> Option<Vec<u8>> {
+    fn run<D: Digest>(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut hasher = D::new();
+        for chunk in chunks {
+            hasher.update(chunk);
+        }
+        hasher.finalize().to_vec()
+    }
+    match oid {
+        ID_SHA1 => Some(run::<sha1::Sha1>(chunks)),
+        ID_SHA256 => Some(run::<Sha256>(chunks)),
+        ID_SHA384 => Some(run::<Sha384>(chunks)),
+        ID_SHA512 => Some(run::<Sha512>(chunks)),
+        _ => None,
+    }
+}
+
+/// Extract the first SignerInfo's signed attributes from a DER ContentInfo
+/// as originally encoded, re-tagged from [0] IMPLICIT to SET as RFC 5652
+/// 5.4 requires for signing.
+fn raw_signed_attributes(content_info: &[u8]) -> Option<Vec<u8>> {
+    let (_, content_info, _) = tlv(content_info)?;
+    let (_, _, rest) = tlv(content_info)?; // contentType
+    let (_, explicit, _) = tlv(rest)?; // [0] EXPLICIT
+    let (_, signed_data, _) = tlv(explicit)?;
+
+    // version, digestAlgorithms, encapContentInfo, then optional [0]
+    // certificates and [1] crls before signerInfos.
+    let mut rest = signed_data;
+    for _ in 0..3 {
+        rest = tlv(rest)?.2;
+    }
+    loop {
+        let (tag, content, next) = tlv(rest)?;
+        if tag == 0x31 {
+            rest = content;
+            break;
+        }
+        rest = next;
+    }
+    let (_, signer_info, _) = tlv(rest)?;
+
+    // version, sid, digestAlgorithm, then [0] signedAttrs.
+    let mut rest = signer_info;
+    for _ in 0..3 {
+        rest = tlv(rest)?.2;
+    }
+    let (tag, _, next) = tlv(rest)?;
+    if tag != 0xA0 {
+        return None;
+    }
+    let mut attributes = rest[..rest.len() - next.len()].to_vec();
+    attributes[0] = 0x31;
+    Some(attributes)
+}
+
+/// Split one DER TLV off the front of `data`: (tag, contents, remainder).
+fn tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    let (len, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7F;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let len = data
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    Some((tag, data.get(header..end)?, data.get(end..)?))
+}
+
+fn summarize(certificate: &Certificate) -> Result<CertificateSummary, String> {
+    let tbs = &certificate.tbs_certificate;
+    let der = certificate
+        .to_der()
+        .map_err(|e| format!("Invalid certificate: {}", e))?;
+    Ok(CertificateSummary {
+        subject: tbs.subject.to_string(),
+        issuer: tbs.issuer.to_string(),
+        common_name: common_name(&tbs.subject),
+        serial_number: hex(tbs.serial_number.as_bytes()),
+        not_before: tbs.validity.not_before.to_date_time().to_string(),
+        not_after: tbs.validity.not_after.to_date_time().to_string(),
+        fingerprint: hex(&Sha256::digest(&der)),
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Convert a PDF date (`D:YYYYMMDDHHmmSSOHH'mm'`) to RFC 3339 UTC.
+fn parse_pdf_date(value: &str) -> Option<String> {
+    let digits = value.strip_prefix("D:").unwrap_or(value);
+    let field = |range: std::ops::Range<usize>, default: u32| -> Option<u32> {
+        match digits.get(range) {
+            Some(s) if s.bytes().all(|b| b.is_ascii_digit()) => s.parse().ok(),
+            Some(_) => None,
+            None => Some(default),
+        }
+    };
+    let year = field(0..4, 0)?;
+    let time = DateTime::new(
+        year as u16,
+        field(4..6, 1)? as u8,
+        field(6..8, 1)? as u8,
+        field(8..10, 0)? as u8,
+        field(10..12, 0)? as u8,
+        field(12..14, 0)? as u8,
+    )
+    .ok()?;
+
+    // Apply the timezone offset, if any, to get UTC.
+    let offset_secs = match digits.get(14..15) {
+        Some(sign @ ("+" | "-")) => {
+            let hours = field(15..17, 0)? as i64;
+            let minutes = digits
+                .get(18..20)
+                .and_then(|m| m.parse::<i64>().ok())
+                .unwrap_or(0);
+            let secs = hours * 3600 + minutes * 60;
+            if sign == "+" {
+                secs
+            } else {
+                -secs
+            }
+        }
+        _ => 0,
+    };
+    let unix = time.unix_duration().as_secs() as i64 - offset_secs;
+    let utc =
+        DateTime::from_unix_duration(std::time::Duration::from_secs(unix.max(0) as u64)).ok()?;
+    Some(utc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::commands::signing::{sign, SignOptions, SigningIdentity};
+    use crate::commands::test_support::{
+        path_in, save, temp_dir, text_pdf, SIGNER_P12, SIGNER_PASSWORD,
+    };
+
+    fn signed_pdf(dir: &Path) -> String {
+        let source = save(&mut text_pdf(&[&["Contract"]]), dir, "source.pdf");
+        let identity = dir.join("signer.p12");
+        fs::write(&identity, SIGNER_P12).unwrap();
+        let options = SignOptions {
+            identity_path: identity.to_string_lossy().into_owned(),
+            password: SIGNER_PASSWORD.to_string(),
+            placement: None,
+            style: None,
+            reason: None,
+            location: None,
+            contact_info: None,
+        };
+        let signed = path_in(dir, "signed.pdf");
+        sign(&source, &signed, &options).unwrap();
+        signed
+    }
+
+    fn identity() -> SigningIdentity {
+        SigningIdentity::from_pkcs12(SIGNER_P12, SIGNER_PASSWORD).unwrap()
+    }
+
+    /// Rewrite the /ByteRange of the signature in `bytes` and sign the
+    /// ranges it now names, as someone holding the key could.
+    fn resign(bytes: &mut [u8], [a, b, len]: [usize; 3]) {
+        let key = bytes.windows(10).rposition(|w| w == b"/ByteRange").unwrap();
+        let open = key + bytes[key..].iter().position(|&c| c == b'[').unwrap();
+        let close = key + bytes[key..].iter().position(|&c| c == b']').unwrap();
+        let text = format!(
+            "{:<width$}",
+            format!("0 {} {} {}", a, b, len),
+            width = close - open - 1
+        );
+        bytes[open + 1..close].copy_from_slice(text.as_bytes());
+
+        let start = bytes.windows(9).rposition(|w| w == b"/Contents").unwrap();
+        let start = start + bytes[start..].iter().position(|&c| c == b'<').unwrap();
+        let digest = Sha256::new()
+            .chain_update(&bytes[..a])
+            .chain_update(&bytes[b..b + len])
+            .finalize();
+        let signature = identity().sign_digest(&digest).unwrap();
+        let hex = hex(&signature);
+        bytes[start + 1..start + 1 + hex.len()].copy_from_slice(hex.as_bytes());
+    }
+
+    fn signed_range(path: &str) -> [usize; 3] {
+        let bytes = fs::read(path).unwrap();
+        let (doc, _) = security::load_pdf(&bytes, Path::new(path)).unwrap();
+        let field = signature_fields(&doc).remove(0);
+        let range = field.value.unwrap();
+        let range = range.get(b"ByteRange").and_then(Object::as_array).unwrap();
+        [1, 2, 3].map(|i| range[i].as_i64().unwrap() as usize)
+    }
+
+    #[test]
+    fn unsigned_bytes_next_to_the_signature_invalidate_it() {
+        let dir = temp_dir("verify-gap");
+        let signed = signed_pdf(&dir);
+        let [a, b, len] = signed_range(&signed);
+
+        // Re-signing the same ranges verifies
+        let mut bytes = fs::read(&signed).unwrap();
+        resign(&mut bytes, [a, b, len]);
+        fs::write(&signed, &bytes).unwrap();
+        assert_eq!(
+            verify(&signed, &[]).unwrap()[0].integrity,
+            IntegrityStatus::Valid
+        );
+
+        // Leaving the byte after /Contents out of the ranges doesn't
+        let mut bytes = fs::read(&signed).unwrap();
+        resign(&mut bytes, [a, b + 1, len - 1]);
+        fs::write(&signed, &bytes).unwrap();
+        let report = &verify(&signed, &[]).unwrap()[0];
+        assert_eq!(report.integrity, IntegrityStatus::Invalid);
+        assert!(report.messages.iter().any(|m| m.contains("unsigned")));
+    }
+
+    #[test]
+    fn appended_bytes_are_reported_as_a_later_change() {
+        let dir = temp_dir("verify-appended");
+        let signed = signed_pdf(&dir);
+        let mut bytes = fs::read(&signed).unwrap();
+        bytes.extend_from_slice(b"\n% appended\n");
+        fs::write(&signed, &bytes).unwrap();
+
+        let report = &verify(&signed, &[]).unwrap()[0];
+        assert_eq!(report.integrity, IntegrityStatus::Valid);
+        assert!(report.modified_after_signing);
+        assert!(!report.covers_whole_document);
+    }
+
+    #[test]
+    fn only_chains_to_the_trust_store_are_trusted() {
+        let dir = temp_dir("verify-trust");
+        let signed = signed_pdf(&dir);
+
+        let report = &verify(&signed, &[]).unwrap()[0];
+        assert_eq!(report.trust, TrustStatus::Untrusted);
+        assert_eq!(report.certificates.len(), 1);
+
+        let report = &verify(&signed, &[identity().certificate]).unwrap()[0];
+        assert_eq!(report.trust, TrustStatus::Trusted, "{:?}", report.messages);
+    }
+
+    #[test]
+    fn certificates_expired_at_signing_time_are_not_trusted() {
+        let chain = [identity().certificate];
+        let before = DateTime::new(1990, 1, 1, 0, 0, 0).unwrap();
+        let mut messages = Vec::new();
+        assert_eq!(
+            chain_trust(&chain, true, before, &mut messages),
+            TrustStatus::Untrusted
+        );
+        assert!(messages[0].contains("not valid at signing time"));
+    }
+}
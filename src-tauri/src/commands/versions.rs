@@ -0,0 +1,330 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use super::documents::{canonical_path, force_overwrite, write_atomic};
+use super::error::CommandError;
+use super::library::{database_path, BUSY_TIMEOUT};
+
+/// Content-addressed store of earlier file contents, in the app data
+/// directory: `versions/<first two hex digits>/<sha256>`. Identical
+/// contents are stored once however many versions refer to them.
+const STORE_DIR: &str = "versions";
+
+/// How many versions to keep per document. The oldest are dropped when a
+/// snapshot would make more.
+const MAX_VERSIONS: usize = 50;
+/// Versions older than this are dropped at the next snapshot of their
+/// document, however few there are.
+const MAX_VERSION_AGE_DAYS: u32 = 90;
+
+/// One row per saved-over state of a file, in the app database next to
+/// `documents`. `content_hash` names the blob in the store.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS document_versions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        file_path TEXT NOT NULL,
+        content_hash TEXT NOT NULL,
+        file_size INTEGER NOT NULL,
+        saved_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX IF NOT EXISTS idx_document_versions_path
+        ON document_versions(file_path, id);
+";
+
+/// An earlier state of a document, as it was before a save replaced it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentVersion {
+    pub id: i64,
+    pub file_path: String,
+    pub content_hash: String,
+    pub file_size: u64,
+    /// UTC, as SQLite's `datetime('now')`.
+    pub saved_at: String,
+}
+
+/// Earlier versions of the document at `path`, newest first.
+#[tauri::command]
+pub async fn list_versions(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<Vec<DocumentVersion>, String> {
+    VersionStore::open(&app)?.list(Path::new(&path))
+}
+
+/// The contents of a version, as a raw IPC response.
+#[tauri::command]
+pub async fn read_version(app: tauri::AppHandle, id: i64) -> Result<tauri::ipc::Response, String> {
+    VersionStore::open(&app)?
+        .read(id)
+        .map(tauri::ipc::Response::new)
+}
+
+/// Put a version back in place of its document. The current contents are
+/// kept as a version (and backup) first, so the restore can be undone.
+#[tauri::command]
+pub async fn restore_version(app: tauri::AppHandle, id: i64) -> Result<(), CommandError> {
+    let store = VersionStore::open(&app)?;
+    let version = store.get(id)?;
+    let data = store.read(id)?;
+    drop(store);
+
+    let path = Path::new(&version.file_path);
+    force_overwrite(&app, path, || write_atomic(path, &data))
+}
+
+/// Record the current contents of `path` as a version, if it exists and
+/// differs from the newest recorded one.
+pub fn snapshot(app: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    VersionStore::open(app)?.snapshot(path)
+}
+
+/// The `document_versions` table and the blob store it points into.
+pub struct VersionStore {
+    conn: Connection,
+    store_dir: PathBuf,
+}
+
+impl VersionStore {
+    fn open(app: &tauri::AppHandle) -> Result<Self, String> {
+        let store_dir = app
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join(STORE_DIR))
+            .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+        Self::open_at(&database_path(app)?, store_dir)
+    }
+
+    pub fn open_at(db_path: &Path, store_dir: PathBuf) -> Result<Self, String> {
+        if let Some(dir) = db_path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create app dir: {}", e))?;
+        }
+        let conn =
+            Connection::open(db_path).map_err(|e| format!("Failed to open database: {}", e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .and_then(|_| conn.execute_batch(SCHEMA))
+            .map_err(|e| format!("Failed to prepare version history: {}", e))?;
+        Ok(VersionStore { conn, store_dir })
+    }
+
+    pub fn snapshot(&self, path: &Path) -> Result<(), String> {
+        let data = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let hash: String = Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let file_path = canonical_path(path);
+
+        let latest: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT content_hash FROM document_versions
+                 WHERE file_path = ?1 ORDER BY id DESC LIMIT 1",
+                params![file_path],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read version history: {}", e))?;
+        if latest.as_deref() == Some(hash.as_str()) {
+            return Ok(());
+        }
+
+        let blob = self.blob_path(&hash);
+        if !blob.is_file() {
+            if let Some(dir) = blob.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create version store: {}", e))?;
+            }
+            write_atomic(&blob, &data)?;
+        }
+        self.conn
+            .execute(
+                "INSERT INTO document_versions (file_path, content_hash, file_size)
+                 VALUES (?1, ?2, ?3)",
+                params![file_path, hash, data.len() as i64],
+            )
+            .map_err(|e| format!("Failed to record version: {}", e))?;
+        self.prune(&file_path)
+    }
+
+    /// Drop the versions of `file_path` beyond `MAX_VERSIONS` or older than
+    /// `MAX_VERSION_AGE_DAYS`, and the blobs no version refers to any more.
+    fn prune(&self, file_path: &str) -> Result<(), String> {
+        let stale = "file_path = ?1 AND (
+                id NOT IN (SELECT id FROM document_versions
+                           WHERE file_path = ?1 ORDER BY id DESC LIMIT ?2)
+                OR saved_at < datetime('now', ?3))";
+        let limit = MAX_VERSIONS as i64;
+        let age = format!("-{} days", MAX_VERSION_AGE_DAYS);
+
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to prune version history: {}", e))?;
+        let hashes: Vec<String> = tx
+            .prepare(&format!(
+                "SELECT DISTINCT content_hash FROM document_versions WHERE {}",
+                stale
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map(params![file_path, limit, age], |row| row.get(0))?
+                    .collect()
+            })
+            .map_err(|e| format!("Failed to prune version history: {}", e))?;
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        tx.execute(
+            &format!("DELETE FROM document_versions WHERE {}", stale),
+            params![file_path, limit, age],
+        )
+        .and_then(|_| tx.commit())
+        .map_err(|e| format!("Failed to prune version history: {}", e))?;
+
+        // Blobs are shared between versions, of this document or others
+        for hash in hashes {
+            let used: bool = self
+                .conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM document_versions WHERE content_hash = ?1)",
+                    params![hash],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to prune version history: {}", e))?;
+            if !used {
+                if let Err(e) = fs::remove_file(self.blob_path(&hash)) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        return Err(format!("Failed to remove old version: {}", e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn list(&self, path: &Path) -> Result<Vec<DocumentVersion>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, file_path, content_hash, file_size, saved_at
+                 FROM document_versions WHERE file_path = ?1 ORDER BY id DESC",
+            )
+            .map_err(|e| format!("Failed to read version history: {}", e))?;
+        let rows = stmt
+            .query_map(params![canonical_path(path)], version_from_row)
+            .map_err(|e| format!("Failed to read version history: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read version history: {}", e))
+    }
+
+    pub fn get(&self, id: i64) -> Result<DocumentVersion, String> {
+        self.conn
+            .query_row(
+                "SELECT id, file_path, content_hash, file_size, saved_at
+                 FROM document_versions WHERE id = ?1",
+                params![id],
+                version_from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read version history: {}", e))?
+            .ok_or_else(|| format!("Version {} does not exist", id))
+    }
+
+    pub fn read(&self, id: i64) -> Result<Vec<u8>, String> {
+        let version = self.get(id)?;
+        fs::read(self.blob_path(&version.content_hash))
+            .map_err(|e| format!("Failed to read version {}: {}", id, e))
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.store_dir.join(&hash[..2]).join(hash)
+    }
+}
+
+fn version_from_row(row: &rusqlite::Row) -> rusqlite::Result<DocumentVersion> {
+    Ok(DocumentVersion {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        content_hash: row.get(2)?,
+        file_size: row.get::<_, i64>(3)? as u64,
+        saved_at: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::commands::test_support::temp_dir;
+
+    fn store(dir: &Path) -> VersionStore {
+        VersionStore::open_at(&dir.join("app.db"), dir.join(STORE_DIR)).unwrap()
+    }
+
+    fn blobs(store: &VersionStore) -> usize {
+        fs::read_dir(&store.store_dir)
+            .unwrap()
+            .map(|entry| fs::read_dir(entry.unwrap().path()).unwrap().count())
+            .sum()
+    }
+
+    #[test]
+    fn keeps_the_newest_versions_and_their_blobs() {
+        let dir = temp_dir("versions-count");
+        let store = store(&dir);
+        let path = dir.join("a.pdf");
+        for i in 0..MAX_VERSIONS + 3 {
+            fs::write(&path, format!("contents {}", i)).unwrap();
+            store.snapshot(&path).unwrap();
+        }
+
+        let versions = store.list(&path).unwrap();
+        assert_eq!(versions.len(), MAX_VERSIONS);
+        let newest = store.read(versions[0].id).unwrap();
+        assert_eq!(newest, format!("contents {}", MAX_VERSIONS + 2).as_bytes());
+        let oldest = store.read(versions[MAX_VERSIONS - 1].id).unwrap();
+        assert_eq!(oldest, b"contents 3");
+        assert_eq!(blobs(&store), MAX_VERSIONS);
+    }
+
+    #[test]
+    fn drops_old_versions_but_not_blobs_still_in_use() {
+        let dir = temp_dir("versions-age");
+        let store = store(&dir);
+        let a = dir.join("a.pdf");
+        let b = dir.join("b.pdf");
+        fs::write(&a, "shared").unwrap();
+        fs::write(&b, "shared").unwrap();
+        store.snapshot(&a).unwrap();
+        store.snapshot(&b).unwrap();
+        fs::write(&a, "only a").unwrap();
+        store.snapshot(&a).unwrap();
+        store
+            .conn
+            .execute(
+                "UPDATE document_versions SET saved_at = datetime('now', '-1 year')",
+                [],
+            )
+            .unwrap();
+
+        fs::write(&a, "newest").unwrap();
+        store.snapshot(&a).unwrap();
+
+        let versions = store.list(&a).unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(store.read(versions[0].id).unwrap(), b"newest");
+        // b's old version is only pruned by b's next snapshot
+        let versions = store.list(&b).unwrap();
+        assert_eq!(store.read(versions[0].id).unwrap(), b"shared");
+        assert_eq!(blobs(&store), 2);
+    }
+}
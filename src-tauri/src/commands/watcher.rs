@@ -0,0 +1,266 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{Emitter, Manager};
+
+use super::error::{CommandError, ErrorCode};
+
+/// Sent to every window when a watched file is modified or removed by
+/// another program, with a `FileEvent` payload.
+const DOCUMENT_CHANGED_EVENT: &str = "document-changed";
+const DOCUMENT_DELETED_EVENT: &str = "document-deleted";
+
+/// Polling rather than OS notifications: it behaves the same on every
+/// platform and on network drives, and only a handful of files are watched.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Files read by the frontend and what they looked like when they were
+/// read. Managed as Tauri state; keyed by resolved path.
+#[derive(Default)]
+pub struct FileWatcher {
+    files: Mutex<HashMap<PathBuf, WatchedFile>>,
+}
+
+struct WatchedFile {
+    /// The path as the frontend gave it, sent back in events.
+    path: String,
+    /// Labels of the windows that read the file and still show it.
+    windows: BTreeSet<String>,
+    /// The file as the frontend last read it, or as we last wrote it.
+    loaded: FileState,
+    /// SHA-256 of the loaded contents, when they were read whole. Lets a
+    /// file that was only touched count as unchanged.
+    loaded_hash: Option<String>,
+    /// What the last poll saw (`None`: missing), so each change is
+    /// reported once.
+    seen: Option<FileState>,
+    /// Saves of ours in progress; polls leave the file alone meanwhile.
+    writing: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileState {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileEvent {
+    path: String,
+}
+
+/// Stop watching `path` for this window. The file is still watched while
+/// another window shows it.
+#[tauri::command]
+pub async fn unwatch_file(
+    window: tauri::WebviewWindow,
+    watcher: tauri::State<'_, FileWatcher>,
+    path: String,
+) -> Result<(), String> {
+    let mut files = watcher.files.lock().unwrap();
+    let key = key(Path::new(&path));
+    if let Some(file) = files.get_mut(&key) {
+        file.windows.remove(window.label());
+        if file.windows.is_empty() {
+            files.remove(&key);
+        }
+    }
+    Ok(())
+}
+
+/// Stop watching files for a window that was closed.
+pub fn release_window(app: &tauri::AppHandle, label: &str) {
+    let watcher = app.state::<FileWatcher>();
+    watcher.files.lock().unwrap().retain(|_, file| {
+        file.windows.remove(label);
+        !file.windows.is_empty()
+    });
+}
+
+/// Start the thread that reports changes to watched files.
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        poll(&app);
+    });
+}
+
+/// Watch `path`, which `window` just read. `data` is its contents when the
+/// whole file was read.
+pub fn watch(app: &tauri::AppHandle, window: &str, path: &str, data: Option<&[u8]>) {
+    let Some(state) = file_state(Path::new(path)) else {
+        return;
+    };
+    let watcher = app.state::<FileWatcher>();
+    let mut files = watcher.files.lock().unwrap();
+    let file = files
+        .entry(key(Path::new(path)))
+        .or_insert_with(|| WatchedFile {
+            path: path.to_string(),
+            windows: BTreeSet::new(),
+            loaded: state,
+            loaded_hash: None,
+            seen: None,
+            writing: 0,
+        });
+    file.windows.insert(window.to_string());
+    file.loaded = state;
+    file.loaded_hash = data.map(hash);
+    file.seen = Some(state);
+}
+
+/// Fail if `path` is watched and was changed on disk since it was loaded,
+/// so a save doesn't silently discard another program's edits.
+pub fn ensure_unchanged(app: &tauri::AppHandle, path: &Path) -> Result<(), CommandError> {
+    let watcher = app.state::<FileWatcher>();
+    let Some((loaded, loaded_hash)) = watcher
+        .files
+        .lock()
+        .unwrap()
+        .get(&key(path))
+        .map(|file| (file.loaded, file.loaded_hash.clone()))
+    else {
+        return Ok(());
+    };
+    // A deleted file can be written again without losing anything
+    let Some(state) = file_state(path) else {
+        return Ok(());
+    };
+    if state == loaded || (loaded_hash.is_some() && contents_hash(path) == loaded_hash) {
+        return Ok(());
+    }
+    Err(CommandError::new(
+        ErrorCode::FileChanged,
+        format!(
+            "{} was changed by another program since it was opened",
+            path.display()
+        ),
+    )
+    .with_path(path))
+}
+
+/// Run `write`, which replaces `path`, without the change being reported
+/// as someone else's: afterwards the file counts as loaded as written.
+/// The watcher isn't locked while `write` runs, which can take a while;
+/// polls skip the file until it's done instead.
+pub fn write_watched<T, E>(
+    app: &tauri::AppHandle,
+    path: &Path,
+    write: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let watcher = app.state::<FileWatcher>();
+    let key = key(path);
+    if let Some(file) = watcher.files.lock().unwrap().get_mut(&key) {
+        file.writing += 1;
+    }
+    let result = write();
+    let state = file_state(path);
+    if let Some(file) = watcher.files.lock().unwrap().get_mut(&key) {
+        file.writing = file.writing.saturating_sub(1);
+        if let (Ok(_), Some(state)) = (&result, state) {
+            file.loaded = state;
+            file.loaded_hash = None;
+            file.seen = Some(state);
+        }
+    }
+    result
+}
+
+/// Report the watched files that changed since the last poll. Files are
+/// inspected and hashed with the watcher unlocked, so a slow disk doesn't
+/// hold up saves; a file saved meanwhile is left for the next poll.
+fn poll(app: &tauri::AppHandle) {
+    let watcher = app.state::<FileWatcher>();
+    let watched: Vec<(PathBuf, Option<FileState>, FileState, bool)> = watcher
+        .files
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, file)| file.writing == 0)
+        .map(|(resolved, file)| {
+            (
+                resolved.clone(),
+                file.seen,
+                file.loaded,
+                file.loaded_hash.is_some(),
+            )
+        })
+        .collect();
+
+    let mut events = Vec::new();
+    for (resolved, seen, loaded, hashed) in watched {
+        let state = file_state(&resolved);
+        if state == seen {
+            continue;
+        }
+        let current_hash = match state {
+            Some(state) if state != loaded && hashed => contents_hash(&resolved),
+            _ => None,
+        };
+
+        let mut files = watcher.files.lock().unwrap();
+        let Some(file) = files.get_mut(&resolved) else {
+            continue;
+        };
+        if file.writing > 0 || file.seen != seen || file.loaded != loaded {
+            continue;
+        }
+        file.seen = state;
+        let event = match state {
+            None => DOCUMENT_DELETED_EVENT,
+            Some(state) if state == file.loaded => continue,
+            Some(state) => {
+                // Touched but with the contents it was loaded with
+                if current_hash.is_some() && current_hash == file.loaded_hash {
+                    file.loaded = state;
+                    continue;
+                }
+                DOCUMENT_CHANGED_EVENT
+            }
+        };
+        events.push((
+            event,
+            FileEvent {
+                path: file.path.clone(),
+            },
+        ));
+    }
+    for (event, payload) in events {
+        if let Err(e) = app.emit(event, payload) {
+            log::warn!("Failed to report file change: {}", e);
+        }
+    }
+}
+
+/// `None` if the file is missing (or can't be inspected).
+fn file_state(path: &Path) -> Option<FileState> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(FileState {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    })
+}
+
+fn contents_hash(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|data| hash(&data))
+}
+
+fn hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// The same file reached through a different spelling of its path is
+/// watched once.
+fn key(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,286 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use super::documents::write_atomic;
+
+/// The window declared in tauri.conf.json. Document windows opened later
+/// are labelled `document-<n>`, which the default capability also covers.
+pub const MAIN_WINDOW: &str = "main";
+const DOCUMENT_WINDOW_PREFIX: &str = "document-";
+
+/// Where the documents open at exit are remembered, in the app config
+/// directory.
+const SESSION_FILE: &str = "workspace.json";
+
+/// Sent to a window without a document to make it open one in place.
+const OPEN_DOCUMENT_EVENT: &str = "open-document";
+
+/// Size of new document windows; matches the main window's configuration.
+const WINDOW_SIZE: (f64, f64) = (1200.0, 800.0);
+const MIN_WINDOW_SIZE: (f64, f64) = (800.0, 600.0);
+
+/// Which document is open in which window, in the order the windows were
+/// opened. Managed as Tauri state.
+#[derive(Default)]
+pub struct Workspace {
+    windows: Mutex<Vec<OpenWindow>>,
+    next_id: AtomicU32,
+}
+
+struct OpenWindow {
+    label: String,
+    /// `None` until the window has a document open.
+    file_path: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    documents: Vec<String>,
+}
+
+/// Open `path` in a window of its own, or focus the window that already has
+/// it open.
+#[tauri::command]
+pub async fn open_document_window(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    open_document(&app, &path)
+}
+
+/// The document this window was opened for (by "Open With", a forwarded
+/// launch or the restored session), if any.
+#[tauri::command]
+pub fn get_window_document(
+    window: tauri::WebviewWindow,
+    workspace: tauri::State<'_, Workspace>,
+) -> Option<String> {
+    let windows = workspace.windows.lock().unwrap();
+    windows
+        .iter()
+        .find(|w| w.label == window.label())
+        .and_then(|w| w.file_path.clone())
+}
+
+/// Record that this window now shows `path` (or no document, for `None`).
+/// If another window already has `path` open, that window is focused
+/// instead and `false` is returned: the caller should not open it again.
+#[tauri::command]
+pub async fn set_window_document(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    path: Option<String>,
+) -> Result<bool, String> {
+    let workspace = app.state::<Workspace>();
+    let mut windows = workspace.windows.lock().unwrap();
+    if let Some(path) = &path {
+        if let Some(other) = windows
+            .iter()
+            .find(|w| w.label != window.label() && is_open(w, path))
+        {
+            let label = other.label.clone();
+            drop(windows);
+            focus(&app, &label);
+            return Ok(false);
+        }
+    }
+    match windows.iter_mut().find(|w| w.label == window.label()) {
+        Some(open) => open.file_path = path,
+        None => windows.push(OpenWindow {
+            label: window.label().to_string(),
+            file_path: path,
+        }),
+    }
+    save_session(&app, &windows)?;
+    Ok(true)
+}
+
+/// Open `path`: focus the window that already shows it, hand it to a window
+/// with no document, or create a new window for it.
+///
+/// The window list is only locked to pick the window and to record the
+/// choice. Creating a window, focusing one or sending it an event waits on
+/// the event loop, which may itself be waiting for the lock.
+pub fn open_document(app: &tauri::AppHandle, path: &str) -> Result<(), String> {
+    enum Target {
+        Showing(String),
+        Empty(String),
+        New(String),
+    }
+    let workspace = app.state::<Workspace>();
+    let target = {
+        let mut windows = workspace.windows.lock().unwrap();
+        if let Some(open) = windows.iter().find(|w| is_open(w, path)) {
+            Target::Showing(open.label.clone())
+        } else {
+            let empty = windows
+                .iter_mut()
+                .find(|w| w.file_path.is_none() && app.get_webview_window(&w.label).is_some());
+            let target = match empty {
+                Some(empty) => {
+                    empty.file_path = Some(path.to_string());
+                    Target::Empty(empty.label.clone())
+                }
+                None => {
+                    // Listed before it exists, so the window finds its document
+                    // when it asks for it
+                    let label = format!(
+                        "{}{}",
+                        DOCUMENT_WINDOW_PREFIX,
+                        workspace.next_id.fetch_add(1, Ordering::Relaxed) + 1
+                    );
+                    windows.push(OpenWindow {
+                        label: label.clone(),
+                        file_path: Some(path.to_string()),
+                    });
+                    Target::New(label)
+                }
+            };
+            // The window is opened either way; it just won't be restored
+            // at the next launch.
+            if let Err(e) = save_session(app, &windows) {
+                log::warn!("{}", e);
+            }
+            target
+        }
+    };
+
+    match target {
+        Target::Showing(label) => focus(app, &label),
+        Target::Empty(label) => {
+            app.emit_to(label.as_str(), OPEN_DOCUMENT_EVENT, path)
+                .map_err(|e| format!("Failed to open document: {}", e))?;
+            focus(app, &label);
+        }
+        Target::New(label) => {
+            if let Err(e) = create_window(app, &label, path) {
+                forget(app, &label);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Set up the windows at launch. The main window gets the first of
+/// `opened_with` (the files passed on the command line) and every other
+/// file gets a window of its own. Without any, the documents that were open
+/// at the last exit are reopened.
+pub fn restore(app: &tauri::AppHandle, opened_with: Vec<String>) -> Result<(), String> {
+    let documents = if opened_with.is_empty() {
+        load_session(app)
+            .documents
+            .into_iter()
+            .filter(|path| Path::new(path).is_file())
+            .collect()
+    } else {
+        opened_with
+    };
+    let mut documents = documents.into_iter();
+    {
+        let workspace = app.state::<Workspace>();
+        let mut windows = workspace.windows.lock().unwrap();
+        windows.push(OpenWindow {
+            label: MAIN_WINDOW.to_string(),
+            file_path: documents.next(),
+        });
+    }
+    for path in documents {
+        open_document(app, &path)?;
+    }
+    focus(app, MAIN_WINDOW);
+    Ok(())
+}
+
+/// Stop tracking a window that was closed. The last window is kept: closing
+/// it quits the app, and its document should come back at the next launch.
+pub fn forget(app: &tauri::AppHandle, label: &str) {
+    let workspace = app.state::<Workspace>();
+    let mut windows = workspace.windows.lock().unwrap();
+    if windows.len() > 1 {
+        windows.retain(|w| w.label != label);
+        if let Err(e) = save_session(app, &windows) {
+            log::warn!("{}", e);
+        }
+    }
+}
+
+/// Bring the app to the front: the first window that is still open.
+pub fn focus_first_window(app: &tauri::AppHandle) {
+    let workspace = app.state::<Workspace>();
+    let label = workspace
+        .windows
+        .lock()
+        .unwrap()
+        .first()
+        .map_or(MAIN_WINDOW.to_string(), |w| w.label.clone());
+    focus(app, &label);
+}
+
+fn focus(app: &tauri::AppHandle, label: &str) {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn create_window(app: &tauri::AppHandle, label: &str, path: &str) -> Result<(), String> {
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    WebviewWindowBuilder::new(app, label, WebviewUrl::App("index.html".into()))
+        .title(format!("{} - Office Tools", file_name))
+        .inner_size(WINDOW_SIZE.0, WINDOW_SIZE.1)
+        .min_inner_size(MIN_WINDOW_SIZE.0, MIN_WINDOW_SIZE.1)
+        .resizable(true)
+        .focused(true)
+        .build()
+        .map_err(|e| format!("Failed to open window: {}", e))?;
+    Ok(())
+}
+
+/// Whether `window` shows `path`. Paths are compared after resolving them,
+/// so the same file reached through a different spelling still matches.
+fn is_open(window: &OpenWindow, path: &str) -> bool {
+    window
+        .file_path
+        .as_deref()
+        .is_some_and(|open| open == path || canonical(open) == canonical(path))
+}
+
+fn canonical(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+fn session_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(SESSION_FILE))
+        .map_err(|e| format!("Failed to get app config dir: {}", e))
+}
+
+/// A missing or unreadable session is an empty one.
+fn load_session(app: &tauri::AppHandle) -> Session {
+    session_path(app)
+        .ok()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_session(app: &tauri::AppHandle, windows: &[OpenWindow]) -> Result<(), String> {
+    let session = Session {
+        documents: windows.iter().filter_map(|w| w.file_path.clone()).collect(),
+    };
+    let path = session_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create app dir: {}", e))?;
+    }
+    let json = serde_json::to_vec_pretty(&session)
+        .map_err(|e| format!("Failed to save workspace: {}", e))?;
+    write_atomic(&path, &json).map_err(|e| format!("Failed to save workspace: {}", e))
+}
@@ -1,10 +1,39 @@
+pub mod cli;
 mod commands;
 
+use std::path::Path;
+
 use tauri::Manager;
 
+use commands::locks::{self, Locks};
+use commands::recovery::{self, Recovery};
+use commands::watcher::{self, FileWatcher};
+use commands::workspace::{self, Workspace};
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // Must be registered first: a second launch forwards its arguments
+        // here and exits before any other plugin sets up.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            let args = argv.get(1..).unwrap_or_default();
+            let files = cli::files_to_open(args, Path::new(&cwd));
+            if files.is_empty() {
+                workspace::focus_first_window(app);
+            }
+            for file in files {
+                if let Err(e) = workspace::open_document(app, &file) {
+                    log::warn!("{}", e);
+                }
+            }
+        }))
+        // Errors the app recovers from on its own go to the log file in
+        // the app's log directory, since packaged builds have no console.
+        .plugin(
+            tauri_plugin_log::Builder::new()
+                .level(log::LevelFilter::Info)
+                .build(),
+        )
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_sql::Builder::new().build())
@@ -12,30 +41,100 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
+            commands::annotations::annotate_pdf,
+            commands::annotations::read_pdf_annotations,
+            commands::backups::list_backups,
+            commands::backups::restore_backup,
             commands::documents::open_file_dialog,
             commands::documents::save_file_dialog,
             commands::documents::read_file_bytes,
+            commands::documents::read_file_bytes_raw,
+            commands::documents::read_file_range,
+            commands::documents::get_file_size,
             commands::documents::write_file_bytes,
             commands::documents::write_file_bytes_raw,
-            commands::documents::get_file_opened_with,
+            commands::exchange::open_exchange_dialog,
+            commands::exchange::save_exchange_dialog,
+            commands::exchange::export_annotations_xfdf,
+            commands::exchange::import_annotations_xfdf,
+            commands::exchange::export_form_fdf,
+            commands::exchange::import_form_fdf,
+            commands::forms::list_form_fields,
+            commands::forms::fill_form_fields,
+            commands::inspect::inspect_file,
+            commands::library::index_document,
+            commands::library::index_library,
+            commands::library::search_library,
+            commands::locks::lock_document,
+            commands::locks::unlock_document,
+            commands::locks::get_lock_owner,
+            commands::pages::open_pdfs_dialog,
+            commands::pages::pick_folder_dialog,
+            commands::pages::merge_pdfs,
+            commands::pages::split_pdf,
+            commands::pages::reorder_pages,
+            commands::pages::rotate_pages,
+            commands::pages::delete_pages,
+            commands::pdf::flatten_pdf,
+            commands::recovery::save_recovery_snapshot,
+            commands::recovery::clear_recovery_snapshot,
+            commands::recovery::list_recoverable_sessions,
+            commands::recovery::open_recoverable_session,
+            commands::recovery::discard_recoverable_session,
+            commands::redaction::redact_pdf,
+            commands::security::get_pdf_encryption,
+            commands::security::unlock_pdf,
+            commands::security::protect_pdf,
+            commands::security::remove_pdf_password,
+            commands::optimize::optimize_pdf,
             commands::settings::get_app_data_dir,
+            commands::signing::open_identity_dialog,
+            commands::signing::sign_pdf,
+            commands::text::extract_pdf_text,
+            commands::text::search_pdf,
+            commands::verification::verify_pdf_signatures,
+            commands::verification::list_trusted_certificates,
+            commands::verification::import_trusted_certificate,
+            commands::verification::remove_trusted_certificate,
+            commands::versions::list_versions,
+            commands::versions::read_version,
+            commands::versions::restore_version,
+            commands::watcher::unwatch_file,
+            commands::workspace::open_document_window,
+            commands::workspace::get_window_document,
+            commands::workspace::set_window_document,
         ])
+        .manage(Workspace::default())
+        .manage(FileWatcher::default())
+        .manage(Locks::default())
+        .manage(Recovery::default())
         .setup(|app| {
-            // Check if a file was passed as CLI argument (Open With)
-            let args: Vec<String> = std::env::args().collect();
-            if args.len() > 1 {
-                let file_path = args[1].clone();
-                if let Some(window) = app.get_webview_window("main") {
-                    if let Ok(json) = serde_json::to_string(&file_path) {
-                        let _ = window.eval(&format!(
-                            "window.__OPENED_FILE__ = {};",
-                            json
-                        ));
-                    }
-                }
+            // Unsaved work left by a crash, offered by the first window
+            if let Err(e) = recovery::find_orphaned(app.handle()) {
+                log::warn!("{}", e);
             }
+            // Open files passed as CLI arguments (Open With), or the
+            // documents that were open at the last exit
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            let cwd = std::env::current_dir().unwrap_or_default();
+            workspace::restore(app.handle(), cli::files_to_open(&args, &cwd))?;
+            watcher::start(app.handle().clone());
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                workspace::forget(window.app_handle(), window.label());
+                locks::release_window(window.app_handle(), window.label());
+                watcher::release_window(window.app_handle(), window.label());
+                recovery::discard_window(window.app_handle(), window.label());
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Don't leave owner files behind for colleagues to trip over
+            if let tauri::RunEvent::Exit = event {
+                locks::release_all(app);
+            }
+        });
 }
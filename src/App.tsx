@@ -1,11 +1,15 @@
-import { useState, useEffect, useCallback } from "react";
-import { getSetting } from "./db/sqlite";
+import { useState, useEffect, useCallback, useRef } from "react";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { getSetting, getSignatures } from "./db/sqlite";
 import { useDocument } from "./hooks/useDocument";
 import { usePdfViewer } from "./hooks/usePdfViewer";
 import { useWordEditor } from "./hooks/useWordEditor";
 import { useSignatures } from "./hooks/useSignatures";
 import { useUpdater } from "./hooks/useUpdater";
 import { useOverlays } from "./hooks/useOverlays";
+import { useSignatureValidation } from "./hooks/useSignatureValidation";
+import { useFormFields } from "./hooks/useFormFields";
+import { useRecovery } from "./hooks/useRecovery";
 import { ToastProvider, useToast } from "./components/common/Toast";
 import Header from "./components/layout/Header";
 import Sidebar from "./components/layout/Sidebar";
@@ -13,16 +17,45 @@ import ToolSelector from "./components/layout/ToolSelector";
 import PdfToolbar from "./components/pdf/PdfToolbar";
 import PdfViewer from "./components/pdf/PdfViewer";
 import FlattenDialog from "./components/pdf/FlattenDialog";
+import FormFillDialog from "./components/pdf/FormFillDialog";
+import PageOrganizerDialog from "./components/pdf/PageOrganizerDialog";
+import PasswordDialog from "./components/pdf/PasswordDialog";
+import ProtectDialog from "./components/pdf/ProtectDialog";
+import OptimizeDialog from "./components/pdf/OptimizeDialog";
 import SignaturePad from "./components/pdf/SignaturePad";
 import WordEditorToolbar from "./components/word/WordEditorToolbar";
 import WordEditor from "./components/word/WordEditor";
 import PlaceholderViewer from "./components/common/PlaceholderViewer";
 import UpdateBanner from "./components/common/UpdateBanner";
 import FileDropZone from "./components/common/FileDropZone";
+import FileChangedBanner from "./components/common/FileChangedBanner";
+import BackupsDialog from "./components/common/BackupsDialog";
+import VersionHistoryDialog from "./components/common/VersionHistoryDialog";
+import RecoveryDialog from "./components/common/RecoveryDialog";
 import Walkthrough from "./components/onboarding/Walkthrough";
 import { createBlankWordDocument } from "./utils/newDocument";
 import { docxToHtml } from "./utils/docxImport";
 import { htmlToDocx } from "./utils/docxExport";
+import { errorDetail, errorMessage } from "./utils/errors";
+import { readFileRaw, writeFile } from "./services/file.service";
+import {
+  exportAnnotationsXfdf,
+  fromAnnotationRecord,
+  importAnnotationsXfdf,
+  pickExchangeFile,
+  pickExchangeSaveFile,
+  readPdfAnnotations,
+  redactPdf,
+} from "./services/pdf.service";
+import type { ImportedAnnotation } from "./services/pdf.service";
+import {
+  discardRecoverableSession,
+  listRecoverableSessions,
+  openRecoverableSession,
+} from "./services/recovery.service";
+import type { Annotation, RedactionArea } from "./types/pdf";
+import type { DocumentVersion, RecoverableSession, RecoverySnapshot } from "./types/document";
+import { documentIdentity } from "./types/document";
 import { invoke } from "@tauri-apps/api/core";
 import "./styles/docx.css";
 
@@ -30,10 +63,24 @@ function AppContent() {
   const [onboardingComplete, setOnboardingComplete] = useState<boolean | null>(null);
   const [sidebarCollapsed, setSidebarCollapsed] = useState(false);
   const [showFlattenDialog, setShowFlattenDialog] = useState(false);
+  const [showFormFillDialog, setShowFormFillDialog] = useState(false);
+  const [showPageOrganizer, setShowPageOrganizer] = useState(false);
+  const [showProtectDialog, setShowProtectDialog] = useState(false);
+  const [showOptimizeDialog, setShowOptimizeDialog] = useState(false);
+  /** Reopens the encrypted PDF being loaded once its password is entered. */
+  const [retryWithPassword, setRetryWithPassword] = useState<
+    ((password: string) => void) | null
+  >(null);
+  const [showBackups, setShowBackups] = useState(false);
+  const [showVersionHistory, setShowVersionHistory] = useState(false);
   const [showSignaturePad, setShowSignaturePad] = useState(false);
+  const [recoverableSessions, setRecoverableSessions] = useState<RecoverableSession[]>([]);
   const [pageDimensions, setPageDimensions] = useState<
     Array<{ width: number; height: number }>
   >([]);
+  const [redactionAreas, setRedactionAreas] = useState<RedactionArea[]>([]);
+  /** Page to show once the document being opened from a search result loads. */
+  const [pendingPage, setPendingPage] = useState<number | null>(null);
 
   const { showToast } = useToast();
   const updater = useUpdater();
@@ -41,13 +88,16 @@ function AppContent() {
     document: currentDoc,
     recentDocuments,
     openFile,
+    openFileInNewWindow,
     openFilePath,
     openNew,
     updateDocumentPath,
     closeFile,
+    externalChange,
+    reloadFile,
+    dismissExternalChange,
     clearRecents,
-    fileSizeWarning,
-  } = useDocument();
+  } = useDocument({ onError: (message) => showToast("error", message) });
   const {
     pdfDoc,
     pageCount,
@@ -57,6 +107,7 @@ function AppContent() {
     goToPage,
     setScale,
     loadDocument: loadPdf,
+    hideAnnotations,
   } = usePdfViewer();
   const {
     editor: wordEditor,
@@ -77,8 +128,105 @@ function AppContent() {
     newAnnotationId,
     clearNewAnnotationId,
     loadAnnotations,
+    replaceAnnotations,
+    importAnnotations,
+    removedImportedIds,
+    addAnnotations,
     saveAllAnnotations,
   } = useOverlays();
+  const annotationsRef = useRef<Annotation[]>(annotations);
+  annotationsRef.current = annotations;
+  // Unsaved work being recovered, applied once its document has loaded
+  const recoveringRef = useRef<{ id: string; snapshot: RecoverySnapshot } | null>(null);
+
+  // What a crash would lose: the Word editor's content, or a PDF's overlays
+  const { markClean } = useRecovery(currentDoc, () => {
+    if (currentDoc.fileType === "word" && currentDoc.fileBytes && wordEditor) {
+      return wordEditor.getHTML();
+    }
+    if (currentDoc.fileType === "pdf" && currentDoc.filePath) {
+      return JSON.stringify(annotationsRef.current);
+    }
+    return null;
+  });
+
+  /**
+   * The recovered content for the document that just loaded, if any. The
+   * session is only discarded here: until its content is back in a
+   * document, it is offered again at the next launch.
+   */
+  const takeRecoveredContent = (filePath: string | null): string | null => {
+    const recovering = recoveringRef.current;
+    if (!recovering || recovering.snapshot.filePath !== filePath) return null;
+    recoveringRef.current = null;
+    discardRecoverableSession(recovering.id).catch((err) =>
+      console.error("Failed to discard recovered session:", err)
+    );
+    return recovering.snapshot.content;
+  };
+
+  /**
+   * Turn imported annotations into overlays. Signatures are matched with a
+   * saved one by name, font and color, or added.
+   */
+  const resolveImportedAnnotations = useCallback(
+    async (records: ImportedAnnotation[]): Promise<Annotation[]> => {
+      const saved = (await getSignatures()).map((r) => ({
+        id: r.id,
+        name: r.name,
+        fontFamily: r.font_family,
+        color: r.color,
+      }));
+      const resolved: Annotation[] = [];
+      for (const record of records) {
+        const style = record.signature;
+        if (!style) {
+          resolved.push(fromAnnotationRecord(record));
+          continue;
+        }
+        let signature = saved.find(
+          (s) =>
+            s.name === style.name && s.fontFamily === style.fontFamily && s.color === style.color
+        );
+        if (!signature) {
+          signature = await addSignature(style.name, style.fontFamily, style.color);
+          saved.push(signature);
+        }
+        resolved.push(fromAnnotationRecord({ ...record, signatureId: signature.id }));
+      }
+      return resolved;
+    },
+    [addSignature]
+  );
+
+  /**
+   * Show the PDF's own FreeText annotations and saved signatures as
+   * overlays, hiding the originals so they aren't drawn twice.
+   */
+  const importPdfAnnotations = async (filePath: string) => {
+    try {
+      const natives = await readPdfAnnotations(filePath);
+      if (natives.length === 0) return;
+      importAnnotations(await resolveImportedAnnotations(natives));
+      hideAnnotations(natives.map((n) => n.objectRef));
+    } catch (err) {
+      console.error("Failed to read PDF annotations:", err);
+    }
+  };
+
+  const signatureValidation = useSignatureValidation(
+    currentDoc.filePath,
+    currentDoc.fileType === "pdf"
+  );
+  const formFields = useFormFields(currentDoc.filePath, currentDoc.fileType === "pdf");
+
+  // Unsaved work from a session that crashed; offered by the first window only
+  useEffect(() => {
+    if (getCurrentWebviewWindow().label !== "main") return;
+    listRecoverableSessions()
+      .then(setRecoverableSessions)
+      .catch((err) => console.error("Failed to list recoverable sessions:", err));
+  }, []);
 
   // Check onboarding status
   useEffect(() => {
@@ -87,52 +235,62 @@ function AppContent() {
     });
   }, []);
 
-  // Show file size warning
-  useEffect(() => {
-    if (fileSizeWarning) {
-      showToast("info", fileSizeWarning);
-    }
-  }, [fileSizeWarning, showToast]);
-
   // Load document when file bytes change — route by file type
   useEffect(() => {
-    if (!currentDoc.fileBytes) return;
+    setRedactionAreas([]);
+    const pdfSource = currentDoc.fileBytes ?? currentDoc.rangedFile;
+    if (!pdfSource) return;
 
     if (currentDoc.fileType === "pdf") {
-      loadPdf(currentDoc.fileBytes)
-        .then(async (numPages) => {
-          const dims: Array<{ width: number; height: number }> = [];
-          for (let i = 0; i < numPages; i++) {
-            dims.push({ width: 612, height: 792 });
-          }
-          setPageDimensions(dims);
-
-          if (currentDoc.filePath) {
-            await loadAnnotations(currentDoc.filePath);
-          }
-        })
-        .catch((err: unknown) => {
-          console.error("Failed to load PDF document:", err);
-
-          if (err instanceof Error && err.name === "PasswordException") {
-            showToast("error", "This PDF is password-protected and cannot be opened");
-            closeFile();
-            return;
-          }
-
-          if (err instanceof Error && err.name === "InvalidPDFException") {
-            showToast("error", "This file is not a valid PDF or is corrupted");
+      const open = (password?: string): Promise<void> =>
+        loadPdf(pdfSource, password)
+          .then(async (numPages) => {
+            const dims: Array<{ width: number; height: number }> = [];
+            for (let i = 0; i < numPages; i++) {
+              dims.push({ width: 612, height: 792 });
+            }
+            setPageDimensions(dims);
+
+            if (currentDoc.filePath) {
+              await loadAnnotations(documentIdentity(currentDoc.filePath, currentDoc.fileInfo));
+              await importPdfAnnotations(currentDoc.filePath);
+            }
+            const recovered = takeRecoveredContent(currentDoc.filePath);
+            if (recovered) {
+              replaceAnnotations(JSON.parse(recovered));
+            } else {
+              markClean();
+            }
+          })
+          .catch((err: unknown) => {
+            if (err instanceof Error && err.name === "PasswordException") {
+              setRetryWithPassword(() => (entered: string) => {
+                setRetryWithPassword(null);
+                open(entered);
+                // Read before the password was known
+                formFields.refresh();
+                signatureValidation.refresh();
+              });
+              return;
+            }
+            console.error("Failed to load PDF document:", err);
+
+            if (err instanceof Error && err.name === "InvalidPDFException") {
+              showToast("error", "This file is not a valid PDF or is corrupted");
+              closeFile();
+              return;
+            }
+
+            showToast("error", "Failed to load PDF document");
             closeFile();
-            return;
-          }
-
-          showToast("error", "Failed to load PDF document");
-          closeFile();
-        });
-    } else if (currentDoc.fileType === "word") {
+          });
+      open();
+    } else if (currentDoc.fileType === "word" && currentDoc.fileBytes) {
       docxToHtml(currentDoc.fileBytes)
         .then((html) => {
-          loadWordHtml(html);
+          const recovered = takeRecoveredContent(currentDoc.filePath);
+          loadWordHtml(recovered ?? html);
+          if (!recovered) markClean();
         })
         .catch((err: unknown) => {
           console.error("Failed to load Word document:", err);
@@ -140,7 +298,7 @@ function AppContent() {
         });
     }
     // eslint-disable-next-line react-hooks/exhaustive-deps -- stable callbacks; re-run on file change
-  }, [currentDoc.fileBytes, currentDoc.fileType]);
+  }, [currentDoc.fileBytes, currentDoc.rangedFile, currentDoc.fileType]);
 
   // Get actual page dimensions from pdf.js
   useEffect(() => {
@@ -158,6 +316,26 @@ function AppContent() {
     getDims();
   }, [pdfDoc, pageCount]);
 
+  // Jump to the page of a library search result once its document is loaded
+  useEffect(() => {
+    if (pendingPage === null || !pdfDoc) return;
+    goToPage(pendingPage);
+    setPendingPage(null);
+    // eslint-disable-next-line react-hooks/exhaustive-deps -- run when the new document arrives
+  }, [pdfDoc, pageCount]);
+
+  const handleOpenSearchResult = useCallback(
+    (filePath: string, pageNumber: number | null) => {
+      if (filePath === currentDoc.filePath) {
+        if (pageNumber !== null) goToPage(pageNumber);
+        return;
+      }
+      setPendingPage(pageNumber);
+      openFilePath(filePath);
+    },
+    [currentDoc.filePath, goToPage, openFilePath]
+  );
+
   const handlePageClick = useCallback(
     (pageNumber: number, x: number, y: number) => {
       if (mode === "text") {
@@ -178,6 +356,96 @@ function AppContent() {
     showToast("success", "PDF saved successfully");
   }, [showToast]);
 
+  const handlePagesOrganized = useCallback(
+    (outputPath: string | null, message: string) => {
+      showToast("success", message);
+      if (outputPath) openFilePath(outputPath);
+    },
+    [showToast, openFilePath]
+  );
+
+  const handleImportAnnotations = useCallback(async () => {
+    if (!currentDoc.filePath) {
+      showToast("info", "Save the PDF to disk before importing annotations");
+      return;
+    }
+    try {
+      const path = await pickExchangeFile("xfdf");
+      if (!path) return;
+      const result = await importAnnotationsXfdf(path, currentDoc.filePath);
+      const existing = new Set(annotationsRef.current.map((a) => a.id));
+      const added = result.annotations.filter((a) => !existing.has(a.id));
+      addAnnotations(await resolveImportedAnnotations(added));
+
+      const skipped = result.skipped > 0 ? ` (${result.skipped} unsupported skipped)` : "";
+      showToast(
+        "success",
+        `Imported ${added.length} annotation${added.length !== 1 ? "s" : ""}${skipped}`
+      );
+      if (result.otherDocument) {
+        showToast("info", "These annotations were exported from a different PDF");
+      }
+    } catch (err) {
+      console.error("Failed to import annotations:", err);
+      showToast("error", errorMessage(err, "Failed to import annotations"));
+    }
+  }, [currentDoc.filePath, addAnnotations, resolveImportedAnnotations, showToast]);
+
+  const handleExportAnnotations = useCallback(async () => {
+    if (!currentDoc.filePath) {
+      showToast("info", "Save the PDF to disk before exporting annotations");
+      return;
+    }
+    try {
+      const base = (currentDoc.fileName || "document").replace(/\.pdf$/i, "");
+      const outputPath = await pickExchangeSaveFile("xfdf", `${base}.xfdf`);
+      if (!outputPath) return;
+      await exportAnnotationsXfdf(currentDoc.filePath, annotations, signatures, outputPath);
+      showToast("success", "Annotations exported");
+    } catch (err) {
+      console.error("Failed to export annotations:", err);
+      showToast("error", errorMessage(err, "Failed to export annotations"));
+    }
+  }, [currentDoc.filePath, currentDoc.fileName, annotations, signatures, showToast]);
+
+  const handleAddRedaction = useCallback((area: Omit<RedactionArea, "id">) => {
+    const id = `red_${Date.now()}_${Math.random().toString(36).substring(2, 9)}`;
+    setRedactionAreas((prev) => [...prev, { ...area, id }]);
+  }, []);
+
+  const handleRemoveRedaction = useCallback((id: string) => {
+    setRedactionAreas((prev) => prev.filter((a) => a.id !== id));
+  }, []);
+
+  const handleApplyRedactions = useCallback(async () => {
+    if (!currentDoc.filePath) {
+      showToast("info", "Save the PDF to disk before redacting");
+      return;
+    }
+    try {
+      const base = (currentDoc.fileName || "document").replace(/\.pdf$/i, "");
+      const selected: string | null = await invoke("save_file_dialog", {
+        defaultName: `${base}_redacted.pdf`,
+      });
+      if (!selected) return;
+      const outputPath = selected.endsWith(".pdf") ? selected : selected + ".pdf";
+      const summary = await redactPdf(currentDoc.filePath, outputPath, redactionAreas);
+      const removed = summary.glyphsRemoved + summary.imagesRedacted + summary.graphicsRemoved + summary.annotationsRemoved;
+      showToast(
+        "success",
+        removed === 0
+          ? "Redacted PDF saved (nothing was found under the marked areas)"
+          : `Redacted PDF saved: ${summary.glyphsRemoved} characters, ${summary.imagesRedacted + summary.graphicsRemoved} images and ${summary.annotationsRemoved} annotations removed`
+      );
+      setRedactionAreas([]);
+      setMode("view");
+      openFilePath(outputPath);
+    } catch (err) {
+      console.error("Failed to redact PDF:", err);
+      showToast("error", errorMessage(err, `Redaction failed: ${errorDetail(err)}`));
+    }
+  }, [currentDoc.filePath, currentDoc.fileName, redactionAreas, setMode, openFilePath, showToast]);
+
   const handleAddSignature = useCallback(
     async (name: string, fontFamily: string, color: string) => {
       await addSignature(name, fontFamily, color);
@@ -188,20 +456,25 @@ function AppContent() {
 
   const handleCloseFile = useCallback(() => {
     closeFile();
+    markClean();
     setPageDimensions([]);
     setMode("view");
     setSelectedId(null);
-  }, [closeFile, setMode, setSelectedId]);
-
-  const handleSaveFile = useCallback(async () => {
-    if (currentDoc.fileType === "pdf" && currentDoc.fileBytes && currentDoc.filePath) {
-      await saveAllAnnotations(currentDoc.filePath);
+  }, [closeFile, markClean, setMode, setSelectedId]);
+
+  // `overwrite` replaces the file even if another program changed it since
+  // it was opened; otherwise the save is refused
+  const saveFile = useCallback(async (overwrite: boolean) => {
+    if (currentDoc.fileType === "pdf" && currentDoc.filePath) {
+      await saveAllAnnotations(documentIdentity(currentDoc.filePath, currentDoc.fileInfo));
+      markClean();
       showToast("success", "Annotations saved");
     } else if (currentDoc.fileType === "word" && wordEditor) {
       try {
         const html = wordEditor.getHTML();
         const docxBytes = await htmlToDocx(html);
-        let savePath = currentDoc.filePath;
+        // A document someone else has open can only be saved as a copy
+        let savePath = currentDoc.lockedBy ? null : currentDoc.filePath;
         if (!savePath) {
           const selected: string | null = await invoke("save_file_dialog", {
             defaultName: currentDoc.fileName || "Untitled.docx",
@@ -209,7 +482,7 @@ function AppContent() {
           if (!selected) return;
           savePath = selected;
         }
-        await invoke("write_file_bytes", { path: savePath, data: Array.from(docxBytes) });
+        await writeFile(savePath, docxBytes, overwrite);
         // Update document state so the header title reflects the saved name
         // and subsequent Ctrl+S saves silently to the same path.
         if (savePath !== currentDoc.filePath) {
@@ -217,12 +490,17 @@ function AppContent() {
           updateDocumentPath(savePath, savedFileName);
         }
         showToast("success", "Word document saved");
+        dismissExternalChange();
+        markClean();
       } catch (err) {
         console.error("Failed to save Word document:", err);
-        showToast("error", "Failed to save Word document");
+        showToast("error", errorMessage(err, "Failed to save Word document"));
       }
     }
-  }, [currentDoc.fileType, currentDoc.fileBytes, currentDoc.filePath, currentDoc.fileName, wordEditor, saveAllAnnotations, updateDocumentPath, showToast]);
+  }, [currentDoc.fileType, currentDoc.filePath, currentDoc.fileName, currentDoc.fileInfo, currentDoc.lockedBy, wordEditor, saveAllAnnotations, updateDocumentPath, dismissExternalChange, markClean, showToast]);
+
+  const handleSaveFile = useCallback(() => saveFile(false), [saveFile]);
+  const handleOverwriteFile = useCallback(() => saveFile(true), [saveFile]);
 
   const handleNewWordDocument = useCallback(async () => {
     try {
@@ -234,13 +512,39 @@ function AppContent() {
     }
   }, [openNew, showToast]);
 
+  // The recovered document replaces this window's, so one session is
+  // recovered at a time; the rest are offered again at the next launch
+  const handleRecoverSession = useCallback(async (session: RecoverableSession) => {
+    const snapshot = await openRecoverableSession(session.id);
+    recoveringRef.current = { id: session.id, snapshot };
+    setRecoverableSessions([]);
+    if (snapshot.filePath) {
+      await openFilePath(snapshot.filePath);
+    } else {
+      openNew(await createBlankWordDocument(), snapshot.fileName, "word");
+    }
+  }, [openFilePath, openNew]);
+
+  const handleDiscardSession = useCallback(async (session: RecoverableSession) => {
+    await discardRecoverableSession(session.id);
+    setRecoverableSessions((prev) => prev.filter((s) => s.id !== session.id));
+  }, []);
+
+  // A version opens as an unsaved copy so it can be read or saved elsewhere
+  // without touching the file it came from
+  const handleOpenVersion = useCallback((bytes: Uint8Array, version: DocumentVersion) => {
+    const dot = currentDoc.fileName.lastIndexOf(".");
+    const stem = dot > 0 ? currentDoc.fileName.slice(0, dot) : currentDoc.fileName;
+    const ext = dot > 0 ? currentDoc.fileName.slice(dot) : "";
+    openNew(bytes, `${stem} (version ${version.id})${ext}`, currentDoc.fileType);
+  }, [currentDoc.fileName, currentDoc.fileType, openNew]);
+
   const handleInsertImage = useCallback(async () => {
     if (!wordEditor) return;
     try {
       const selected: string | null = await invoke("open_file_dialog");
       if (!selected) return;
-      const bytes: number[] = await invoke("read_file_bytes", { path: selected });
-      const uint8 = new Uint8Array(bytes);
+      const uint8 = await readFileRaw(selected);
       const ext = selected.split(".").pop()?.toLowerCase() || "png";
       const mimeMap: Record<string, string> = { png: "image/png", jpg: "image/jpeg", jpeg: "image/jpeg", gif: "image/gif", bmp: "image/bmp", webp: "image/webp" };
       const mime = mimeMap[ext] || "image/png";
@@ -261,6 +565,11 @@ function AppContent() {
   // Global keyboard shortcuts
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
+      if ((e.ctrlKey || e.metaKey) && e.shiftKey && !e.altKey && e.key.toLowerCase() === "o") {
+        e.preventDefault();
+        openFileInNewWindow();
+        return;
+      }
       if ((e.ctrlKey || e.metaKey) && !e.shiftKey && !e.altKey) {
         switch (e.key.toLowerCase()) {
           case "o":
@@ -276,7 +585,7 @@ function AppContent() {
     };
     window.addEventListener("keydown", handleKeyDown);
     return () => window.removeEventListener("keydown", handleKeyDown);
-  }, [openFile, handleSaveFile]);
+  }, [openFile, openFileInNewWindow, handleSaveFile]);
 
   // Loading state
   if (onboardingComplete === null) {
@@ -297,7 +606,7 @@ function AppContent() {
     );
   }
 
-  const hasDocument = currentDoc.fileBytes !== null;
+  const hasDocument = currentDoc.fileBytes !== null || currentDoc.rangedFile !== null;
 
   const renderDocumentViewer = () => {
     switch (currentDoc.fileType) {
@@ -314,8 +623,17 @@ function AppContent() {
               onModeChange={setMode}
               onFlattenClick={() => setShowFlattenDialog(true)}
               onCreateSignature={() => setShowSignaturePad(true)}
+              onFillFormClick={() => setShowFormFillDialog(true)}
+              onOrganizeClick={() => setShowPageOrganizer(true)}
+              onImportAnnotations={handleImportAnnotations}
+              onExportAnnotations={handleExportAnnotations}
+              onSecurityClick={() => setShowProtectDialog(true)}
+              onOptimizeClick={() => setShowOptimizeDialog(true)}
+              onApplyRedactions={handleApplyRedactions}
               annotationCount={annotations.length}
+              formFieldCount={formFields.fields.length}
               signatureCount={signatures.length}
+              redactionCount={redactionAreas.length}
             />
             <PdfViewer
               pageCount={pageCount}
@@ -333,6 +651,9 @@ function AppContent() {
               onDeleteAnnotation={removeAnnotation}
               onPageClick={handlePageClick}
               onNewAnnotationHandled={clearNewAnnotationId}
+              redactionAreas={redactionAreas}
+              onAddRedaction={handleAddRedaction}
+              onRemoveRedaction={handleRemoveRedaction}
             />
           </>
         );
@@ -369,15 +690,24 @@ function AppContent() {
             fileName={currentDoc.fileName}
           />
         );
-      default:
+      default: {
+        // Say what the file really is when its contents were recognised
+        const info = currentDoc.fileInfo;
+        let description = "This file type is not yet supported.";
+        if (info?.encrypted) {
+          description = "This file is encrypted and cannot be opened.";
+        } else if (info && info.format !== "unknown") {
+          description = `This is a ${info.format.toUpperCase()} file, which is not yet supported.`;
+        }
         return (
           <PlaceholderViewer
             icon="📄"
             title="Unsupported Format"
-            description="This file type is not yet supported."
+            description={description}
             fileName={currentDoc.fileName}
           />
         );
+      }
     }
   };
 
@@ -385,10 +715,14 @@ function AppContent() {
     <div className="h-full flex flex-col">
       <Header
         fileName={currentDoc.fileName}
+        lockedBy={currentDoc.lockedBy}
         onOpenFile={openFile}
+        onOpenInNewWindow={openFileInNewWindow}
         onNewWordDocument={handleNewWordDocument}
         onSaveFile={handleSaveFile}
         onCloseFile={handleCloseFile}
+        onRestoreBackup={() => setShowBackups(true)}
+        onShowVersionHistory={() => setShowVersionHistory(true)}
         hasDocument={hasDocument}
         onCheckForUpdates={updater.checkForUpdate}
       />
@@ -404,6 +738,14 @@ function AppContent() {
         onRetry={updater.checkForUpdate}
       />
 
+      <FileChangedBanner
+        change={externalChange}
+        fileName={currentDoc.fileName}
+        onReload={reloadFile}
+        onOverwrite={currentDoc.fileType === "word" ? handleOverwriteFile : undefined}
+        onDismiss={dismissExternalChange}
+      />
+
       <div className="flex-1 flex overflow-hidden">
         <Sidebar
           recentDocuments={recentDocuments}
@@ -411,6 +753,9 @@ function AppContent() {
           onClearRecents={clearRecents}
           collapsed={sidebarCollapsed}
           onToggleCollapse={() => setSidebarCollapsed(!sidebarCollapsed)}
+          signatureReports={signatureValidation.reports}
+          onImportCertificate={signatureValidation.importCertificate}
+          onOpenSearchResult={handleOpenSearchResult}
         />
 
         <div className="flex-1 flex flex-col overflow-hidden">
@@ -430,11 +775,75 @@ function AppContent() {
         onClose={() => setShowFlattenDialog(false)}
         pdfBytes={currentDoc.fileBytes}
         annotations={annotations}
+        removedAnnotationIds={removedImportedIds}
         signatures={signatures}
         currentFilePath={currentDoc.filePath}
         onSaveComplete={handleSaveComplete}
       />
 
+      <FormFillDialog
+        isOpen={showFormFillDialog}
+        onClose={() => setShowFormFillDialog(false)}
+        fields={formFields.fields}
+        currentFilePath={currentDoc.filePath}
+        onSaveComplete={handleSaveComplete}
+      />
+
+      <ProtectDialog
+        isOpen={showProtectDialog}
+        onClose={() => setShowProtectDialog(false)}
+        currentFilePath={currentDoc.filePath}
+        onSaveComplete={handleSaveComplete}
+      />
+
+      <OptimizeDialog
+        isOpen={showOptimizeDialog}
+        onClose={() => setShowOptimizeDialog(false)}
+        currentFilePath={currentDoc.filePath}
+        onSaveComplete={handleSaveComplete}
+      />
+
+      <PasswordDialog
+        isOpen={retryWithPassword !== null}
+        filePath={currentDoc.filePath}
+        onUnlock={(password) => retryWithPassword?.(password)}
+        onCancel={() => {
+          setRetryWithPassword(null);
+          closeFile();
+        }}
+      />
+
+      <PageOrganizerDialog
+        isOpen={showPageOrganizer}
+        onClose={() => setShowPageOrganizer(false)}
+        currentFilePath={currentDoc.filePath}
+        pageCount={pageCount}
+        onComplete={handlePagesOrganized}
+      />
+
+      <BackupsDialog
+        isOpen={showBackups}
+        onClose={() => setShowBackups(false)}
+        filePath={currentDoc.filePath}
+        onRestored={openFilePath}
+      />
+
+      <RecoveryDialog
+        isOpen={recoverableSessions.length > 0}
+        onClose={() => setRecoverableSessions([])}
+        sessions={recoverableSessions}
+        onRecover={handleRecoverSession}
+        onDiscard={handleDiscardSession}
+      />
+
+      <VersionHistoryDialog
+        isOpen={showVersionHistory}
+        onClose={() => setShowVersionHistory(false)}
+        filePath={currentDoc.filePath}
+        onOpenVersion={handleOpenVersion}
+        onRestored={openFilePath}
+      />
+
       <SignaturePad
         isOpen={showSignaturePad}
         onClose={() => setShowSignaturePad(false)}
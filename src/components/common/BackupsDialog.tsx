@@ -0,0 +1,96 @@
+import { useEffect, useState } from "react";
+import Modal from "./Modal";
+import type { BackupInfo } from "../../types/document";
+import { listBackups, restoreBackup } from "../../services/file.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+interface BackupsDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  filePath: string | null;
+  /** Called after a backup was put back, to reload the file. */
+  onRestored: (filePath: string) => void;
+}
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024 * 1024) return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+/**
+ * The backups kept of the current file, newest first, each of which can be
+ * restored over it.
+ */
+export default function BackupsDialog({ isOpen, onClose, filePath, onRestored }: BackupsDialogProps) {
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [isLoading, setIsLoading] = useState(false);
+  const [restoringId, setRestoringId] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!isOpen || !filePath) return;
+    setError(null);
+    setIsLoading(true);
+    listBackups(filePath)
+      .then(setBackups)
+      .catch((err) => setError(String(err)))
+      .finally(() => setIsLoading(false));
+  }, [isOpen, filePath]);
+
+  const handleRestore = async (backup: BackupInfo) => {
+    if (!filePath) return;
+    setRestoringId(backup.id);
+    setError(null);
+    try {
+      await restoreBackup(filePath, backup.id);
+      onRestored(filePath);
+      onClose();
+    } catch (err) {
+      setError(errorMessage(err, errorDetail(err)));
+    } finally {
+      setRestoringId(null);
+    }
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Restore Backup">
+      <p className="text-sm text-slate-500 mb-3">
+        A copy is kept each time the file is saved over. Restoring one replaces the current file,
+        which is backed up first.
+      </p>
+
+      {error && (
+        <div className="mb-3 px-3 py-2 text-sm text-red-700 bg-red-50 border border-red-200 rounded-md">
+          {error}
+        </div>
+      )}
+
+      <div className="max-h-72 overflow-y-auto -mx-2">
+        {isLoading ? (
+          <p className="px-2 py-4 text-sm text-slate-400 text-center">Loading...</p>
+        ) : backups.length === 0 ? (
+          <p className="px-2 py-4 text-sm text-slate-400 text-center">No backups of this file yet</p>
+        ) : (
+          backups.map((backup) => (
+            <div
+              key={backup.id}
+              className="flex items-center justify-between px-2 py-2 rounded-lg hover:bg-slate-50"
+            >
+              <div>
+                <p className="text-sm text-slate-700">{new Date(backup.createdAt).toLocaleString()}</p>
+                <p className="text-xs text-slate-400">{formatSize(backup.size)}</p>
+              </div>
+              <button
+                onClick={() => handleRestore(backup)}
+                disabled={restoringId !== null}
+                className="px-3 py-1 text-sm text-blue-600 border border-blue-200 rounded-md hover:bg-blue-50 disabled:opacity-50 transition-colors"
+              >
+                {restoringId === backup.id ? "Restoring..." : "Restore"}
+              </button>
+            </div>
+          ))
+        )}
+      </div>
+    </Modal>
+  );
+}
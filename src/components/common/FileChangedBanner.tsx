@@ -0,0 +1,55 @@
+import type { ExternalChange } from "../../types/document";
+
+interface FileChangedBannerProps {
+  change: ExternalChange | null;
+  fileName: string | null;
+  onReload: () => void;
+  /** Save the open document over the other program's changes. Omitted
+   *  when there is nothing to save. */
+  onOverwrite?: () => void;
+  onDismiss: () => void;
+}
+
+export default function FileChangedBanner({
+  change,
+  fileName,
+  onReload,
+  onOverwrite,
+  onDismiss,
+}: FileChangedBannerProps) {
+  if (!change) return null;
+
+  const name = fileName ?? "This file";
+
+  return (
+    <div className="flex items-center gap-3 px-4 py-2 bg-amber-50 border-b border-amber-200 text-amber-800 text-sm select-none">
+      {change.kind === "changed" ? (
+        <>
+          <span className="flex-1">{name} was changed by another program.</span>
+          <button
+            onClick={onReload}
+            className="px-3 py-1 bg-amber-600 text-white text-xs font-medium rounded hover:bg-amber-700 transition-colors"
+          >
+            Reload
+          </button>
+          {onOverwrite && (
+            <button
+              onClick={onOverwrite}
+              className="px-3 py-1 text-xs text-amber-700 border border-amber-300 rounded hover:bg-amber-100 transition-colors"
+            >
+              Keep My Version
+            </button>
+          )}
+        </>
+      ) : (
+        <span className="flex-1">{name} was deleted or moved by another program.</span>
+      )}
+      <button
+        onClick={onDismiss}
+        className="px-3 py-1 text-xs text-amber-700 hover:text-amber-900 transition-colors"
+      >
+        Dismiss
+      </button>
+    </div>
+  );
+}
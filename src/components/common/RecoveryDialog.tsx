@@ -0,0 +1,86 @@
+import { useState } from "react";
+import Modal from "./Modal";
+import { APP_NAME } from "../../constants";
+import type { RecoverableSession } from "../../types/document";
+
+interface RecoveryDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  sessions: RecoverableSession[];
+  /** Reopen the session's document with its unsaved work. */
+  onRecover: (session: RecoverableSession) => Promise<void>;
+  onDiscard: (session: RecoverableSession) => Promise<void>;
+}
+
+/**
+ * Unsaved work from a session that crashed, offered at launch. Sessions
+ * left alone are offered again next time.
+ */
+export default function RecoveryDialog({
+  isOpen,
+  onClose,
+  sessions,
+  onRecover,
+  onDiscard,
+}: RecoveryDialogProps) {
+  const [busyId, setBusyId] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = async (session: RecoverableSession, action: typeof onRecover) => {
+    setBusyId(session.id);
+    setError(null);
+    try {
+      await action(session);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setBusyId(null);
+    }
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Recover Unsaved Work">
+      <p className="text-sm text-slate-500 mb-3">
+        {APP_NAME} closed unexpectedly. These documents had changes that were not saved.
+      </p>
+
+      {error && (
+        <div className="mb-3 px-3 py-2 text-sm text-red-700 bg-red-50 border border-red-200 rounded-md">
+          {error}
+        </div>
+      )}
+
+      <div className="max-h-72 overflow-y-auto -mx-2">
+        {sessions.map((session) => (
+          <div
+            key={session.id}
+            className="flex items-center justify-between px-2 py-2 rounded-lg hover:bg-slate-50"
+          >
+            <div className="min-w-0">
+              <p className="text-sm text-slate-700 truncate">{session.fileName}</p>
+              <p className="text-xs text-slate-400">
+                {new Date(session.savedAt).toLocaleString()}
+              </p>
+            </div>
+            <div className="flex gap-2 shrink-0">
+              <button
+                onClick={() => run(session, onDiscard)}
+                disabled={busyId !== null}
+                className="px-3 py-1 text-sm text-slate-600 border border-slate-200 rounded-md hover:bg-slate-100 disabled:opacity-50 transition-colors"
+              >
+                Discard
+              </button>
+              <button
+                onClick={() => run(session, onRecover)}
+                disabled={busyId !== null}
+                className="px-3 py-1 text-sm text-blue-600 border border-blue-200 rounded-md hover:bg-blue-50 disabled:opacity-50 transition-colors"
+              >
+                {busyId === session.id ? "Working..." : "Recover"}
+              </button>
+            </div>
+          </div>
+        ))}
+      </div>
+    </Modal>
+  );
+}
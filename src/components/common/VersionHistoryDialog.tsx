@@ -0,0 +1,125 @@
+import { useEffect, useState } from "react";
+import Modal from "./Modal";
+import type { DocumentVersion } from "../../types/document";
+import { listVersions, readVersion, restoreVersion } from "../../services/file.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+interface VersionHistoryDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  filePath: string | null;
+  /** Open a version's contents as a new, unsaved document. */
+  onOpenVersion: (bytes: Uint8Array, version: DocumentVersion) => void;
+  /** Called after a version was put back, to reload the file. */
+  onRestored: (filePath: string) => void;
+}
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024 * 1024) return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+/** SQLite's datetime('now') is UTC without a zone marker. */
+function formatSavedAt(savedAt: string): string {
+  return new Date(savedAt.replace(" ", "T") + "Z").toLocaleString();
+}
+
+/**
+ * Every earlier state of the current file, newest first. A version can be
+ * opened as a copy to look at, or restored over the file.
+ */
+export default function VersionHistoryDialog({
+  isOpen,
+  onClose,
+  filePath,
+  onOpenVersion,
+  onRestored,
+}: VersionHistoryDialogProps) {
+  const [versions, setVersions] = useState<DocumentVersion[]>([]);
+  const [isLoading, setIsLoading] = useState(false);
+  const [busyId, setBusyId] = useState<number | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!isOpen || !filePath) return;
+    setError(null);
+    setIsLoading(true);
+    listVersions(filePath)
+      .then(setVersions)
+      .catch((err) => setError(String(err)))
+      .finally(() => setIsLoading(false));
+  }, [isOpen, filePath]);
+
+  const run = async (version: DocumentVersion, action: () => Promise<void>) => {
+    setBusyId(version.id);
+    setError(null);
+    try {
+      await action();
+      onClose();
+    } catch (err) {
+      setError(errorMessage(err, errorDetail(err)));
+    } finally {
+      setBusyId(null);
+    }
+  };
+
+  const handleOpen = (version: DocumentVersion) =>
+    run(version, async () => onOpenVersion(await readVersion(version.id), version));
+
+  const handleRestore = (version: DocumentVersion) =>
+    run(version, async () => {
+      await restoreVersion(version.id);
+      onRestored(version.filePath);
+    });
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Version History">
+      <p className="text-sm text-slate-500 mb-3">
+        The file as it was before each save. Restoring a version keeps the current file in the
+        history too.
+      </p>
+
+      {error && (
+        <div className="mb-3 px-3 py-2 text-sm text-red-700 bg-red-50 border border-red-200 rounded-md">
+          {error}
+        </div>
+      )}
+
+      <div className="max-h-72 overflow-y-auto -mx-2">
+        {isLoading ? (
+          <p className="px-2 py-4 text-sm text-slate-400 text-center">Loading...</p>
+        ) : versions.length === 0 ? (
+          <p className="px-2 py-4 text-sm text-slate-400 text-center">No earlier versions yet</p>
+        ) : (
+          versions.map((version) => (
+            <div
+              key={version.id}
+              className="flex items-center justify-between px-2 py-2 rounded-lg hover:bg-slate-50"
+            >
+              <div>
+                <p className="text-sm text-slate-700">{formatSavedAt(version.savedAt)}</p>
+                <p className="text-xs text-slate-400">{formatSize(version.fileSize)}</p>
+              </div>
+              <div className="flex gap-2">
+                <button
+                  onClick={() => handleOpen(version)}
+                  disabled={busyId !== null}
+                  className="px-3 py-1 text-sm text-slate-600 border border-slate-200 rounded-md hover:bg-slate-100 disabled:opacity-50 transition-colors"
+                >
+                  Open
+                </button>
+                <button
+                  onClick={() => handleRestore(version)}
+                  disabled={busyId !== null}
+                  className="px-3 py-1 text-sm text-blue-600 border border-blue-200 rounded-md hover:bg-blue-50 disabled:opacity-50 transition-colors"
+                >
+                  {busyId === version.id ? "Working..." : "Restore"}
+                </button>
+              </div>
+            </div>
+          ))
+        )}
+      </div>
+    </Modal>
+  );
+}
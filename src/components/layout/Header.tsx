@@ -1,18 +1,24 @@
 import { useState, useRef, useEffect } from "react";
 import { getVersion } from "@tauri-apps/api/app";
 import { APP_NAME } from "../../constants";
+import type { LockOwner } from "../../types/document";
 
 interface HeaderProps {
   fileName: string | null;
+  /** Set when the document is open read-only because someone else has it. */
+  lockedBy?: LockOwner | null;
   onOpenFile: () => void;
+  onOpenInNewWindow: () => void;
   onNewWordDocument: () => void;
   onSaveFile: () => void;
   onCloseFile: () => void;
+  onRestoreBackup: () => void;
+  onShowVersionHistory: () => void;
   hasDocument: boolean;
   onCheckForUpdates?: () => void;
 }
 
-export default function Header({ fileName, onOpenFile, onNewWordDocument, onSaveFile, onCloseFile, hasDocument, onCheckForUpdates }: HeaderProps) {
+export default function Header({ fileName, lockedBy, onOpenFile, onOpenInNewWindow, onNewWordDocument, onSaveFile, onCloseFile, onRestoreBackup, onShowVersionHistory, hasDocument, onCheckForUpdates }: HeaderProps) {
   const [fileMenuOpen, setFileMenuOpen] = useState(false);
   const [helpMenuOpen, setHelpMenuOpen] = useState(false);
   const [aboutOpen, setAboutOpen] = useState(false);
@@ -73,12 +79,27 @@ export default function Header({ fileName, onOpenFile, onNewWordDocument, onSave
                   shortcut="Ctrl+O"
                   onClick={() => { onOpenFile(); setFileMenuOpen(false); }}
                 />
+                <MenuItem
+                  label="Open in New Window..."
+                  shortcut="Ctrl+Shift+O"
+                  onClick={() => { onOpenInNewWindow(); setFileMenuOpen(false); }}
+                />
                 <MenuItem
                   label="Save"
                   shortcut="Ctrl+S"
                   onClick={() => { onSaveFile(); setFileMenuOpen(false); }}
                   disabled={!hasDocument}
                 />
+                <MenuItem
+                  label="Version History..."
+                  onClick={() => { onShowVersionHistory(); setFileMenuOpen(false); }}
+                  disabled={!hasDocument}
+                />
+                <MenuItem
+                  label="Restore Backup..."
+                  onClick={() => { onRestoreBackup(); setFileMenuOpen(false); }}
+                  disabled={!hasDocument}
+                />
                 <div className="my-1 border-t border-slate-100" />
                 <MenuItem
                   label="Close"
@@ -114,12 +135,20 @@ export default function Header({ fileName, onOpenFile, onNewWordDocument, onSave
         </div>
 
         {/* Center: File Name */}
-        <div className="flex-1 flex justify-center">
+        <div className="flex-1 flex items-center justify-center">
           {fileName && (
             <span className="text-sm text-slate-500 truncate max-w-[300px]">
               {fileName}
             </span>
           )}
+          {fileName && lockedBy && (
+            <span
+              className="ml-2 px-2 py-0.5 text-xs text-amber-700 bg-amber-50 border border-amber-200 rounded"
+              title={`Opened by ${lockedBy.user}${lockedBy.host ? ` on ${lockedBy.host}` : ""}. Save a copy to keep your changes.`}
+            >
+              Read-Only
+            </span>
+          )}
         </div>
 
         {/* Right: Window controls placeholder */}
@@ -0,0 +1,94 @@
+import { useEffect, useState } from "react";
+import type { LibraryHit } from "../../types/document";
+import { searchLibrary } from "../../services/library.service";
+
+/** Wait this long after the last keystroke before searching. */
+const SEARCH_DEBOUNCE_MS = 250;
+
+interface LibrarySearchProps {
+  onOpenResult: (filePath: string, pageNumber: number | null) => void;
+}
+
+/**
+ * Search box for the text of every recent document. Results come from the
+ * Rust-side full-text index, best match first.
+ */
+export default function LibrarySearch({ onOpenResult }: LibrarySearchProps) {
+  const [query, setQuery] = useState("");
+  const [results, setResults] = useState<LibraryHit[]>([]);
+  const [isSearching, setIsSearching] = useState(false);
+
+  useEffect(() => {
+    if (!query.trim()) {
+      setResults([]);
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(async () => {
+      setIsSearching(true);
+      try {
+        const hits = await searchLibrary(query);
+        if (!cancelled) setResults(hits);
+      } catch (err) {
+        console.error("Library search failed:", err);
+        if (!cancelled) setResults([]);
+      } finally {
+        if (!cancelled) setIsSearching(false);
+      }
+    }, SEARCH_DEBOUNCE_MS);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [query]);
+
+  return (
+    <div className="px-3 pt-2 pb-1 border-b border-slate-200">
+      <input
+        type="search"
+        value={query}
+        onChange={(e) => setQuery(e.target.value)}
+        onKeyDown={(e) => e.key === "Escape" && setQuery("")}
+        placeholder="Search in documents..."
+        className="w-full px-2 py-1 text-sm border border-slate-200 rounded-md bg-white focus:outline-none focus:border-blue-400"
+      />
+
+      {query.trim() && (
+        <div className="max-h-72 overflow-y-auto mt-1 -mx-1.5">
+          {results.length === 0 ? (
+            <p className="px-2 py-2 text-xs text-slate-400 text-center">
+              {isSearching ? "Searching..." : "No matches"}
+            </p>
+          ) : (
+            results.map((hit, index) => (
+              <button
+                key={`${hit.filePath}-${hit.pageNumber}-${index}`}
+                onClick={() => onOpenResult(hit.filePath, hit.pageNumber)}
+                className="w-full px-2 py-1.5 rounded-lg hover:bg-slate-100 transition-colors text-left"
+                title={hit.filePath}
+              >
+                <p className="text-xs font-medium text-slate-700 truncate">
+                  {hit.fileName}
+                  {hit.pageNumber !== null && (
+                    <span className="font-normal text-slate-400"> &middot; p. {hit.pageNumber}</span>
+                  )}
+                </p>
+                <p className="text-xs text-slate-500 mt-0.5 line-clamp-2">
+                  {hit.snippet.map((part, i) =>
+                    part.matched ? (
+                      <mark key={i} className="bg-yellow-200 text-slate-800 rounded-sm">
+                        {part.text}
+                      </mark>
+                    ) : (
+                      <span key={i}>{part.text}</span>
+                    )
+                  )}
+                </p>
+              </button>
+            ))
+          )}
+        </div>
+      )}
+    </div>
+  );
+}
@@ -1,4 +1,7 @@
 import type { RecentDocument } from "../../types/document";
+import type { SignatureReport } from "../../types/pdf";
+import SignaturePanel from "../pdf/SignaturePanel";
+import LibrarySearch from "./LibrarySearch";
 
 interface SidebarProps {
   recentDocuments: RecentDocument[];
@@ -6,6 +9,9 @@ interface SidebarProps {
   onClearRecents: () => void;
   collapsed: boolean;
   onToggleCollapse: () => void;
+  signatureReports?: SignatureReport[];
+  onImportCertificate?: () => void;
+  onOpenSearchResult: (filePath: string, pageNumber: number | null) => void;
 }
 
 export default function Sidebar({
@@ -14,6 +20,9 @@ export default function Sidebar({
   onClearRecents,
   collapsed,
   onToggleCollapse,
+  signatureReports = [],
+  onImportCertificate = () => {},
+  onOpenSearchResult,
 }: SidebarProps) {
   return (
     <aside
@@ -44,6 +53,14 @@ export default function Sidebar({
 
       {!collapsed && (
         <>
+          {/* Digital signatures in the open PDF */}
+          {signatureReports.length > 0 && (
+            <SignaturePanel reports={signatureReports} onImportCertificate={onImportCertificate} />
+          )}
+
+          {/* Full-text search across recent documents */}
+          <LibrarySearch onOpenResult={onOpenSearchResult} />
+
           {/* Header */}
           <div className="flex items-center justify-between px-3 py-2">
             <span className="text-xs font-semibold uppercase tracking-wider text-slate-400">
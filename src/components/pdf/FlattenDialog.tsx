@@ -1,27 +1,47 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import Modal from "../common/Modal";
-import type { Annotation } from "../../types/pdf";
+import type { Annotation, SignatureAnnotation } from "../../types/pdf";
+import { isSignatureAnnotation } from "../../types/pdf";
 import type { Signature } from "../../types/signature";
-import { flattenPdf } from "../../services/pdf.service";
+import { readFileRaw, writeFileRaw } from "../../services/file.service";
+import { annotatePdf, flattenPdf, pickDigitalId, signPdf } from "../../services/pdf.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
 
 /**
- * Write binary data to a file using raw IPC, bypassing JSON serialization.
- * This passes the Uint8Array as a raw request body with the file path in a
- * header, avoiding the ~4x memory overhead of Array.from() + JSON encoding.
- * Prevents OOM crashes for large PDFs (10+ MB).
+ * How overlays are saved: drawn into the page content, as PDF annotations
+ * other viewers can still edit, or not at all.
  */
-async function writeFileRaw(path: string, data: Uint8Array): Promise<void> {
-  await invoke("write_file_bytes_raw", data, {
-    headers: { "X-File-Path": path },
-  });
-}
+type AnnotationOutput = "flatten" | "annotations" | "none";
+
+const OUTPUT_OPTIONS: { value: AnnotationOutput; title: string; description: string }[] = [
+  {
+    value: "flatten",
+    title: "Flatten annotations into PDF",
+    description:
+      "Embeds text and signatures directly into the PDF. The file will look the same in Adobe, Edge, Chrome, and all standard viewers.",
+  },
+  {
+    value: "annotations",
+    title: "Keep as editable annotations",
+    description:
+      "Saves text as comments and signatures as stamps that can still be moved, edited or deleted here and in other PDF viewers.",
+  },
+  {
+    value: "none",
+    title: "Leave annotations out",
+    description: "Saves a copy of the PDF as it is on disk, without the text and signatures added here.",
+  },
+];
 
 interface FlattenDialogProps {
   isOpen: boolean;
   onClose: () => void;
+  /** Null for large PDFs loaded a range at a time; read from disk on save. */
   pdfBytes: Uint8Array | null;
   annotations: Annotation[];
+  /** Overlays read from the PDF's own annotations that have been deleted. */
+  removedAnnotationIds: string[];
   signatures: Signature[];
   currentFilePath: string | null;
   onSaveComplete: () => void;
@@ -32,26 +52,46 @@ export default function FlattenDialog({
   onClose,
   pdfBytes,
   annotations,
+  removedAnnotationIds,
   signatures,
   currentFilePath,
   onSaveComplete,
 }: FlattenDialogProps) {
   const [isSaving, setIsSaving] = useState(false);
   const [progress, setProgress] = useState("");
-  const [flatten, setFlatten] = useState(true);
+  const [output, setOutput] = useState<AnnotationOutput>("flatten");
+  const [preserveSignatures, setPreserveSignatures] = useState(true);
+  const [signDocument, setSignDocument] = useState(false);
+  const [identityPath, setIdentityPath] = useState<string | null>(null);
+  const [identityPassword, setIdentityPassword] = useState("");
+  const [signReason, setSignReason] = useState("");
+
+  // When signing, the first placed signature becomes the visible digital
+  // signature instead of being flattened as plain text.
+  const signatureAnnotation = signDocument
+    ? (annotations.find(isSignatureAnnotation) as SignatureAnnotation | undefined)
+    : undefined;
+  const flattenAnnotations = signatureAnnotation
+    ? annotations.filter((a) => a !== signatureAnnotation)
+    : annotations;
+
+  const handleChooseIdentity = async () => {
+    const path = await pickDigitalId();
+    if (path) setIdentityPath(path);
+  };
 
   const handleSave = async () => {
-    if (!pdfBytes) return;
+    if (!pdfBytes && !currentFilePath) return;
 
     setIsSaving(true);
     try {
-      let saveBytes: Uint8Array;
-
-      if (flatten && annotations.length > 0) {
-        setProgress("Flattening annotations...");
-        saveBytes = await flattenPdf(pdfBytes, annotations, signatures);
-      } else {
-        saveBytes = pdfBytes;
+      const shouldWrite =
+        output !== "none" && (flattenAnnotations.length > 0 || removedAnnotationIds.length > 0);
+      if (shouldWrite && !currentFilePath) {
+        throw new Error("Save the PDF to disk before saving annotations");
+      }
+      if (signDocument && !identityPath) {
+        throw new Error("Choose a digital ID to sign with");
       }
 
       setProgress("Choosing save location...");
@@ -67,11 +107,51 @@ export default function FlattenDialog({
 
       const targetPath = savePath.endsWith(".pdf") ? savePath : savePath + ".pdf";
 
-      setProgress("Saving file...");
-      // Use raw IPC to pass Uint8Array directly, avoiding JSON serialization
-      // of the entire byte array which causes ~4x memory overhead and OOM on
-      // large files. See write_file_bytes_raw in documents.rs.
-      await writeFileRaw(targetPath, saveBytes);
+      if (shouldWrite && currentFilePath) {
+        // Both read the source file and write the output natively, so the
+        // PDF bytes never pass through IPC.
+        const save = output === "annotations" ? annotatePdf : flattenPdf;
+        setProgress(output === "annotations" ? "Saving annotations..." : "Flattening annotations...");
+        await save(
+          currentFilePath,
+          flattenAnnotations,
+          signatures,
+          removedAnnotationIds,
+          targetPath,
+          preserveSignatures ? "incremental" : "rewrite"
+        );
+      } else {
+        setProgress("Saving file...");
+        // Use raw IPC to pass Uint8Array directly, avoiding JSON serialization
+        // of the entire byte array which causes ~4x memory overhead and OOM on
+        // large files. See write_file_bytes_raw in documents.rs.
+        await writeFileRaw(targetPath, pdfBytes ?? (await readFileRaw(currentFilePath!)));
+      }
+
+      if (signDocument && identityPath) {
+        setProgress("Signing document...");
+        const style = signatureAnnotation
+          ? signatures.find((s) => s.id === signatureAnnotation.signatureId)
+          : undefined;
+        await signPdf(targetPath, targetPath, {
+          identityPath,
+          password: identityPassword,
+          placement: signatureAnnotation && {
+            pageNumber: signatureAnnotation.pageNumber,
+            x: signatureAnnotation.x,
+            y: signatureAnnotation.y,
+            width: signatureAnnotation.width,
+            height: signatureAnnotation.height,
+          },
+          style: style && {
+            id: style.id,
+            name: style.name,
+            fontFamily: style.fontFamily,
+            color: style.color,
+          },
+          reason: signReason || undefined,
+        });
+      }
 
       setProgress("Done!");
       setTimeout(() => {
@@ -82,12 +162,13 @@ export default function FlattenDialog({
       }, 500);
     } catch (err) {
       console.error("Save failed:", err);
-      setProgress("Error: " + String(err));
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
       setIsSaving(false);
     }
   };
 
   const annotationCount = annotations.length;
+  const hasAnnotationChanges = annotationCount > 0 || removedAnnotationIds.length > 0;
 
   return (
     <Modal isOpen={isOpen} onClose={onClose} title="Save PDF">
@@ -112,27 +193,106 @@ export default function FlattenDialog({
           </div>
         </div>
 
-        {/* Flatten option */}
-        {annotationCount > 0 && (
+        {/* How annotations are saved */}
+        {hasAnnotationChanges && (
+          <div className="space-y-2">
+            {OUTPUT_OPTIONS.map((option) => (
+              <label
+                key={option.value}
+                className="flex items-start gap-3 p-3 rounded-lg border border-slate-200 hover:bg-slate-50 cursor-pointer transition-colors"
+              >
+                <input
+                  type="radio"
+                  name="annotation-output"
+                  checked={output === option.value}
+                  onChange={() => setOutput(option.value)}
+                  className="mt-0.5 w-4 h-4 text-blue-600 border-slate-300 focus:ring-blue-500"
+                />
+                <div>
+                  <p className="text-sm font-medium text-slate-700">{option.title}</p>
+                  <p className="text-xs text-slate-500 mt-0.5">{option.description}</p>
+                </div>
+              </label>
+            ))}
+          </div>
+        )}
+
+        {/* Incremental save option */}
+        {hasAnnotationChanges && output !== "none" && (
           <label className="flex items-start gap-3 p-3 rounded-lg border border-slate-200 hover:bg-slate-50 cursor-pointer transition-colors">
             <input
               type="checkbox"
-              checked={flatten}
-              onChange={(e) => setFlatten(e.target.checked)}
+              checked={preserveSignatures}
+              onChange={(e) => setPreserveSignatures(e.target.checked)}
               className="mt-0.5 w-4 h-4 text-blue-600 rounded border-slate-300 focus:ring-blue-500"
             />
             <div>
               <p className="text-sm font-medium text-slate-700">
-                Flatten annotations into PDF
+                Keep existing digital signatures valid
               </p>
               <p className="text-xs text-slate-500 mt-0.5">
-                Embeds text and signatures directly into the PDF. The file will
-                look the same in Adobe, Edge, Chrome, and all standard viewers.
+                Appends your changes to the original file instead of rewriting
+                it, so signatures already on the document are not broken.
               </p>
             </div>
           </label>
         )}
 
+        {/* Digital signature option */}
+        <div className="p-3 rounded-lg border border-slate-200">
+          <label className="flex items-start gap-3 cursor-pointer">
+            <input
+              type="checkbox"
+              checked={signDocument}
+              onChange={(e) => setSignDocument(e.target.checked)}
+              className="mt-0.5 w-4 h-4 text-blue-600 rounded border-slate-300 focus:ring-blue-500"
+            />
+            <div>
+              <p className="text-sm font-medium text-slate-700">
+                Digitally sign with a certificate
+              </p>
+              <p className="text-xs text-slate-500 mt-0.5">
+                Adds a cryptographic signature from your .p12/.pfx digital ID.
+                {signDocument &&
+                  (signatureAnnotation
+                    ? " Your placed signature becomes the visible signature."
+                    : " Place a signature on the page to make it visible.")}
+              </p>
+            </div>
+          </label>
+          {signDocument && (
+            <div className="mt-3 ml-7 space-y-2">
+              <div className="flex items-center gap-2">
+                <button
+                  type="button"
+                  onClick={handleChooseIdentity}
+                  disabled={isSaving}
+                  className="px-3 py-1.5 text-xs border border-slate-300 rounded-md hover:bg-slate-50 disabled:opacity-50"
+                >
+                  Choose digital ID...
+                </button>
+                <span className="text-xs text-slate-500 truncate">
+                  {identityPath?.split(/[\\/]/).pop() ?? "No file selected"}
+                </span>
+              </div>
+              <input
+                type="password"
+                value={identityPassword}
+                onChange={(e) => setIdentityPassword(e.target.value)}
+                placeholder="Digital ID password"
+                className="w-full px-3 py-1.5 text-sm border border-slate-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+              <input
+                type="text"
+                value={signReason}
+                onChange={(e) => setSignReason(e.target.value)}
+                placeholder="Reason (optional)"
+                className="w-full px-3 py-1.5 text-sm border border-slate-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </div>
+          )}
+        </div>
+
         {/* Progress */}
         {progress && (
           <div className="flex items-center gap-2 text-sm text-blue-600">
@@ -159,7 +319,7 @@ export default function FlattenDialog({
           </button>
           <button
             onClick={handleSave}
-            disabled={isSaving || !pdfBytes}
+            disabled={isSaving || (!pdfBytes && !currentFilePath)}
             className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
           >
             {isSaving ? "Saving..." : "Save As..."}
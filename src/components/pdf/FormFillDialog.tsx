@@ -0,0 +1,320 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import Modal from "../common/Modal";
+import type { FormField, FormFieldValue } from "../../types/pdf";
+import {
+  exportFormFdf,
+  fillFormFields,
+  importFormFdf,
+  pickExchangeFile,
+  pickExchangeSaveFile,
+} from "../../services/pdf.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+interface FormFillDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  fields: FormField[];
+  currentFilePath: string | null;
+  onSaveComplete: () => void;
+}
+
+const EDITABLE_TYPES = new Set(["text", "checkbox", "radio", "comboBox", "listBox"]);
+
+const INPUT_CLASS =
+  "w-full px-3 py-1.5 text-sm border border-slate-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 disabled:bg-slate-50 disabled:text-slate-400";
+
+/**
+ * Fill the AcroForm fields of the open PDF and save the result with
+ * fill_form_fields.
+ */
+export default function FormFillDialog({
+  isOpen,
+  onClose,
+  fields,
+  currentFilePath,
+  onSaveComplete,
+}: FormFillDialogProps) {
+  const [values, setValues] = useState<Record<string, FormFieldValue>>({});
+  const [regenerateAppearances, setRegenerateAppearances] = useState(true);
+  const [flattenForm, setFlattenForm] = useState(false);
+  const [isSaving, setIsSaving] = useState(false);
+  const [progress, setProgress] = useState("");
+
+  const editableFields = fields.filter((f) => EDITABLE_TYPES.has(f.fieldType));
+
+  // Start from the document's current values each time the dialog opens.
+  useEffect(() => {
+    if (!isOpen) return;
+    const initial: Record<string, FormFieldValue> = {};
+    for (const field of fields) {
+      if (field.value !== null) initial[field.name] = field.value;
+    }
+    setValues(initial);
+    setProgress("");
+  }, [isOpen, fields]);
+
+  const setValue = (name: string, value: FormFieldValue) =>
+    setValues((prev) => ({ ...prev, [name]: value }));
+
+  const handleSave = async () => {
+    if (!currentFilePath) return;
+
+    setIsSaving(true);
+    try {
+      // Only send fields the user changed, so untouched fields keep their
+      // original appearance.
+      const changed: Record<string, FormFieldValue> = {};
+      for (const field of editableFields) {
+        const value = values[field.name];
+        if (value !== undefined && JSON.stringify(value) !== JSON.stringify(field.value)) {
+          changed[field.name] = value;
+        }
+      }
+
+      setProgress("Choosing save location...");
+      const defaultName = currentFilePath.split(/[\\/]/).pop()?.replace(/\.pdf$/i, "") ?? "document";
+      const savePath: string | null = await invoke("save_file_dialog", {
+        defaultName: defaultName + "_filled.pdf",
+      });
+      if (!savePath) {
+        setIsSaving(false);
+        setProgress("");
+        return;
+      }
+      const targetPath = savePath.endsWith(".pdf") ? savePath : savePath + ".pdf";
+
+      setProgress(flattenForm ? "Filling and flattening form..." : "Filling form...");
+      await fillFormFields(currentFilePath, targetPath, changed, {
+        regenerateAppearances,
+        flatten: flattenForm,
+      });
+
+      setProgress("Done!");
+      setTimeout(() => {
+        onSaveComplete();
+        onClose();
+        setProgress("");
+        setIsSaving(false);
+      }, 500);
+    } catch (err) {
+      console.error("Form fill failed:", err);
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
+      setIsSaving(false);
+    }
+  };
+
+  // Imported values are merged in, so fields missing from the file keep
+  // what was entered here.
+  const handleImportData = async () => {
+    if (!currentFilePath) return;
+    try {
+      const path = await pickExchangeFile("fdf");
+      if (!path) return;
+      const imported = await importFormFdf(path, currentFilePath);
+      setValues((prev) => ({ ...prev, ...imported }));
+      const count = Object.keys(imported).length;
+      setProgress(`Imported ${count} field value${count !== 1 ? "s" : ""}`);
+    } catch (err) {
+      console.error("Form data import failed:", err);
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
+    }
+  };
+
+  const handleExportData = async () => {
+    if (!currentFilePath) return;
+    try {
+      const defaultName = currentFilePath.split(/[\\/]/).pop()?.replace(/\.pdf$/i, "") ?? "document";
+      const path = await pickExchangeSaveFile("fdf", defaultName + ".fdf");
+      if (!path) return;
+      const exported: Record<string, FormFieldValue> = {};
+      for (const field of editableFields) {
+        const value = values[field.name];
+        if (value !== undefined) exported[field.name] = value;
+      }
+      await exportFormFdf(currentFilePath, exported, path);
+      setProgress("Form data exported");
+    } catch (err) {
+      console.error("Form data export failed:", err);
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
+    }
+  };
+
+  const renderInput = (field: FormField) => {
+    const disabled = field.readOnly || isSaving;
+    const value = values[field.name];
+    switch (field.fieldType) {
+      case "checkbox":
+        return (
+          <input
+            type="checkbox"
+            checked={value === true}
+            disabled={disabled}
+            onChange={(e) => setValue(field.name, e.target.checked)}
+            className="w-4 h-4 text-blue-600 rounded border-slate-300 focus:ring-blue-500"
+          />
+        );
+      case "radio":
+      case "comboBox":
+        return (
+          <select
+            value={typeof value === "string" ? value : ""}
+            disabled={disabled}
+            onChange={(e) => setValue(field.name, e.target.value || "Off")}
+            className={INPUT_CLASS}
+          >
+            <option value="">—</option>
+            {field.options.map((option) => (
+              <option key={option.value} value={option.value}>
+                {option.label}
+              </option>
+            ))}
+          </select>
+        );
+      case "listBox":
+        return (
+          <select
+            multiple
+            value={Array.isArray(value) ? value : []}
+            disabled={disabled}
+            onChange={(e) =>
+              setValue(
+                field.name,
+                Array.from(e.target.selectedOptions, (option) => option.value)
+              )
+            }
+            className={INPUT_CLASS}
+          >
+            {field.options.map((option) => (
+              <option key={option.value} value={option.value}>
+                {option.label}
+              </option>
+            ))}
+          </select>
+        );
+      default:
+        return field.multiline ? (
+          <textarea
+            value={typeof value === "string" ? value : ""}
+            disabled={disabled}
+            maxLength={field.maxLength ?? undefined}
+            rows={3}
+            onChange={(e) => setValue(field.name, e.target.value)}
+            className={INPUT_CLASS}
+          />
+        ) : (
+          <input
+            type="text"
+            value={typeof value === "string" ? value : ""}
+            disabled={disabled}
+            maxLength={field.maxLength ?? undefined}
+            onChange={(e) => setValue(field.name, e.target.value)}
+            className={INPUT_CLASS}
+          />
+        );
+    }
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Fill Form">
+      <div className="space-y-4">
+        {/* Fields */}
+        <div className="max-h-80 overflow-y-auto space-y-3 pr-1">
+          {editableFields.map((field) => (
+            <div key={field.name}>
+              <label className="block text-xs font-medium text-slate-600 mb-1">
+                {field.name}
+                {field.required && <span className="text-red-500"> *</span>}
+                {field.widgets[0]?.pageNumber != null && (
+                  <span className="text-slate-400 font-normal"> &middot; p. {field.widgets[0].pageNumber}</span>
+                )}
+              </label>
+              {renderInput(field)}
+            </div>
+          ))}
+        </div>
+
+        {/* Options */}
+        <label className="flex items-start gap-3 p-3 rounded-lg border border-slate-200 hover:bg-slate-50 cursor-pointer transition-colors">
+          <input
+            type="checkbox"
+            checked={regenerateAppearances}
+            onChange={(e) => setRegenerateAppearances(e.target.checked)}
+            disabled={flattenForm}
+            className="mt-0.5 w-4 h-4 text-blue-600 rounded border-slate-300 focus:ring-blue-500"
+          />
+          <div>
+            <p className="text-sm font-medium text-slate-700">Regenerate field appearances</p>
+            <p className="text-xs text-slate-500 mt-0.5">
+              Redraws the filled fields so the values show in every viewer. When
+              off, viewers are asked to redraw the fields themselves.
+            </p>
+          </div>
+        </label>
+
+        <label className="flex items-start gap-3 p-3 rounded-lg border border-slate-200 hover:bg-slate-50 cursor-pointer transition-colors">
+          <input
+            type="checkbox"
+            checked={flattenForm}
+            onChange={(e) => setFlattenForm(e.target.checked)}
+            className="mt-0.5 w-4 h-4 text-blue-600 rounded border-slate-300 focus:ring-blue-500"
+          />
+          <div>
+            <p className="text-sm font-medium text-slate-700">Flatten form</p>
+            <p className="text-xs text-slate-500 mt-0.5">
+              Burns the values into the pages. The saved file will no longer be
+              fillable.
+            </p>
+          </div>
+        </label>
+
+        {/* Progress */}
+        {progress && (
+          <div className="flex items-center gap-2 text-sm text-blue-600">
+            {isSaving && !progress.startsWith("Done") && !progress.startsWith("Error") && (
+              <svg className="animate-spin w-4 h-4" viewBox="0 0 24 24" fill="none">
+                <circle className="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" strokeWidth="4" />
+                <path className="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4z" />
+              </svg>
+            )}
+            <span className={progress.startsWith("Error") ? "text-red-500" : ""}>
+              {progress}
+            </span>
+          </div>
+        )}
+
+        {/* Actions */}
+        <div className="flex justify-end gap-2 pt-2">
+          <button
+            onClick={handleImportData}
+            disabled={isSaving || !currentFilePath}
+            className="px-3 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+          >
+            Import data...
+          </button>
+          <button
+            onClick={handleExportData}
+            disabled={isSaving || !currentFilePath}
+            className="mr-auto px-3 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+          >
+            Export data...
+          </button>
+          <button
+            onClick={onClose}
+            disabled={isSaving}
+            className="px-4 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+          >
+            Cancel
+          </button>
+          <button
+            onClick={handleSave}
+            disabled={isSaving || !currentFilePath}
+            className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+          >
+            {isSaving ? "Saving..." : "Save As..."}
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+}
@@ -0,0 +1,167 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import Modal from "../common/Modal";
+import type { OptimizePreset, OptimizeReport } from "../../types/pdf";
+import { optimizePdf } from "../../services/pdf.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+interface OptimizeDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  currentFilePath: string | null;
+  onSaveComplete: () => void;
+}
+
+const PRESETS: Array<[OptimizePreset, string, string]> = [
+  ["email", "Email", "Smallest file; images reduced to 150 dpi, JPEG quality 60"],
+  ["print", "Print", "Images reduced to 300 dpi, JPEG quality 85"],
+  ["archive", "Archive", "Lossless, without object streams (PDF/A)"],
+];
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024 * 1024) return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+/**
+ * Save a smaller copy of the open PDF (optimize_pdf) and show what it
+ * saved.
+ */
+export default function OptimizeDialog({
+  isOpen,
+  onClose,
+  currentFilePath,
+  onSaveComplete,
+}: OptimizeDialogProps) {
+  const [preset, setPreset] = useState<OptimizePreset>("email");
+  const [report, setReport] = useState<OptimizeReport | null>(null);
+  const [isSaving, setIsSaving] = useState(false);
+  const [progress, setProgress] = useState("");
+
+  useEffect(() => {
+    if (!isOpen) return;
+    setReport(null);
+    setProgress("");
+  }, [isOpen]);
+
+  const handleOptimize = async () => {
+    if (!currentFilePath) return;
+    setIsSaving(true);
+    try {
+      setProgress("Choosing save location...");
+      const defaultName = currentFilePath.split(/[\\/]/).pop()?.replace(/\.pdf$/i, "") ?? "document";
+      const savePath: string | null = await invoke("save_file_dialog", {
+        defaultName: `${defaultName}_${preset}.pdf`,
+      });
+      if (!savePath) {
+        setProgress("");
+        return;
+      }
+      const targetPath = savePath.endsWith(".pdf") ? savePath : savePath + ".pdf";
+
+      setProgress("Optimizing...");
+      setReport(await optimizePdf(currentFilePath, targetPath, { preset }));
+      setProgress("");
+      onSaveComplete();
+    } catch (err) {
+      console.error("Optimizing PDF failed:", err);
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
+  const saved = report ? report.originalSize - report.optimizedSize : 0;
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Reduce File Size">
+      <div className="space-y-4">
+        {report ? (
+          <div className="space-y-2 text-sm text-slate-700">
+            <p>
+              {formatSize(report.originalSize)} &rarr;{" "}
+              <span className="font-medium">{formatSize(report.optimizedSize)}</span>
+              {saved > 0
+                ? ` (${Math.round((saved / report.originalSize) * 100)}% smaller)`
+                : " (this file couldn't be made smaller)"}
+            </p>
+            <ul className="text-xs text-slate-500 space-y-0.5">
+              <li>{report.imagesDownsampled} image(s) downsampled</li>
+              {report.imagesSkipped > 0 && (
+                <li>{report.imagesSkipped} image(s) in other formats kept at full resolution</li>
+              )}
+              <li>{report.streamsRecompressed} stream(s) recompressed</li>
+              <li>
+                {report.duplicatesRemoved} duplicate object(s) removed, {report.fontsMerged} of them
+                fonts
+              </li>
+            </ul>
+          </div>
+        ) : (
+          <div className="space-y-2">
+            {PRESETS.map(([value, label, description]) => (
+              <label key={value} className="flex items-start gap-2 text-sm text-slate-700 cursor-pointer">
+                <input
+                  type="radio"
+                  name="optimize-preset"
+                  checked={preset === value}
+                  disabled={isSaving}
+                  onChange={() => setPreset(value)}
+                  className="mt-0.5 w-4 h-4 text-blue-600 border-slate-300 focus:ring-blue-500"
+                />
+                <span>
+                  {label}
+                  <span className="block text-xs text-slate-400">{description}</span>
+                </span>
+              </label>
+            ))}
+          </div>
+        )}
+
+        {/* Progress */}
+        {progress && (
+          <div className="flex items-center gap-2 text-sm text-blue-600">
+            {isSaving && (
+              <svg className="animate-spin w-4 h-4" viewBox="0 0 24 24" fill="none">
+                <circle className="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" strokeWidth="4" />
+                <path className="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4z" />
+              </svg>
+            )}
+            <span className={progress.startsWith("Error") ? "text-red-500" : ""}>
+              {progress}
+            </span>
+          </div>
+        )}
+
+        {/* Actions */}
+        <div className="flex justify-end gap-2 pt-2">
+          {report ? (
+            <button
+              onClick={onClose}
+              className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 transition-colors"
+            >
+              Done
+            </button>
+          ) : (
+            <>
+              <button
+                onClick={onClose}
+                disabled={isSaving}
+                className="px-4 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+              >
+                Cancel
+              </button>
+              <button
+                onClick={handleOptimize}
+                disabled={isSaving || !currentFilePath}
+                className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+              >
+                {isSaving ? "Saving..." : "Save As..."}
+              </button>
+            </>
+          )}
+        </div>
+      </div>
+    </Modal>
+  );
+}
@@ -0,0 +1,275 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import Modal from "../common/Modal";
+import {
+  deletePages,
+  mergePdfs,
+  pickFolder,
+  pickPdfFiles,
+  reorderPages,
+  rotatePages,
+  splitPdf,
+} from "../../services/pdf.service";
+import { parsePageList, parsePageRanges } from "../../utils/pageRanges";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+type Operation = "merge" | "split" | "reorder" | "rotate" | "delete";
+
+const OPERATIONS: { id: Operation; label: string }[] = [
+  { id: "merge", label: "Merge" },
+  { id: "split", label: "Split" },
+  { id: "reorder", label: "Reorder" },
+  { id: "rotate", label: "Rotate" },
+  { id: "delete", label: "Delete" },
+];
+
+const INPUT_CLASS =
+  "w-full px-3 py-1.5 text-sm border border-slate-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500";
+
+interface PageOrganizerDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  currentFilePath: string | null;
+  pageCount: number;
+  /** Called with the file to open afterwards, or null when several were written. */
+  onComplete: (outputPath: string | null, message: string) => void;
+}
+
+/**
+ * Merge, split, reorder, rotate and delete pages with the native page
+ * organizer commands. Every operation writes a new file.
+ */
+export default function PageOrganizerDialog({
+  isOpen,
+  onClose,
+  currentFilePath,
+  pageCount,
+  onComplete,
+}: PageOrganizerDialogProps) {
+  const [operation, setOperation] = useState<Operation>("merge");
+  const [mergePaths, setMergePaths] = useState<string[]>([]);
+  const [pages, setPages] = useState("");
+  const [splitEvery, setSplitEvery] = useState(true);
+  const [splitSize, setSplitSize] = useState(1);
+  const [degrees, setDegrees] = useState(90);
+  const [isSaving, setIsSaving] = useState(false);
+  const [progress, setProgress] = useState("");
+
+  useEffect(() => {
+    if (!isOpen) return;
+    setMergePaths(currentFilePath ? [currentFilePath] : []);
+    setPages("");
+    setProgress("");
+  }, [isOpen, currentFilePath]);
+
+  const fileName = (path: string) => path.split(/[\\/]/).pop() ?? path;
+
+  const handleAddFiles = async () => {
+    const paths = await pickPdfFiles();
+    setMergePaths((prev) => [...prev, ...paths]);
+  };
+
+  const moveMergePath = (index: number, delta: number) => {
+    setMergePaths((prev) => {
+      const next = [...prev];
+      const [path] = next.splice(index, 1);
+      next.splice(Math.max(0, Math.min(next.length, index + delta)), 0, path);
+      return next;
+    });
+  };
+
+  const chooseOutput = async (suffix: string): Promise<string | null> => {
+    const base = currentFilePath ? fileName(currentFilePath).replace(/\.pdf$/i, "") : "document";
+    const savePath: string | null = await invoke("save_file_dialog", {
+      defaultName: `${base}_${suffix}.pdf`,
+    });
+    if (!savePath) return null;
+    return savePath.endsWith(".pdf") ? savePath : savePath + ".pdf";
+  };
+
+  const handleRun = async () => {
+    setIsSaving(true);
+    try {
+      if (operation === "merge") {
+        if (mergePaths.length < 2) throw new Error("Add at least two PDFs to merge");
+        const output = await chooseOutput("merged");
+        if (!output) return;
+        setProgress("Merging documents...");
+        await mergePdfs(mergePaths, output);
+        onComplete(output, `Merged ${mergePaths.length} documents`);
+      } else {
+        if (!currentFilePath) throw new Error("Save the PDF to disk first");
+        if (operation === "split") {
+          const mode = splitEvery
+            ? ({ by: "every", pages: splitSize } as const)
+            : ({ by: "ranges", ranges: parsePageRanges(pages, pageCount) } as const);
+          const folder = await pickFolder();
+          if (!folder) return;
+          setProgress("Splitting document...");
+          const written = await splitPdf(currentFilePath, folder, mode);
+          onComplete(null, `Split into ${written.length} files`);
+        } else {
+          const pageList = parsePageList(pages, pageCount);
+          const output = await chooseOutput(operation === "delete" ? "trimmed" : operation === "rotate" ? "rotated" : "reordered");
+          if (!output) return;
+          setProgress("Saving pages...");
+          if (operation === "reorder") {
+            await reorderPages(currentFilePath, output, pageList);
+          } else if (operation === "rotate") {
+            await rotatePages(currentFilePath, output, pageList, degrees);
+          } else {
+            await deletePages(currentFilePath, output, pageList);
+          }
+          onComplete(output, "Pages saved");
+        }
+      }
+      setProgress("");
+      onClose();
+    } catch (err) {
+      console.error("Page operation failed:", err);
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
+  const pagesHint: Record<Exclude<Operation, "merge">, string> = {
+    split: "Ranges, one file each, e.g. 1-3, 4-6, 7-",
+    reorder: `New order of all ${pageCount} pages, e.g. 3, 1-2, 4-${Math.max(4, pageCount)}`,
+    rotate: "Pages to rotate, e.g. 1, 3-5",
+    delete: "Pages to delete, e.g. 2, 7-9",
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Organize Pages">
+      <div className="space-y-4">
+        {/* Operation */}
+        <div className="flex gap-1 p-1 bg-slate-100 rounded-lg">
+          {OPERATIONS.map(({ id, label }) => (
+            <button
+              key={id}
+              onClick={() => setOperation(id)}
+              disabled={isSaving || (id !== "merge" && !currentFilePath)}
+              className={`flex-1 px-2 py-1 text-sm rounded-md transition-colors disabled:opacity-40 ${
+                operation === id ? "bg-white text-blue-600 shadow-sm" : "text-slate-600 hover:text-slate-800"
+              }`}
+            >
+              {label}
+            </button>
+          ))}
+        </div>
+
+        {operation === "merge" && (
+          <div className="space-y-2">
+            <div className="max-h-48 overflow-y-auto space-y-1">
+              {mergePaths.map((path, index) => (
+                <div key={`${path}-${index}`} className="flex items-center gap-2 px-2 py-1.5 rounded-md bg-slate-50 text-sm">
+                  <span className="text-slate-400 w-5 text-right tabular-nums">{index + 1}</span>
+                  <span className="flex-1 truncate text-slate-700" title={path}>
+                    {fileName(path)}
+                  </span>
+                  <button onClick={() => moveMergePath(index, -1)} disabled={index === 0} className="px-1 text-slate-400 hover:text-slate-700 disabled:opacity-30" title="Move up">
+                    ↑
+                  </button>
+                  <button onClick={() => moveMergePath(index, 1)} disabled={index === mergePaths.length - 1} className="px-1 text-slate-400 hover:text-slate-700 disabled:opacity-30" title="Move down">
+                    ↓
+                  </button>
+                  <button onClick={() => setMergePaths((prev) => prev.filter((_, i) => i !== index))} className="px-1 text-slate-400 hover:text-red-500" title="Remove">
+                    ×
+                  </button>
+                </div>
+              ))}
+            </div>
+            <button
+              type="button"
+              onClick={handleAddFiles}
+              disabled={isSaving}
+              className="px-3 py-1.5 text-xs border border-slate-300 rounded-md hover:bg-slate-50 disabled:opacity-50"
+            >
+              Add PDFs...
+            </button>
+          </div>
+        )}
+
+        {operation === "split" && (
+          <div className="space-y-2">
+            <label className="flex items-center gap-2 text-sm text-slate-700">
+              <input type="radio" checked={splitEvery} onChange={() => setSplitEvery(true)} />
+              Every
+              <input
+                type="number"
+                min={1}
+                max={pageCount}
+                value={splitSize}
+                onChange={(e) => setSplitSize(Math.max(1, parseInt(e.target.value, 10) || 1))}
+                className="w-16 px-2 py-1 text-sm border border-slate-300 rounded-md"
+              />
+              page{splitSize !== 1 ? "s" : ""}
+            </label>
+            <label className="flex items-center gap-2 text-sm text-slate-700">
+              <input type="radio" checked={!splitEvery} onChange={() => setSplitEvery(false)} />
+              By page ranges
+            </label>
+          </div>
+        )}
+
+        {operation !== "merge" && (operation !== "split" || !splitEvery) && (
+          <div>
+            <input
+              type="text"
+              value={pages}
+              onChange={(e) => setPages(e.target.value)}
+              placeholder={pagesHint[operation]}
+              className={INPUT_CLASS}
+            />
+            <p className="text-xs text-slate-500 mt-1">
+              {pagesHint[operation]} &middot; {pageCount} page{pageCount !== 1 ? "s" : ""}
+            </p>
+          </div>
+        )}
+
+        {operation === "rotate" && (
+          <div className="flex gap-2">
+            {[90, 180, 270].map((value) => (
+              <label key={value} className="flex items-center gap-1.5 text-sm text-slate-700">
+                <input type="radio" checked={degrees === value} onChange={() => setDegrees(value)} />
+                {value === 270 ? "90° left" : `${value}°${value === 90 ? " right" : ""}`}
+              </label>
+            ))}
+          </div>
+        )}
+
+        {/* Progress */}
+        {progress && (
+          <div className="flex items-center gap-2 text-sm text-blue-600">
+            {isSaving && (
+              <svg className="animate-spin w-4 h-4" viewBox="0 0 24 24" fill="none">
+                <circle className="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" strokeWidth="4" />
+                <path className="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4z" />
+              </svg>
+            )}
+            <span className={progress.startsWith("Error") ? "text-red-500" : ""}>{progress}</span>
+          </div>
+        )}
+
+        {/* Actions */}
+        <div className="flex justify-end gap-2 pt-2">
+          <button
+            onClick={onClose}
+            disabled={isSaving}
+            className="px-4 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+          >
+            Cancel
+          </button>
+          <button
+            onClick={handleRun}
+            disabled={isSaving}
+            className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+          >
+            {isSaving ? "Working..." : operation === "split" ? "Choose Folder..." : "Save As..."}
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+}
@@ -0,0 +1,88 @@
+import { useEffect, useState } from "react";
+import Modal from "../common/Modal";
+import { unlockPdf } from "../../services/pdf.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+interface PasswordDialogProps {
+  isOpen: boolean;
+  /** The encrypted PDF being opened. */
+  filePath: string | null;
+  onUnlock: (password: string) => void;
+  onCancel: () => void;
+}
+
+/**
+ * Ask for the password of an encrypted PDF. It is checked by unlock_pdf,
+ * which also keeps it for the Rust commands, before pdf.js is given it.
+ */
+export default function PasswordDialog({
+  isOpen,
+  filePath,
+  onUnlock,
+  onCancel,
+}: PasswordDialogProps) {
+  const [password, setPassword] = useState("");
+  const [error, setError] = useState("");
+  const [isChecking, setIsChecking] = useState(false);
+
+  useEffect(() => {
+    if (!isOpen) return;
+    setPassword("");
+    setError("");
+  }, [isOpen]);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!filePath) return;
+    setIsChecking(true);
+    try {
+      await unlockPdf(filePath, password);
+      onUnlock(password);
+    } catch (err) {
+      setError(errorMessage(err, errorDetail(err)));
+    } finally {
+      setIsChecking(false);
+    }
+  };
+
+  const fileName = filePath?.split(/[\\/]/).pop() ?? "This PDF";
+
+  return (
+    <Modal isOpen={isOpen} onClose={onCancel} title="Password Required" width="max-w-sm">
+      <form onSubmit={handleSubmit} className="space-y-4">
+        <p className="text-sm text-slate-600">
+          {fileName} is password-protected. Enter the password to open it.
+        </p>
+        <input
+          type="password"
+          value={password}
+          autoFocus
+          onChange={(e) => {
+            setPassword(e.target.value);
+            setError("");
+          }}
+          placeholder="Password"
+          className="w-full px-3 py-1.5 text-sm border border-slate-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+        />
+        {error && <p className="text-sm text-red-500">{error}</p>}
+        <div className="flex justify-end gap-2">
+          <button
+            type="button"
+            onClick={onCancel}
+            disabled={isChecking}
+            className="px-4 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+          >
+            Cancel
+          </button>
+          <button
+            type="submit"
+            disabled={isChecking || !password}
+            className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+          >
+            {isChecking ? "Checking..." : "Open"}
+          </button>
+        </div>
+      </form>
+    </Modal>
+  );
+}
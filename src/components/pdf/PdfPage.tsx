@@ -1,9 +1,10 @@
 import { useRef, useEffect, useCallback } from "react";
-import type { Annotation, SignatureAnnotation, PdfMode } from "../../types/pdf";
+import type { Annotation, SignatureAnnotation, PdfMode, RedactionArea } from "../../types/pdf";
 import { isTextAnnotation, isSignatureAnnotation } from "../../types/pdf";
 import type { Signature } from "../../types/signature";
 import TextOverlay from "./TextOverlay";
 import SignatureOverlay from "./SignatureOverlay";
+import RedactionLayer from "./RedactionLayer";
 
 interface PdfPageProps {
   pageNumber: number;
@@ -19,6 +20,9 @@ interface PdfPageProps {
   onDeleteAnnotation: (id: string) => void;
   onPageClick: (pageNumber: number, x: number, y: number) => void;
   onNewAnnotationHandled: () => void;
+  redactionAreas: RedactionArea[];
+  onAddRedaction: (area: Omit<RedactionArea, "id">) => void;
+  onRemoveRedaction: (id: string) => void;
 }
 
 /**
@@ -41,6 +45,9 @@ export default function PdfPage({
   onDeleteAnnotation,
   onPageClick,
   onNewAnnotationHandled,
+  redactionAreas,
+  onAddRedaction,
+  onRemoveRedaction,
 }: PdfPageProps) {
   const canvasRef = useRef<HTMLCanvasElement>(null);
 
@@ -65,6 +72,7 @@ export default function PdfPage({
   );
 
   const pageAnnotations = annotations.filter((a) => a.pageNumber === pageNumber);
+  const pageRedactions = redactionAreas.filter((a) => a.pageNumber === pageNumber);
 
   return (
     <>
@@ -116,6 +124,16 @@ export default function PdfPage({
           return null;
         })}
       </div>
+
+      {/* Pending redaction areas */}
+      <RedactionLayer
+        pageNumber={pageNumber}
+        scale={scale}
+        areas={pageRedactions}
+        active={mode === "redact"}
+        onAdd={onAddRedaction}
+        onRemove={onRemoveRedaction}
+      />
     </>
   );
 }
@@ -15,8 +15,17 @@ interface PdfToolbarProps {
   onModeChange: (mode: PdfMode) => void;
   onFlattenClick: () => void;
   onCreateSignature: () => void;
+  onFillFormClick: () => void;
+  onOrganizeClick: () => void;
+  onImportAnnotations: () => void;
+  onExportAnnotations: () => void;
+  onSecurityClick: () => void;
+  onOptimizeClick: () => void;
+  onApplyRedactions: () => void;
   annotationCount: number;
+  formFieldCount: number;
   signatureCount: number;
+  redactionCount: number;
 }
 
 export default function PdfToolbar({
@@ -29,8 +38,17 @@ export default function PdfToolbar({
   onModeChange,
   onFlattenClick,
   onCreateSignature,
+  onFillFormClick,
+  onOrganizeClick,
+  onImportAnnotations,
+  onExportAnnotations,
+  onSecurityClick,
+  onOptimizeClick,
+  onApplyRedactions,
   annotationCount,
+  formFieldCount,
   signatureCount,
+  redactionCount,
 }: PdfToolbarProps) {
   const handlePageInput = (e: React.KeyboardEvent<HTMLInputElement>) => {
     if (e.key === "Enter") {
@@ -110,6 +128,56 @@ export default function PdfToolbar({
 
       <Divider />
 
+      {/* Page Organizer */}
+      <ToolbarButton onClick={onOrganizeClick} title="Merge, split, reorder, rotate or delete pages">
+        <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+          <rect x="3" y="3" width="7" height="9" rx="1" />
+          <rect x="14" y="3" width="7" height="9" rx="1" />
+          <rect x="3" y="15" width="7" height="6" rx="1" />
+          <rect x="14" y="15" width="7" height="6" rx="1" />
+        </svg>
+      </ToolbarButton>
+
+      {/* Annotation exchange (XFDF) */}
+      <ToolbarButton onClick={onImportAnnotations} title="Import annotations from XFDF">
+        <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+          <path d="M21 15v4a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2v-4" />
+          <polyline points="7 10 12 15 17 10" />
+          <line x1="12" y1="15" x2="12" y2="3" />
+        </svg>
+      </ToolbarButton>
+      <ToolbarButton
+        onClick={onExportAnnotations}
+        disabled={annotationCount === 0}
+        title="Export annotations to XFDF"
+      >
+        <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+          <path d="M21 15v4a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2v-4" />
+          <polyline points="17 8 12 3 7 8" />
+          <line x1="12" y1="3" x2="12" y2="15" />
+        </svg>
+      </ToolbarButton>
+
+      {/* Password protection */}
+      <ToolbarButton onClick={onSecurityClick} title="Password protection">
+        <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+          <rect x="3" y="11" width="18" height="11" rx="2" />
+          <path d="M7 11V7a5 5 0 0 1 10 0v4" />
+        </svg>
+      </ToolbarButton>
+
+      {/* Size optimization */}
+      <ToolbarButton onClick={onOptimizeClick} title="Reduce file size">
+        <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+          <polyline points="4 14 10 14 10 20" />
+          <polyline points="20 10 14 10 14 4" />
+          <line x1="14" y1="10" x2="21" y2="3" />
+          <line x1="3" y1="21" x2="10" y2="14" />
+        </svg>
+      </ToolbarButton>
+
+      <Divider />
+
       {/* Mode Buttons */}
       <div className="flex items-center gap-0.5">
         <ModeButton
@@ -152,11 +220,55 @@ export default function PdfToolbar({
             </svg>
           </ToolbarButton>
         )}
+        <ModeButton
+          active={mode === "redact"}
+          onClick={() => onModeChange("redact")}
+          title="Redact areas"
+        >
+          <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+            <rect x="3" y="5" width="18" height="14" rx="1" />
+            <rect x="6" y="10" width="12" height="4" fill="currentColor" />
+          </svg>
+        </ModeButton>
       </div>
 
       {/* Spacer */}
       <div className="flex-1" />
 
+      {/* Apply Redactions */}
+      {mode === "redact" && (
+        <button
+          onClick={onApplyRedactions}
+          disabled={redactionCount === 0}
+          className="flex items-center gap-1.5 px-3 py-1.5 mr-1 text-sm font-medium text-white bg-red-600 hover:bg-red-700 rounded-lg transition-colors disabled:opacity-40"
+          title="Permanently remove the content under the marked areas"
+        >
+          Apply Redactions
+          {redactionCount > 0 && (
+            <span className="px-1.5 py-0.5 text-[10px] font-bold bg-red-500 rounded-full">
+              {redactionCount}
+            </span>
+          )}
+        </button>
+      )}
+
+      {/* Fill Form */}
+      {formFieldCount > 0 && (
+        <button
+          onClick={onFillFormClick}
+          className="flex items-center gap-1.5 px-3 py-1.5 mr-1 text-sm font-medium text-slate-600 hover:bg-slate-100 rounded-lg transition-colors"
+          title={`${formFieldCount} form field${formFieldCount !== 1 ? "s" : ""}`}
+        >
+          <svg width="14" height="14" viewBox="0 0 24 24" fill="none" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round">
+            <rect x="3" y="4" width="18" height="16" rx="2" />
+            <line x1="7" y1="9" x2="17" y2="9" />
+            <line x1="7" y1="13" x2="17" y2="13" />
+            <line x1="7" y1="17" x2="12" y2="17" />
+          </svg>
+          Fill Form
+        </button>
+      )}
+
       {/* Flatten & Save */}
       <button
         onClick={onFlattenClick}
@@ -1,5 +1,5 @@
 import { useRef, useCallback, useEffect, useState } from "react";
-import type { Annotation, PdfMode } from "../../types/pdf";
+import type { Annotation, PdfMode, RedactionArea } from "../../types/pdf";
 import type { Signature } from "../../types/signature";
 import PdfPage from "./PdfPage";
 
@@ -24,6 +24,9 @@ interface PdfViewerProps {
   onDeleteAnnotation: (id: string) => void;
   onPageClick: (pageNumber: number, x: number, y: number) => void;
   onNewAnnotationHandled: () => void;
+  redactionAreas: RedactionArea[];
+  onAddRedaction: (area: Omit<RedactionArea, "id">) => void;
+  onRemoveRedaction: (id: string) => void;
 }
 
 /** Number of pages to render above/below the visible area */
@@ -45,6 +48,9 @@ export default function PdfViewer({
   onDeleteAnnotation,
   onPageClick,
   onNewAnnotationHandled,
+  redactionAreas,
+  onAddRedaction,
+  onRemoveRedaction,
 }: PdfViewerProps) {
   const containerRef = useRef<HTMLDivElement>(null);
   const [visiblePages, setVisiblePages] = useState<Set<number>>(new Set([1]));
@@ -162,6 +168,9 @@ export default function PdfViewer({
                   onDeleteAnnotation={onDeleteAnnotation}
                   onPageClick={onPageClick}
                   onNewAnnotationHandled={onNewAnnotationHandled}
+                  redactionAreas={redactionAreas}
+                  onAddRedaction={onAddRedaction}
+                  onRemoveRedaction={onRemoveRedaction}
                 />
               )}
             </div>
@@ -0,0 +1,276 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import Modal from "../common/Modal";
+import type { EncryptionInfo, EncryptionMethod, PdfPermissions } from "../../types/pdf";
+import { getPdfEncryption, protectPdf, removePdfPassword } from "../../services/pdf.service";
+import { errorDetail, errorMessage } from "../../utils/errors";
+
+interface ProtectDialogProps {
+  isOpen: boolean;
+  onClose: () => void;
+  currentFilePath: string | null;
+  onSaveComplete: () => void;
+}
+
+const INPUT_CLASS =
+  "w-full px-3 py-1.5 text-sm border border-slate-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500";
+
+const PERMISSION_LABELS: Array<[keyof PdfPermissions, string]> = [
+  ["print", "Allow printing"],
+  ["copy", "Allow copying text and images"],
+  ["modify", "Allow editing, comments and form filling"],
+];
+
+/**
+ * Save a copy of the open PDF with an open password, an owner password and
+ * permissions (protect_pdf), or without its password (remove_pdf_password).
+ */
+export default function ProtectDialog({
+  isOpen,
+  onClose,
+  currentFilePath,
+  onSaveComplete,
+}: ProtectDialogProps) {
+  const [info, setInfo] = useState<EncryptionInfo | null>(null);
+  const [userPassword, setUserPassword] = useState("");
+  const [confirmPassword, setConfirmPassword] = useState("");
+  const [ownerPassword, setOwnerPassword] = useState("");
+  const [currentPassword, setCurrentPassword] = useState("");
+  const [permissions, setPermissions] = useState<PdfPermissions>({
+    print: true,
+    copy: true,
+    modify: true,
+  });
+  const [method, setMethod] = useState<EncryptionMethod>("aes256");
+  const [isSaving, setIsSaving] = useState(false);
+  const [progress, setProgress] = useState("");
+
+  useEffect(() => {
+    if (!isOpen || !currentFilePath) return;
+    setUserPassword("");
+    setConfirmPassword("");
+    setOwnerPassword("");
+    setCurrentPassword("");
+    setProgress("");
+    setInfo(null);
+    getPdfEncryption(currentFilePath)
+      .then((current) => {
+        setInfo(current);
+        if (current.encrypted) setPermissions(current.permissions);
+      })
+      .catch((err) => console.error("Failed to read PDF encryption:", err));
+  }, [isOpen, currentFilePath]);
+
+  const needsOwnerPassword = info?.encrypted === true && !info.ownerUnlocked;
+  const passwordsMatch = userPassword === confirmPassword;
+
+  const chooseOutput = async (suffix: string): Promise<string | null> => {
+    if (!currentFilePath) return null;
+    const defaultName = currentFilePath.split(/[\\/]/).pop()?.replace(/\.pdf$/i, "") ?? "document";
+    const savePath: string | null = await invoke("save_file_dialog", {
+      defaultName: defaultName + suffix,
+    });
+    if (!savePath) return null;
+    return savePath.endsWith(".pdf") ? savePath : savePath + ".pdf";
+  };
+
+  const run = async (suffix: string, label: string, save: (target: string) => Promise<void>) => {
+    setIsSaving(true);
+    try {
+      setProgress("Choosing save location...");
+      const targetPath = await chooseOutput(suffix);
+      if (!targetPath) {
+        setIsSaving(false);
+        setProgress("");
+        return;
+      }
+      setProgress(label);
+      await save(targetPath);
+
+      setProgress("Done!");
+      setTimeout(() => {
+        onSaveComplete();
+        onClose();
+        setProgress("");
+        setIsSaving(false);
+      }, 500);
+    } catch (err) {
+      console.error("Changing PDF protection failed:", err);
+      setProgress("Error: " + errorMessage(err, errorDetail(err)));
+      setIsSaving(false);
+    }
+  };
+
+  const handleProtect = () => {
+    if (!currentFilePath) return;
+    run("_protected.pdf", "Encrypting...", (target) =>
+      protectPdf(
+        currentFilePath,
+        target,
+        {
+          userPassword,
+          ownerPassword: ownerPassword || undefined,
+          permissions,
+          method,
+        },
+        currentPassword
+      )
+    );
+  };
+
+  const handleRemove = () => {
+    if (!currentFilePath) return;
+    run("_unprotected.pdf", "Removing password...", (target) =>
+      removePdfPassword(currentFilePath, target, currentPassword)
+    );
+  };
+
+  return (
+    <Modal isOpen={isOpen} onClose={onClose} title="Password Protection">
+      <div className="space-y-4">
+        {info?.encrypted && (
+          <p className="text-sm text-slate-600">
+            This PDF is encrypted{info.method ? ` with ${info.method}` : ""}.
+            {needsOwnerPassword && " Enter its owner password to change or remove the protection."}
+          </p>
+        )}
+
+        {needsOwnerPassword && (
+          <div>
+            <label className="block text-xs font-medium text-slate-600 mb-1">
+              Current owner password
+            </label>
+            <input
+              type="password"
+              value={currentPassword}
+              disabled={isSaving}
+              onChange={(e) => setCurrentPassword(e.target.value)}
+              className={INPUT_CLASS}
+            />
+          </div>
+        )}
+
+        {/* New passwords */}
+        <div className="grid grid-cols-2 gap-3">
+          <div>
+            <label className="block text-xs font-medium text-slate-600 mb-1">
+              Password to open
+            </label>
+            <input
+              type="password"
+              value={userPassword}
+              disabled={isSaving}
+              onChange={(e) => setUserPassword(e.target.value)}
+              className={INPUT_CLASS}
+            />
+          </div>
+          <div>
+            <label className="block text-xs font-medium text-slate-600 mb-1">Confirm</label>
+            <input
+              type="password"
+              value={confirmPassword}
+              disabled={isSaving}
+              onChange={(e) => setConfirmPassword(e.target.value)}
+              className={INPUT_CLASS}
+            />
+          </div>
+        </div>
+        <div>
+          <label className="block text-xs font-medium text-slate-600 mb-1">
+            Owner password
+            <span className="text-slate-400 font-normal"> &middot; to change permissions; defaults to the password to open</span>
+          </label>
+          <input
+            type="password"
+            value={ownerPassword}
+            disabled={isSaving}
+            onChange={(e) => setOwnerPassword(e.target.value)}
+            className={INPUT_CLASS}
+          />
+        </div>
+
+        {/* Permissions */}
+        <div className="space-y-2">
+          {PERMISSION_LABELS.map(([key, label]) => (
+            <label key={key} className="flex items-center gap-2 text-sm text-slate-700 cursor-pointer">
+              <input
+                type="checkbox"
+                checked={permissions[key]}
+                disabled={isSaving}
+                onChange={(e) => setPermissions((prev) => ({ ...prev, [key]: e.target.checked }))}
+                className="w-4 h-4 text-blue-600 rounded border-slate-300 focus:ring-blue-500"
+              />
+              {label}
+            </label>
+          ))}
+        </div>
+
+        <div>
+          <label className="block text-xs font-medium text-slate-600 mb-1">Encryption</label>
+          <select
+            value={method}
+            disabled={isSaving}
+            onChange={(e) => setMethod(e.target.value as EncryptionMethod)}
+            className={INPUT_CLASS}
+          >
+            <option value="aes256">AES-256 (Acrobat X and later)</option>
+            <option value="aes128">AES-128 (Acrobat 7 and later)</option>
+            <option value="rc4">RC4 128-bit (older readers)</option>
+          </select>
+        </div>
+
+        {!passwordsMatch && confirmPassword && (
+          <p className="text-sm text-red-500">The passwords don't match</p>
+        )}
+
+        {/* Progress */}
+        {progress && (
+          <div className="flex items-center gap-2 text-sm text-blue-600">
+            {isSaving && !progress.startsWith("Done") && !progress.startsWith("Error") && (
+              <svg className="animate-spin w-4 h-4" viewBox="0 0 24 24" fill="none">
+                <circle className="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" strokeWidth="4" />
+                <path className="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4z" />
+              </svg>
+            )}
+            <span className={progress.startsWith("Error") ? "text-red-500" : ""}>
+              {progress}
+            </span>
+          </div>
+        )}
+
+        {/* Actions */}
+        <div className="flex justify-end gap-2 pt-2">
+          {info?.encrypted && (
+            <button
+              onClick={handleRemove}
+              disabled={isSaving || (needsOwnerPassword && !currentPassword)}
+              className="mr-auto px-3 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+            >
+              Remove password...
+            </button>
+          )}
+          <button
+            onClick={onClose}
+            disabled={isSaving}
+            className="px-4 py-2 text-sm text-slate-600 hover:bg-slate-100 rounded-lg transition-colors disabled:opacity-50"
+          >
+            Cancel
+          </button>
+          <button
+            onClick={handleProtect}
+            disabled={
+              isSaving ||
+              !currentFilePath ||
+              !passwordsMatch ||
+              (!userPassword && !ownerPassword) ||
+              (needsOwnerPassword && !currentPassword)
+            }
+            className="px-4 py-2 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+          >
+            {isSaving ? "Saving..." : "Save As..."}
+          </button>
+        </div>
+      </div>
+    </Modal>
+  );
+}
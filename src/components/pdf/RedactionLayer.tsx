@@ -0,0 +1,138 @@
+import { useState, useRef, useEffect, useCallback } from "react";
+import type { RedactionArea } from "../../types/pdf";
+
+/** Drags smaller than this (in PDF points) are treated as stray clicks. */
+const MIN_AREA_SIZE = 2;
+
+interface RedactionLayerProps {
+  pageNumber: number;
+  scale: number;
+  /** Pending areas on this page. */
+  areas: RedactionArea[];
+  /** Whether new areas can be drawn (redact mode). */
+  active: boolean;
+  onAdd: (area: Omit<RedactionArea, "id">) => void;
+  onRemove: (id: string) => void;
+}
+
+/**
+ * Pending redaction areas for one page. In redact mode, dragging across the
+ * page marks a new area. Nothing is removed until the areas are applied.
+ */
+export default function RedactionLayer({
+  pageNumber,
+  scale,
+  areas,
+  active,
+  onAdd,
+  onRemove,
+}: RedactionLayerProps) {
+  const layerRef = useRef<HTMLDivElement>(null);
+  const [draft, setDraft] = useState<{ x0: number; y0: number; x1: number; y1: number } | null>(
+    null
+  );
+
+  const toPagePoint = useCallback(
+    (clientX: number, clientY: number) => {
+      const rect = layerRef.current!.getBoundingClientRect();
+      return {
+        x: Math.max(0, Math.min(rect.width, clientX - rect.left)) / scale,
+        y: Math.max(0, Math.min(rect.height, clientY - rect.top)) / scale,
+      };
+    },
+    [scale]
+  );
+
+  const handleMouseDown = useCallback(
+    (e: React.MouseEvent) => {
+      if (!active || e.target !== e.currentTarget) return;
+      e.preventDefault();
+      const { x, y } = toPagePoint(e.clientX, e.clientY);
+      setDraft({ x0: x, y0: y, x1: x, y1: y });
+    },
+    [active, toPagePoint]
+  );
+
+  const isDrawing = draft !== null;
+  useEffect(() => {
+    if (!isDrawing) return;
+
+    const handleMouseMove = (e: MouseEvent) => {
+      const { x, y } = toPagePoint(e.clientX, e.clientY);
+      setDraft((prev) => (prev ? { ...prev, x1: x, y1: y } : prev));
+    };
+
+    const handleMouseUp = () => {
+      setDraft((prev) => {
+        if (prev) {
+          const width = Math.abs(prev.x1 - prev.x0);
+          const height = Math.abs(prev.y1 - prev.y0);
+          if (width >= MIN_AREA_SIZE && height >= MIN_AREA_SIZE) {
+            onAdd({
+              pageNumber,
+              x: Math.min(prev.x0, prev.x1),
+              y: Math.min(prev.y0, prev.y1),
+              width,
+              height,
+            });
+          }
+        }
+        return null;
+      });
+    };
+
+    window.addEventListener("mousemove", handleMouseMove);
+    window.addEventListener("mouseup", handleMouseUp);
+    return () => {
+      window.removeEventListener("mousemove", handleMouseMove);
+      window.removeEventListener("mouseup", handleMouseUp);
+    };
+  }, [isDrawing, pageNumber, onAdd, toPagePoint]);
+
+  if (!active && areas.length === 0) return null;
+
+  return (
+    <div
+      ref={layerRef}
+      className={`absolute inset-0 ${active ? "cursor-crosshair" : ""}`}
+      style={{ pointerEvents: active ? "auto" : "none" }}
+      onMouseDown={handleMouseDown}
+      onClick={(e) => e.stopPropagation()}
+    >
+      {areas.map((area) => (
+        <div
+          key={area.id}
+          className="absolute group bg-black/60 border-2 border-red-500"
+          style={{
+            left: area.x * scale,
+            top: area.y * scale,
+            width: area.width * scale,
+            height: area.height * scale,
+          }}
+        >
+          {active && (
+            <button
+              onClick={() => onRemove(area.id)}
+              className="absolute -top-2.5 -right-2.5 w-5 h-5 flex items-center justify-center text-xs text-white bg-red-500 rounded-full opacity-0 group-hover:opacity-100 transition-opacity"
+              title="Remove area"
+            >
+              ×
+            </button>
+          )}
+        </div>
+      ))}
+
+      {draft && (
+        <div
+          className="absolute bg-black/30 border-2 border-dashed border-red-500 pointer-events-none"
+          style={{
+            left: Math.min(draft.x0, draft.x1) * scale,
+            top: Math.min(draft.y0, draft.y1) * scale,
+            width: Math.abs(draft.x1 - draft.x0) * scale,
+            height: Math.abs(draft.y1 - draft.y0) * scale,
+          }}
+        />
+      )}
+    </div>
+  );
+}
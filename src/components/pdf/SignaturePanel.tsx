@@ -0,0 +1,77 @@
+import type { SignatureReport } from "../../types/pdf";
+
+interface SignaturePanelProps {
+  reports: SignatureReport[];
+  onImportCertificate: () => void;
+}
+
+type Verdict = "valid" | "warning" | "invalid" | "unsigned";
+
+function verdictFor(report: SignatureReport): Verdict {
+  if (!report.signed) return "unsigned";
+  if (report.integrity === "invalid") return "invalid";
+  if (report.integrity === "valid" && report.trust === "trusted" && !report.modifiedAfterSigning) {
+    return "valid";
+  }
+  return "warning";
+}
+
+const VERDICT_STYLES: Record<Verdict, { dot: string; label: string }> = {
+  valid: { dot: "bg-green-500", label: "Valid" },
+  warning: { dot: "bg-amber-400", label: "Needs review" },
+  invalid: { dot: "bg-red-500", label: "Invalid" },
+  unsigned: { dot: "bg-slate-300", label: "Not signed" },
+};
+
+/**
+ * Sidebar section listing the digital signatures in the open PDF, as
+ * reported by verify_pdf_signatures.
+ */
+export default function SignaturePanel({ reports, onImportCertificate }: SignaturePanelProps) {
+  return (
+    <div className="border-b border-slate-200 pb-2">
+      <div className="flex items-center justify-between px-3 py-2">
+        <span className="text-xs font-semibold uppercase tracking-wider text-slate-400">
+          Signatures
+        </span>
+        <button
+          onClick={onImportCertificate}
+          className="text-xs text-slate-400 hover:text-blue-600 transition-colors"
+          title="Add a certificate to the trust store"
+        >
+          Trust...
+        </button>
+      </div>
+
+      <div className="px-1.5 space-y-1">
+        {reports.map((report) => {
+          const verdict = verdictFor(report);
+          const style = VERDICT_STYLES[verdict];
+          return (
+            <div key={report.fieldName} className="px-2 py-2 rounded-lg hover:bg-slate-100">
+              <div className="flex items-center gap-2">
+                <span className={`w-2 h-2 rounded-full flex-shrink-0 ${style.dot}`} />
+                <p className="text-sm text-slate-700 truncate">
+                  {report.signerName ?? report.fieldName}
+                </p>
+              </div>
+              <p className="text-xs text-slate-400 mt-0.5 ml-4">
+                {style.label}
+                {report.signingTime && <> &middot; {new Date(report.signingTime).toLocaleString()}</>}
+                {report.pageNumber !== null && <> &middot; p. {report.pageNumber}</>}
+              </p>
+              {report.reason && (
+                <p className="text-xs text-slate-500 mt-0.5 ml-4 truncate">{report.reason}</p>
+              )}
+              {report.messages.map((message) => (
+                <p key={message} className="text-xs text-slate-500 mt-0.5 ml-4">
+                  {message}
+                </p>
+              ))}
+            </div>
+          );
+        })}
+      </div>
+    </div>
+  );
+}
@@ -6,6 +6,8 @@ import {
   getAdapter,
 } from "@skeleton-database/embedded";
 import { DB_NAME } from "../constants";
+import { inspectFile } from "../services/file.service";
+import type { DocumentIdentity } from "../types/document";
 
 let initPromise: Promise<void> | null = null;
 
@@ -27,6 +29,8 @@ async function doInit(): Promise<void> {
   initialize(new TauriSqlAdapter(db));
   await runMigrations();
   await runAppMigrations();
+  // Hashes every file in the recents list, so it runs in the background
+  backfillFingerprints().catch((err) => console.error("Failed to fingerprint documents:", err));
 }
 
 /**
@@ -75,6 +79,19 @@ async function runAppMigrations(): Promise<void> {
     )
   `);
 
+  // What documents are recognised by besides their path (see
+  // DocumentIdentity); added after the tables above were created
+  await addColumnIfMissing("documents", "fingerprint", "TEXT");
+  await addColumnIfMissing("documents", "pdf_id", "TEXT");
+  await addColumnIfMissing("annotations", "document_fingerprint", "TEXT");
+  await addColumnIfMissing("annotations", "document_pdf_id", "TEXT");
+  await adapter.execute(
+    "CREATE INDEX IF NOT EXISTS documents_fingerprint ON documents (fingerprint)"
+  );
+  await adapter.execute(
+    "CREATE INDEX IF NOT EXISTS annotations_fingerprint ON annotations (document_fingerprint)"
+  );
+
   await adapter.execute(`
     CREATE TABLE IF NOT EXISTS app_settings (
       key TEXT PRIMARY KEY,
@@ -83,6 +100,41 @@ async function runAppMigrations(): Promise<void> {
   `);
 }
 
+async function addColumnIfMissing(table: string, column: string, type: string): Promise<void> {
+  const adapter = getAdapter();
+  const columns: Array<{ name: string }> = await adapter.select(`PRAGMA table_info(${table})`);
+  if (!columns.some((c) => c.name === column)) {
+    await adapter.execute(`ALTER TABLE ${table} ADD COLUMN ${column} ${type}`);
+  }
+}
+
+/**
+ * Give rows stored before fingerprints existed the fingerprint of their
+ * file, so it can be found again once it moves. Rows whose file is already
+ * gone stay keyed by path alone, and are tried again next launch.
+ */
+async function backfillFingerprints(): Promise<void> {
+  const adapter = getAdapter();
+  const rows: Array<{ path: string }> = await adapter.select(
+    `SELECT file_path AS path FROM documents WHERE fingerprint IS NULL
+     UNION
+     SELECT document_path FROM annotations WHERE document_fingerprint IS NULL`
+  );
+  for (const { path } of rows) {
+    const info = await inspectFile(path).catch(() => null);
+    if (!info?.fingerprint) continue;
+    await adapter.execute(
+      "UPDATE documents SET fingerprint = ?, pdf_id = ? WHERE file_path = ? AND fingerprint IS NULL",
+      [info.fingerprint, info.pdfId, path]
+    );
+    await adapter.execute(
+      `UPDATE annotations SET document_fingerprint = ?, document_pdf_id = ?
+       WHERE document_path = ? AND document_fingerprint IS NULL`,
+      [info.fingerprint, info.pdfId, path]
+    );
+  }
+}
+
 // Helper to ensure DB is initialized before any query
 async function db() {
   await initDatabase();
@@ -92,23 +144,49 @@ async function db() {
 // --- Documents ---
 
 export async function addRecentDocument(
-  filePath: string,
+  identity: DocumentIdentity,
   fileName: string,
   fileSize: number,
   pageCount: number
 ): Promise<void> {
   const adapter = await db();
   await adapter.execute(
-    `INSERT INTO documents (file_path, file_name, file_size, page_count, last_opened)
-     VALUES (?, ?, ?, ?, datetime('now'))
+    `INSERT INTO documents (file_path, file_name, file_size, page_count, fingerprint, pdf_id, last_opened)
+     VALUES (?, ?, ?, ?, ?, ?, datetime('now'))
      ON CONFLICT(file_path) DO UPDATE SET
        last_opened = datetime('now'),
        file_size = excluded.file_size,
-       page_count = excluded.page_count`,
-    [filePath, fileName, fileSize, pageCount]
+       page_count = excluded.page_count,
+       fingerprint = excluded.fingerprint,
+       pdf_id = excluded.pdf_id`,
+    [identity.path, fileName, fileSize, pageCount, identity.fingerprint, identity.pdfId]
   );
 }
 
+/** Paths of other recent documents with the same contents as `identity`. */
+export async function getDocumentsWithSameContent(identity: DocumentIdentity): Promise<string[]> {
+  if (!identity.fingerprint) return [];
+  const adapter = await db();
+  const rows: Array<{ file_path: string }> = await adapter.select(
+    "SELECT file_path FROM documents WHERE fingerprint = ? AND file_path <> ?",
+    [identity.fingerprint, identity.path]
+  );
+  return rows.map((r) => r.file_path);
+}
+
+/**
+ * The document at `oldPath` was moved to `newPath`: its annotations follow
+ * it, and its recents entry is dropped in favour of the new one.
+ */
+export async function relinkDocument(oldPath: string, newPath: string): Promise<void> {
+  const adapter = await db();
+  await adapter.execute("UPDATE annotations SET document_path = ? WHERE document_path = ?", [
+    newPath,
+    oldPath,
+  ]);
+  await adapter.execute("DELETE FROM documents WHERE file_path = ?", [oldPath]);
+}
+
 export async function getRecentDocuments(limit = 20): Promise<Array<{
   id: number;
   file_path: string;
@@ -124,6 +202,15 @@ export async function getRecentDocuments(limit = 20): Promise<Array<{
   );
 }
 
+/** Paths of every recent document, most recently opened first. */
+export async function getRecentDocumentPaths(): Promise<string[]> {
+  const adapter = await db();
+  const rows: Array<{ file_path: string }> = await adapter.select(
+    "SELECT file_path FROM documents ORDER BY last_opened DESC"
+  );
+  return rows.map((r) => r.file_path);
+}
+
 export async function removeRecentDocument(filePath: string): Promise<void> {
   const adapter = await db();
   await adapter.execute("DELETE FROM documents WHERE file_path = ?", [filePath]);
@@ -169,7 +256,7 @@ export async function deleteSignature(id: number): Promise<void> {
 
 export async function saveAnnotation(annotation: {
   id: string;
-  documentPath: string;
+  document: DocumentIdentity;
   pageNumber: number;
   type: "text" | "signature";
   x: number;
@@ -184,11 +271,13 @@ export async function saveAnnotation(annotation: {
   const adapter = await db();
   await adapter.execute(
     `INSERT OR REPLACE INTO annotations
-     (id, document_path, page_number, type, x, y, width, height, text_content, font_size, color, signature_id)
-     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+     (id, document_path, document_fingerprint, document_pdf_id, page_number, type, x, y, width, height, text_content, font_size, color, signature_id)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
     [
       annotation.id,
-      annotation.documentPath,
+      annotation.document.path,
+      annotation.document.fingerprint,
+      annotation.document.pdfId,
       annotation.pageNumber,
       annotation.type,
       annotation.x,
@@ -203,9 +292,17 @@ export async function saveAnnotation(annotation: {
   );
 }
 
-export async function getAnnotationsForDocument(documentPath: string): Promise<Array<{
+/**
+ * The annotations of a document. When several stored documents match, the
+ * one with the same contents wins, preferring the same path among those;
+ * then the same path (the file was changed by another program); then the
+ * same PDF /ID (it was moved and edited elsewhere).
+ */
+export async function getAnnotationsForDocument(document: DocumentIdentity): Promise<Array<{
   id: string;
   document_path: string;
+  document_fingerprint: string | null;
+  document_pdf_id: string | null;
   page_number: number;
   type: string;
   x: number;
@@ -219,8 +316,21 @@ export async function getAnnotationsForDocument(documentPath: string): Promise<A
 }>> {
   const adapter = await db();
   return adapter.select(
-    "SELECT * FROM annotations WHERE document_path = ? ORDER BY page_number, created_at",
-    [documentPath]
+    `SELECT * FROM annotations WHERE document_path = (
+       SELECT document_path FROM annotations
+       WHERE document_path = ? OR document_fingerprint = ? OR document_pdf_id = ?
+       GROUP BY document_path
+       ORDER BY MAX(document_fingerprint = ?) DESC, MAX(document_path = ?) DESC, MAX(created_at) DESC
+       LIMIT 1
+     )
+     ORDER BY page_number, created_at`,
+    [
+      document.path,
+      document.fingerprint,
+      document.pdfId,
+      document.fingerprint,
+      document.path,
+    ]
   );
 }
 
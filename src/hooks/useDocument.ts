@@ -1,50 +1,113 @@
-import { useState, useEffect, useCallback } from "react";
+import { useState, useEffect, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { getRecentDocuments, addRecentDocument, clearRecentDocuments } from "../db/sqlite";
-import type { RecentDocument, FileType } from "../types/document";
-import { detectFileType } from "../types/document";
-
-declare global {
-  interface Window {
-    __OPENED_FILE__?: string;
-  }
-}
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import {
+  getRecentDocuments,
+  getRecentDocumentPaths,
+  addRecentDocument,
+  clearRecentDocuments,
+  getDocumentsWithSameContent,
+  relinkDocument,
+} from "../db/sqlite";
+import type {
+  RecentDocument,
+  FileType,
+  RangedFile,
+  ExternalChange,
+  FileInfo,
+  LockOwner,
+} from "../types/document";
+import { detectFileType, documentIdentity, viewerFileType } from "../types/document";
+import { errorMessage } from "../utils/errors";
+import {
+  getFileSize,
+  inspectFile,
+  lockDocument,
+  readFileRaw,
+  unlockDocument,
+  unwatchFile,
+  writeFile,
+} from "../services/file.service";
+import { indexDocument, indexLibrary } from "../services/library.service";
+import {
+  getWindowDocument,
+  openDocumentWindow,
+  setWindowDocument,
+} from "../services/workspace.service";
 
 interface DocumentState {
   filePath: string | null;
   fileName: string | null;
   fileBytes: Uint8Array | null;
+  /** Set instead of fileBytes for large PDFs, which pdf.js reads a range
+   *  at a time (see usePdfViewer). */
+  rangedFile: RangedFile | null;
   fileType: FileType;
+  /** What the file's contents turned out to be; null for new documents and
+   *  files that couldn't be inspected. */
+  fileInfo: FileInfo | null;
+  /** Someone else has the file open for editing: it is open read-only. */
+  lockedBy: LockOwner | null;
 }
 
-/** Max file size (in bytes) that can reliably pass through Tauri JSON IPC.
- *  Beyond this, the JSON array serialization becomes very slow. */
-const MAX_FILE_SIZE_BYTES = 50 * 1024 * 1024; // 50 MB
+/** PDFs larger than this (in bytes) are not read up front: pdf.js fetches
+ *  the ranges it needs for the pages on screen. */
+const RANGED_LOAD_THRESHOLD_BYTES = 20 * 1024 * 1024; // 20 MB
+
+interface UseDocumentOptions {
+  /** Called with a message for the user when a file can't be opened. */
+  onError?: (message: string) => void;
+}
 
 interface UseDocumentReturn {
   document: DocumentState;
   recentDocuments: RecentDocument[];
   openFile: () => Promise<void>;
+  openFileInNewWindow: () => Promise<void>;
   openFilePath: (path: string) => Promise<void>;
   openNew: (bytes: Uint8Array, fileName: string, fileType: FileType) => void;
   saveFile: (bytes: Uint8Array, path?: string) => Promise<void>;
   updateDocumentPath: (filePath: string, fileName: string) => void;
   closeFile: () => void;
+  /** Set when another program changed or removed the open file. */
+  externalChange: ExternalChange | null;
+  /** Read the open file again, dropping unsaved changes. */
+  reloadFile: () => Promise<void>;
+  dismissExternalChange: () => void;
   clearRecents: () => Promise<void>;
   isLoading: boolean;
-  fileSizeWarning: string | null;
 }
 
-export function useDocument(): UseDocumentReturn {
+export function useDocument({ onError }: UseDocumentOptions = {}): UseDocumentReturn {
   const [document, setDocument] = useState<DocumentState>({
     filePath: null,
     fileName: null,
     fileBytes: null,
+    rangedFile: null,
     fileType: "unknown",
+    fileInfo: null,
+    lockedBy: null,
   });
   const [recentDocuments, setRecentDocuments] = useState<RecentDocument[]>([]);
   const [isLoading, setIsLoading] = useState(false);
-  const [fileSizeWarning, setFileSizeWarning] = useState<string | null>(null);
+  const [externalChange, setExternalChange] = useState<ExternalChange | null>(null);
+  const filePathRef = useRef<string | null>(null);
+  const onErrorRef = useRef(onError);
+  onErrorRef.current = onError;
+
+  useEffect(() => {
+    filePathRef.current = document.filePath;
+    setExternalChange(null);
+    // Reading the file started watching it, and opening it locked it; stop
+    // both once it's closed or replaced
+    const path = document.filePath;
+    return () => {
+      if (!path) return;
+      unwatchFile(path).catch(() => {});
+      unlockDocument(path).catch(() => {});
+    };
+  }, [document.filePath]);
 
   const loadRecentDocuments = async () => {
     try {
@@ -65,36 +128,70 @@ export function useDocument(): UseDocumentReturn {
   };
 
   const openFilePath = useCallback(async (path: string) => {
+    // Already open in another window: that window has been brought forward
+    if (!(await setWindowDocument(path).catch(() => true))) return;
+
     setIsLoading(true);
-    setFileSizeWarning(null);
     try {
-      const bytes: number[] = await invoke("read_file_bytes", { path });
-      const uint8 = new Uint8Array(bytes);
       const fileName = path.split(/[\\/]/).pop() || "unknown.pdf";
+      // The extension can lie (an HTML page saved as .pdf, RTF saved as .doc)
+      const fileInfo = await inspectFile(path).catch((err) => {
+        console.error("Failed to inspect file:", err);
+        return null;
+      });
+      const fileType = fileInfo ? viewerFileType(fileInfo) : detectFileType(fileName);
+      const fileSize = await getFileSize(path);
+      // Without a lock (a read-only folder, say) the file still opens
+      const lockedBy = await lockDocument(path).catch((err) => {
+        console.error("Failed to lock file:", err);
+        return null;
+      });
 
-      if (uint8.length > MAX_FILE_SIZE_BYTES) {
-        const sizeMB = (uint8.length / (1024 * 1024)).toFixed(1);
-        setFileSizeWarning(
-          `This file is ${sizeMB} MB. Files over 50 MB may be slow to load.`
-        );
+      if (fileType === "pdf" && fileSize > RANGED_LOAD_THRESHOLD_BYTES) {
+        setDocument({
+          filePath: path,
+          fileName,
+          fileBytes: null,
+          rangedFile: { path, size: fileSize },
+          fileType,
+          fileInfo,
+          lockedBy,
+        });
+      } else {
+        setDocument({
+          filePath: path,
+          fileName,
+          fileBytes: await readFileRaw(path),
+          rangedFile: null,
+          fileType,
+          fileInfo,
+          lockedBy,
+        });
       }
 
-      setDocument({
-        filePath: path,
-        fileName,
-        fileBytes: uint8,
-        fileType: detectFileType(fileName),
-      });
-
-      // Record in recent documents
+      // Record in recent documents. An entry for the same contents at a
+      // path that no longer exists is this file before it was moved.
       try {
-        await addRecentDocument(path, fileName, uint8.length, 0);
+        const identity = documentIdentity(path, fileInfo);
+        for (const oldPath of await getDocumentsWithSameContent(identity)) {
+          const moved = await getFileSize(oldPath).then(() => false, () => true);
+          if (moved) await relinkDocument(oldPath, path);
+        }
+        await addRecentDocument(identity, fileName, fileSize, 0);
         await loadRecentDocuments();
       } catch (dbErr) {
         console.error("Failed to record recent document:", dbErr);
       }
+
+      // Make it findable from library search; runs in the background
+      if (/\.(pdf|docx)$/i.test(fileName)) {
+        indexDocument(path).catch((err) => console.error("Failed to index document:", err));
+      }
     } catch (err) {
       console.error("Failed to read file:", err);
+      onErrorRef.current?.(errorMessage(err, "Failed to open file"));
+      setWindowDocument(filePathRef.current).catch(() => {});
+      if (path !== filePathRef.current) unlockDocument(path).catch(() => {});
     } finally {
       setIsLoading(false);
     }
@@ -115,6 +212,15 @@ export function useDocument(): UseDocumentReturn {
     }
   }, [openFilePath]);
 
+  const openFileInNewWindow = useCallback(async () => {
+    try {
+      const selected: string | null = await invoke("open_file_dialog");
+      if (selected) await openDocumentWindow(selected);
+    } catch (err) {
+      console.error("Failed to open window:", err);
+    }
+  }, []);
+
   /**
    * Save (or "Save As") the PDF bytes.
    * If a path is provided, save there; otherwise save to the current path.
@@ -125,10 +231,7 @@ export function useDocument(): UseDocumentReturn {
       if (!savePath) return;
 
       try {
-        await invoke("write_file_bytes", {
-          path: savePath,
-          data: Array.from(bytes),
-        });
+        await writeFile(savePath, bytes);
       } catch (err) {
         console.error("Failed to save file:", err);
       }
@@ -137,68 +240,115 @@ export function useDocument(): UseDocumentReturn {
   );
 
   const openNew = useCallback((bytes: Uint8Array, fileName: string, fileType: FileType) => {
-    setDocument({ filePath: null, fileName, fileBytes: bytes, fileType });
+    setDocument({
+      filePath: null,
+      fileName,
+      fileBytes: bytes,
+      rangedFile: null,
+      fileType,
+      fileInfo: null,
+      lockedBy: null,
+    });
+    setWindowDocument(null).catch(() => {});
   }, []);
 
   const updateDocumentPath = useCallback((filePath: string, fileName: string) => {
-    setDocument((prev) => ({ ...prev, filePath, fileName }));
+    setDocument((prev) => ({ ...prev, filePath, fileName, lockedBy: null }));
+    setWindowDocument(filePath).catch(() => {});
+    lockDocument(filePath)
+      .then((lockedBy) => setDocument((prev) => ({ ...prev, lockedBy })))
+      .catch((err) => console.error("Failed to lock file:", err));
   }, []);
 
   const closeFile = useCallback(() => {
+    setWindowDocument(null).catch(() => {});
     setDocument({
       filePath: null,
       fileName: null,
       fileBytes: null,
+      rangedFile: null,
       fileType: "unknown",
+      fileInfo: null,
+      lockedBy: null,
     });
   }, []);
 
+  const reloadFile = useCallback(async () => {
+    const path = filePathRef.current;
+    setExternalChange(null);
+    if (path) await openFilePath(path);
+  }, [openFilePath]);
+
+  const dismissExternalChange = useCallback(() => setExternalChange(null), []);
+
   const clearRecents = useCallback(async () => {
     try {
       await clearRecentDocuments();
       setRecentDocuments([]);
+      await indexLibrary([]);
     } catch (err) {
       console.error("Failed to clear recent documents:", err);
     }
   }, []);
 
-  // Load recent documents on mount
+  // Load recent documents on mount, then bring the search index up to date
   useEffect(() => {
-    loadRecentDocuments();
+    loadRecentDocuments().then(() =>
+      getRecentDocumentPaths()
+        .then(indexLibrary)
+        .catch((err) => console.error("Failed to update search index:", err))
+    );
   }, []);
 
-  // Check for "Open With" file on mount
+  // Open the document this window was created for ("Open With", another
+  // launch, or the previous session)
   useEffect(() => {
-    const checkOpenWith = async () => {
-      if (window.__OPENED_FILE__) {
-        const filePath = window.__OPENED_FILE__;
-        delete window.__OPENED_FILE__;
-        await openFilePath(filePath);
-        return;
-      }
-      try {
-        const filePath: string | null = await invoke("get_file_opened_with");
-        if (filePath) {
-          await openFilePath(filePath);
-        }
-      } catch {
-        // No file was passed — that's fine
-      }
+    getWindowDocument()
+      .then((filePath) => {
+        if (filePath) openFilePath(filePath);
+      })
+      .catch((err) => console.error("Failed to get window document:", err));
+  }, [openFilePath]);
+
+  // Files opened from elsewhere while this window has no document
+  useEffect(() => {
+    const unlisten = getCurrentWebviewWindow().listen<string>("open-document", (event) => {
+      openFilePath(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
     };
-    checkOpenWith();
   }, [openFilePath]);
 
+  // Changes to the open file made by other programs. The watcher reports
+  // every watched file to every window, so keep only this window's.
+  useEffect(() => {
+    const unlisteners = (["changed", "deleted"] as const).map((kind) =>
+      listen<{ path: string }>(`document-${kind}`, (event) => {
+        if (event.payload.path === filePathRef.current) {
+          setExternalChange({ kind, path: event.payload.path });
+        }
+      })
+    );
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
+  }, []);
+
   return {
     document,
     recentDocuments,
     openFile,
+    openFileInNewWindow,
     openFilePath,
     openNew,
     saveFile,
     updateDocumentPath,
     closeFile,
+    externalChange,
+    reloadFile,
+    dismissExternalChange,
     clearRecents,
     isLoading,
-    fileSizeWarning,
   };
 }
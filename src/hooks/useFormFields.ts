@@ -0,0 +1,34 @@
+import { useState, useEffect, useCallback } from "react";
+import type { FormField } from "../types/pdf";
+import { listFormFields } from "../services/pdf.service";
+
+interface UseFormFieldsReturn {
+  fields: FormField[];
+  refresh: () => Promise<void>;
+}
+
+/**
+ * Load the AcroForm fields of the open PDF whenever the file changes.
+ */
+export function useFormFields(filePath: string | null, isPdf: boolean): UseFormFieldsReturn {
+  const [fields, setFields] = useState<FormField[]>([]);
+
+  const refresh = useCallback(async () => {
+    if (!filePath || !isPdf) {
+      setFields([]);
+      return;
+    }
+    try {
+      setFields(await listFormFields(filePath));
+    } catch (err) {
+      console.error("Failed to read form fields:", err);
+      setFields([]);
+    }
+  }, [filePath, isPdf]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  return { fields, refresh };
+}
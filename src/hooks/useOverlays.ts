@@ -1,4 +1,4 @@
-import { useState, useCallback, useRef } from "react";
+import { useState, useCallback, useMemo, useRef } from "react";
 import type {
   Annotation,
   TextAnnotation,
@@ -14,7 +14,9 @@ import {
   deleteAnnotation as deleteAnnotationFromDb,
   deleteAnnotationsForDocument,
 } from "../db/sqlite";
+import { getFileSize } from "../services/file.service";
 import { TEXT_DEFAULT_FONT_SIZE } from "../constants";
+import type { DocumentIdentity } from "../types/document";
 
 interface UseOverlaysReturn {
   annotations: Annotation[];
@@ -33,8 +35,25 @@ interface UseOverlaysReturn {
   setSelectedId: (id: string | null) => void;
   newAnnotationId: string | null;
   clearNewAnnotationId: () => void;
-  loadAnnotations: (documentPath: string) => Promise<void>;
-  saveAllAnnotations: (documentPath: string) => Promise<void>;
+  loadAnnotations: (document: DocumentIdentity) => Promise<void>;
+  /** Replace the overlays, e.g. with ones recovered after a crash. */
+  replaceAnnotations: (annotations: Annotation[]) => void;
+  /**
+   * Add overlays read from the PDF's own annotations, except those already
+   * loaded from the database (which may have been edited since).
+   */
+  importAnnotations: (imported: Annotation[]) => void;
+  /**
+   * Ids of imported overlays that have been deleted, so saving removes
+   * their annotations from the PDF too.
+   */
+  removedImportedIds: string[];
+  /**
+   * Add overlays from another file, e.g. an XFDF export, except those
+   * already present. Unlike importAnnotations they are new to the PDF.
+   */
+  addAnnotations: (added: Annotation[]) => void;
+  saveAllAnnotations: (document: DocumentIdentity) => Promise<void>;
 }
 
 function generateId(): string {
@@ -46,20 +65,34 @@ export function useOverlays(): UseOverlaysReturn {
   const [mode, setMode] = useState<PdfMode>("view");
   const [selectedId, setSelectedId] = useState<string | null>(null);
   const [newAnnotationId, setNewAnnotationId] = useState<string | null>(null);
+  const [importedIds, setImportedIds] = useState<string[]>([]);
   const annotationsRef = useRef<Annotation[]>(annotations);
   annotationsRef.current = annotations;
 
+  const removedImportedIds = useMemo(() => {
+    const current = new Set(annotations.map((a) => a.id));
+    return importedIds.filter((id) => !current.has(id));
+  }, [annotations, importedIds]);
+
   const clearNewAnnotationId = useCallback(() => {
     setNewAnnotationId(null);
   }, []);
 
-  const loadAnnotations = useCallback(async (documentPath: string) => {
+  const loadAnnotations = useCallback(async (document: DocumentIdentity) => {
     try {
-      const rows = await getAnnotationsForDocument(documentPath);
+      const rows = await getAnnotationsForDocument(document);
+      // Rows saved for a file that still exists elsewhere belong to that
+      // copy: take new ids so saving here doesn't move them off it.
+      const source = rows[0]?.document_path;
+      const copied =
+        source !== undefined &&
+        source !== document.path &&
+        (await getFileSize(source).then(() => true, () => false));
       const loaded: Annotation[] = rows.map((row) => {
+        const id = copied ? generateId() : row.id;
         if (row.type === "text") {
           return {
-            id: row.id,
+            id,
             pageNumber: row.page_number,
             x: row.x,
             y: row.y,
@@ -71,7 +104,7 @@ export function useOverlays(): UseOverlaysReturn {
           } as TextAnnotation;
         } else {
           return {
-            id: row.id,
+            id,
             pageNumber: row.page_number,
             x: row.x,
             y: row.y,
@@ -82,11 +115,32 @@ export function useOverlays(): UseOverlaysReturn {
         }
       });
       setAnnotations(loaded);
+      setImportedIds([]);
     } catch (err) {
       console.error("Failed to load annotations:", err);
     }
   }, []);
 
+  const replaceAnnotations = useCallback((replacement: Annotation[]) => {
+    setAnnotations(replacement);
+    setSelectedId(null);
+  }, []);
+
+  const importAnnotations = useCallback((imported: Annotation[]) => {
+    setImportedIds(imported.map((a) => a.id));
+    setAnnotations((prev) => {
+      const existing = new Set(prev.map((a) => a.id));
+      return [...prev, ...imported.filter((a) => !existing.has(a.id))];
+    });
+  }, []);
+
+  const addAnnotations = useCallback((added: Annotation[]) => {
+    setAnnotations((prev) => {
+      const existing = new Set(prev.map((a) => a.id));
+      return [...prev, ...added.filter((a) => !existing.has(a.id))];
+    });
+  }, []);
+
   const addTextAnnotation = useCallback(
     (pageNumber: number, x: number, y: number): TextAnnotation => {
       const annotation: TextAnnotation = {
@@ -152,20 +206,22 @@ export function useOverlays(): UseOverlaysReturn {
   }, []);
 
   const saveAllAnnotations = useCallback(
-    async (documentPath: string) => {
+    async (document: DocumentIdentity) => {
       // Read from ref to always get the latest annotations,
       // avoiding stale closure when this callback is captured elsewhere.
       const current = annotationsRef.current;
       try {
-        // Delete existing annotations for this document, then re-insert all
-        await deleteAnnotationsForDocument(documentPath);
+        // Delete existing annotations for this document, then re-insert all.
+        // Ones loaded from a moved file move here; a copy's were given
+        // new ids when loaded, so the original keeps its own.
+        await deleteAnnotationsForDocument(document.path);
 
         for (const ann of current) {
           if (isTextAnnotation(ann)) {
             const textAnn = ann as TextAnnotation;
             await saveAnnotation({
               id: textAnn.id,
-              documentPath,
+              document,
               pageNumber: textAnn.pageNumber,
               type: "text",
               x: textAnn.x,
@@ -180,7 +236,7 @@ export function useOverlays(): UseOverlaysReturn {
             const sigAnn = ann as SignatureAnnotation;
             await saveAnnotation({
               id: sigAnn.id,
-              documentPath,
+              document,
               pageNumber: sigAnn.pageNumber,
               type: "signature",
               x: sigAnn.x,
@@ -211,6 +267,10 @@ export function useOverlays(): UseOverlaysReturn {
     newAnnotationId,
     clearNewAnnotationId,
     loadAnnotations,
+    replaceAnnotations,
+    importAnnotations,
+    removedImportedIds,
+    addAnnotations,
     saveAllAnnotations,
   };
 }
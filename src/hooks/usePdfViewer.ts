@@ -6,6 +6,8 @@ import {
   PDF_MAX_SCALE,
   PDF_SCALE_STEP,
 } from "../constants";
+import type { RangedFile } from "../types/document";
+import { readFileRange } from "../services/file.service";
 
 // Set up pdf.js worker — use Vite's URL resolution for the worker file.
 // The ?url suffix ensures Vite emits the file and returns a resolved path,
@@ -13,6 +15,26 @@ import {
 import pdfWorkerUrl from "pdfjs-dist/build/pdf.worker.mjs?url";
 pdfjsLib.GlobalWorkerOptions.workerSrc = pdfWorkerUrl;
 
+/** Size of the ranges pdf.js requests from a RangedFile. */
+const RANGE_CHUNK_SIZE = 1024 * 1024;
+
+/**
+ * Feeds pdf.js from the file on disk: it asks for the byte ranges it needs
+ * (the xref, then the objects of the pages being rendered) and each one is
+ * read natively with read_file_range.
+ */
+class FileRangeTransport extends pdfjsLib.PDFDataRangeTransport {
+  constructor(private readonly path: string, length: number) {
+    super(length, null);
+  }
+
+  requestDataRange(begin: number, end: number) {
+    readFileRange(this.path, begin, end - begin)
+      .then((chunk) => this.onDataRange(begin, chunk))
+      .catch((err) => console.error("Failed to read PDF range:", err));
+  }
+}
+
 interface UsePdfViewerReturn {
   pdfDoc: pdfjsLib.PDFDocumentProxy | null;
   pageCount: number;
@@ -26,7 +48,13 @@ interface UsePdfViewerReturn {
   zoomOut: () => void;
   resetZoom: () => void;
   setScale: (scale: number) => void;
-  loadDocument: (bytes: Uint8Array) => Promise<number>;
+  /** Rejects with a PasswordException if the PDF needs a password it wasn't given. */
+  loadDocument: (source: Uint8Array | RangedFile, password?: string) => Promise<number>;
+  /**
+   * Stop drawing the given annotations (pdf.js ids such as "12R"), e.g.
+   * those shown as editable overlays instead.
+   */
+  hideAnnotations: (objectRefs: string[]) => void;
   error: string | null;
 }
 
@@ -36,6 +64,8 @@ export function usePdfViewer(): UsePdfViewerReturn {
   const [currentPage, setCurrentPage] = useState(1);
   const [scale, setScaleState] = useState(PDF_DEFAULT_SCALE);
   const [error, setError] = useState<string | null>(null);
+  // Bumped when annotations are hidden, so rendered pages are redrawn
+  const [hiddenVersion, setHiddenVersion] = useState(0);
   const renderTaskRef = useRef<Map<number, pdfjsLib.RenderTask>>(new Map());
   const pdfDocRef = useRef<pdfjsLib.PDFDocumentProxy | null>(null);
   const scaleRef = useRef(PDF_DEFAULT_SCALE);
@@ -57,33 +87,46 @@ export function usePdfViewer(): UsePdfViewerReturn {
     };
   }, []);
 
-  const loadDocument = useCallback(async (bytes: Uint8Array): Promise<number> => {
-    setError(null);
-
-    // Destroy previous document using ref to avoid stale closure
-    if (pdfDocRef.current) {
-      await pdfDocRef.current.destroy();
-      pdfDocRef.current = null;
-    }
-
-    // Cancel all in-flight renders from the old document
-    renderTaskRef.current.forEach((task) => task.cancel());
-    renderTaskRef.current.clear();
+  const loadDocument = useCallback(
+    async (source: Uint8Array | RangedFile, password?: string): Promise<number> => {
+      setError(null);
 
-    // Copy the bytes — pdf.js transfers the ArrayBuffer to its web worker,
-    // which neuters the original Uint8Array (byteLength becomes 0).
-    // The caller keeps the original for later use (e.g. flatten/save).
-    const loadingTask = pdfjsLib.getDocument({ data: bytes.slice() });
-    const doc = await loadingTask.promise;
+      // Destroy previous document using ref to avoid stale closure
+      if (pdfDocRef.current) {
+        await pdfDocRef.current.destroy();
+        pdfDocRef.current = null;
+      }
 
-    pdfDocRef.current = doc;
-    setPdfDoc(doc);
-    setPageCount(doc.numPages);
-    setCurrentPage(1);
-    setScaleState(PDF_DEFAULT_SCALE);
+      // Cancel all in-flight renders from the old document
+      renderTaskRef.current.forEach((task) => task.cancel());
+      renderTaskRef.current.clear();
 
-    return doc.numPages;
-  }, []);
+      // Copy the bytes — pdf.js transfers the ArrayBuffer to its web worker,
+      // which neuters the original Uint8Array (byteLength becomes 0).
+      // The caller keeps the original for later use (e.g. flatten/save).
+      // A RangedFile is never read whole; disableAutoFetch keeps pdf.js from
+      // pulling in the rest of the file in the background.
+      const loadingTask =
+        source instanceof Uint8Array
+          ? pdfjsLib.getDocument({ data: source.slice(), password })
+          : pdfjsLib.getDocument({
+              range: new FileRangeTransport(source.path, source.size),
+              rangeChunkSize: RANGE_CHUNK_SIZE,
+              disableAutoFetch: true,
+              password,
+            });
+      const doc = await loadingTask.promise;
+
+      pdfDocRef.current = doc;
+      setPdfDoc(doc);
+      setPageCount(doc.numPages);
+      setCurrentPage(1);
+      setScaleState(PDF_DEFAULT_SCALE);
+
+      return doc.numPages;
+    },
+    []
+  );
 
   // Use pdfDocRef inside renderPage to avoid stale closures — the callback
   // identity changes only when scale changes (to trigger re-renders in
@@ -117,9 +160,13 @@ export function usePdfViewer(): UsePdfViewerReturn {
 
       ctx.scale(dpr, dpr);
 
+      // ENABLE_STORAGE applies hideAnnotations' annotationStorage entries.
+      // It also draws form fields from their appearance streams, as there
+      // is no annotation layer to show them otherwise.
       const renderTask = page.render({
         canvasContext: ctx,
         viewport,
+        annotationMode: pdfjsLib.AnnotationMode.ENABLE_STORAGE,
       });
 
       renderTaskRef.current.set(pageNum, renderTask);
@@ -135,10 +182,20 @@ export function usePdfViewer(): UsePdfViewerReturn {
       }
     },
     // eslint-disable-next-line react-hooks/exhaustive-deps -- reads from refs;
-    // scale is included only so PdfPage re-triggers its effect on zoom change
-    [scale]
+    // scale and hiddenVersion are included only so PdfPage re-triggers its
+    // effect on zoom change or when annotations are hidden
+    [scale, hiddenVersion]
   );
 
+  const hideAnnotations = useCallback((objectRefs: string[]) => {
+    const doc = pdfDocRef.current;
+    if (!doc || objectRefs.length === 0) return;
+    for (const ref of objectRefs) {
+      doc.annotationStorage.setValue(ref, { noView: true });
+    }
+    setHiddenVersion((v) => v + 1);
+  }, []);
+
   const nextPage = useCallback(() => {
     setCurrentPage((prev) => Math.min(prev + 1, pageCount));
   }, [pageCount]);
@@ -0,0 +1,70 @@
+import { useEffect, useCallback, useRef } from "react";
+import type { FileType } from "../types/document";
+import { clearRecoverySnapshot, saveRecoverySnapshot } from "../services/recovery.service";
+
+/** How often unsaved work is written to the recovery store. */
+const AUTOSAVE_INTERVAL_MS = 30 * 1000;
+
+interface RecoveryDocument {
+  filePath: string | null;
+  fileName: string | null;
+  fileType: FileType;
+}
+
+interface UseRecoveryReturn {
+  /**
+   * The document's current state is saved (or was just loaded): drop its
+   * snapshot, and only snapshot again once the content changes.
+   */
+  markClean: () => void;
+}
+
+/**
+ * Autosave for crash recovery. `getContent` returns what would be lost in
+ * a crash (null without a document); whenever it differs from the last
+ * clean or snapshotted state, it is written to the recovery store.
+ */
+export function useRecovery(
+  document: RecoveryDocument,
+  getContent: () => string | null
+): UseRecoveryReturn {
+  const getContentRef = useRef(getContent);
+  getContentRef.current = getContent;
+  const documentRef = useRef(document);
+  documentRef.current = document;
+  // The content as of the last save, load or snapshot
+  const savedContentRef = useRef<string | null>(null);
+  // Set by markClean until the next render, which has the state it meant
+  const cleanPendingRef = useRef(false);
+
+  const markClean = useCallback(() => {
+    savedContentRef.current = getContentRef.current();
+    cleanPendingRef.current = true;
+    clearRecoverySnapshot().catch((err) =>
+      console.error("Failed to clear recovery snapshot:", err)
+    );
+  }, []);
+
+  useEffect(() => {
+    if (!cleanPendingRef.current) return;
+    cleanPendingRef.current = false;
+    savedContentRef.current = getContentRef.current();
+  });
+
+  useEffect(() => {
+    const timer = setInterval(() => {
+      const { filePath, fileName, fileType } = documentRef.current;
+      const content = getContentRef.current();
+      if (cleanPendingRef.current || content === null || !fileName) return;
+      if (content === savedContentRef.current) return;
+      saveRecoverySnapshot({ filePath, fileName, fileType, content })
+        .then(() => {
+          savedContentRef.current = content;
+        })
+        .catch((err) => console.error("Failed to save recovery snapshot:", err));
+    }, AUTOSAVE_INTERVAL_MS);
+    return () => clearInterval(timer);
+  }, []);
+
+  return { markClean };
+}
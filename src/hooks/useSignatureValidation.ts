@@ -0,0 +1,48 @@
+import { useState, useEffect, useCallback } from "react";
+import type { SignatureReport } from "../types/pdf";
+import { importTrustedCertificate, verifyPdfSignatures } from "../services/pdf.service";
+
+interface UseSignatureValidationReturn {
+  reports: SignatureReport[];
+  isVerifying: boolean;
+  refresh: () => Promise<void>;
+  importCertificate: () => Promise<void>;
+}
+
+/**
+ * Verify the digital signatures of the open PDF whenever the file changes.
+ */
+export function useSignatureValidation(
+  filePath: string | null,
+  isPdf: boolean
+): UseSignatureValidationReturn {
+  const [reports, setReports] = useState<SignatureReport[]>([]);
+  const [isVerifying, setIsVerifying] = useState(false);
+
+  const refresh = useCallback(async () => {
+    if (!filePath || !isPdf) {
+      setReports([]);
+      return;
+    }
+    setIsVerifying(true);
+    try {
+      setReports(await verifyPdfSignatures(filePath));
+    } catch (err) {
+      console.error("Failed to verify signatures:", err);
+      setReports([]);
+    } finally {
+      setIsVerifying(false);
+    }
+  }, [filePath, isPdf]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  const importCertificate = useCallback(async () => {
+    const added = await importTrustedCertificate();
+    if (added) await refresh();
+  }, [refresh]);
+
+  return { reports, isVerifying, refresh, importCertificate };
+}
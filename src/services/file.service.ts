@@ -0,0 +1,104 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { BackupInfo, DocumentVersion, FileInfo, LockOwner } from "../types/document";
+
+/**
+ * Binary file reads over raw IPC (src-tauri/src/commands/documents.rs). The
+ * bytes arrive as an ArrayBuffer instead of a JSON number array, so reading
+ * costs the same however large the file is.
+ */
+
+export async function readFileRaw(path: string): Promise<Uint8Array> {
+  return new Uint8Array(await invoke<ArrayBuffer>("read_file_bytes_raw", { path }));
+}
+
+/** Read `length` bytes from `offset`; shorter if the file ends first. */
+export async function readFileRange(
+  path: string,
+  offset: number,
+  length: number
+): Promise<Uint8Array> {
+  return new Uint8Array(await invoke<ArrayBuffer>("read_file_range", { path, offset, length }));
+}
+
+export async function getFileSize(path: string): Promise<number> {
+  return invoke("get_file_size", { path });
+}
+
+/** Identify a file from its contents (src-tauri/src/commands/inspect.rs). */
+export async function inspectFile(path: string): Promise<FileInfo> {
+  return invoke("inspect_file", { path });
+}
+
+/**
+ * Reading a file also watches it (src-tauri/src/commands/watcher.rs): the
+ * "document-changed" and "document-deleted" events report changes made by
+ * other programs, and writes fail with a "file_changed" error (see
+ * utils/errors.ts) rather than replace them unless `force` is set.
+ */
+export async function writeFile(path: string, data: Uint8Array, force = false): Promise<void> {
+  await invoke("write_file_bytes", { path, data: Array.from(data), force });
+}
+
+/**
+ * Write over raw IPC, with the path in a header. Avoids the ~4x memory
+ * overhead of Array.from() + JSON encoding, which crashes on large PDFs.
+ */
+export async function writeFileRaw(path: string, data: Uint8Array, force = false): Promise<void> {
+  await invoke("write_file_bytes_raw", data, {
+    headers: { "X-File-Path": path, "X-Force-Write": String(force) },
+  });
+}
+
+export async function unwatchFile(path: string): Promise<void> {
+  await invoke("unwatch_file", { path });
+}
+
+/**
+ * Documents open for editing have an Office-style `~$name` owner file next
+ * to them (src-tauri/src/commands/locks.rs). Returns null once this window
+ * holds the lock, or whoever else has the document open; it should then be
+ * opened read-only. Writes to a document someone else has open fail.
+ */
+export async function lockDocument(path: string): Promise<LockOwner | null> {
+  return invoke("lock_document", { path });
+}
+
+export async function unlockDocument(path: string): Promise<void> {
+  await invoke("unlock_document", { path });
+}
+
+/** Whoever holds the lock on `path`, this user included. */
+export async function getLockOwner(path: string): Promise<LockOwner | null> {
+  return invoke("get_lock_owner", { path });
+}
+
+/**
+ * Saves through write_file_bytes keep the last few versions of the file in
+ * the app data folder (src-tauri/src/commands/backups.rs). Newest first.
+ */
+export async function listBackups(path: string): Promise<BackupInfo[]> {
+  return invoke("list_backups", { path });
+}
+
+/** Put a backup back in place; the current file is backed up first. */
+export async function restoreBackup(path: string, id: string): Promise<void> {
+  await invoke("restore_backup", { path, id });
+}
+
+/**
+ * Every save over a file also records its previous contents in the version
+ * history (src-tauri/src/commands/versions.rs), which, unlike backups, is
+ * never rotated. Newest first.
+ */
+export async function listVersions(path: string): Promise<DocumentVersion[]> {
+  return invoke("list_versions", { path });
+}
+
+export async function readVersion(id: number): Promise<Uint8Array> {
+  return new Uint8Array(await invoke<ArrayBuffer>("read_version", { id }));
+}
+
+/** Put a version back in place of its file; the current contents are kept as a version first. */
+export async function restoreVersion(id: number): Promise<void> {
+  await invoke("restore_version", { id });
+}
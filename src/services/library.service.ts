@@ -0,0 +1,28 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { LibraryHit } from "../types/document";
+
+/**
+ * Full-text index of recent documents, kept by the Rust side in FTS5
+ * tables in the app database (src-tauri/src/commands/library.rs).
+ */
+
+/** Index (or re-index, if it changed) the PDF or Word document at `path`. */
+export async function indexDocument(path: string): Promise<boolean> {
+  return invoke("index_document", { path });
+}
+
+/**
+ * Catch up on the documents at `paths` (the recent list) that changed since
+ * they were last indexed, and drop every other document from the index.
+ */
+export async function indexLibrary(paths: string[]): Promise<number> {
+  return invoke("index_library", { paths });
+}
+
+/**
+ * Search every indexed document, best match first. Wrap the query in
+ * double quotes to match an exact phrase.
+ */
+export async function searchLibrary(query: string, limit?: number): Promise<LibraryHit[]> {
+  return invoke("search_library", { query, limit: limit ?? null });
+}
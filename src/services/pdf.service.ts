@@ -1,278 +1,495 @@
-import { PDFDocument, rgb, StandardFonts, degrees } from "pdf-lib";
-import type { PDFFont, PDFPage } from "pdf-lib";
-import fontkit from "@pdf-lib/fontkit";
-import type { Annotation, TextAnnotation, SignatureAnnotation } from "../types/pdf";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  Annotation,
+  CertificateSummary,
+  EncryptionInfo,
+  FillFormOptions,
+  FormField,
+  FormFieldValue,
+  OptimizeOptions,
+  OptimizeReport,
+  PageText,
+  PdfSaveMode,
+  ProtectOptions,
+  RedactionArea,
+  RedactionSummary,
+  SearchHit,
+  SearchOptions,
+  SignatureAnnotation,
+  SignatureReport,
+  SignOptions,
+  SplitMode,
+  TextAnnotation,
+} from "../types/pdf";
 import { isTextAnnotation } from "../types/pdf";
 import type { Signature } from "../types/signature";
-import { SIGNATURE_DEFAULT_FONT_SIZE } from "../constants";
+import { TEXT_DEFAULT_FONT_SIZE } from "../constants";
 
 /**
- * Map hex color string to pdf-lib rgb color (DeviceRGB color space).
+ * Annotation payload accepted by the Rust PDF commands. Mirrors a row of
+ * the `annotations` table (see saveAnnotation in db/sqlite.ts).
  */
-function hexToRgb(hex: string) {
-  const h = hex.replace("#", "");
-  const r = parseInt(h.substring(0, 2), 16) / 255;
-  const g = parseInt(h.substring(2, 4), 16) / 255;
-  const b = parseInt(h.substring(4, 6), 16) / 255;
-  return rgb(r, g, b);
+export interface AnnotationRecord {
+  id: string;
+  pageNumber: number;
+  type: "text" | "signature";
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  textContent?: string;
+  fontSize?: number;
+  color?: string;
+  signatureId?: number;
 }
 
-/**
- * Convert annotation coordinates from the displayed (rotated) frame to
- * the unrotated MediaBox frame for pdf-lib drawText.
- *
- * Annotation x,y are captured from pdf.js which applies page rotation,
- * so they're relative to the top-left of the *displayed* page. pdf-lib's
- * drawText operates in the unrotated MediaBox coordinate system (origin
- * at bottom-left).
- *
- * @param x - annotation x in displayed frame (top-left origin)
- * @param y - annotation y in displayed frame (top-left origin)
- * @param fontAscent - font ascent in points (baseline offset from top of text)
- * @param page - the pdf-lib page
- * @returns { pdfX, pdfY, textRotation } in MediaBox coordinates + text rotation angle
- */
-function toMediaBoxCoords(
-  x: number,
-  y: number,
-  fontAscent: number,
-  page: PDFPage
-): { pdfX: number; pdfY: number; textRotation: number } {
-  const rotation = page.getRotation().angle;
-  const { x: mbX, y: mbY, width: mbWidth, height: mbHeight } = page.getMediaBox();
-
-  // Normalize rotation to 0, 90, 180, 270
-  const rot = ((rotation % 360) + 360) % 360;
-
-  // pdf.js renders from viewport (0,0) regardless of MediaBox origin, but
-  // pdf-lib draws in raw MediaBox coordinates, so we offset by (mbX, mbY).
-  switch (rot) {
-    case 0: {
-      // No rotation. Displayed size = mbWidth x mbHeight.
-      // pdfY: flip from top-origin to bottom-origin, offset by ascent for baseline
-      return {
-        pdfX: mbX + x,
-        pdfY: mbY + mbHeight - y - fontAscent,
-        textRotation: 0,
-      };
-    }
-    case 90: {
-      // Page rotated 90deg CW for display. Displayed size = mbHeight x mbWidth.
-      return {
-        pdfX: mbX + y,
-        pdfY: mbY + x + fontAscent,
-        textRotation: 90,
-      };
-    }
-    case 180: {
-      // Page rotated 180deg. Displayed size = mbWidth x mbHeight.
-      return {
-        pdfX: mbX + mbWidth - x,
-        pdfY: mbY + y + fontAscent,
-        textRotation: 180,
-      };
-    }
-    case 270: {
-      // Page rotated 270deg CW (= 90 CCW). Displayed size = mbHeight x mbWidth.
-      return {
-        pdfX: mbX + mbHeight - y,
-        pdfY: mbY + mbWidth - x - fontAscent,
-        textRotation: 270,
-      };
-    }
-    default: {
-      // Non-standard rotation — fall back to unrotated behavior
-      return {
-        pdfX: mbX + x,
-        pdfY: mbY + mbHeight - y - fontAscent,
-        textRotation: 0,
-      };
-    }
+export function toAnnotationRecord(annotation: Annotation): AnnotationRecord {
+  const { id, pageNumber, x, y, width, height } = annotation;
+  if (isTextAnnotation(annotation)) {
+    const textAnn = annotation as TextAnnotation;
+    return {
+      id,
+      pageNumber,
+      type: "text",
+      x,
+      y,
+      width,
+      height,
+      textContent: textAnn.text,
+      fontSize: textAnn.fontSize,
+      color: textAnn.color,
+    };
   }
+  const sigAnn = annotation as SignatureAnnotation;
+  return {
+    id,
+    pageNumber,
+    type: "signature",
+    x,
+    y,
+    width,
+    height,
+    signatureId: sigAnn.signatureId,
+  };
 }
 
 /**
- * Get the font ascent in points for a given font size.
- * The ascent is the distance from the baseline to the top of the tallest glyph.
+ * An overlay read from another file, a PDF's own annotations or an XFDF
+ * export (ImportedAnnotation in src-tauri/src/commands/exchange.rs).
  */
-function getFontAscent(font: PDFFont, fontSize: number): number {
-  // heightAtSize with descender=false returns just the ascent portion
-  return font.heightAtSize(fontSize, { descender: false });
+export interface ImportedAnnotation extends AnnotationRecord {
+  /**
+   * How an imported signature is drawn; `signatureId` is never set, the
+   * signature is matched by these instead.
+   */
+  signature: { name: string; fontFamily: string; color: string } | null;
 }
 
 /**
- * Font file paths keyed by font family name.
- * These resolve to files in public/fonts/ (served at /fonts/ by Vite/Tauri).
+ * An overlay read from a PDF's own annotations by read_pdf_annotations
+ * (NativeAnnotation in src-tauri/src/commands/annotations.rs).
  */
-const FONT_FILE_MAP: Record<string, string> = {
-  "Dancing Script": "/fonts/DancingScript-Regular.ttf",
-  "Great Vibes": "/fonts/GreatVibes-Regular.ttf",
-  Sacramento: "/fonts/Sacramento-Regular.ttf",
-};
+export interface NativeAnnotation extends ImportedAnnotation {
+  /** The annotation's object in pdf.js notation, e.g. "12R". */
+  objectRef: string;
+}
+
+/** What importAnnotationsXfdf read. */
+export interface XfdfImport {
+  annotations: ImportedAnnotation[];
+  /** Markup that can't be shown as an overlay, which was left out. */
+  skipped: number;
+  /** The file was exported from a different PDF. */
+  otherDocument: boolean;
+}
+
+/** Files annotations ("xfdf") and form data ("fdf") are exchanged in. */
+export type ExchangeFormat = "xfdf" | "fdf";
 
 /**
- * Fetch a font file as bytes for embedding in pdf-lib.
+ * Turn a record into an overlay. Signatures need `signatureId`, which
+ * records read from a PDF don't have.
  */
-async function fetchFontBytes(url: string): Promise<Uint8Array> {
-  const response = await fetch(url);
-  if (!response.ok) {
-    throw new Error(`Failed to fetch font: ${url} (${response.status})`);
+export function fromAnnotationRecord(record: AnnotationRecord): Annotation {
+  const { id, pageNumber, x, y, width, height } = record;
+  if (record.type === "text") {
+    return {
+      id,
+      pageNumber,
+      x,
+      y,
+      width,
+      height,
+      text: record.textContent || "",
+      fontSize: record.fontSize || TEXT_DEFAULT_FONT_SIZE,
+      color: record.color || "#000000",
+    } as TextAnnotation;
   }
-  const buffer = await response.arrayBuffer();
-  return new Uint8Array(buffer);
+  return {
+    id,
+    pageNumber,
+    x,
+    y,
+    width,
+    height,
+    signatureId: record.signatureId!,
+  } as SignatureAnnotation;
 }
 
 /**
- * Flatten all annotations (text + signature) into the PDF bytes,
- * producing a new PDF with embedded text.
+ * Flatten all annotations (text + signature) into the PDF at `sourcePath`
+ * and write the result to `outputPath`.
  *
- * ## Output format
- * pdf-lib outputs PDF 1.7 with a proper %PDF-1.7 header, cross-reference
- * table, and trailer. Compatible with Adobe Acrobat, Edge, Chrome, Firefox,
- * and Preview (macOS).
+ * Runs in Rust (flatten_pdf in src-tauri/src/commands/pdf.rs): the PDF is
+ * loaded, drawn on and saved on the native side, so the document bytes never
+ * cross the IPC boundary. Only the annotation list and signature styles are
+ * sent. See the `flatten` docs there for font embedding, rotation handling
+ * and known limitations.
  *
- * ## Font embedding
- * Custom TTF fonts are embedded as CIDFont Type2 (composite fonts) with
- * Identity-H encoding and a ToUnicode CMap for text selection/search.
- * Fonts are subset-embedded to reduce file size. Font descriptors include
- * FontBBox, ItalicAngle, Ascent, Descent, CapHeight, XHeight.
- * NOTE: pdf-lib hardcodes StemV=0 in font descriptors -- this is technically
- * non-ideal but accepted by all major viewers including Adobe Acrobat.
+ * Overlays read from the PDF's own annotations replace them; `removed`
+ * lists the ids of those that were deleted since.
  *
- * ## Content stream behavior
- * pdf-lib appends drawing operators to the existing page content stream via
- * pushOperators(), preserving all original page content. Existing form fields
- * (AcroForm), bookmarks/outlines, internal links, embedded attachments, and
- * JavaScript actions are preserved in the document structure.
+ * Use mode "incremental" to append the changes as a PDF incremental update,
+ * which keeps any existing digital signatures valid.
+ */
+export async function flattenPdf(
+  sourcePath: string,
+  annotations: Annotation[],
+  signatures: Signature[],
+  removed: string[],
+  outputPath: string,
+  mode: PdfSaveMode = "rewrite"
+): Promise<void> {
+  await invoke("flatten_pdf", {
+    sourcePath,
+    annotations: annotations.map(toAnnotationRecord),
+    signatures,
+    removed,
+    outputPath,
+    mode,
+  });
+}
+
+/**
+ * Save the overlays into the PDF at `sourcePath` as real PDF annotations
+ * (FreeText for text, a Stamp for signatures) that stay editable in other
+ * viewers, and write the result to `outputPath`.
  *
- * ## Known limitations
- * - Digital signatures: pdf-lib performs a full rewrite (not incremental save),
- *   so any existing digital signatures WILL be INVALIDATED after flatten.
- * - PDF/A conformance: Modifications add DeviceRGB colors and non-PDF/A fonts,
- *   which may break strict PDF/A compliance. ICC color profiles and XMP
- *   metadata are preserved in the document structure but conformance level
- *   is not guaranteed after modification.
- * - Text overflow: drawText does not clip or wrap -- long text that exceeds
- *   the annotation width will extend beyond the visible overlay boundary.
+ * Runs in Rust (annotate_pdf in src-tauri/src/commands/annotations.rs).
+ * Annotations saved or imported earlier are replaced rather than
+ * duplicated; `removed` lists the ids of those that were deleted since.
+ */
+export async function annotatePdf(
+  sourcePath: string,
+  annotations: Annotation[],
+  signatures: Signature[],
+  removed: string[],
+  outputPath: string,
+  mode: PdfSaveMode = "rewrite"
+): Promise<void> {
+  await invoke("annotate_pdf", {
+    sourcePath,
+    annotations: annotations.map(toAnnotationRecord),
+    signatures,
+    removed,
+    outputPath,
+    mode,
+  });
+}
+
+/**
+ * Read the PDF's FreeText annotations and the signatures saved by
+ * annotatePdf, to be shown and edited as overlays.
+ */
+export async function readPdfAnnotations(path: string): Promise<NativeAnnotation[]> {
+  return invoke("read_pdf_annotations", { path });
+}
+
+/**
+ * Ask the user for an XFDF or FDF file to import.
+ */
+export async function pickExchangeFile(format: ExchangeFormat): Promise<string | null> {
+  return invoke("open_exchange_dialog", { format });
+}
+
+/**
+ * Ask the user where to export an XFDF or FDF file. The extension is added
+ * if the dialog left it off.
+ */
+export async function pickExchangeSaveFile(
+  format: ExchangeFormat,
+  defaultName?: string
+): Promise<string | null> {
+  return invoke("save_exchange_dialog", { format, defaultName });
+}
+
+/**
+ * Export the overlays to an XFDF file other PDF viewers can import; the
+ * PDF at `pdfPath` is only read for its page geometry.
  *
- * ## Coordinate system
- * Annotations store x,y as point offsets from the top-left of the rendered
- * page at pdf.js scale=1 (which accounts for page rotation). pdf-lib operates
- * in the unrotated MediaBox coordinate system with origin at bottom-left, so
- * we convert through toMediaBoxCoords() which handles:
- *   - Y-axis flip (top-origin to bottom-origin)
- *   - Font ascent offset for accurate baseline placement
- *   - Page rotation (0/90/180/270 degrees)
+ * Runs in Rust (export_annotations_xfdf in
+ * src-tauri/src/commands/exchange.rs). Text is written as FreeText and
+ * signatures as stamps.
  */
-export async function flattenPdf(
-  pdfBytes: Uint8Array,
+export async function exportAnnotationsXfdf(
+  pdfPath: string,
   annotations: Annotation[],
-  signatures: Signature[]
-): Promise<Uint8Array> {
-  const pdfDoc = await PDFDocument.load(pdfBytes);
-
-  // Register fontkit so pdf-lib can parse and embed custom TTF fonts
-  pdfDoc.registerFontkit(fontkit);
-
-  // Pre-embed fonts we'll need
-  const embeddedFonts = new Map<string, Awaited<ReturnType<typeof pdfDoc.embedFont>>>();
-
-  // Standard font for text annotations
-  const helvetica = await pdfDoc.embedFont(StandardFonts.Helvetica);
-  embeddedFonts.set("Helvetica", helvetica);
-
-  // Pre-embed custom signature fonts that are actually used
-  const usedFontFamilies = new Set<string>();
-  for (const annotation of annotations) {
-    if (!isTextAnnotation(annotation)) {
-      const sigAnn = annotation as SignatureAnnotation;
-      const sig = signatures.find((s) => s.id === sigAnn.signatureId);
-      if (sig) usedFontFamilies.add(sig.fontFamily);
-    }
-  }
+  signatures: Signature[],
+  outputPath: string
+): Promise<void> {
+  await invoke("export_annotations_xfdf", {
+    pdfPath,
+    annotations: annotations.map(toAnnotationRecord),
+    signatures,
+    outputPath,
+  });
+}
 
-  for (const family of usedFontFamilies) {
-    const fontPath = FONT_FILE_MAP[family];
-    if (fontPath) {
-      try {
-        const fontBytes = await fetchFontBytes(fontPath);
-        // Subset embed: only glyphs actually used are included,
-        // reducing file size for large TTF fonts.
-        const embedded = await pdfDoc.embedFont(fontBytes, { subset: true });
-        embeddedFonts.set(family, embedded);
-      } catch (err) {
-        console.warn(`Could not embed font "${family}", falling back to Helvetica:`, err);
-        embeddedFonts.set(family, helvetica);
-      }
-    } else {
-      embeddedFonts.set(family, helvetica);
-    }
-  }
+/**
+ * Read the FreeText annotations of an XFDF file, and signatures exported by
+ * exportAnnotationsXfdf, as overlays on the PDF at `pdfPath`.
+ */
+export async function importAnnotationsXfdf(path: string, pdfPath: string): Promise<XfdfImport> {
+  return invoke("import_annotations_xfdf", { path, pdfPath });
+}
 
-  // Group annotations by page
-  const pages = pdfDoc.getPages();
-
-  for (const annotation of annotations) {
-    const pageIndex = annotation.pageNumber - 1;
-    if (pageIndex < 0 || pageIndex >= pages.length) continue;
-
-    const page = pages[pageIndex];
-
-    if (isTextAnnotation(annotation)) {
-      const textAnn = annotation as TextAnnotation;
-      if (!textAnn.text) continue; // skip empty text annotations
-
-      const color = hexToRgb(textAnn.color);
-      const fontSize = textAnn.fontSize;
-      const fontAscent = getFontAscent(helvetica, fontSize);
-
-      // Convert from displayed (rotated, top-left origin) to MediaBox (bottom-left origin)
-      const { pdfX, pdfY, textRotation } = toMediaBoxCoords(
-        textAnn.x,
-        textAnn.y,
-        fontAscent,
-        page
-      );
-
-      page.drawText(textAnn.text, {
-        x: pdfX,
-        y: pdfY,
-        size: fontSize,
-        font: helvetica,
-        color,
-        rotate: textRotation !== 0 ? degrees(textRotation) : undefined,
-      });
-    } else {
-      const sigAnn = annotation as SignatureAnnotation;
-      const sig = signatures.find((s) => s.id === sigAnn.signatureId);
-      if (!sig) continue;
-
-      const font = embeddedFonts.get(sig.fontFamily) ?? helvetica;
-      const color = hexToRgb(sig.color);
-
-      // Use the same font size as the on-screen overlay for consistent rendering
-      const fontSize = SIGNATURE_DEFAULT_FONT_SIZE;
-      const fontAscent = getFontAscent(font, fontSize);
-
-      // Convert from displayed (rotated, top-left origin) to MediaBox (bottom-left origin)
-      const { pdfX, pdfY, textRotation } = toMediaBoxCoords(
-        sigAnn.x,
-        sigAnn.y,
-        fontAscent,
-        page
-      );
-
-      page.drawText(sig.name, {
-        x: pdfX,
-        y: pdfY,
-        size: fontSize,
-        font,
-        color,
-        rotate: textRotation !== 0 ? degrees(textRotation) : undefined,
-      });
-    }
-  }
+/**
+ * Export form values (keyed by fully qualified field name) for the PDF at
+ * `pdfPath` to an FDF file.
+ */
+export async function exportFormFdf(
+  pdfPath: string,
+  values: Record<string, FormFieldValue>,
+  outputPath: string
+): Promise<void> {
+  await invoke("export_form_fdf", { pdfPath, values, outputPath });
+}
+
+/**
+ * Read the values of an FDF file for the fields of the PDF at `pdfPath`.
+ * Fields the form doesn't have are left out.
+ */
+export async function importFormFdf(
+  path: string,
+  pdfPath: string
+): Promise<Record<string, FormFieldValue>> {
+  return invoke("import_form_fdf", { path, pdfPath });
+}
+
+/**
+ * Ask the user for a PKCS#12 digital ID (.p12/.pfx) to sign with.
+ */
+export async function pickDigitalId(): Promise<string | null> {
+  return invoke("open_identity_dialog");
+}
+
+/**
+ * Digitally sign the PDF at `sourcePath` with a certificate from a PKCS#12
+ * digital ID and write the result to `outputPath` (which may be the same
+ * file).
+ *
+ * Runs in Rust (sign_pdf in src-tauri/src/commands/signing.rs). The signature
+ * is appended as an incremental update with a CMS detached signature over
+ * the whole file, so any signatures already on the document stay valid.
+ */
+export async function signPdf(
+  sourcePath: string,
+  outputPath: string,
+  options: SignOptions
+): Promise<void> {
+  await invoke("sign_pdf", { sourcePath, outputPath, options });
+}
+
+/**
+ * Check every signature field in the PDF at `path`: byte-range digest,
+ * signature, and certificate chain against the local trust store.
+ */
+export async function verifyPdfSignatures(path: string): Promise<SignatureReport[]> {
+  return invoke("verify_pdf_signatures", { path });
+}
+
+export async function listTrustedCertificates(): Promise<CertificateSummary[]> {
+  return invoke("list_trusted_certificates");
+}
+
+/**
+ * Ask the user for a certificate file and add it to the trust store.
+ * Resolves to null if the dialog was cancelled.
+ */
+export async function importTrustedCertificate(): Promise<CertificateSummary[] | null> {
+  return invoke("import_trusted_certificate");
+}
+
+export async function removeTrustedCertificate(fingerprint: string): Promise<void> {
+  await invoke("remove_trusted_certificate", { fingerprint });
+}
+
+/**
+ * List the AcroForm fields of the PDF at `path`, with their current values.
+ */
+export async function listFormFields(path: string): Promise<FormField[]> {
+  return invoke("list_form_fields", { path });
+}
+
+/**
+ * Write `values` (keyed by fully qualified field name) into the form of the
+ * PDF at `sourcePath` and save it to `outputPath`.
+ *
+ * Runs in Rust (fill_form_fields in src-tauri/src/commands/forms.rs). The
+ * values go into the real AcroForm, so the output stays fillable unless
+ * `flatten` is set.
+ */
+export async function fillFormFields(
+  sourcePath: string,
+  outputPath: string,
+  values: Record<string, FormFieldValue>,
+  options: FillFormOptions = {}
+): Promise<void> {
+  await invoke("fill_form_fields", { sourcePath, outputPath, values, options });
+}
+
+/** Whether the PDF at `path` is encrypted, how, and what it allows. */
+export async function getPdfEncryption(path: string): Promise<EncryptionInfo> {
+  return invoke("get_pdf_encryption", { path });
+}
+
+/**
+ * Check the password of an encrypted PDF. Rust keeps it for the rest of the
+ * session, so the other PDF commands can read the file; rejects with
+ * "Incorrect password" otherwise.
+ */
+export async function unlockPdf(path: string, password: string): Promise<EncryptionInfo> {
+  return invoke("unlock_pdf", { path, password });
+}
+
+/**
+ * Save the PDF at `sourcePath` to `outputPath` encrypted with new passwords
+ * and permissions (protect_pdf in src-tauri/src/commands/security.rs). An
+ * already encrypted file needs its owner password: `currentPassword`, or
+ * the one it was unlocked with.
+ */
+export async function protectPdf(
+  sourcePath: string,
+  outputPath: string,
+  options: ProtectOptions,
+  currentPassword?: string
+): Promise<void> {
+  await invoke("protect_pdf", {
+    sourcePath,
+    outputPath,
+    options,
+    currentPassword: currentPassword || null,
+  });
+}
+
+/** Save the PDF at `sourcePath` to `outputPath` without its password. */
+export async function removePdfPassword(
+  sourcePath: string,
+  outputPath: string,
+  currentPassword?: string
+): Promise<void> {
+  await invoke("remove_pdf_password", {
+    sourcePath,
+    outputPath,
+    currentPassword: currentPassword || null,
+  });
+}
+
+/** Save a smaller copy of the PDF at `sourcePath` to `outputPath`. */
+export async function optimizePdf(
+  sourcePath: string,
+  outputPath: string,
+  options: OptimizeOptions
+): Promise<OptimizeReport> {
+  return invoke<OptimizeReport>("optimize_pdf", { sourcePath, outputPath, options });
+}
+
+/**
+ * Ask the user for one or more PDFs. Resolves to an empty list if the
+ * dialog was cancelled.
+ */
+export async function pickPdfFiles(): Promise<string[]> {
+  return invoke("open_pdfs_dialog");
+}
+
+export async function pickFolder(): Promise<string | null> {
+  return invoke("pick_folder_dialog");
+}
+
+/**
+ * Page organizer commands (src-tauri/src/commands/pages.rs). They read and
+ * write files natively; bookmarks and links survive wherever the pages
+ * they point at do.
+ */
+export async function mergePdfs(sourcePaths: string[], outputPath: string): Promise<void> {
+  await invoke("merge_pdfs", { sourcePaths, outputPath });
+}
+
+/** Split a PDF into several files in `outputDir`; resolves to their paths. */
+export async function splitPdf(
+  sourcePath: string,
+  outputDir: string,
+  mode: SplitMode
+): Promise<string[]> {
+  return invoke("split_pdf", { sourcePath, outputDir, mode });
+}
+
+/** `order` lists every page number once, in the new order. */
+export async function reorderPages(
+  sourcePath: string,
+  outputPath: string,
+  order: number[]
+): Promise<void> {
+  await invoke("reorder_pages", { sourcePath, outputPath, order });
+}
+
+/** Rotate pages clockwise by a multiple of 90 degrees. */
+export async function rotatePages(
+  sourcePath: string,
+  outputPath: string,
+  pages: number[],
+  degrees: number
+): Promise<void> {
+  await invoke("rotate_pages", { sourcePath, outputPath, pages, degrees });
+}
+
+export async function deletePages(
+  sourcePath: string,
+  outputPath: string,
+  pages: number[]
+): Promise<void> {
+  await invoke("delete_pages", { sourcePath, outputPath, pages });
+}
+
+/**
+ * Permanently remove the text, image pixels and annotations under `areas`
+ * and save the result to `outputPath`.
+ *
+ * Runs in Rust (redact_pdf in src-tauri/src/commands/redaction.rs), which
+ * re-checks the areas of the saved file and rejects the redaction if
+ * anything is left in them.
+ */
+export async function redactPdf(
+  sourcePath: string,
+  outputPath: string,
+  areas: RedactionArea[]
+): Promise<RedactionSummary> {
+  return invoke("redact_pdf", { sourcePath, outputPath, areas });
+}
+
+/**
+ * Read the text of a PDF natively, with the position of every glyph.
+ * Extracts all pages unless `pages` (1-based) is given.
+ */
+export async function extractPdfText(path: string, pages?: number[]): Promise<PageText[]> {
+  return invoke("extract_pdf_text", { path, pages: pages ?? null });
+}
 
-  const flattenedBytes = await pdfDoc.save();
-  return new Uint8Array(flattenedBytes);
+/** Find every occurrence of `query` in a PDF, in page order. */
+export async function searchPdf(
+  path: string,
+  query: string,
+  options: SearchOptions = {}
+): Promise<SearchHit[]> {
+  return invoke("search_pdf", { path, query, options });
 }
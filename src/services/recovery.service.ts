@@ -0,0 +1,32 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { RecoverableSession, RecoverySnapshot } from "../types/document";
+
+/**
+ * Crash recovery (src-tauri/src/commands/recovery.rs). Each window keeps a
+ * snapshot of its unsaved work on disk; a window that closes normally drops
+ * it, so snapshots found at the next launch are from a session that crashed.
+ */
+
+/** Replace this window's snapshot. */
+export async function saveRecoverySnapshot(snapshot: RecoverySnapshot): Promise<void> {
+  await invoke("save_recovery_snapshot", { snapshot });
+}
+
+/** Drop this window's snapshot, once its document is saved or closed. */
+export async function clearRecoverySnapshot(): Promise<void> {
+  await invoke("clear_recovery_snapshot");
+}
+
+/** Snapshots left by sessions that crashed, newest first. */
+export async function listRecoverableSessions(): Promise<RecoverableSession[]> {
+  return invoke("list_recoverable_sessions");
+}
+
+export async function openRecoverableSession(id: string): Promise<RecoverySnapshot> {
+  return invoke("open_recoverable_session", { id });
+}
+
+/** Delete a recoverable session, recovered or not. */
+export async function discardRecoverableSession(id: string): Promise<void> {
+  await invoke("discard_recoverable_session", { id });
+}
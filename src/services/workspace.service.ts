@@ -0,0 +1,26 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/**
+ * Document windows, tracked on the Rust side so a file is only ever open in
+ * one window and the open set comes back at the next launch
+ * (src-tauri/src/commands/workspace.rs).
+ */
+
+/** Open `path` in a new window, or focus the window that already has it. */
+export async function openDocumentWindow(path: string): Promise<void> {
+  await invoke("open_document_window", { path });
+}
+
+/** The document this window was opened for, if any. */
+export async function getWindowDocument(): Promise<string | null> {
+  return invoke("get_window_document");
+}
+
+/**
+ * Record the document shown in this window (`null` for none). Resolves to
+ * false if another window already has `path` open; that window is focused
+ * and this one should leave it alone.
+ */
+export async function setWindowDocument(path: string | null): Promise<boolean> {
+  return invoke("set_window_document", { path });
+}
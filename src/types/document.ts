@@ -9,6 +9,156 @@ export interface RecentDocument {
   pageCount: number;
 }
 
+/**
+ * A file on disk that is read a range at a time instead of all at once, for
+ * PDFs too large to pass to pdf.js in one piece.
+ */
+export interface RangedFile {
+  path: string;
+  size: number;
+}
+
+/** A copy of a file from before a save (BackupInfo in backups.rs). */
+export interface BackupInfo {
+  id: string;
+  /** Milliseconds since the Unix epoch. */
+  createdAt: number;
+  size: number;
+}
+
+/** Who has a document open for editing (LockOwner in locks.rs). */
+export interface LockOwner {
+  user: string;
+  /** Empty for documents opened in Microsoft Office. */
+  host: string;
+  /** Milliseconds since the Unix epoch, when known. */
+  lockedAt: number | null;
+}
+
+/** The open file was changed or removed by another program. */
+export interface ExternalChange {
+  kind: "changed" | "deleted";
+  path: string;
+}
+
+/**
+ * Unsaved work, snapshotted for crash recovery (RecoverySnapshot in
+ * recovery.rs). `content` is the editor's HTML for Word documents and the
+ * overlays as JSON for PDFs.
+ */
+export interface RecoverySnapshot {
+  filePath: string | null;
+  fileName: string;
+  fileType: FileType;
+  content: string;
+}
+
+/** A snapshot left behind by a session that crashed. */
+export interface RecoverableSession {
+  id: string;
+  filePath: string | null;
+  fileName: string;
+  fileType: FileType;
+  /** Milliseconds since the Unix epoch. */
+  savedAt: number;
+}
+
+/** An earlier state of a document (DocumentVersion in versions.rs). */
+export interface DocumentVersion {
+  id: number;
+  filePath: string;
+  contentHash: string;
+  fileSize: number;
+  /** UTC, "YYYY-MM-DD HH:MM:SS". */
+  savedAt: string;
+}
+
+/** A piece of a library search snippet (SnippetPart in library.rs). */
+export interface SnippetPart {
+  text: string;
+  matched: boolean;
+}
+
+/** A page matching a library search (LibraryHit in library.rs). */
+export interface LibraryHit {
+  filePath: string;
+  fileName: string;
+  /** Null for Word documents. */
+  pageNumber: number | null;
+  snippet: SnippetPart[];
+}
+
+/** Why a command failed (ErrorCode in error.rs). */
+export type ErrorCode =
+  | "not_found"
+  | "permission_denied"
+  | "disk_full"
+  | "in_use"
+  | "invalid_path"
+  | "invalid_data"
+  | "file_changed"
+  | "file_locked"
+  | "other";
+
+/**
+ * The error the document and file-writing commands reject with
+ * (CommandError in error.rs). `message` is for the log; show the user
+ * `errorMessage` from utils/errors.ts instead.
+ */
+export interface CommandError {
+  code: ErrorCode;
+  message: string;
+  path: string | null;
+}
+
+/**
+ * What a file really is, judged by its contents rather than its extension
+ * (FileInfo in inspect.rs). `format` is the specific format, such as "docx",
+ * "rtf" or "csv".
+ */
+export interface FileInfo {
+  fileType: FileType;
+  format: string;
+  version: string | null;
+  encrypted: boolean;
+  pageCount: number | null;
+  sheetCount: number | null;
+  slideCount: number | null;
+  /** SHA-256 of the contents; identifies the document wherever it moves. */
+  fingerprint: string | null;
+  /** The permanent part of a PDF's /ID, which survives most edits. */
+  pdfId: string | null;
+}
+
+/**
+ * What a document's overlays and recents entry are stored against. The
+ * path is only a hint: a moved or renamed file is found again by its
+ * fingerprint, and a PDF edited elsewhere by its /ID.
+ */
+export interface DocumentIdentity {
+  path: string;
+  fingerprint: string | null;
+  pdfId: string | null;
+}
+
+export function documentIdentity(path: string, info: FileInfo | null): DocumentIdentity {
+  return { path, fingerprint: info?.fingerprint ?? null, pdfId: info?.pdfId ?? null };
+}
+
+/** Formats the Word editor can load. */
+const WORD_EDITOR_FORMATS = ["docx", "docm", "dotx", "dotm"];
+
+/**
+ * The viewer to open an inspected file in. Word documents in formats the
+ * editor can't read, and encrypted Office files, are unsupported.
+ */
+export function viewerFileType(info: FileInfo): FileType {
+  if (info.fileType === "word" && !WORD_EDITOR_FORMATS.includes(info.format)) return "unknown";
+  if (info.encrypted && info.fileType !== "pdf") return "unknown";
+  return info.fileType;
+}
+
+/** Judge by extension alone, for files that can't be inspected. */
 const EXT_MAP: Record<string, FileType> = {
   ".pdf": "pdf",
   ".docx": "word",
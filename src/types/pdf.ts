@@ -39,4 +39,241 @@ export function isSignatureAnnotation(a: Annotation): a is SignatureAnnotation {
   return "signatureId" in a;
 }
 
-export type PdfMode = "view" | "text" | "sign";
+export type PdfMode = "view" | "text" | "sign" | "redact";
+
+/**
+ * How the Rust PDF commands write an edited file. "incremental" appends the
+ * changes after the original bytes, which keeps existing digital signatures
+ * valid; "rewrite" produces a compact new file but invalidates them.
+ */
+export type PdfSaveMode = "rewrite" | "incremental";
+
+/**
+ * Options for the Rust sign_pdf command (SignOptions in
+ * src-tauri/src/commands/signing.rs).
+ */
+export interface SignOptions {
+  /** Path to a PKCS#12 (.p12/.pfx) digital ID. */
+  identityPath: string;
+  password: string;
+  /** Visible signature box in annotation coordinates; omit for an invisible signature. */
+  placement?: {
+    pageNumber: number;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+  };
+  /** Saved signature style drawn in the box. */
+  style?: { id: number; name: string; fontFamily: string; color: string };
+  reason?: string;
+  location?: string;
+  contactInfo?: string;
+}
+
+/** A certificate as summarized by the Rust verifier. */
+export interface CertificateSummary {
+  subject: string;
+  issuer: string;
+  commonName: string;
+  serialNumber: string;
+  notBefore: string;
+  notAfter: string;
+  /** SHA-256 of the certificate; identifies it in the trust store. */
+  fingerprint: string;
+}
+
+/**
+ * Verification result for one signature field, returned by
+ * verify_pdf_signatures (SignatureReport in
+ * src-tauri/src/commands/verification.rs).
+ */
+export interface SignatureReport {
+  fieldName: string;
+  pageNumber: number | null;
+  signed: boolean;
+  signerName: string | null;
+  signingTime: string | null;
+  reason: string | null;
+  location: string | null;
+  contactInfo: string | null;
+  subFilter: string | null;
+  integrity: "valid" | "invalid" | "unknown";
+  trust: "trusted" | "untrusted" | "unknown";
+  coversWholeDocument: boolean;
+  modifiedAfterSigning: boolean;
+  /** Signer first, then its issuers. */
+  certificates: CertificateSummary[];
+  messages: string[];
+}
+
+export type FormFieldType =
+  | "text"
+  | "checkbox"
+  | "radio"
+  | "comboBox"
+  | "listBox"
+  | "pushButton"
+  | "signature";
+
+/** A checkbox state, a text/choice/radio value, or a list box selection. */
+export type FormFieldValue = boolean | string | string[];
+
+/**
+ * An AcroForm field returned by list_form_fields (FormField in
+ * src-tauri/src/commands/forms.rs). Widget rectangles use annotation
+ * coordinates.
+ */
+export interface FormField {
+  /** Fully qualified name, e.g. "applicant.address.city". */
+  name: string;
+  fieldType: FormFieldType;
+  value: FormFieldValue | null;
+  /** Choice entries, or the on-states of a checkbox/radio group. */
+  options: { value: string; label: string }[];
+  widgets: {
+    pageNumber: number | null;
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    exportValue: string | null;
+  }[];
+  readOnly: boolean;
+  required: boolean;
+  multiline: boolean;
+  maxLength: number | null;
+}
+
+/** Options for fill_form_fields (FillOptions in forms.rs). */
+export interface FillFormOptions {
+  /** Draw new appearances for filled fields (default true). */
+  regenerateAppearances?: boolean;
+  /** Burn the fields into the pages so the form is no longer fillable. */
+  flatten?: boolean;
+  mode?: PdfSaveMode;
+}
+
+/** What readers opening with the user password may do (PdfPermissions in security.rs). */
+export interface PdfPermissions {
+  print: boolean;
+  copy: boolean;
+  /** Editing pages, annotating, filling forms and assembling pages. */
+  modify: boolean;
+}
+
+/** How a PDF is encrypted (EncryptionInfo in security.rs). */
+export interface EncryptionInfo {
+  encrypted: boolean;
+  /** "RC4-40", "RC4-128", "AES-128" or "AES-256". */
+  method: string | null;
+  /** A password must be given to unlock_pdf before the file can be read. */
+  needsPassword: boolean;
+  /** The password entered is the owner password, so protection can be changed. */
+  ownerUnlocked: boolean;
+  permissions: PdfPermissions;
+}
+
+export type EncryptionMethod = "aes256" | "aes128" | "rc4";
+
+/** Options for protect_pdf (ProtectOptions in security.rs). */
+export interface ProtectOptions {
+  /** Needed to open the file; may be empty. */
+  userPassword: string;
+  /** Needed to change the protection; defaults to the user password. */
+  ownerPassword?: string;
+  permissions: PdfPermissions;
+  method: EncryptionMethod;
+}
+
+/**
+ * What optimize_pdf is for: email downsamples images to 150 dpi, print to
+ * 300 dpi, and archive keeps them and skips object streams for PDF/A.
+ */
+export type OptimizePreset = "email" | "print" | "archive";
+
+/** Options for optimize_pdf (OptimizeOptions in optimize.rs). */
+export interface OptimizeOptions {
+  preset: OptimizePreset;
+  /** Overrides the preset's image resolution; 0 leaves images alone. */
+  imageDpi?: number;
+}
+
+/** What optimize_pdf did (OptimizeReport in optimize.rs), sizes in bytes. */
+export interface OptimizeReport {
+  originalSize: number;
+  optimizedSize: number;
+  duplicatesRemoved: number;
+  fontsMerged: number;
+  streamsRecompressed: number;
+  imagesDownsampled: number;
+  /** Images above the resolution that couldn't be resampled (JPEG 2000, CMYK JPEG, ...). */
+  imagesSkipped: number;
+}
+
+/** An inclusive, 1-based page range. */
+export interface PageRange {
+  start: number;
+  end: number;
+}
+
+/** How split_pdf divides a document (SplitMode in pages.rs). */
+export type SplitMode =
+  | { by: "ranges"; ranges: PageRange[] }
+  | { by: "every"; pages: number };
+
+/**
+ * An area to redact, in the same page coordinates as annotations
+ * (RedactionArea in src-tauri/src/commands/redaction.rs).
+ */
+export interface RedactionArea {
+  id: string;
+  pageNumber: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/** What redact_pdf removed (RedactionSummary in redaction.rs). */
+export interface RedactionSummary {
+  glyphsRemoved: number;
+  imagesRedacted: number;
+  /** Images and drawings that couldn't be edited and were removed whole. */
+  graphicsRemoved: number;
+  annotationsRemoved: number;
+}
+
+/** A rectangle in the same page coordinates as annotations. */
+export interface TextRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/** A glyph and where its text starts in PageText.text. */
+export interface TextGlyph extends TextRect {
+  text: string;
+  offset: number;
+}
+
+/** What extract_pdf_text returns per page (PageText in text.rs). */
+export interface PageText {
+  pageNumber: number;
+  text: string;
+  glyphs: TextGlyph[];
+}
+
+export interface SearchOptions {
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+}
+
+/** One match from search_pdf, with a rectangle per line it spans. */
+export interface SearchHit {
+  pageNumber: number;
+  text: string;
+  context: string;
+  rects: TextRect[];
+}
@@ -0,0 +1,45 @@
+import type { CommandError, ErrorCode } from "../types/document";
+
+/**
+ * What the user is told for each error code, with `{name}` standing for the
+ * file's name. Kept in one place so the wording can be translated. "other"
+ * has no wording of its own: the caller's fallback is shown.
+ */
+const MESSAGES: Record<ErrorCode, string | null> = {
+  not_found: "{name} could not be found. It may have been moved, renamed or deleted.",
+  permission_denied:
+    "{name} could not be accessed because you don't have permission. Check the file's permissions or use a different folder.",
+  disk_full: "{name} could not be saved because the disk is full. Free up some space and try again.",
+  in_use: "{name} is open in another program. Close it there and try again.",
+  invalid_path: "{name} is not a valid file location. Choose a different name or folder.",
+  invalid_data: "{name} could not be read. It may be damaged or in an unexpected format.",
+  file_changed: "{name} was changed by another program. Reload it or keep your version.",
+  file_locked: "{name} is open for editing by someone else. Save your changes as a copy.",
+  other: null,
+};
+
+export function isCommandError(err: unknown): err is CommandError {
+  return typeof err === "object" && err !== null && "code" in err && "message" in err;
+}
+
+/** The code of a command error, or null for any other kind of error. */
+export function errorCode(err: unknown): ErrorCode | null {
+  return isCommandError(err) ? err.code : null;
+}
+
+/** A message for the user about `err`, or `fallback` if there's nothing
+ *  more specific to say. */
+export function errorMessage(err: unknown, fallback: string): string {
+  if (!isCommandError(err)) return fallback;
+  const template = MESSAGES[err.code];
+  if (!template) return fallback;
+  const name = err.path?.split(/[\\/]/).pop();
+  return template.replace("{name}", name ? `"${name}"` : "The file");
+}
+
+/** The text of `err` as it came: a command error's log message, or the
+ *  error itself. For fallbacks where there is no better wording. */
+export function errorDetail(err: unknown): string {
+  if (isCommandError(err)) return err.message;
+  return err instanceof Error ? err.message : String(err);
+}
@@ -0,0 +1,28 @@
+import type { PageRange } from "../types/pdf";
+
+/**
+ * Parse a page list such as "1-3, 5, 8-" into ranges. An open end runs to
+ * the last page. Throws on anything outside 1..pageCount.
+ */
+export function parsePageRanges(input: string, pageCount: number): PageRange[] {
+  const ranges: PageRange[] = [];
+  for (const part of input.split(",").map((p) => p.trim()).filter(Boolean)) {
+    const match = /^(\d+)\s*(?:-\s*(\d*))?$/.exec(part);
+    if (!match) throw new Error(`"${part}" is not a page or page range`);
+    const start = parseInt(match[1], 10);
+    const end = match[2] === undefined ? start : match[2] === "" ? pageCount : parseInt(match[2], 10);
+    if (start < 1 || end > pageCount || start > end) {
+      throw new Error(`Pages ${part} are outside 1-${pageCount}`);
+    }
+    ranges.push({ start, end });
+  }
+  if (ranges.length === 0) throw new Error("Enter at least one page");
+  return ranges;
+}
+
+/** Expand a page list such as "1-3, 5" into page numbers, in order. */
+export function parsePageList(input: string, pageCount: number): number[] {
+  return parsePageRanges(input, pageCount).flatMap(({ start, end }) =>
+    Array.from({ length: end - start + 1 }, (_, i) => start + i)
+  );
+}